@@ -0,0 +1,257 @@
+//! Builds an animated GIF or WebP out of a set of images.
+//!
+//! GIFs are encoded with the `image` crate. The `image` crate can't write animated WebP,
+//! so those are assembled by libwebp's `img2webp` tool (on `PATH`, or set `BIV_IMG2WEBP`).
+
+use crate::image_loader::{decode_image, resize_linear};
+use eframe::egui;
+use image::RgbaImage;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AnimationFormat {
+    Gif,
+    WebP,
+}
+
+impl AnimationFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::WebP => "webp",
+        }
+    }
+}
+
+enum Progress {
+    Frame(usize),
+    Done(Result<PathBuf, String>),
+}
+
+pub struct AnimatedExport {
+    pub open: bool,
+    frames: Vec<PathBuf>,
+    format: AnimationFormat,
+    delay_ms: u32,
+    // Longest side of the output; larger frames are scaled down
+    max_size: u32,
+    rx: Option<Receiver<Progress>>,
+    encoded: usize,
+    status: Option<String>,
+}
+
+impl Default for AnimatedExport {
+    fn default() -> Self {
+        Self {
+            open: false,
+            frames: Vec::new(),
+            format: AnimationFormat::Gif,
+            delay_ms: 500,
+            max_size: 800,
+            rx: None,
+            encoded: 0,
+            status: None,
+        }
+    }
+}
+
+/// Scales `image` into a `width`x`height` canvas, centered on a transparent background.
+fn fit_frame(image: &image::DynamicImage, width: u32, height: u32) -> RgbaImage {
+    let scaled = resize_linear(image, width, height);
+    let mut canvas = RgbaImage::new(width, height);
+    let x = (width - scaled.width()) / 2;
+    let y = (height - scaled.height()) / 2;
+    image::imageops::overlay(&mut canvas, &scaled, x as i64, y as i64);
+    canvas
+}
+
+/// Decodes and normalizes the frames; every frame takes the size of the first one.
+fn prepare_frames(
+    paths: &[PathBuf],
+    max_size: u32,
+    tx: &Sender<Progress>,
+    ctx: &egui::Context,
+) -> Result<Vec<RgbaImage>, String> {
+    let mut frames = Vec::with_capacity(paths.len());
+    let mut size = None;
+    for (i, path) in paths.iter().enumerate() {
+        let image = decode_image(path)
+            .map_err(|e| format!("{}: {}", path.file_name().unwrap_or_default().to_string_lossy(), e))?;
+        let (width, height) = *size.get_or_insert_with(|| {
+            let scale = (max_size as f32 / image.width().max(image.height()) as f32).min(1.0);
+            (
+                ((image.width() as f32 * scale).round() as u32).max(1),
+                ((image.height() as f32 * scale).round() as u32).max(1),
+            )
+        });
+        frames.push(fit_frame(&image, width, height));
+        let _ = tx.send(Progress::Frame(i + 1));
+        ctx.request_repaint();
+    }
+    Ok(frames)
+}
+
+fn encode_gif(frames: Vec<RgbaImage>, delay_ms: u32, path: &Path) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = image::codecs::gif::GifEncoder::new_with_speed(BufWriter::new(file), 10);
+    encoder
+        .set_repeat(image::codecs::gif::Repeat::Infinite)
+        .map_err(|e| e.to_string())?;
+    let delay = image::Delay::from_numer_denom_ms(delay_ms, 1);
+    encoder
+        .encode_frames(
+            frames
+                .into_iter()
+                .map(|frame| image::Frame::from_parts(frame, 0, 0, delay)),
+        )
+        .map_err(|e| e.to_string())
+}
+
+fn encode_webp(frames: Vec<RgbaImage>, delay_ms: u32, path: &Path) -> Result<(), String> {
+    let dir = std::env::temp_dir().join(format!("biv_anim_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let result = (|| {
+        let mut command =
+            Command::new(std::env::var_os("BIV_IMG2WEBP").unwrap_or_else(|| "img2webp".into()));
+        command.args(["-loop", "0", "-lossy", "-d", &delay_ms.to_string()]);
+        for (i, frame) in frames.iter().enumerate() {
+            let frame_path = dir.join(format!("{:05}.png", i));
+            frame.save(&frame_path).map_err(|e| e.to_string())?;
+            command.arg(frame_path);
+        }
+        let output = command
+            .arg("-o")
+            .arg(path)
+            .output()
+            .map_err(|e| format!("Could not run img2webp ({}); is libwebp installed?", e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    })();
+
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+impl AnimatedExport {
+    pub fn is_running(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    /// Opens the dialog for `frames`, in the order they will be played.
+    pub fn open(&mut self, frames: Vec<PathBuf>) {
+        if self.is_running() {
+            self.open = true;
+            return;
+        }
+        self.frames = frames;
+        self.status = None;
+        self.open = true;
+    }
+
+    fn start(&mut self, ctx: &egui::Context, path: PathBuf) {
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.encoded = 0;
+        self.status = None;
+
+        let frames = self.frames.clone();
+        let (format, delay_ms, max_size) = (self.format, self.delay_ms, self.max_size);
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = prepare_frames(&frames, max_size, &tx, &ctx).and_then(|images| {
+                match format {
+                    AnimationFormat::Gif => encode_gif(images, delay_ms, &path),
+                    AnimationFormat::WebP => encode_webp(images, delay_ms, &path),
+                }
+                .map(|_| path)
+            });
+            let _ = tx.send(Progress::Done(result));
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.rx {
+            while let Ok(progress) = rx.try_recv() {
+                match progress {
+                    Progress::Frame(n) => self.encoded = n,
+                    Progress::Done(result) => {
+                        self.status = Some(match result {
+                            Ok(path) => format!("Saved {}", path.display()),
+                            Err(e) => format!("Export failed: {}", e),
+                        });
+                        self.rx = None;
+                        break;
+                    }
+                }
+            }
+        }
+
+        if !self.open {
+            return;
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Create Animation")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} frames. Ctrl+click or Shift+click thumbnails to choose frames.",
+                    self.frames.len()
+                ));
+
+                ui.add_enabled_ui(!self.is_running(), |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Format:");
+                        ui.radio_value(&mut self.format, AnimationFormat::Gif, "GIF");
+                        ui.radio_value(&mut self.format, AnimationFormat::WebP, "WebP");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Frame delay:");
+                        ui.add(egui::DragValue::new(&mut self.delay_ms).range(10..=10000).suffix(" ms"));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Max size:");
+                        ui.add(egui::DragValue::new(&mut self.max_size).range(16..=4096).suffix(" px"));
+                    });
+
+                    let button = egui::Button::new("Export…");
+                    if ui.add_enabled(self.frames.len() > 1, button).clicked()
+                        && let Some(path) = rfd::FileDialog::new()
+                            .add_filter(self.format.extension().to_uppercase(), &[self.format.extension()])
+                            .set_file_name(format!("animation.{}", self.format.extension()))
+                            .save_file()
+                    {
+                        self.start(ctx, path);
+                    }
+                });
+
+                if self.is_running() {
+                    let total = self.frames.len().max(1);
+                    let text = if self.encoded < total {
+                        format!("Decoding frame {} / {}…", self.encoded + 1, total)
+                    } else {
+                        "Encoding…".to_string()
+                    };
+                    ui.add(
+                        egui::ProgressBar::new(self.encoded as f32 / total as f32)
+                            .text(text)
+                            .animate(true),
+                    );
+                } else if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+            });
+        self.open = open;
+    }
+}
@@ -0,0 +1,460 @@
+use crate::clipboard;
+use crate::image_loader::decode_image;
+use crate::osd;
+use crate::view_state::ViewState;
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tool {
+    Arrow,
+    Rect,
+    Freehand,
+    Text,
+}
+
+/// A single annotation. All coordinates and sizes are in image pixels.
+#[derive(Clone, Debug)]
+pub enum Shape {
+    Arrow { from: egui::Pos2, to: egui::Pos2 },
+    // Stored as drag start (min) and end (max), which may be unordered
+    Rect(egui::Rect),
+    Freehand(Vec<egui::Pos2>),
+    Text { pos: egui::Pos2, text: String, size: f32 },
+}
+
+#[derive(Clone, Debug)]
+pub struct Annotation {
+    pub shape: Shape,
+    pub color: egui::Color32,
+    pub width: f32,
+}
+
+pub enum ExportTarget {
+    File(PathBuf),
+    Clipboard,
+}
+
+/// Annotation layer drawn over the current image, with undo/redo and export.
+pub struct Annotations {
+    pub enabled: bool,
+    tool: Tool,
+    color: egui::Color32,
+    // Stroke width in screen points; converted to image pixels when a shape starts
+    stroke_width: f32,
+
+    shapes: Vec<Annotation>,
+    redo_stack: Vec<Annotation>,
+    in_progress: Option<Annotation>,
+    // Text box being typed: position in image pixels and the current text
+    pending_text: Option<(egui::Pos2, String)>,
+
+    export_rx: Option<Receiver<Result<String, String>>>,
+    status: Option<String>,
+}
+
+impl Default for Annotations {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tool: Tool::Arrow,
+            color: egui::Color32::RED,
+            stroke_width: 3.0,
+            shapes: Vec::new(),
+            redo_stack: Vec::new(),
+            in_progress: None,
+            pending_text: None,
+            export_rx: None,
+            status: None,
+        }
+    }
+}
+
+impl Annotations {
+    /// Drops all annotations, e.g. when switching to another image.
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+        self.redo_stack.clear();
+        self.in_progress = None;
+        self.pending_text = None;
+    }
+
+    fn push(&mut self, annotation: Annotation) {
+        self.shapes.push(annotation);
+        self.redo_stack.clear();
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(a) = self.shapes.pop() {
+            self.redo_stack.push(a);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(a) = self.redo_stack.pop() {
+            self.shapes.push(a);
+        }
+    }
+
+    pub fn process_input(&mut self, ui: &egui::Ui, view_state: &ViewState, image_size: egui::Vec2) {
+        if !self.enabled {
+            return;
+        }
+
+        let viewport = ui.clip_rect();
+        let wants_pointer = ui.ctx().wants_pointer_input() || ui.ctx().is_pointer_over_area();
+        let (pressed, down, pos, undo, redo) = ui.input(|i| {
+            (
+                i.pointer.button_pressed(egui::PointerButton::Primary),
+                i.pointer.button_down(egui::PointerButton::Primary),
+                i.pointer.interact_pos(),
+                i.modifiers.command && i.key_pressed(egui::Key::Z),
+                i.modifiers.command && i.key_pressed(egui::Key::Y),
+            )
+        });
+
+        if self.pending_text.is_none() {
+            if undo {
+                self.undo();
+            } else if redo {
+                self.redo();
+            }
+        }
+
+        let Some(pos) = pos else {
+            return;
+        };
+        let p = view_state.screen_to_image(viewport, image_size, pos);
+        let width = self.stroke_width / view_state.zoom;
+
+        if pressed && !wants_pointer && !ui.input(|i| i.modifiers.command) {
+            self.in_progress = match self.tool {
+                Tool::Arrow => Some(Shape::Arrow { from: p, to: p }),
+                Tool::Rect => Some(Shape::Rect(egui::Rect::from_min_max(p, p))),
+                Tool::Freehand => Some(Shape::Freehand(vec![p])),
+                Tool::Text => {
+                    self.pending_text = Some((p, String::new()));
+                    None
+                }
+            }
+            .map(|shape| Annotation {
+                shape,
+                color: self.color,
+                width,
+            });
+        }
+
+        if let Some(current) = &mut self.in_progress {
+            match &mut current.shape {
+                Shape::Arrow { to, .. } => *to = p,
+                Shape::Rect(rect) => rect.max = p,
+                Shape::Freehand(points) => {
+                    if points.last().is_none_or(|last| last.distance(p) * view_state.zoom > 2.0) {
+                        points.push(p);
+                    }
+                }
+                Shape::Text { .. } => {}
+            }
+            ui.ctx().request_repaint();
+
+            if !down && let Some(done) = self.in_progress.take() {
+                self.push(done);
+            }
+        }
+    }
+
+    /// Paints all annotations (and the one being drawn) over the image.
+    pub fn paint(&mut self, ui: &mut egui::Ui, view_state: &ViewState, image_size: egui::Vec2) {
+        let viewport = ui.clip_rect();
+        let to_screen = |p: egui::Pos2| view_state.image_to_screen(viewport, image_size, p);
+
+        for annotation in self.shapes.iter().chain(self.in_progress.iter()) {
+            let stroke = egui::Stroke::new(annotation.width * view_state.zoom, annotation.color);
+            let painter = ui.painter();
+            match &annotation.shape {
+                Shape::Arrow { from, to } => {
+                    painter.arrow(to_screen(*from), to_screen(*to) - to_screen(*from), stroke);
+                }
+                Shape::Rect(rect) => {
+                    painter.rect_stroke(
+                        egui::Rect::from_two_pos(to_screen(rect.min), to_screen(rect.max)),
+                        0.0,
+                        stroke,
+                    );
+                }
+                Shape::Freehand(points) => {
+                    painter.add(egui::Shape::line(
+                        points.iter().map(|p| to_screen(*p)).collect(),
+                        stroke,
+                    ));
+                }
+                Shape::Text { pos, text, size } => {
+                    painter.text(
+                        to_screen(*pos),
+                        egui::Align2::LEFT_TOP,
+                        text,
+                        egui::FontId::proportional(size * view_state.zoom),
+                        annotation.color,
+                    );
+                }
+            }
+        }
+
+        // Inline editor for the text tool
+        if let Some((pos, mut text)) = self.pending_text.take() {
+            let mut commit = false;
+            let mut cancel = false;
+            egui::Area::new(egui::Id::new("annotation_text_edit"))
+                .fixed_pos(to_screen(pos))
+                .show(ui.ctx(), |ui| {
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut text)
+                            .hint_text("Text, Enter to place")
+                            .desired_width(200.0),
+                    );
+                    response.request_focus();
+                    commit = ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    cancel = ui.input(|i| i.key_pressed(egui::Key::Escape));
+                });
+
+            if commit && !text.trim().is_empty() {
+                self.push(Annotation {
+                    shape: Shape::Text {
+                        pos,
+                        text,
+                        size: 20.0 / view_state.zoom,
+                    },
+                    color: self.color,
+                    width: self.stroke_width / view_state.zoom,
+                });
+            } else if !commit && !cancel {
+                self.pending_text = Some((pos, text));
+            }
+        }
+    }
+
+    /// Toolbar for picking tools and exporting. Returns the requested export, if any.
+    ///
+    /// Exports redraw the shapes onto the current file alone, so `can_export` is false
+    /// while another image shares the canvas (two-page spreads).
+    pub fn toolbar(&mut self, ctx: &egui::Context, can_export: bool) -> Option<ExportTarget> {
+        if let Some(rx) = &self.export_rx
+            && let Ok(result) = rx.try_recv()
+        {
+            match result {
+                Ok(msg) => osd::toast(ctx, msg),
+                Err(msg) => self.status = Some(msg),
+            }
+            self.export_rx = None;
+        }
+
+        if !self.enabled {
+            return None;
+        }
+
+        let mut export = None;
+        egui::Area::new(egui::Id::new("annotation_toolbar"))
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 10.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.tool, Tool::Arrow, "Arrow");
+                        ui.selectable_value(&mut self.tool, Tool::Rect, "Rect");
+                        ui.selectable_value(&mut self.tool, Tool::Freehand, "Pen");
+                        ui.selectable_value(&mut self.tool, Tool::Text, "Text");
+                        ui.separator();
+                        ui.color_edit_button_srgba(&mut self.color);
+                        ui.add(egui::Slider::new(&mut self.stroke_width, 1.0..=20.0).text("px"));
+                        ui.separator();
+                        if ui.add_enabled(!self.shapes.is_empty(), egui::Button::new("Undo")).clicked() {
+                            self.undo();
+                        }
+                        if ui.add_enabled(!self.redo_stack.is_empty(), egui::Button::new("Redo")).clicked() {
+                            self.redo();
+                        }
+                        ui.separator();
+                        let busy = self.export_rx.is_some();
+                        let hint = if can_export {
+                            "Exporting…"
+                        } else {
+                            "Not available in two-page spread mode"
+                        };
+                        if ui
+                            .add_enabled(can_export && !busy, egui::Button::new("Save PNG…"))
+                            .on_disabled_hover_text(hint)
+                            .clicked()
+                            && let Some(path) = rfd::FileDialog::new()
+                                .add_filter("PNG", &["png"])
+                                .set_file_name("annotated.png")
+                                .save_file()
+                        {
+                            export = Some(ExportTarget::File(path));
+                        }
+                        if ui
+                            .add_enabled(can_export && !busy, egui::Button::new("Copy"))
+                            .on_disabled_hover_text(hint)
+                            .clicked()
+                        {
+                            export = Some(ExportTarget::Clipboard);
+                        }
+                        if busy {
+                            ui.spinner();
+                        } else if let Some(status) = &self.status {
+                            ui.label(status);
+                        }
+                    });
+                });
+            });
+
+        export
+    }
+
+    /// Burns the annotations into a fresh decode of `source` on a worker thread.
+    pub fn export(&mut self, ctx: &egui::Context, source: PathBuf, target: ExportTarget) {
+        let shapes = self.shapes.clone();
+        let ctx = ctx.clone();
+        let (tx, rx) = channel();
+        self.export_rx = Some(rx);
+        self.status = None;
+
+        thread::spawn(move || {
+            let result = decode_image(&source)
+                .map_err(|e| format!("Export failed: {}", e))
+                .and_then(|image| {
+                    let mut image = image.to_rgba8();
+                    rasterize(&mut image, &shapes);
+                    match target {
+                        ExportTarget::File(path) => image
+                            .save(&path)
+                            .map(|_| format!("Saved {}", path.display()))
+                            .map_err(|e| format!("Export failed: {}", e)),
+                        ExportTarget::Clipboard => clipboard::copy_image(&image)
+                            .map(|_| "Copied to clipboard".to_string()),
+                    }
+                });
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+}
+
+fn blend(image: &mut image::RgbaImage, x: i64, y: i64, color: egui::Color32, coverage: f32) {
+    if x < 0 || y < 0 || x >= image.width() as i64 || y >= image.height() as i64 {
+        return;
+    }
+    let a = coverage.clamp(0.0, 1.0) * color.a() as f32 / 255.0;
+    let px = image.get_pixel_mut(x as u32, y as u32);
+    for (c, src) in px.0.iter_mut().zip([color.r(), color.g(), color.b()]) {
+        *c = (*c as f32 * (1.0 - a) + src as f32 * a).round() as u8;
+    }
+    px.0[3] = (px.0[3] as f32 + (255.0 - px.0[3] as f32) * a).round() as u8;
+}
+
+fn draw_disc(image: &mut image::RgbaImage, center: egui::Pos2, radius: f32, color: egui::Color32) {
+    let r = radius.max(0.5);
+    for y in (center.y - r).floor() as i64..=(center.y + r).ceil() as i64 {
+        for x in (center.x - r).floor() as i64..=(center.x + r).ceil() as i64 {
+            let d = egui::pos2(x as f32 + 0.5, y as f32 + 0.5).distance(center);
+            // One pixel of anti-aliasing at the edge
+            let coverage = r + 0.5 - d;
+            if coverage > 0.0 {
+                blend(image, x, y, color, coverage);
+            }
+        }
+    }
+}
+
+fn draw_line(image: &mut image::RgbaImage, a: egui::Pos2, b: egui::Pos2, width: f32, color: egui::Color32) {
+    // Coverage from the distance to the segment, so wide strokes never overdraw themselves
+    let r = (width / 2.0).max(0.5);
+    let min = a.min(b) - egui::Vec2::splat(r + 1.0);
+    let max = a.max(b) + egui::Vec2::splat(r + 1.0);
+    let ab = b - a;
+    let len_sq = ab.length_sq().max(f32::EPSILON);
+    for y in min.y.floor() as i64..=max.y.ceil() as i64 {
+        for x in min.x.floor() as i64..=max.x.ceil() as i64 {
+            let p = egui::pos2(x as f32 + 0.5, y as f32 + 0.5);
+            let t = ((p - a).dot(ab) / len_sq).clamp(0.0, 1.0);
+            let d = p.distance(a + ab * t);
+            let coverage = r + 0.5 - d;
+            if coverage > 0.0 {
+                blend(image, x, y, color, coverage);
+            }
+        }
+    }
+}
+
+/// Draws `shapes` into `image` in software, matching what is shown on screen.
+pub fn rasterize(image: &mut image::RgbaImage, shapes: &[Annotation]) {
+    for annotation in shapes {
+        let (color, width) = (annotation.color, annotation.width);
+        match &annotation.shape {
+            Shape::Arrow { from, to } => {
+                draw_line(image, *from, *to, width, color);
+                // Same head proportions as egui's Painter::arrow
+                let vec = *to - *from;
+                let rot = egui::emath::Rot2::from_angle(std::f32::consts::TAU / 10.0);
+                let tip_length = vec.length() / 4.0;
+                let dir = vec.normalized();
+                draw_line(image, *to, *to - tip_length * (rot * dir), width, color);
+                draw_line(image, *to, *to - tip_length * (rot.inverse() * dir), width, color);
+            }
+            Shape::Rect(rect) => {
+                let corners = [rect.left_top(), rect.right_top(), rect.right_bottom(), rect.left_bottom()];
+                for i in 0..4 {
+                    draw_line(image, corners[i], corners[(i + 1) % 4], width, color);
+                }
+            }
+            Shape::Freehand(points) => {
+                if let [single] = points.as_slice() {
+                    draw_disc(image, *single, width / 2.0, color);
+                }
+                for pair in points.windows(2) {
+                    draw_line(image, pair[0], pair[1], width, color);
+                }
+            }
+            Shape::Text { pos, text, size } => draw_text(image, *pos, text, *size, color),
+        }
+    }
+}
+
+fn draw_text(image: &mut image::RgbaImage, pos: egui::Pos2, text: &str, size: f32, color: egui::Color32) {
+    use ab_glyph::{Font, FontRef, ScaleFont};
+
+    // Use the same default font egui renders the on-screen preview with
+    let fonts = egui::FontDefinitions::default();
+    let Some(data) = fonts.font_data.get("Ubuntu-Light") else {
+        return;
+    };
+    let Ok(font) = FontRef::try_from_slice(&data.font) else {
+        return;
+    };
+    let scaled = font.as_scaled(size);
+
+    let mut caret = egui::pos2(pos.x, pos.y + scaled.ascent());
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(prev) = previous {
+            caret.x += scaled.kern(prev, id);
+        }
+        let glyph = id.with_scale_and_position(size, ab_glyph::point(caret.x, caret.y));
+        caret.x += scaled.h_advance(id);
+        previous = Some(id);
+
+        if let Some(outlined) = font.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+            outlined.draw(|x, y, coverage| {
+                blend(
+                    image,
+                    bounds.min.x as i64 + x as i64,
+                    bounds.min.y as i64 + y as i64,
+                    color,
+                    coverage,
+                );
+            });
+        }
+    }
+}
@@ -1,514 +1,3213 @@
-use eframe::egui;
-use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
-
-use crate::config::AppConfig;
-use crate::image_loader::{ImageCommand, ImageLoader, ImageResult};
-use crate::view_state::ViewState;
-use crate::thumbnail_list::{ThumbnailList, ThumbnailAction};
-
-pub struct ImageViewer {
-    // Communication
-    loader: ImageLoader,
-
-    // Image State
-    error_msg: Option<String>,
-
-    // View State
-    view_state: ViewState,
-    
-    // Thumbnail List Component
-    thumbnail_list: ThumbnailList,
-
-    // Debug info
-    last_loaded_path: Option<String>,
-    image_size: Option<[usize; 2]>,
-    show_debug_info: bool,
-
-    // Folder State
-    current_folder_images: Vec<PathBuf>,
-    current_image_index: usize,
-
-    // Config
-    config: AppConfig,
-
-    // Caching and Preloading
-    current_image_path: Option<PathBuf>,
-    texture_cache: HashMap<PathBuf, egui::TextureHandle>,
-    loading_paths: HashSet<PathBuf>,
-    reset_view_on_load: bool,
-
-    first_frame: bool,
-}
-
-fn setup_custom_fonts(ctx: &egui::Context) {
-    let mut fonts = egui::FontDefinitions::default();
-    
-    let font_paths = [
-        "C:\\Windows\\Fonts\\msyh.ttc",   // Microsoft YaHei
-        "C:\\Windows\\Fonts\\msyh.ttf",
-        "C:\\Windows\\Fonts\\simhei.ttf", // SimHei
-        "C:\\Windows\\Fonts\\simsun.ttc", // SimSun
-    ];
-    
-    let mut font_data = None;
-    for path in font_paths.iter() {
-        if let Ok(data) = std::fs::read(path) {
-            font_data = Some(data);
-            break;
-        }
-    }
-    
-    if let Some(data) = font_data {
-        fonts.font_data.insert(
-            "cjk".to_owned(),
-            egui::FontData::from_owned(data),
-        );
-        
-        fonts.families.get_mut(&egui::FontFamily::Proportional).unwrap().insert(0, "cjk".to_owned());
-        fonts.families.get_mut(&egui::FontFamily::Monospace).unwrap().push("cjk".to_owned());
-    }
-    
-    ctx.set_fonts(fonts);
-}
-
-impl ImageViewer {
-    pub fn new(
-        cc: &eframe::CreationContext<'_>,
-        config: AppConfig,
-        initial_path: Option<PathBuf>,
-    ) -> Self {
-        setup_custom_fonts(&cc.egui_ctx);
-        
-        let mut viewer = Self {
-            loader: ImageLoader::new(cc.egui_ctx.clone()),
-            error_msg: None,
-            view_state: ViewState::default(),
-            thumbnail_list: ThumbnailList::new(&cc.egui_ctx),
-            last_loaded_path: None,
-            image_size: None,
-            show_debug_info: false,
-            current_folder_images: Vec::new(),
-            current_image_index: 0,
-            config,
-            current_image_path: None,
-            texture_cache: HashMap::new(),
-            loading_paths: HashSet::new(),
-            reset_view_on_load: true,
-            first_frame: true,
-        };
-
-        if let Some(path) = initial_path {
-            viewer.load_path(path);
-        }
-
-        viewer
-    }
-
-    fn is_loading(&self) -> bool {
-        if let Some(path) = &self.current_image_path {
-            self.loading_paths.contains(path)
-        } else {
-            false
-        }
-    }
-
-    fn load_file(&mut self, path: PathBuf, reset_view: bool) {
-        self.current_image_path = Some(path.clone());
-        self.reset_view_on_load = reset_view;
-        self.error_msg = None;
-
-        self.request_load(path);
-        self.update_preloads();
-        
-        self.thumbnail_list.update_folder(&self.current_folder_images, self.current_image_index);
-    }
-
-    fn request_load(&mut self, path: PathBuf) {
-        if !self.texture_cache.contains_key(&path) && !self.loading_paths.contains(&path) {
-            println!("UI: Requesting load for {:?}", path);
-            self.loading_paths.insert(path.clone());
-            self.loader.tx.send(ImageCommand::Load(path)).unwrap();
-        }
-    }
-
-    fn update_preloads(&mut self) {
-        if self.current_folder_images.is_empty() {
-            return;
-        }
-
-        let len = self.current_folder_images.len();
-        if len == 0 {
-            return;
-        }
-
-        let prev_idx = if self.current_image_index == 0 {
-            len - 1
-        } else {
-            self.current_image_index - 1
-        };
-        let next_idx = (self.current_image_index + 1) % len;
-
-        let prev_path = self.current_folder_images[prev_idx].clone();
-        let next_path = self.current_folder_images[next_idx].clone();
-
-        self.request_load(prev_path.clone());
-        self.request_load(next_path.clone());
-
-        // Cleanup cache: keep only current, prev, next
-        let mut keep_paths = HashSet::new();
-        if let Some(curr) = &self.current_image_path {
-            keep_paths.insert(curr.clone());
-        }
-        keep_paths.insert(prev_path);
-        keep_paths.insert(next_path);
-
-        self.texture_cache.retain(|k, _| keep_paths.contains(k));
-    }
-
-    fn load_path(&mut self, path: PathBuf) {
-        if path.is_dir() {
-            self.load_folder_contents(&path);
-            if self.current_folder_images.is_empty() {
-                self.error_msg = Some("No images found in the folder.".to_string());
-                self.current_image_path = None;
-            } else {
-                self.current_image_index = 0;
-                self.load_file(self.current_folder_images[0].clone(), true);
-            }
-        } else {
-            if let Some(parent) = path.parent() {
-                self.load_folder_contents(parent);
-                if let Some(idx) = self.current_folder_images.iter().position(|p| p == &path) {
-                    self.current_image_index = idx;
-                } else {
-                    self.current_folder_images = vec![path.clone()];
-                    self.current_image_index = 0;
-                }
-            } else {
-                self.current_folder_images = vec![path.clone()];
-                self.current_image_index = 0;
-            }
-            self.load_file(path, true);
-        }
-    }
-
-    fn load_folder_contents(&mut self, folder_path: &std::path::Path) {
-        let mut images = Vec::new();
-        if let Ok(entries) = std::fs::read_dir(folder_path) {
-            for entry in entries.flatten() {
-                let p = entry.path();
-                if p.is_file() {
-                    if let Some(ext) = p.extension().and_then(|e| e.to_str()) {
-                        let ext = ext.to_lowercase();
-                        if matches!(
-                            ext.as_str(),
-                            "jpg"
-                                | "jpeg"
-                                | "png"
-                                | "gif"
-                                | "webp"
-                                | "bmp"
-                                | "ico"
-                                | "tiff"
-                                | "avif"
-                        ) {
-                            images.push(p);
-                        }
-                    }
-                }
-            }
-        }
-        // Sort alphabetically
-        images.sort();
-        self.current_folder_images = images;
-    }
-
-    fn next_image(&mut self) {
-        if self.current_folder_images.is_empty() {
-            return;
-        }
-        self.current_image_index =
-            (self.current_image_index + 1) % self.current_folder_images.len();
-        self.load_file(
-            self.current_folder_images[self.current_image_index].clone(),
-            false,
-        );
-    }
-
-    fn prev_image(&mut self) {
-        if self.current_folder_images.is_empty() {
-            return;
-        }
-        if self.current_image_index == 0 {
-            self.current_image_index = self.current_folder_images.len() - 1;
-        } else {
-            self.current_image_index -= 1;
-        }
-        self.load_file(
-            self.current_folder_images[self.current_image_index].clone(),
-            false,
-        );
-    }
-}
-
-impl eframe::App for ImageViewer {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if self.first_frame {
-            self.first_frame = false;
-            // Delay maximization to the first frame.
-            // This prevents winit bugs on Windows where setting size/position
-            // inside the builder conflicts with the maximized state.
-            if self.config.is_maximized == Some(true) {
-                ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(true));
-            }
-        }
-
-        // Process Thumbnail Loading
-        self.thumbnail_list.process_results(ctx, &self.current_folder_images, self.current_image_index);
-
-        // 1. Handle Async Results
-        while let Ok(result) = self.loader.rx.try_recv() {
-            match result {
-                ImageResult::Success(path, image) => {
-                    println!("UI: Received texture for {:?}", path);
-                    self.loading_paths.remove(&path);
-
-                    let texture = ctx.load_texture(
-                        path.to_string_lossy().to_string(),
-                        image.clone(),
-                        egui::TextureOptions::LINEAR,
-                    );
-                    self.texture_cache.insert(path.clone(), texture);
-
-                    if Some(path.clone()) == self.current_image_path {
-                        self.last_loaded_path = Some(path.to_string_lossy().to_string());
-                        self.image_size = Some(image.size);
-                        if self.reset_view_on_load {
-                            self.view_state.reset();
-                            self.reset_view_on_load = false;
-                        }
-                    }
-                }
-                ImageResult::Error(path, err) => {
-                    println!("UI: Received Error for {:?}: {}", path, err);
-                    self.loading_paths.remove(&path);
-                    if Some(path) == self.current_image_path {
-                        self.error_msg = Some(err);
-                    }
-                }
-            }
-        }
-
-        // 2. Handle File Drops
-        if !ctx.input(|i| i.raw.dropped_files.is_empty()) {
-            let dropped_files = ctx.input(|i| i.raw.dropped_files.clone());
-            if let Some(file) = dropped_files.first() {
-                // Check if the path is provided (it might not be on web, but this is native)
-                if let Some(path) = &file.path {
-                    self.load_path(path.clone());
-                }
-            }
-        }
-
-        // Handle Keyboard Navigation
-        if ctx.input(|i| i.key_pressed(egui::Key::F1)) {
-            self.show_debug_info = !self.show_debug_info;
-        }
-
-        if !self.current_folder_images.is_empty() {
-            // egui's key_pressed returns true repeatedly if the key is held down (key repeat).
-            // To only trigger once per physical press, we can check if it was pressed this frame
-            // but we actually want to avoid the OS key repeat.
-            // A simpler way in egui is to check `key_pressed` but we need to track if it was already handled.
-            // Actually, `key_pressed` in egui *does* repeat.
-            // To avoid repeat, we can track the previous frame's key state, or use `key_released`.
-            // Let's use `key_released` for single action per press.
-            if ctx.input(|i| i.key_released(egui::Key::ArrowRight) || i.key_released(egui::Key::D))
-            {
-                self.next_image();
-            } else if ctx
-                .input(|i| i.key_released(egui::Key::ArrowLeft) || i.key_released(egui::Key::A))
-            {
-                self.prev_image();
-            }
-        }
-
-        // Update Window Title based on current image
-        if let Some(path) = &self.current_image_path {
-            if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                ctx.send_viewport_cmd(egui::ViewportCommand::Title(format!(
-                    "{} - Better Image Viewer",
-                    file_name
-                )));
-            }
-        } else {
-            ctx.send_viewport_cmd(egui::ViewportCommand::Title(
-                "Better Image Viewer".to_string(),
-            ));
-        }
-
-        // 3. UI Layout
-        egui::CentralPanel::default().show(ctx, |ui| {
-            if self.is_loading() {
-                ui.centered_and_justified(|ui| ui.spinner());
-                // IMPORTANT: Do NOT return here if you want debug overlays or other persistent UI
-                // But generally for a modal loading screen, returning is fine,
-                // provided we are sure is_loading will flip back.
-                return;
-            }
-
-            if let Some(err) = &self.error_msg {
-                ui.centered_and_justified(|ui| ui.heading(format!("Error: {}", err)));
-                return;
-            }
-
-            let current_texture = self
-                .current_image_path
-                .as_ref()
-                .and_then(|p| self.texture_cache.get(p));
-
-            if let Some(texture) = current_texture {
-                let texture_size = texture.size_vec2();
-                // let available_size = ui.available_size(); // unused
-
-                // 4. Zoom & Pan Logic
-                self.view_state.process_input(ui);
-
-                // 5. Drawing
-                let center_pos = ui.clip_rect().center() + self.view_state.pan;
-                let final_size = texture_size * self.view_state.zoom;
-                let image_rect = egui::Rect::from_center_size(center_pos, final_size);
-
-                ui.painter().image(
-                    texture.id(),
-                    image_rect,
-                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
-                    egui::Color32::WHITE,
-                );
-
-                // Debug overlay
-                if self.show_debug_info {
-                    ui.scope(|ui| {
-                        let debug_text = format!(
-                            "Zoom: {:.2}x\nPan: {:.0}, {:.0}\nSize: {}x{}",
-                            self.view_state.zoom,
-                            self.view_state.pan.x,
-                            self.view_state.pan.y,
-                            texture_size.x,
-                            texture_size.y
-                        );
-
-                        let pos = ui.clip_rect().min + egui::vec2(10.0, 10.0);
-                        let font_id = egui::FontId::monospace(14.0);
-
-                        // Draw text shadow/outline for better visibility
-                        let offsets = [
-                            egui::vec2(-1.0, -1.0),
-                            egui::vec2(0.0, -1.0),
-                            egui::vec2(1.0, -1.0),
-                            egui::vec2(-1.0, 0.0),
-                            egui::vec2(1.0, 0.0),
-                            egui::vec2(-1.0, 1.0),
-                            egui::vec2(0.0, 1.0),
-                            egui::vec2(1.0, 1.0),
-                        ];
-
-                        for offset in offsets {
-                            ui.painter().text(
-                                pos + offset,
-                                egui::Align2::LEFT_TOP,
-                                &debug_text,
-                                font_id.clone(),
-                                egui::Color32::BLACK,
-                            );
-                        }
-
-                        // Draw main text
-                        ui.painter().text(
-                            pos,
-                            egui::Align2::LEFT_TOP,
-                            debug_text,
-                            font_id,
-                            egui::Color32::YELLOW,
-                        );
-                    });
-                }
-            } else {
-                ui.centered_and_justified(|ui| {
-                    if ui.button("Open Image or Folder").clicked() {
-                        if let Some(path) = rfd::FileDialog::new().pick_file() {
-                            self.load_path(path);
-                        }
-                    }
-                });
-
-                // Show drop text
-                let text_color = if !ctx.input(|i| i.raw.dropped_files.is_empty()) {
-                    egui::Color32::LIGHT_BLUE
-                } else {
-                    egui::Color32::GRAY
-                };
-
-                egui::Area::new(egui::Id::new("drop_text_area"))
-                    .order(egui::Order::Background)
-                    .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 40.0))
-                    .show(ctx, |ui| {
-                        ui.colored_label(text_color, "Drag & Drop an image or folder here");
-                    });
-            }
-
-            // Thumbnail List
-            if !self.current_folder_images.is_empty() {
-                let action = self.thumbnail_list.display(ui, &self.current_folder_images, self.current_image_index);
-                if let ThumbnailAction::SelectImage(idx) = action {
-                    self.current_image_index = idx;
-                    self.load_file(self.current_folder_images[self.current_image_index].clone(), false);
-                }
-            }
-        });
-
-        // Save window state periodically or on close
-        let window_info = ctx.input(|i| i.viewport().clone());
-        let mut changed = false;
-
-        let is_maximized = window_info.maximized.unwrap_or(false);
-        let is_minimized = window_info.minimized.unwrap_or(false);
-
-        // Only save position and size if not maximized and not minimized
-        if !is_maximized && !is_minimized {
-            // Usually it's better to save the outer position, but inner_rect is what eframe provides readily
-            if let Some(pos) = window_info.inner_rect.map(|r| r.min) {
-                // Ignore suspicious (0,0) or negative positions which might occur during window creation/snapping
-                if pos.x > -10000.0 && pos.y > -10000.0 {
-                    let new_pos = [pos.x, pos.y];
-                    if self.config.window_pos != Some(new_pos) {
-                        self.config.window_pos = Some(new_pos);
-                        changed = true;
-                    }
-                }
-            }
-
-            if let Some(size) = window_info.inner_rect.map(|r| r.size()) {
-                let new_size = [size.x, size.y];
-                if self.config.window_size != Some(new_size) {
-                    self.config.window_size = Some(new_size);
-                    changed = true;
-                }
-            }
-        }
-
-        if let Some(is_max) = window_info.maximized {
-            if self.config.is_maximized != Some(is_max) {
-                self.config.is_maximized = Some(is_max);
-                changed = true;
-            }
-        }
-
-        // In a real app, you might want to debounce this save operation
-        // or only save on exit. For simplicity, we save when it changes.
-        if changed {
-            self.config.save();
-        }
-    }
-}
+use eframe::egui;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+use crate::animated_export::AnimatedExport;
+use crate::animation::exp_decay;
+use crate::annotations::{Annotations, ExportTarget};
+use crate::batch::{BatchAction, BatchWindow};
+use crate::clipboard_watch::ClipboardWatcher;
+use crate::app_data;
+use crate::config::{AppConfig, ConfigWatcher, SavedView, ViewMemory};
+#[cfg(feature = "cutout")]
+use crate::cutout::Cutout;
+use crate::diagnostics::{self, Diagnostics};
+use crate::false_color::Colormap;
+use crate::drop_import::{self, Downloader, Dropped};
+use crate::duplicates::{DuplicateAction, DuplicateFinder};
+use crate::enhance::Enhance;
+use crate::external_tools::{self, ToolAction, ToolsMenu};
+use crate::eyedropper::{Eyedropper, EyedropperAction};
+use crate::filter::{FilterAction, FilterBar};
+use crate::folder_compare::FolderCompare;
+use crate::folder_nav;
+use crate::folder_scan::{self, FolderScan, ScanEvent, ScanOptions};
+use crate::folder_tree::FolderTree;
+use crate::format_info::FormatInfo;
+use crate::folder_watch::FolderWatch;
+use crate::image_loader::{
+    ImageCommand, ImageLoader, ImageResult, is_cloud_placeholder, is_supported_image,
+};
+use crate::loupe::Loupe;
+use crate::measure::Measure;
+use crate::kiosk::Kiosk;
+use crate::library::{Library, LibraryAction, LibraryWindow};
+use crate::search::{self, SearchAction, SearchWindow};
+use crate::metadata::{self, CleanCopy, MetadataPanel};
+use crate::monitor;
+use crate::perceptual_hash::SimilaritySort;
+#[cfg(feature = "pdf")]
+use crate::pdf::{self, PdfView};
+use crate::playlist;
+use crate::presentation::{Presentation, PresentationAction, Slide};
+#[cfg(feature = "ocr")]
+use crate::ocr::Ocr;
+use crate::osd;
+use crate::redaction::Redaction;
+use crate::remote::{RemoteCommand, RemoteOptions, RemoteServer};
+use crate::rename::{RenameAction, RenameWindow};
+use crate::selection::Selection;
+use crate::sequence::{self, SequencePlayer};
+use crate::sharpness::{self, SharpnessScores};
+use crate::slideshow::Slideshow;
+use crate::start_screen::{StartAction, StartScreen};
+use crate::texture_pool::TexturePool;
+use crate::timeline::{self, CaptureDateSort};
+#[cfg(feature = "screenshot")]
+use crate::screen_capture::{self, ScreenCapture};
+use crate::straighten::Straighten;
+use crate::strip_view::{StripAction, StripView};
+use crate::tags::{TagAction, TagPanel, TagStore};
+use crate::view_state::{FitMode, ViewState};
+use crate::viewport_export::ViewportExport;
+use crate::thumbnail_list::{ThumbnailList, ThumbnailAction};
+use crate::upscale::{UpscaleAction, Upscaler};
+use crate::video;
+use crate::zoom_indicator::{self, ZoomIndicator, ZoomPreset};
+#[cfg(all(windows, feature = "taskbar"))]
+use crate::taskbar::Taskbar;
+#[cfg(feature = "tray")]
+use crate::tray::{Tray, TrayCommand};
+
+// In fullscreen and slideshows, the cursor and overlays hide after the mouse rests this long
+const IDLE_HIDE: Duration = Duration::from_secs(3);
+// How long the announcement at the end of a folder waits for the confirming press
+const FOLDER_CONFIRM: Duration = Duration::from_secs(3);
+// How far below the top of the window a point surely lies in its title bar
+const TITLE_BAR_PROBE: f32 = 8.0;
+// Room left for a taskbar before the work area has been seen (see `work_size`)
+const TASKBAR_RESERVE: f32 = 48.0;
+const MIN_WINDOW: egui::Vec2 = egui::vec2(200.0, 150.0);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SortOrder {
+    Name,
+    // Oldest shot first (EXIF capture date), grouped into shooting sessions
+    CaptureDate,
+    // Visually related images cluster together (perceptual hash walk)
+    Similarity,
+}
+
+pub struct ImageViewer {
+    // Communication
+    loader: ImageLoader,
+    // Kept for background jobs started outside of `update`
+    egui_ctx: egui::Context,
+
+    // Image State
+    error_msg: Option<String>,
+    // Window focus last frame; the current file is re-checked when focus comes back
+    window_focused: bool,
+    // Files that failed to decode this session, and the direction of the last
+    // next/previous step (0 for any other kind of load) so failures can be skipped
+    failed_paths: HashSet<PathBuf>,
+    nav_step: isize,
+
+    // View State
+    view_state: ViewState,
+    loupe: Loupe,
+    measure: Measure,
+    annotations: Annotations,
+    redaction: Redaction,
+    straighten: Straighten,
+    #[cfg(feature = "ocr")]
+    ocr: Ocr,
+    #[cfg(feature = "cutout")]
+    cutout: Cutout,
+    duplicates: DuplicateFinder,
+    folder_compare: FolderCompare,
+    animated_export: AnimatedExport,
+    sequence_player: SequencePlayer,
+    // Frame count of the numbered sequence the current image belongs to (cached per image)
+    sequence_hint: Option<(PathBuf, usize)>,
+    slideshow: Slideshow,
+    // Shown while no image is open
+    start_screen: StartScreen,
+    // Slideshow shown fullscreen on another monitor, steered from this window
+    presentation: Presentation,
+    batch: BatchWindow,
+    rename: RenameWindow,
+    tools_menu: ToolsMenu,
+    upscaler: Upscaler,
+    enhance: Enhance,
+    eyedropper: Eyedropper,
+    metadata_panel: MetadataPanel,
+    tags: TagStore,
+    // Set with --kiosk: no shortcuts, overlays or file changes, always a fullscreen slideshow
+    kiosk: Option<Kiosk>,
+    library: Library,
+    library_window: LibraryWindow,
+    search: SearchWindow,
+    tag_panel: TagPanel,
+    clean_copy: CleanCopy,
+    clipboard_watcher: ClipboardWatcher,
+    downloader: Downloader,
+    remote: Option<RemoteServer>,
+    #[cfg(feature = "tray")]
+    tray: Option<Tray>,
+    #[cfg(all(windows, feature = "taskbar"))]
+    taskbar: Taskbar,
+    // Set by the tray's "Quit" so the close isn't turned into a minimize
+    #[cfg(feature = "tray")]
+    quitting: bool,
+    #[cfg(feature = "screenshot")]
+    screen_capture: Option<ScreenCapture>,
+    viewport_export: ViewportExport,
+    
+    // Thumbnail List Component
+    thumbnail_list: ThumbnailList,
+    sharpness: SharpnessScores,
+    // Multi-selection made in the filmstrip, used by batch actions and animations
+    selection: Selection,
+
+    // Continuous vertical strip reading mode
+    strip_view: StripView,
+    strip_mode: bool,
+
+    // Two-page book spread, optionally right-to-left for manga
+    spread_mode: bool,
+    spread_rtl: bool,
+
+    // Debug info
+    last_loaded_path: Option<String>,
+    image_size: Option<[usize; 2]>,
+    show_debug_info: bool,
+    diagnostics: Diagnostics,
+
+    // Images decoded with more than 8 bits per channel, and the exposure (in stops)
+    // used when mapping them to the display
+    high_bit_depth: HashSet<PathBuf>,
+    // Bit depth, color model and encoding of files read this session
+    format_info: HashMap<PathBuf, FormatInfo>,
+    // Full size of images shown scaled down to fit the GPU's texture limit
+    reduced: HashMap<PathBuf, [usize; 2]>,
+    // Damaged files shown as far as they could be decoded
+    corrupt_paths: HashSet<PathBuf>,
+    // Page and render scale of PDFs, the re-render last asked for and the zoom
+    // correction to apply once it arrives
+    #[cfg(feature = "pdf")]
+    pdf_views: HashMap<PathBuf, PdfView>,
+    #[cfg(feature = "pdf")]
+    pdf_rerender: Option<(PathBuf, f32)>,
+    #[cfg(feature = "pdf")]
+    pdf_rescale: Option<(PathBuf, f32)>,
+    exposure: f32,
+    false_color: Option<Colormap>,
+
+    // Folder State
+    current_folder_images: Vec<PathBuf>,
+    // Listing of the opened folder, still streaming in
+    folder_scan: Option<FolderScan>,
+    // Folder the navigation list came from; None for playlists and other lists
+    current_folder: Option<PathBuf>,
+    // Relists `current_folder` during slideshows
+    folder_watch: Option<FolderWatch>,
+    // Neighbouring folder announced at the end of this one, opened on a second press
+    folder_end: Option<(PathBuf, Instant)>,
+    folder_tree: FolderTree,
+    // Start on the last image once the folder being scanned is listed
+    open_at_end: bool,
+    // The whole folder while the filter bar narrows `current_folder_images`
+    unfiltered_images: Option<Vec<PathBuf>>,
+    filter_bar: FilterBar,
+    current_image_index: usize,
+    sort_order: SortOrder,
+    similarity_sort: Option<SimilaritySort>,
+    capture_sort: Option<CaptureDateSort>,
+    // Capture times (seconds, camera local time) read for the capture date order
+    capture_times: HashMap<PathBuf, i64>,
+
+    // Config
+    config: AppConfig,
+    config_watcher: ConfigWatcher,
+
+    // Caching and Preloading
+    current_image_path: Option<PathBuf>,
+    texture_cache: HashMap<PathBuf, egui::TextureHandle>,
+    texture_pool: TexturePool,
+    // Image preloaded for the filmstrip thumbnail under the pointer
+    hover_prefetch: Option<PathBuf>,
+    loading_paths: HashSet<PathBuf>,
+    // Loads the worker reported as slow (network shares), which can be cancelled
+    slow_paths: HashSet<PathBuf>,
+    reset_view_on_load: bool,
+    // Numbered zoom/pan bookmarks inside each image (Ctrl+1..9 to set, 1..9 to recall)
+    view_bookmarks: HashMap<PathBuf, [Option<(f32, egui::Vec2)>; 9]>,
+    // Zoom and pan of images visited this session (see `ViewMemory`)
+    session_views: HashMap<PathBuf, (f32, egui::Vec2)>,
+    // Fit the current image to the window on the next frame
+    fit_requested: bool,
+
+    zoom_indicator: ZoomIndicator,
+    // Monitor size and the rect a maximized window last covered on it
+    maximized_area: Option<(egui::Vec2, egui::Rect)>,
+
+    // Last mouse movement or click, and the opacity overlays are faded to when idle
+    last_activity: Instant,
+    ui_opacity: f32,
+
+    first_frame: bool,
+    // Embedded thumbnail of the image opened at launch, shown until it's decoded
+    startup_preview: Option<Receiver<egui::ColorImage>>,
+    preview: Option<(PathBuf, egui::TextureHandle)>,
+    // The library is indexed once the image and folder opened at launch are in
+    index_after_startup: bool,
+}
+
+fn setup_custom_fonts(ctx: &egui::Context) {
+    let mut fonts = egui::FontDefinitions::default();
+    
+    let font_paths = [
+        "C:\\Windows\\Fonts\\msyh.ttc",   // Microsoft YaHei
+        "C:\\Windows\\Fonts\\msyh.ttf",
+        "C:\\Windows\\Fonts\\simhei.ttf", // SimHei
+        "C:\\Windows\\Fonts\\simsun.ttc", // SimSun
+    ];
+    
+    let mut font_data = None;
+    for path in font_paths.iter() {
+        if let Ok(data) = std::fs::read(path) {
+            font_data = Some(data);
+            break;
+        }
+    }
+    
+    if let Some(data) = font_data {
+        fonts.font_data.insert(
+            "cjk".to_owned(),
+            egui::FontData::from_owned(data),
+        );
+        
+        fonts.families.get_mut(&egui::FontFamily::Proportional).unwrap().insert(0, "cjk".to_owned());
+        fonts.families.get_mut(&egui::FontFamily::Monospace).unwrap().push("cjk".to_owned());
+    }
+    
+    ctx.set_fonts(fonts);
+}
+
+impl ImageViewer {
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        config: AppConfig,
+        initial_path: Option<PathBuf>,
+        remote: RemoteOptions,
+        kiosk: bool,
+    ) -> Self {
+        setup_custom_fonts(&cc.egui_ctx);
+
+        #[cfg(feature = "tray")]
+        let tray = Tray::new(&cc.egui_ctx, &config.recent_files)
+            .map_err(|e| eprintln!("Tray icon unavailable: {}", e))
+            .ok();
+        
+        let loader = ImageLoader::new(cc.egui_ctx.clone());
+        let mut viewer = Self {
+            thumbnail_list: ThumbnailList::new(&cc.egui_ctx, loader.decoded()),
+            loader,
+            egui_ctx: cc.egui_ctx.clone(),
+            error_msg: None,
+            window_focused: true,
+            failed_paths: HashSet::new(),
+            nav_step: 0,
+            view_state: ViewState::default(),
+            loupe: Loupe::default(),
+            measure: Measure::default(),
+            annotations: Annotations::default(),
+            redaction: Redaction::new(&cc.egui_ctx),
+            straighten: Straighten::default(),
+            #[cfg(feature = "ocr")]
+            ocr: Ocr::default(),
+            #[cfg(feature = "cutout")]
+            cutout: Cutout::default(),
+            duplicates: DuplicateFinder::default(),
+            folder_compare: FolderCompare::default(),
+            animated_export: AnimatedExport::default(),
+            sequence_player: SequencePlayer::default(),
+            slideshow: Slideshow::default(),
+            start_screen: StartScreen::default(),
+            presentation: Presentation::default(),
+            sequence_hint: None,
+            batch: BatchWindow::default(),
+            rename: RenameWindow::default(),
+            tools_menu: ToolsMenu::default(),
+            upscaler: Upscaler::default(),
+            enhance: Enhance::default(),
+            eyedropper: Eyedropper::default(),
+            metadata_panel: MetadataPanel::default(),
+            tags: TagStore::load(),
+            kiosk: kiosk.then(|| Kiosk::new(&config.kiosk_exit)),
+            library: Library::load(),
+            library_window: LibraryWindow::default(),
+            search: SearchWindow::default(),
+            tag_panel: TagPanel::default(),
+            clean_copy: CleanCopy::default(),
+            clipboard_watcher: ClipboardWatcher::default(),
+            downloader: Downloader::default(),
+            remote: (remote.enabled || remote.stdin)
+                .then(|| RemoteServer::start(&cc.egui_ctx, remote))
+                .and_then(|r| r.map_err(|e| eprintln!("Remote control unavailable: {}", e)).ok()),
+            #[cfg(feature = "tray")]
+            tray,
+            #[cfg(all(windows, feature = "taskbar"))]
+            taskbar: Taskbar::new(cc, &config.recent_files),
+            #[cfg(feature = "tray")]
+            quitting: false,
+            #[cfg(feature = "screenshot")]
+            screen_capture: ScreenCapture::new(&cc.egui_ctx)
+                .map_err(|e| eprintln!("Screenshot hotkey unavailable: {}", e))
+                .ok(),
+            viewport_export: ViewportExport::default(),
+            sharpness: SharpnessScores::new(&cc.egui_ctx),
+            selection: Selection::default(),
+            strip_view: StripView::new(&cc.egui_ctx),
+            strip_mode: false,
+            spread_mode: false,
+            spread_rtl: false,
+            last_loaded_path: None,
+            image_size: None,
+            show_debug_info: false,
+            diagnostics: Diagnostics::default(),
+            high_bit_depth: HashSet::new(),
+            format_info: HashMap::new(),
+            reduced: HashMap::new(),
+            corrupt_paths: HashSet::new(),
+            #[cfg(feature = "pdf")]
+            pdf_views: HashMap::new(),
+            #[cfg(feature = "pdf")]
+            pdf_rerender: None,
+            #[cfg(feature = "pdf")]
+            pdf_rescale: None,
+            exposure: 0.0,
+            false_color: None,
+            current_folder_images: Vec::new(),
+            folder_scan: None,
+            current_folder: None,
+            folder_watch: None,
+            folder_end: None,
+            folder_tree: FolderTree::default(),
+            open_at_end: false,
+            unfiltered_images: None,
+            filter_bar: FilterBar::default(),
+            current_image_index: 0,
+            sort_order: SortOrder::Name,
+            similarity_sort: None,
+            capture_sort: None,
+            capture_times: HashMap::new(),
+            config_watcher: ConfigWatcher::new(&cc.egui_ctx, &config),
+            config,
+            current_image_path: None,
+            texture_cache: HashMap::new(),
+            texture_pool: TexturePool::default(),
+            hover_prefetch: None,
+            loading_paths: HashSet::new(),
+            slow_paths: HashSet::new(),
+            reset_view_on_load: true,
+            session_views: HashMap::new(),
+            view_bookmarks: HashMap::new(),
+            fit_requested: false,
+            zoom_indicator: ZoomIndicator::default(),
+            maximized_area: None,
+            last_activity: Instant::now(),
+            ui_opacity: 1.0,
+            first_frame: true,
+            startup_preview: None,
+            preview: None,
+            index_after_startup: false,
+        };
+
+        let profile = viewer.config.display_profile;
+        viewer.set_render_option(ImageCommand::SetDisplayProfile(profile));
+        viewer.thumbnail_list.set_dock(viewer.config.filmstrip_dock);
+        viewer.view_state.set_zoom_settings(viewer.config.zoom);
+        viewer.view_state.pan_scheme = viewer.config.pan_scheme;
+        viewer.eyedropper.size = viewer.config.eyedropper_sample;
+        viewer.index_after_startup = !viewer.config.library_roots.is_empty();
+
+        if let Some(path) = initial_path {
+            // Read on the side, so the window doesn't wait for it either
+            if path.is_file() {
+                let (tx, rx) = std::sync::mpsc::channel();
+                let ctx = cc.egui_ctx.clone();
+                let preview_path = path.clone();
+                std::thread::spawn(move || {
+                    if let Some(image) = metadata::embedded_thumbnail(&preview_path) {
+                        let image = image.to_rgba8();
+                        let size = [image.width() as usize, image.height() as usize];
+                        let _ = tx.send(egui::ColorImage::from_rgba_unmultiplied(size, &image));
+                        ctx.request_repaint();
+                    }
+                });
+                viewer.startup_preview = Some(rx);
+            }
+            viewer.load_path(path);
+        }
+
+        viewer
+    }
+
+    fn is_loading(&self) -> bool {
+        if let Some(path) = &self.current_image_path {
+            self.loading_paths.contains(path)
+        } else {
+            false
+        }
+    }
+
+    fn load_file(&mut self, path: PathBuf, reset_view: bool) {
+        self.remember_view();
+        self.current_image_path = Some(path.clone());
+        self.reset_view_on_load = reset_view;
+        self.error_msg = None;
+        self.nav_step = 0;
+        self.annotations.clear();
+        self.redaction.clear();
+        self.straighten.clear();
+        #[cfg(feature = "ocr")]
+        self.ocr.dismiss_status();
+        #[cfg(feature = "cutout")]
+        self.cutout.dismiss_status();
+        self.downloader.dismiss_status();
+        self.clean_copy.dismiss_status();
+
+        // Fit modes are reading modes: every new page starts at its top/left edge
+        if self.view_state.fit_mode != FitMode::Free {
+            self.view_state.reset();
+        }
+
+        if self.strip_mode {
+            self.strip_view.jump_to(self.current_image_index);
+        }
+
+        // A cached texture would keep showing a file that has since been deleted
+        if self.texture_cache.contains_key(&path) && !path.exists() {
+            self.drop_missing(path);
+            return;
+        }
+
+        self.config.add_recent_file(&path);
+        self.config.mark_dirty();
+
+        if reset_view && self.texture_cache.contains_key(&path) {
+            if self.restore_view(&path) {
+                self.reset_view_on_load = false;
+            } else if self.config.fit_to_work_area {
+                self.fit_to_work_area(&self.egui_ctx.clone());
+            }
+        }
+
+        self.request_load(path);
+        self.update_preloads();
+        
+        self.thumbnail_list.update_folder(&self.current_folder_images, self.current_image_index);
+    }
+
+    /// Stores the zoom and pan of the image being left, if view memory is on.
+    fn remember_view(&mut self) {
+        if self.config.view_memory == ViewMemory::Off
+            || self.view_state.fit_mode != FitMode::Free
+            || self.image_size.is_none()
+        {
+            return;
+        }
+        let Some(path) = self.current_image_path.clone() else {
+            return;
+        };
+        // Kept as scale, so views come back the same size on another monitor
+        let zoom = self.view_state.to_scale(self.view_state.target_zoom);
+        let pan = self.view_state.target_pan * self.view_state.to_scale(1.0);
+        self.session_views.insert(path.clone(), (zoom, pan));
+        if self.config.view_memory == ViewMemory::Persistent {
+            self.config.save_view(SavedView {
+                path,
+                zoom,
+                pan: pan.into(),
+            });
+        }
+    }
+
+    /// Returns to the remembered view of `path`; false if there is none.
+    fn restore_view(&mut self, path: &PathBuf) -> bool {
+        if self.config.view_memory == ViewMemory::Off || self.view_state.fit_mode != FitMode::Free {
+            return false;
+        }
+        let saved = self.session_views.get(path).copied().or_else(|| {
+            self.config
+                .saved_view(path)
+                .map(|v| (v.zoom, egui::Vec2::from(v.pan)))
+        });
+        match saved {
+            Some((zoom, pan)) => {
+                let pan = pan * self.view_state.from_scale(1.0);
+                self.view_state.restore(self.view_state.from_scale(zoom), pan);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn request_load(&mut self, path: PathBuf) {
+        if !self.texture_cache.contains_key(&path) && !self.loading_paths.contains(&path) {
+            println!("UI: Requesting load for {:?}", path);
+            self.loading_paths.insert(path.clone());
+            // PDFs come back on the page they were left at
+            #[cfg(feature = "pdf")]
+            if let Some(view) = self.pdf_views.get(&path) {
+                let command = ImageCommand::LoadPdfPage(path, view.page, view.scale);
+                self.loader.send(command);
+                return;
+            }
+            self.loader.send(ImageCommand::Load(path));
+        }
+    }
+
+    /// Changes the exposure of high-bit-depth images and re-renders the cached ones.
+    fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure.clamp(-8.0, 8.0);
+        self.loader.send(ImageCommand::SetExposure(self.exposure));
+
+        let high_bit_depth = self.high_bit_depth.clone();
+        self.evict_textures(|p| !high_bit_depth.contains(p));
+        if let Some(path) = self.current_image_path.clone() {
+            self.request_load(path);
+        }
+        self.update_preloads();
+    }
+
+    /// Changes how images are rendered (monitor profile, false color) and re-renders
+    /// the cached ones.
+    fn set_render_option(&mut self, command: ImageCommand) {
+        self.strip_view.set_render_option(command.clone());
+        self.loader.send(command);
+        self.evict_textures(|_| false);
+        if let Some(path) = self.current_image_path.clone() {
+            self.request_load(path);
+        }
+        self.update_preloads();
+    }
+
+    fn update_preloads(&mut self) {
+        if self.current_folder_images.is_empty() {
+            return;
+        }
+
+        let len = self.current_folder_images.len();
+        if len == 0 {
+            return;
+        }
+
+        // Spread mode shows two pages at once, so keep a full spread on either side
+        let offsets: &[isize] = if self.spread_mode {
+            &[-2, -1, 1, 2, 3]
+        } else {
+            &[-1, 1]
+        };
+
+        // Cleanup cache: keep only current and its neighbours
+        let mut keep_paths = HashSet::new();
+        if let Some(curr) = &self.current_image_path {
+            keep_paths.insert(curr.clone());
+        }
+        for offset in offsets {
+            let idx = (self.current_image_index as isize + offset).rem_euclid(len as isize) as usize;
+            let path = self.current_folder_images[idx].clone();
+            // Online-only files are downloaded when navigated to, not ahead of time
+            if !self.texture_cache.contains_key(&path) && is_cloud_placeholder(&path) {
+                continue;
+            }
+            self.request_load(path.clone());
+            keep_paths.insert(path);
+        }
+
+        if let Some(path) = &self.hover_prefetch {
+            keep_paths.insert(path.clone());
+        }
+        self.evict_textures(|p| keep_paths.contains(p));
+    }
+
+    /// Drops the textures of images `keep` says no to, keeping a few to overwrite.
+    fn evict_textures(&mut self, keep: impl Fn(&PathBuf) -> bool) {
+        let evicted: Vec<PathBuf> =
+            self.texture_cache.keys().filter(|p| !keep(p)).cloned().collect();
+        for path in evicted {
+            if let Some(texture) = self.texture_cache.remove(&path) {
+                self.texture_pool.recycle(texture);
+            }
+        }
+    }
+
+    fn load_path(&mut self, path: PathBuf) {
+        if playlist::is_playlist(&path) {
+            self.load_playlist(&path);
+        } else if path.is_dir() {
+            // The first image found is opened once the scan delivers it
+            self.remember_view();
+            self.current_image_path = None;
+            self.error_msg = None;
+            self.start_folder_scan(&path, Vec::new());
+        } else {
+            // The file is shown right away; its neighbours stream in around it
+            self.folder_scan = None;
+            if let Some(parent) = path.parent() {
+                self.start_folder_scan(parent, vec![path.clone()]);
+            } else {
+                self.current_folder = None;
+                self.current_folder_images = vec![path.clone()];
+            }
+            self.current_image_index = 0;
+            self.load_file(path, true);
+        }
+    }
+
+    /// Starts listing `folder` in the background, with `images` as the list until then.
+    fn start_folder_scan(&mut self, folder: &std::path::Path, images: Vec<PathBuf>) {
+        self.current_folder_images = images;
+        self.current_image_index = 0;
+        self.unfiltered_images = None;
+        self.filter_bar.reset();
+        self.strip_view.clear();
+        self.selection.retain(&self.current_folder_images);
+        self.similarity_sort = None;
+        self.capture_sort = None;
+
+        self.current_folder = Some(folder.to_path_buf());
+        self.folder_scan = Some(FolderScan::start(&self.egui_ctx, folder, self.scan_options()));
+    }
+
+    /// Lists the current folder again, staying on the current image.
+    fn rescan_folder(&mut self) {
+        if let Some(folder) = self.current_folder.clone() {
+            let images = self.current_image_path.iter().cloned().collect();
+            self.start_folder_scan(&folder, images);
+        }
+    }
+
+    fn scan_options(&self) -> ScanOptions {
+        ScanOptions {
+            extensions: self.config.extensions.clone(),
+            include_hidden: self.config.include_hidden,
+            follow_symlinks: self.config.follow_symlinks,
+        }
+    }
+
+    /// Merges the images found by the folder scan so far into the list.
+    fn poll_folder_scan(&mut self) {
+        let Some(scan) = &mut self.folder_scan else {
+            return;
+        };
+        let mut found = Vec::new();
+        let mut done = false;
+        while let Some(event) = scan.poll() {
+            match event {
+                ScanEvent::Found(batch) => found.extend(batch),
+                ScanEvent::Done => {
+                    done = true;
+                    break;
+                }
+            }
+        }
+
+        if !found.is_empty() {
+            self.merge_images(found);
+        }
+
+        if done {
+            self.folder_scan = None;
+            if self.current_folder_images.is_empty() && self.current_image_path.is_none() {
+                self.error_msg = Some("No images found in the folder.".to_string());
+            } else {
+                if std::mem::take(&mut self.open_at_end) && !self.current_folder_images.is_empty()
+                {
+                    self.current_image_index = self.current_folder_images.len() - 1;
+                    self.load_file(self.current_folder_images[self.current_image_index].clone(), true);
+                }
+                self.start_sort();
+            }
+        }
+    }
+
+    /// Adds newly found images to the list, opening the first one if none is shown yet.
+    fn merge_images(&mut self, found: Vec<PathBuf>) {
+        // With the filter bar open, new images join the unfiltered list
+        let list = self
+            .unfiltered_images
+            .as_mut()
+            .unwrap_or(&mut self.current_folder_images);
+        folder_scan::merge_found(list, found);
+
+        match self
+            .current_image_path
+            .as_ref()
+            .map(|c| self.current_folder_images.iter().position(|p| p == c))
+        {
+            Some(Some(idx)) => {
+                self.current_image_index = idx;
+                self.update_preloads();
+            }
+            Some(None) => {}
+            None if !self.current_folder_images.is_empty() => {
+                self.current_image_index = 0;
+                self.load_file(self.current_folder_images[0].clone(), true);
+            }
+            None => {}
+        }
+        self.thumbnail_list
+            .update_folder(&self.current_folder_images, self.current_image_index);
+    }
+
+    /// Relists the folder during slideshows (and always in kiosk mode, which may start on
+    /// an empty folder) and follows files that were added or removed.
+    fn update_folder_watch(&mut self, ctx: &egui::Context) {
+        let watching = self.slideshow.active || self.kiosk.is_some();
+        let folder = self
+            .current_folder
+            .as_ref()
+            .filter(|_| watching && self.folder_scan.is_none());
+        let Some(folder) = folder else {
+            self.folder_watch = None;
+            return;
+        };
+        if self.folder_watch.as_ref().is_none_or(|w| w.folder() != folder) {
+            self.folder_watch = Some(FolderWatch::new(folder.clone(), self.scan_options()));
+        }
+        let Some(images) = self.folder_watch.as_mut().and_then(|w| w.poll(ctx)) else {
+            return;
+        };
+
+        let listed = self
+            .unfiltered_images
+            .as_ref()
+            .unwrap_or(&self.current_folder_images);
+        let on_disk: HashSet<&PathBuf> = images.iter().collect();
+        let removed: Vec<PathBuf> = listed
+            .iter()
+            .filter(|p| !on_disk.contains(p))
+            .cloned()
+            .collect();
+        let known: HashSet<&PathBuf> = listed.iter().collect();
+        let added: Vec<PathBuf> = images
+            .iter()
+            .filter(|p| !known.contains(p))
+            .cloned()
+            .collect();
+        if !removed.is_empty() {
+            self.remove_paths(&removed);
+        }
+        if !added.is_empty() {
+            self.merge_images(added);
+            if self.sort_order != SortOrder::Name {
+                self.start_sort();
+            }
+        }
+    }
+
+    /// Opens a folder, announcing it since the view changes without the user picking a file.
+    fn open_folder(&mut self, folder: PathBuf) {
+        osd::toast(&self.egui_ctx, folder_nav::name(&folder));
+        self.load_path(folder);
+    }
+
+    /// At either end of a folder (with `continue_folders` on), the first press announces the
+    /// neighbouring folder and a second one opens it. Returns false to wrap around as usual.
+    fn leave_folder(&mut self, step: isize) -> bool {
+        // While the folder is still being listed its end isn't known yet
+        if !self.config.continue_folders || self.slideshow.active || self.folder_scan.is_some() {
+            return false;
+        }
+        let Some(target) = self
+            .current_folder
+            .as_deref()
+            .and_then(|f| folder_nav::sibling_with_images(f, step, &self.config.extensions))
+        else {
+            return false;
+        };
+        let confirmed = self
+            .folder_end
+            .as_ref()
+            .is_some_and(|(folder, at)| *folder == target && at.elapsed() < FOLDER_CONFIRM);
+        if confirmed {
+            self.folder_end = None;
+            self.open_at_end = step < 0;
+            self.open_folder(target);
+        } else {
+            let end = if step > 0 { "Last image" } else { "First image" };
+            osd::toast(
+                &self.egui_ctx,
+                format!("{}, press again for {}", end, folder_nav::name(&target)),
+            );
+            self.folder_end = Some((target, Instant::now()));
+        }
+        true
+    }
+
+    /// Moves to the next (1) or previous (-1) folder next to the current one.
+    fn step_folder(&mut self, step: isize) {
+        let sibling = self
+            .current_folder
+            .as_deref()
+            .and_then(|f| folder_nav::sibling(f, step));
+        match sibling {
+            Some(folder) => self.open_folder(folder),
+            None => osd::toast(
+                &self.egui_ctx,
+                if step > 0 { "Last folder" } else { "First folder" },
+            ),
+        }
+    }
+
+    /// Uses the entries of a playlist file as the navigation list, in playlist order.
+    fn load_playlist(&mut self, path: &std::path::Path) {
+        let images = match playlist::read(path) {
+            Ok(images) => images,
+            Err(e) => {
+                self.error_msg = Some(format!("Failed to read playlist: {}", e));
+                self.current_image_path = None;
+                return;
+            }
+        };
+        if images.is_empty() {
+            self.error_msg = Some("No images found in the playlist.".to_string());
+            self.current_image_path = None;
+            return;
+        }
+        self.load_list(images);
+    }
+
+    /// Navigates a hand-picked list of images instead of a folder.
+    fn load_list(&mut self, images: Vec<PathBuf>) {
+        self.folder_scan = None;
+        self.current_folder = None;
+        self.current_folder_images = images;
+        self.unfiltered_images = None;
+        self.filter_bar.reset();
+        self.strip_view.clear();
+        self.selection.retain(&self.current_folder_images);
+        self.similarity_sort = None;
+        self.capture_sort = None;
+        self.start_sort();
+        self.current_image_index = 0;
+        self.load_file(self.current_folder_images[0].clone(), true);
+    }
+
+    /// Browses the library images matching a saved album search.
+    fn open_album(&mut self, index: usize) {
+        let album = &self.config.albums[index];
+        match search::album_images(&self.library, album) {
+            Ok(images) if images.is_empty() => {
+                osd::toast(&self.egui_ctx, format!("No images in \"{}\"", album.name));
+            }
+            Ok(images) => self.load_list(images),
+            Err(e) => self.error_msg = Some(format!("Album \"{}\": {}", album.name, e)),
+        }
+    }
+
+    fn export_playlist(&self) {
+        if self.current_folder_images.is_empty() {
+            return;
+        }
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Playlist", playlist::EXTENSIONS)
+            .set_file_name("playlist.m3u")
+            .save_file()
+        {
+            match playlist::write(&path, &self.current_folder_images) {
+                Ok(()) => println!("Saved playlist to {:?}", path),
+                Err(e) => eprintln!("Failed to save playlist: {}", e),
+            }
+        }
+    }
+
+    /// Asks for an image (or playlist) to open, or with `folder` for a folder, starting
+    /// where the last one was picked.
+    fn open_dialog(&mut self, folder: bool) {
+        let mut dialog = rfd::FileDialog::new();
+        if let Some(dir) = &self.config.last_open_dir {
+            dialog = dialog.set_directory(dir);
+        }
+        let picked = if folder {
+            dialog.set_title("Open Folder").pick_folder()
+        } else {
+            dialog
+                .set_title("Open Image")
+                .add_filter("Images", &self.config.extensions)
+                .add_filter("Playlists", playlist::EXTENSIONS)
+                .add_filter("All files", &["*"])
+                .pick_file()
+        };
+        let Some(path) = picked else {
+            return;
+        };
+        let dir = if folder { Some(path.as_path()) } else { path.parent() };
+        self.config.last_open_dir = dir.map(Path::to_path_buf);
+        self.config.mark_dirty();
+        self.load_path(path);
+    }
+
+    fn paste_clipboard_image(&mut self) {
+        match self.clipboard_watcher.paste() {
+            Ok(path) => self.load_path(path),
+            Err(e) => self.downloader.set_status(e),
+        }
+    }
+
+    #[cfg(feature = "tray")]
+    fn handle_tray(&mut self, ctx: &egui::Context) {
+        let Some(tray) = &mut self.tray else {
+            return;
+        };
+        tray.set_recent(&self.config.recent_files);
+
+        let mut commands = Vec::new();
+        while let Some(command) = tray.poll() {
+            commands.push(command);
+        }
+        for command in commands {
+            let show = match command {
+                TrayCommand::Show => true,
+                TrayCommand::Open(path) => {
+                    self.load_path(path);
+                    true
+                }
+                TrayCommand::PasteClipboard => {
+                    self.paste_clipboard_image();
+                    true
+                }
+                TrayCommand::Quit => {
+                    self.quitting = true;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    false
+                }
+            };
+            if show {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            }
+        }
+
+        // Closing the window keeps the viewer around in the tray
+        if ctx.input(|i| i.viewport().close_requested()) && !self.quitting {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+        }
+    }
+
+    fn handle_remote(&mut self, ctx: &egui::Context) {
+        while let Some(command) = self.remote.as_ref().and_then(|r| r.poll()) {
+            match command {
+                RemoteCommand::Open(path) => {
+                    self.load_path(path);
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                }
+                RemoteCommand::Next => self.next_image(),
+                RemoteCommand::Prev => self.prev_image(),
+                RemoteCommand::Fullscreen => {
+                    let fullscreen = ctx.input(|i| i.viewport().fullscreen.unwrap_or(false));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(!fullscreen));
+                }
+                RemoteCommand::Quit => {
+                    if let Some(kiosk) = &mut self.kiosk {
+                        kiosk.leave();
+                    }
+                    #[cfg(feature = "tray")]
+                    {
+                        self.quitting = true;
+                    }
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "screenshot")]
+    fn handle_screen_capture(&mut self, ctx: &egui::Context) {
+        let Some(capture) = &mut self.screen_capture else {
+            return;
+        };
+
+        match capture.poll() {
+            Some(Ok(path)) => {
+                self.load_path(path);
+                // Ready for dragging out the region to keep
+                self.measure.enabled = true;
+                self.measure.clear();
+                self.annotations.enabled = false;
+                self.redaction.enabled = false;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            }
+            Some(Err(e)) => self.downloader.set_status(format!("Screenshot failed: {}", e)),
+            None => {}
+        }
+
+        // Enter crops the fresh capture to the measured region
+        let Some(capture) = &mut self.screen_capture else {
+            return;
+        };
+        if capture.pending.is_some() && capture.pending != self.current_image_path {
+            capture.pending = None;
+        }
+        if let Some(path) = capture.pending.clone()
+            && self.measure.enabled
+            && let Some(region) = self.measure.region()
+            && !ctx.wants_keyboard_input()
+            && ctx.input(|i| i.key_pressed(egui::Key::Enter))
+        {
+            capture.pending = None;
+            match screen_capture::crop(&path, region) {
+                Ok(()) => {
+                    self.measure.enabled = false;
+                    self.measure.clear();
+                    self.texture_cache.remove(&path);
+                    self.load_file(path, true);
+                }
+                Err(e) => self.downloader.set_status(format!("Crop failed: {}", e)),
+            }
+        }
+    }
+
+    /// Starts reordering the list in the background for orders that need file contents.
+    fn start_sort(&mut self) {
+        if self.current_folder_images.len() < 2 {
+            return;
+        }
+        match self.sort_order {
+            SortOrder::Name => {}
+            SortOrder::CaptureDate => {
+                self.capture_sort = Some(CaptureDateSort::start(
+                    &self.egui_ctx,
+                    &self.current_folder_images,
+                ));
+            }
+            SortOrder::Similarity => {
+                self.similarity_sort = Some(SimilaritySort::start(
+                    &self.egui_ctx,
+                    &self.current_folder_images,
+                ));
+            }
+        }
+    }
+
+    fn toggle_sort_order(&mut self) {
+        self.similarity_sort = None;
+        self.capture_sort = None;
+        match self.sort_order {
+            SortOrder::Name => {
+                self.sort_order = SortOrder::CaptureDate;
+                self.start_sort();
+                osd::toast(&self.egui_ctx, "Sort: by capture date");
+            }
+            SortOrder::CaptureDate => {
+                self.sort_order = SortOrder::Similarity;
+                self.start_sort();
+                osd::toast(&self.egui_ctx, "Sort: by similarity");
+            }
+            SortOrder::Similarity => {
+                self.sort_order = SortOrder::Name;
+                osd::toast(&self.egui_ctx, "Sort: by name");
+                let mut images = std::mem::take(&mut self.current_folder_images);
+                images.sort();
+                self.apply_order(images);
+            }
+        }
+    }
+
+    /// Replaces the navigation order, keeping the current image selected.
+    fn apply_order(&mut self, ordered: Vec<PathBuf>) {
+        self.current_folder_images = ordered;
+        if let Some(idx) = self
+            .current_image_path
+            .as_ref()
+            .and_then(|c| self.current_folder_images.iter().position(|p| p == c))
+        {
+            self.current_image_index = idx;
+        }
+        self.strip_view.clear();
+        self.update_preloads();
+        self.thumbnail_list
+            .update_folder(&self.current_folder_images, self.current_image_index);
+    }
+
+    /// Drops `removed` from the navigation list, moving off the current image if it was removed.
+    fn remove_paths(&mut self, removed: &[PathBuf]) {
+        self.current_folder_images.retain(|p| !removed.contains(p));
+        if let Some(all) = &mut self.unfiltered_images {
+            all.retain(|p| !removed.contains(p));
+        }
+        self.texture_cache.retain(|p, _| !removed.contains(p));
+        self.selection.retain(&self.current_folder_images);
+
+        if self.current_folder_images.is_empty() {
+            self.current_image_path = None;
+            self.error_msg = Some("No images left in the folder.".to_string());
+            return;
+        }
+
+        let current = self
+            .current_image_path
+            .as_ref()
+            .and_then(|c| self.current_folder_images.iter().position(|p| p == c));
+        match current {
+            Some(idx) => {
+                self.current_image_index = idx;
+                self.thumbnail_list
+                    .update_folder(&self.current_folder_images, self.current_image_index);
+            }
+            None => {
+                // Stay at the same position, which is now the following image
+                self.current_image_index =
+                    self.current_image_index.min(self.current_folder_images.len() - 1);
+                self.load_file(self.current_folder_images[self.current_image_index].clone(), true);
+            }
+        }
+    }
+
+    /// Follows files renamed on disk (old, new) in the image lists, caches and selection.
+    fn rename_paths(&mut self, renamed: &[(PathBuf, PathBuf)]) {
+        let new_path: HashMap<&PathBuf, &PathBuf> = renamed.iter().map(|(a, b)| (a, b)).collect();
+        let follow = |p: &mut PathBuf| {
+            if let Some(new) = new_path.get(p) {
+                *p = (*new).clone();
+            }
+        };
+        self.current_folder_images.iter_mut().for_each(follow);
+        if let Some(all) = &mut self.unfiltered_images {
+            all.iter_mut().for_each(follow);
+        }
+        self.config.recent_files.iter_mut().for_each(follow);
+        self.config.mark_dirty();
+        if let Some(current) = &mut self.current_image_path {
+            follow(current);
+        }
+        for (old, new) in renamed {
+            if let Some(texture) = self.texture_cache.remove(old) {
+                self.texture_cache.insert(new.clone(), texture);
+            }
+        }
+        self.selection.rename(renamed);
+        self.tags.rename(renamed);
+        self.metadata_panel.invalidate();
+        self.thumbnail_list
+            .update_folder(&self.current_folder_images, self.current_image_index);
+    }
+
+    /// Drops an image that vanished from disk (deleted, or its drive or share went away)
+    /// and moves on to the nearest image that's still there.
+    fn drop_missing(&mut self, path: PathBuf) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        osd::toast(&self.egui_ctx, format!("{} is no longer available", name));
+        self.loading_paths.remove(&path);
+        self.config.recent_files.retain(|p| p != &path);
+        self.remove_paths(&[path]);
+    }
+
+    /// Swaps in the filtered (or restored) image list, staying on the current image if
+    /// it's still listed.
+    fn show_filtered(&mut self, images: Vec<PathBuf>) {
+        self.current_folder_images = images;
+        self.strip_view.clear();
+        self.selection.retain(&self.current_folder_images);
+        self.similarity_sort = None;
+        self.capture_sort = None;
+
+        let current = self
+            .current_image_path
+            .as_ref()
+            .and_then(|c| self.current_folder_images.iter().position(|p| p == c));
+        match current {
+            Some(idx) => {
+                self.current_image_index = idx;
+                self.update_preloads();
+                self.thumbnail_list
+                    .update_folder(&self.current_folder_images, self.current_image_index);
+            }
+            None if !self.current_folder_images.is_empty() => {
+                self.current_image_index = 0;
+                self.load_file(self.current_folder_images[0].clone(), true);
+            }
+            // Nothing matches; keep showing the current image
+            None => self.current_image_index = 0,
+        }
+    }
+
+    fn toggle_strip_mode(&mut self) {
+        self.strip_mode = !self.strip_mode;
+        if self.strip_mode {
+            self.strip_view.jump_to(self.current_image_index);
+        } else if let Some(path) = self.current_image_path.clone() {
+            self.load_file(path, true);
+        }
+    }
+
+    fn show_thumbnail_list(&mut self, ui: &mut egui::Ui) {
+        if self.current_folder_images.is_empty() {
+            return;
+        }
+        let action = ui
+            .scope(|ui| {
+                ui.multiply_opacity(self.ui_opacity);
+                let sharpness = &self.sharpness;
+                let badges = self.config.sharpness_badges;
+                let separators = if self.sort_order == SortOrder::CaptureDate {
+                    timeline::sessions(&self.current_folder_images, &self.capture_times)
+                } else {
+                    Vec::new()
+                };
+                self.thumbnail_list.display(
+                    ui,
+                    &self.current_folder_images,
+                    self.current_image_index,
+                    &mut self.selection,
+                    &separators,
+                    |path| {
+                        let score = sharpness.get(path).filter(|_| badges)?;
+                        Some((format!("{:.0}", score), sharpness::verdict(score).1))
+                    },
+                )
+            })
+            .inner;
+        match action {
+            ThumbnailAction::SelectImage(idx) => {
+                self.current_image_index = idx;
+                self.load_file(self.current_folder_images[self.current_image_index].clone(), false);
+            }
+            ThumbnailAction::SetDock(dock) => {
+                self.config.filmstrip_dock = dock;
+                self.config.mark_dirty();
+            }
+            ThumbnailAction::OpenBatch => self.batch.open = true,
+            ThumbnailAction::Prefetch(idx) => {
+                // Queued behind the current image and its neighbours
+                let path = self.current_folder_images[idx].clone();
+                self.hover_prefetch = Some(path.clone());
+                self.request_load(path);
+            }
+            ThumbnailAction::None => {}
+        }
+    }
+
+    fn is_wide(&self, index: usize) -> bool {
+        self.texture_cache
+            .get(&self.current_folder_images[index])
+            .is_some_and(|t| t.size()[0] > t.size()[1])
+    }
+
+    fn current_is_high_bit_depth(&self) -> bool {
+        self.current_image_path
+            .as_ref()
+            .is_some_and(|p| self.high_bit_depth.contains(p))
+    }
+
+    /// The page shown next to `index` in spread mode, if it can be paired.
+    ///
+    /// Wide images (double-page scans, landscape art) are always shown alone, and
+    /// the partner is only paired once it has been decoded so the layout can't jump.
+    fn spread_partner(&self, index: usize) -> Option<usize> {
+        let partner = index + 1;
+        if !self.spread_mode || partner >= self.current_folder_images.len() {
+            return None;
+        }
+        let loaded = |i: usize| self.texture_cache.contains_key(&self.current_folder_images[i]);
+        if loaded(index) && loaded(partner) && !self.is_wide(index) && !self.is_wide(partner) {
+            Some(partner)
+        } else {
+            None
+        }
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| i.key_pressed(egui::Key::F1)) {
+            self.show_debug_info = !self.show_debug_info;
+        }
+        // F2 renames the folder or the selection from a pattern
+        if ctx.input(|i| i.key_pressed(egui::Key::F2)) && !self.current_folder_images.is_empty() {
+            self.rename.open = !self.rename.open;
+        }
+        // F3 shows frame rate, memory and loader diagnostics
+        if ctx.input(|i| i.key_pressed(egui::Key::F3)) {
+            self.diagnostics.toggle();
+        }
+
+        // Ctrl+[ and Ctrl+] go to the previous and next folder, Backspace to the parent
+        if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::OpenBracket)) {
+            self.step_folder(-1);
+        } else if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::CloseBracket)) {
+            self.step_folder(1);
+        } else if ctx.input(|i| i.key_pressed(egui::Key::Backspace))
+            && let Some(parent) = self.current_folder.as_deref().and_then(Path::parent)
+        {
+            self.open_folder(parent.to_path_buf());
+        }
+
+        // [ and ] adjust the exposure of high-bit-depth images in half stops, \\ resets it
+        if ctx.input(|i| i.key_pressed(egui::Key::OpenBracket) && !i.modifiers.command) {
+            self.set_exposure(self.exposure - 0.5);
+        } else if ctx.input(|i| i.key_pressed(egui::Key::CloseBracket) && !i.modifiers.command) {
+            self.set_exposure(self.exposure + 0.5);
+        } else if ctx.input(|i| i.key_pressed(egui::Key::Backslash)) && self.exposure != 0.0 {
+            self.set_exposure(0.0);
+        }
+
+        // Ctrl+Shift+P switches between sRGB and Display P3 monitors
+        if ctx.input(|i| i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::P)) {
+            self.config.display_profile = self.config.display_profile.next();
+            self.config.mark_dirty();
+            osd::toast(ctx, format!("Display: {}", self.config.display_profile.label()));
+            self.set_render_option(ImageCommand::SetDisplayProfile(self.config.display_profile));
+        }
+
+        // F6 cycles the false-color luminance views
+        if ctx.input(|i| i.key_pressed(egui::Key::F6)) {
+            self.false_color = Colormap::cycle(self.false_color);
+            osd::toast(
+                ctx,
+                match self.false_color {
+                    Some(colormap) => format!("False color: {}", colormap.label()),
+                    None => "False color off".to_string(),
+                },
+            );
+            self.set_render_option(ImageCommand::SetFalseColor(self.false_color));
+        }
+
+        // 0 sizes the window to the image at 100%
+        if ctx.input(|i| i.key_pressed(egui::Key::Num0) && i.modifiers.is_none()) {
+            self.size_window_to_image(ctx);
+        }
+
+        // Alt+0 toggles fitting new images to the monitor's work area
+        if ctx.input(|i| i.key_pressed(egui::Key::Num0) && i.modifiers == egui::Modifiers::ALT) {
+            self.toggle_fit_to_work_area(ctx);
+        }
+
+        // Ctrl+1..9 bookmarks the current zoom and pan, 1..9 animates back to it
+        const DIGITS: [egui::Key; 9] = [
+            egui::Key::Num1,
+            egui::Key::Num2,
+            egui::Key::Num3,
+            egui::Key::Num4,
+            egui::Key::Num5,
+            egui::Key::Num6,
+            egui::Key::Num7,
+            egui::Key::Num8,
+            egui::Key::Num9,
+        ];
+        if let Some(path) = self.current_image_path.clone()
+            && let Some(slot) = DIGITS.iter().position(|k| ctx.input(|i| i.key_pressed(*k)))
+        {
+            let bookmarks = self.view_bookmarks.entry(path).or_default();
+            if ctx.input(|i| i.modifiers.command) {
+                bookmarks[slot] = Some((self.view_state.target_zoom, self.view_state.target_pan));
+            } else if let Some((zoom, pan)) = bookmarks[slot] {
+                self.view_state.animate_to(zoom, pan);
+            }
+        }
+
+        // Page Up/Down turn the pages of a PDF
+        #[cfg(feature = "pdf")]
+        if ctx.input(|i| i.key_pressed(egui::Key::PageDown)) {
+            self.step_pdf_page(1);
+        } else if ctx.input(|i| i.key_pressed(egui::Key::PageUp)) {
+            self.step_pdf_page(-1);
+        }
+
+        // Enter plays a video in the system player; only its first frame is shown here
+        if !self.thumbnail_list.is_expanded()
+            && ctx.input(|i| i.key_pressed(egui::Key::Enter))
+            && let Some(path) = self.current_image_path.as_ref().filter(|p| video::is_video(p))
+            && let Err(e) = external_tools::open_with_system(path)
+        {
+            osd::toast(ctx, format!("Could not start the video player: {}", e));
+        }
+
+        // P plays the numbered frame sequence the current image belongs to
+        if ctx.input(|i| i.key_pressed(egui::Key::P) && !i.modifiers.any())
+            && let Some(path) = &self.current_image_path
+            && let Some(frames) = sequence::detect(path, &self.current_folder_images)
+        {
+            let start = frames.iter().position(|p| p == path).unwrap_or(0);
+            self.sequence_player.open(ctx, frames, start);
+        }
+
+        // F5 starts or stops the fullscreen slideshow, Shift+F5 toggles its Ken Burns motion,
+        // Ctrl+F5 presents it on another display
+        if ctx.input(|i| i.key_pressed(egui::Key::F5)) {
+            if self.presentation.active {
+                self.stop_presentation();
+            } else if ctx.input(|i| i.modifiers.command) {
+                if !self.current_folder_images.is_empty() {
+                    self.start_presentation(ctx);
+                }
+            } else if ctx.input(|i| i.modifiers.shift) {
+                self.config.ken_burns = !self.config.ken_burns;
+                self.config.mark_dirty();
+                let state = if self.config.ken_burns { "on" } else { "off" };
+                osd::toast(ctx, format!("Ken Burns effect: {}", state));
+            } else if !self.current_folder_images.is_empty() || self.slideshow.active {
+                self.toggle_slideshow(ctx);
+            }
+        } else if self.presentation.active && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.stop_presentation();
+        } else if self.slideshow.active && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.toggle_slideshow(ctx);
+        }
+
+        // F4 toggles stepping over files that fail to decode
+        if ctx.input(|i| i.key_pressed(egui::Key::F4)) {
+            self.config.skip_unreadable = !self.config.skip_unreadable;
+            self.config.mark_dirty();
+            let state = if self.config.skip_unreadable { "on" } else { "off" };
+            osd::toast(ctx, format!("Skip unreadable files: {}", state));
+        }
+
+        // R cycles view memory: off, this session, kept across sessions
+        if ctx.input(|i| i.key_pressed(egui::Key::R) && !i.modifiers.any()) {
+            self.config.view_memory = self.config.view_memory.next();
+            self.config.mark_dirty();
+            osd::toast(ctx, format!("View memory: {:?}", self.config.view_memory));
+        }
+
+        // Fit modes: W = fit width (vertical scrolling), H = fit height (horizontal scrolling)
+        let fit_mode = self.view_state.fit_mode;
+        if ctx.input(|i| i.key_pressed(egui::Key::W)) {
+            self.view_state.toggle_fit_mode(FitMode::FitWidth);
+        } else if ctx.input(|i| i.key_pressed(egui::Key::H)) {
+            self.view_state.toggle_fit_mode(FitMode::FitHeight);
+        }
+        if self.view_state.fit_mode != fit_mode {
+            osd::toast(
+                ctx,
+                match self.view_state.fit_mode {
+                    FitMode::FitWidth => "Fit width",
+                    FitMode::FitHeight => "Fit height",
+                    FitMode::Free => "Free zoom",
+                },
+            );
+        }
+
+        // Ctrl+B shows the folder path bar
+        if ctx.input(|i| i.modifiers.command && !i.modifiers.shift && i.key_pressed(egui::Key::B)) {
+            self.config.breadcrumb = !self.config.breadcrumb;
+            self.config.mark_dirty();
+        }
+
+        // B toggles the two-page spread, Shift+B flips the reading direction
+        if ctx.input(|i| i.key_pressed(egui::Key::B) && !i.modifiers.command) {
+            if ctx.input(|i| i.modifiers.shift) {
+                self.spread_rtl = !self.spread_rtl;
+            } else {
+                self.spread_mode = !self.spread_mode;
+                self.update_preloads();
+            }
+        }
+
+        // L toggles the magnifier loupe; the main view fits the window while it's active
+        if ctx.input(|i| i.key_pressed(egui::Key::L) && !i.modifiers.command) {
+            self.loupe.enabled = !self.loupe.enabled;
+            self.fit_requested = self.loupe.enabled;
+            osd::toast(ctx, if self.loupe.enabled { "Loupe on" } else { "Loupe off" });
+        }
+
+        // M toggles the measure tool, which takes over plain left-drag from panning
+        if ctx.input(|i| i.key_pressed(egui::Key::M)) {
+            self.measure.enabled = !self.measure.enabled;
+            self.annotations.enabled = false;
+            self.redaction.enabled = false;
+            self.measure.clear();
+        }
+
+        // N toggles the annotation tools
+        if ctx.input(|i| i.key_pressed(egui::Key::N)) {
+            self.annotations.enabled = !self.annotations.enabled;
+            self.measure.enabled = false;
+            self.redaction.enabled = false;
+            self.straighten.enabled = false;
+        }
+
+        // X toggles the redaction (pixelate/blur) tool
+        if ctx.input(|i| i.key_pressed(egui::Key::X)) {
+            self.redaction.enabled = !self.redaction.enabled;
+            self.measure.enabled = false;
+            self.annotations.enabled = false;
+            self.straighten.enabled = false;
+        }
+
+        // Shift+R toggles the straighten tool; marks in image coordinates wouldn't follow
+        // the rotated preview, so the drawing tools are put away
+        if ctx.input(|i| i.modifiers.shift_only() && i.key_pressed(egui::Key::R)) {
+            self.straighten.enabled = !self.straighten.enabled;
+            self.annotations.enabled = false;
+            self.redaction.enabled = false;
+        }
+        self.view_state.left_drag_pans =
+            !self.measure.enabled && !self.annotations.enabled && !self.redaction.enabled;
+
+        // Ctrl+Shift+C copies recognized text, limited to the measured region if there is one
+        #[cfg(feature = "ocr")]
+        if ctx.input(|i| i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::C))
+            && let Some(path) = self.current_image_path.clone()
+        {
+            let region = self.measure.enabled.then(|| self.measure.region()).flatten();
+            self.ocr.copy_text(ctx, path, region);
+        }
+
+        // Ctrl+Shift+B saves the image with its background removed
+        #[cfg(feature = "cutout")]
+        if ctx.input(|i| i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::B))
+            && let Some(path) = self.current_image_path.clone()
+        {
+            self.cutout.start(ctx, path);
+        }
+
+        // Ctrl+F opens the filter bar; closing it shows the whole folder again
+        if ctx.input(|i| i.modifiers.command && !i.modifiers.shift && i.key_pressed(egui::Key::F)) {
+            self.filter_bar.toggle();
+        }
+
+        // Ctrl+Shift+F searches the whole library
+        if ctx.input(|i| i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::F)) {
+            self.search.toggle();
+        }
+
+        // Ctrl+G builds an animation from the selected thumbnails (or the whole folder)
+        if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::G))
+            && !self.current_folder_images.is_empty()
+        {
+            let mut frames = self.selection.paths(&self.current_folder_images);
+            if frames.is_empty() {
+                frames = self.current_folder_images.clone();
+            }
+            self.animated_export.open(frames);
+        }
+
+        // Pasting a path or an image link opens it
+        let pasted = ctx.input(|i| {
+            i.events.iter().find_map(|e| match e {
+                egui::Event::Paste(text) => Some(text.clone()),
+                _ => None,
+            })
+        });
+        match pasted.as_deref().and_then(drop_import::from_text) {
+            Some(Dropped::Path(path)) => self.load_path(path),
+            Some(Dropped::Url(url)) => self.downloader.download(ctx, url),
+            None => {}
+        }
+
+        // Ctrl+Shift+S saves the current navigation list as a playlist
+        if ctx.input(|i| i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::S)) {
+            self.export_playlist();
+        }
+
+        // Ctrl+O opens an image, Ctrl+Shift+O a folder
+        if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::O)) {
+            let folder = ctx.input(|i| i.modifiers.shift);
+            self.open_dialog(folder);
+        }
+
+        // Ctrl+Shift+V opens the image on the clipboard
+        if ctx.input(|i| i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::V)) {
+            self.paste_clipboard_image();
+        }
+
+        // F9 toggles clipboard watching: copied images are shown as they arrive
+        if ctx.input(|i| i.key_pressed(egui::Key::F9)) {
+            self.clipboard_watcher.toggle(ctx);
+        }
+
+        // I shows file info, EXIF and the metadata editor
+        if ctx.input(|i| i.key_pressed(egui::Key::I) && !i.modifiers.shift) {
+            self.metadata_panel.open = !self.metadata_panel.open;
+        }
+
+        // Shift+I toggles the eyedropper
+        if ctx.input(|i| i.modifiers.shift_only() && i.key_pressed(egui::Key::I)) {
+            self.eyedropper.enabled = !self.eyedropper.enabled;
+        }
+
+        // Ctrl+Shift+K saves a copy without EXIF/GPS/XMP for sharing
+        if ctx.input(|i| i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::K))
+            && let Some(path) = self.current_image_path.clone()
+        {
+            self.clean_copy.start(ctx, path);
+        }
+
+        // Ctrl+Shift+U opens the AI upscaler
+        if ctx.input(|i| i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::U)) {
+            self.upscaler.open = !self.upscaler.open;
+        }
+
+        // J opens the denoise and sharpen filters
+        if ctx.input(|i| i.key_pressed(egui::Key::J) && !i.modifiers.any()) {
+            self.enhance.open = !self.enhance.open;
+        }
+
+        // Y opens the tag panel
+        if ctx.input(|i| i.key_pressed(egui::Key::Y) && !i.modifiers.any()) {
+            self.tag_panel.open = !self.tag_panel.open;
+        }
+
+        // Ctrl+T shows the folder tree
+        if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::T)) {
+            self.config.folder_tree = !self.config.folder_tree;
+            self.config.mark_dirty();
+        }
+
+        // T opens the user-configured external tools
+        if ctx.input(|i| i.key_pressed(egui::Key::T) && !i.modifiers.command) {
+            self.tools_menu.open = !self.tools_menu.open;
+        }
+
+        // F7 scans the folder for near-duplicate images
+        if ctx.input(|i| i.key_pressed(egui::Key::F7)) && !self.current_folder_images.is_empty() {
+            self.duplicates.start(ctx, &self.current_folder_images);
+        }
+
+        // Ctrl+L opens the library of indexed folders
+        if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::L)) {
+            self.library_window.open = !self.library_window.open;
+        }
+
+        // F10 compares two folders of images pair by pair
+        if ctx.input(|i| i.key_pressed(egui::Key::F10)) {
+            self.folder_compare.open = !self.folder_compare.open;
+        }
+
+        // Ctrl+E saves the visible view as PNG, Ctrl+Shift+E copies it; hold Alt to keep overlays
+        if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::E)) {
+            let (shift, alt) = ctx.input(|i| (i.modifiers.shift, i.modifiers.alt));
+            let target = if shift {
+                Some(ExportTarget::Clipboard)
+            } else {
+                rfd::FileDialog::new()
+                    .add_filter("PNG", &["png"])
+                    .set_file_name("view.png")
+                    .save_file()
+                    .map(ExportTarget::File)
+            };
+            if let Some(target) = target {
+                self.viewport_export.request(target, alt);
+            }
+        }
+
+        // F8 switches between name order and visual similarity order
+        if ctx.input(|i| i.key_pressed(egui::Key::F8)) {
+            self.toggle_sort_order();
+        }
+
+        // V toggles the continuous vertical strip (webtoon) mode
+        if ctx.input(|i| i.key_pressed(egui::Key::V) && !i.modifiers.any()) {
+            self.toggle_strip_mode();
+        }
+
+        // Alt+Left/Right step through the recent zoom/pan states of the current image
+        if ctx.input(|i| i.modifiers.alt) {
+            if ctx.input(|i| i.key_pressed(egui::Key::ArrowLeft)) {
+                self.view_state.step_history(-1);
+            } else if ctx.input(|i| i.key_pressed(egui::Key::ArrowRight)) {
+                self.view_state.step_history(1);
+            }
+        } else if self.sort_order == SortOrder::CaptureDate && ctx.input(|i| i.modifiers.command) {
+            // Ctrl+Left/Right jump between shooting sessions in capture date order
+            if ctx.input(|i| i.key_released(egui::Key::ArrowRight)) {
+                self.step_session(1);
+            } else if ctx.input(|i| i.key_released(egui::Key::ArrowLeft)) {
+                self.step_session(-1);
+            }
+        } else if !self.current_folder_images.is_empty() && !self.thumbnail_list.is_expanded() {
+            // The expanded filmstrip uses the arrow keys for its own cursor
+            // egui's key_pressed returns true repeatedly if the key is held down (key repeat).
+            // To only trigger once per physical press, we can check if it was pressed this frame
+            // but we actually want to avoid the OS key repeat.
+            // A simpler way in egui is to check `key_pressed` but we need to track if it was already handled.
+            // Actually, `key_pressed` in egui *does* repeat.
+            // To avoid repeat, we can track the previous frame's key state, or use `key_released`.
+            // Let's use `key_released` for single action per press.
+            if ctx.input(|i| i.key_released(egui::Key::ArrowRight) || i.key_released(egui::Key::D))
+            {
+                self.next_image();
+            } else if ctx
+                .input(|i| i.key_released(egui::Key::ArrowLeft) || i.key_released(egui::Key::A))
+            {
+                self.prev_image();
+            }
+        }
+    }
+
+    /// Switches to settings edited outside the viewer, re-applying the ones that are
+    /// cached elsewhere.
+    fn apply_config(&mut self, config: AppConfig) {
+        let old = std::mem::replace(&mut self.config, config);
+        if self.config.display_profile != old.display_profile {
+            self.set_render_option(ImageCommand::SetDisplayProfile(self.config.display_profile));
+        }
+        self.thumbnail_list.set_dock(self.config.filmstrip_dock);
+        self.view_state.set_zoom_settings(self.config.zoom);
+        self.view_state.pan_scheme = self.config.pan_scheme;
+        self.eyedropper.size = self.config.eyedropper_sample;
+        // The folder is listed again when what counts as one of its images changed
+        if self.config.extensions != old.extensions
+            || self.config.include_hidden != old.include_hidden
+            || self.config.follow_symlinks != old.follow_symlinks
+        {
+            self.rescan_folder();
+        }
+        osd::toast(&self.egui_ctx, "Settings reloaded");
+    }
+
+    fn export_app_data(&self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Viewer data", &[app_data::EXTENSION])
+            .set_file_name("image-viewer-data.json")
+            .save_file()
+        else {
+            return;
+        };
+        match app_data::export(&path, &self.config, &self.tags) {
+            Ok(()) => osd::toast(&self.egui_ctx, format!("Exported to {}", path.display())),
+            Err(e) => osd::toast(&self.egui_ctx, format!("Export failed: {}", e)),
+        }
+    }
+
+    /// Replaces the settings with exported ones and adds the exported tags.
+    fn import_app_data(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Viewer data", &[app_data::EXTENSION])
+            .pick_file()
+        else {
+            return;
+        };
+        match app_data::import(&path, &self.config) {
+            Ok((config, tags)) => {
+                self.tags.merge(tags);
+                self.apply_config(config);
+                if !self.config.library_roots.is_empty() {
+                    self.library.start_indexing(
+                        &self.egui_ctx,
+                        &self.config.library_roots,
+                        &self.config.extensions,
+                    );
+                }
+                osd::toast(&self.egui_ctx, "Settings and tags imported");
+            }
+            Err(e) => osd::toast(&self.egui_ctx, format!("Import failed: {}", e)),
+        }
+    }
+
+    /// Puts the window back in the state it was left in, pulling it onto the screen if it
+    /// no longer fits the monitor (the layout changed, or a monitor was unplugged).
+    fn restore_window(&self, ctx: &egui::Context) {
+        let (outer, monitor) = ctx.input(|i| (i.viewport().outer_rect, i.viewport().monitor_size));
+        if let (Some(outer), Some(monitor)) = (outer, monitor) {
+            let same_monitor = self
+                .config
+                .window_monitor
+                .is_none_or(|saved| egui::Vec2::from(saved) == monitor);
+            let fits = outer.width() <= monitor.x && outer.height() <= monitor.y;
+            // A window whose title bar is off every monitor can't be dragged back
+            let title_bar = egui::pos2(outer.center().x, outer.min.y + TITLE_BAR_PROBE);
+            let reachable =
+                monitor::is_on_screen(title_bar, ctx.pixels_per_point()).unwrap_or(true);
+            if !same_monitor || !fits || !reachable {
+                let size = egui::Vec2::from(self.config.window_size.unwrap_or([800.0, 600.0]))
+                    .min(monitor * 0.9);
+                ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+                // Centered on the nearest monitor, assuming the window will be the new size.
+                // Where monitors can't be located the window manager places the window.
+                if let Some(area) = monitor::work_area_near(outer.center(), ctx.pixels_per_point())
+                {
+                    let outer_size =
+                        size + (outer.size() - ctx.screen_rect().size()).max(egui::Vec2::ZERO);
+                    let pos = area.min + ((area.size() - outer_size) / 2.0).max(egui::Vec2::ZERO);
+                    ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(pos));
+                }
+            }
+        }
+
+        // Delay maximization to the first frame.
+        // This prevents winit bugs on Windows where setting size/position
+        // inside the builder conflicts with the maximized state.
+        if self.config.is_fullscreen {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+        } else if self.config.is_maximized == Some(true) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(true));
+        }
+    }
+
+    /// The part of the window's monitor that windows may cover, in desktop coordinates.
+    /// Taskbars and docks are reported on Windows only; elsewhere this is where the window
+    /// was last maximized to, if that was on this monitor, and unknown until then.
+    fn work_rect(&self, ctx: &egui::Context) -> Option<egui::Rect> {
+        let outer = ctx.input(|i| i.viewport().outer_rect)?;
+        monitor::work_area_near(outer.center(), ctx.pixels_per_point()).or_else(|| {
+            let monitor = ctx.input(|i| i.viewport().monitor_size)?;
+            self.maximized_area
+                .filter(|(size, area)| *size == monitor && area.contains(outer.center()))
+                .map(|(_, area)| area)
+        })
+    }
+
+    /// The size of the work area, or the monitor less room for a taskbar while its
+    /// position isn't known.
+    fn work_size(&self, ctx: &egui::Context) -> Option<egui::Vec2> {
+        self.work_rect(ctx).map(|area| area.size()).or_else(|| {
+            ctx.input(|i| i.viewport().monitor_size)
+                .map(|monitor| monitor - egui::vec2(0.0, TASKBAR_RESERVE))
+        })
+    }
+
+    /// Resizes the window around the current image with nothing else in it, keeping it in
+    /// the work area: at 100% (cropped if the image is larger), or with `fit` shrunk until
+    /// the whole image fits. Returns the scale the image is shown at.
+    fn size_window(&mut self, ctx: &egui::Context, fit: bool) -> Option<f32> {
+        let texture = self
+            .current_image_path
+            .as_ref()
+            .and_then(|p| self.texture_cache.get(p))?;
+        let outer = ctx.input(|i| i.viewport().outer_rect);
+        // At 100% an image pixel is a device pixel, not a point
+        let image = texture.size_vec2() / ctx.pixels_per_point();
+        let frame = outer.map_or(egui::Vec2::ZERO, |outer| {
+            (outer.size() - ctx.screen_rect().size()).max(egui::Vec2::ZERO)
+        });
+        let room = self.work_size(ctx).map(|size| (size - frame).max(MIN_WINDOW));
+        // Either side may be the limit: wide images on portrait monitors, tall ones on
+        // ultrawides
+        let scale = match room {
+            Some(room) if fit => (room.x / image.x).min(room.y / image.y).min(1.0),
+            _ => 1.0,
+        };
+        let size = match room {
+            Some(room) => (image * scale).min(room),
+            None => image * scale,
+        }
+        .max(MIN_WINDOW);
+
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+        // Moved back into the work area if the larger window would hang out of it. Left
+        // alone while the work area's position is unknown rather than moved to another monitor.
+        if let (Some(outer), Some(area)) = (outer, self.work_rect(ctx)) {
+            let max = (area.max - (size + frame)).max(area.min);
+            let pos = outer.min.clamp(area.min, max);
+            if pos != outer.min {
+                ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(pos));
+            }
+        }
+        Some(scale)
+    }
+
+    /// Resizes the window to show the current image at 100% with nothing around it, as
+    /// far as the monitor allows, for clean screenshots and pixel inspection.
+    fn size_window_to_image(&mut self, ctx: &egui::Context) {
+        if self.size_window(ctx, false).is_some() {
+            self.view_state
+                .animate_to(self.view_state.from_scale(1.0), egui::Vec2::ZERO);
+            osd::toast(ctx, "Window sized to the image");
+        }
+    }
+
+    /// Shows the whole current image as large as the work area allows (up to 100%), with
+    /// the window sized around it. A maximized or fullscreen window is left as it is, and
+    /// the image fitted to it.
+    fn fit_to_work_area(&mut self, ctx: &egui::Context) {
+        let (maximized, fullscreen) = ctx.input(|i| {
+            (
+                i.viewport().maximized.unwrap_or(false),
+                i.viewport().fullscreen.unwrap_or(false),
+            )
+        });
+        if maximized || fullscreen || self.slideshow.active || self.kiosk.is_some() {
+            self.fit_requested = true;
+        } else if let Some(scale) = self.size_window(ctx, true) {
+            self.view_state
+                .restore(self.view_state.from_scale(scale), egui::Vec2::ZERO);
+        }
+    }
+
+    fn toggle_fit_to_work_area(&mut self, ctx: &egui::Context) {
+        self.config.fit_to_work_area = !self.config.fit_to_work_area;
+        self.config.mark_dirty();
+        if self.config.fit_to_work_area {
+            self.fit_to_work_area(ctx);
+            osd::toast(ctx, "Images fit the monitor");
+        } else {
+            osd::toast(ctx, "Images open at 100%");
+        }
+    }
+
+    /// A badge and its explanation when what's on screen isn't the file's own pixels, so
+    /// a stand-in isn't judged for sharpness.
+    fn proxy_badge(&self) -> Option<(String, String)> {
+        let path = self.current_image_path.as_ref()?;
+        if self.is_loading() && self.preview.as_ref().is_some_and(|(p, _)| p == path) {
+            return Some((
+                "Preview".to_string(),
+                "The thumbnail embedded in the file, until the image is decoded".to_string(),
+            ));
+        }
+        let [width, height] = *self.reduced.get(path)?;
+        let shown = self.texture_cache.get(path)?.size();
+        (shown != [width, height]).then(|| {
+            (
+                format!("Reduced to {}%", 100 * shown[0] / width.max(1)),
+                format!(
+                    "Shown at {} × {} of {} × {} px, the largest texture the graphics card takes",
+                    shown[0], shown[1], width, height
+                ),
+            )
+        })
+    }
+
+    fn eyedropper_action(&mut self, ctx: &egui::Context, action: EyedropperAction) {
+        match action {
+            EyedropperAction::SetSize(size) => {
+                self.config.eyedropper_sample = size;
+                self.config.mark_dirty();
+            }
+            EyedropperAction::Copy(hex) => {
+                ctx.copy_text(hex.clone());
+                osd::toast(ctx, format!("Copied {}", hex));
+            }
+        }
+    }
+
+    fn slide_interval(&self) -> Duration {
+        Duration::from_secs_f32(self.config.slideshow_interval.max(0.5))
+    }
+
+    fn start_presentation(&mut self, ctx: &egui::Context) {
+        if self.slideshow.active {
+            self.toggle_slideshow(ctx);
+        }
+        self.slideshow.toggle();
+        self.presentation.start(ctx, self.config.presentation_display);
+    }
+
+    fn stop_presentation(&mut self) {
+        self.presentation.stop();
+        if self.slideshow.active {
+            self.slideshow.toggle();
+        }
+    }
+
+    fn toggle_slideshow(&mut self, ctx: &egui::Context) {
+        self.slideshow.toggle();
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(self.slideshow.active));
+        if !self.slideshow.active && self.config.ken_burns {
+            // Leave the Ken Burns framing behind
+            self.view_state.reset();
+        }
+    }
+
+    /// Error card with the failing file, the decoder's message and ways to move on.
+    fn show_error(&mut self, ctx: &egui::Context, err: &str) {
+        let path = self.current_image_path.clone();
+        let can_navigate = self.current_folder_images.len() > 1;
+        let (mut retry, mut open_system, mut prev, mut next) = (false, false, false, false);
+
+        egui::Area::new(egui::Id::new("error_view"))
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_max_width(520.0);
+                    ui.heading("Couldn't show this image");
+                    if let Some(path) = &path {
+                        ui.add(egui::Label::new(path.display().to_string()).wrap());
+                    }
+                    ui.separator();
+                    egui::ScrollArea::vertical().max_height(160.0).show(ui, |ui| {
+                        ui.add(egui::Label::new(egui::RichText::new(err).monospace()).wrap());
+                    });
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if path.is_some() {
+                            retry = ui.button("Retry").clicked();
+                            // Kiosk mode hands nothing to other programs
+                            if self.kiosk.is_none() {
+                                open_system = ui.button("Open with system viewer").clicked();
+                            }
+                        }
+                        if can_navigate {
+                            prev = ui.button("◀ Previous").clicked();
+                            next = ui.button("Next ▶").clicked();
+                        }
+                    });
+                    if can_navigate {
+                        ui.weak("← / → keep browsing past this file");
+                    }
+                });
+            });
+
+        if let Some(path) = path {
+            if retry {
+                self.failed_paths.remove(&path);
+                self.corrupt_paths.remove(&path);
+                self.texture_cache.remove(&path);
+                self.load_file(path, false);
+            } else if open_system
+                && let Err(e) = external_tools::open_with_system(&path)
+            {
+                self.error_msg = Some(format!("{}\n\nCould not open the system viewer: {}", err, e));
+            }
+        }
+        if prev {
+            self.prev_image();
+        } else if next {
+            self.next_image();
+        }
+    }
+
+    /// Shows another page of the current PDF.
+    #[cfg(feature = "pdf")]
+    fn step_pdf_page(&mut self, step: isize) {
+        let Some(path) = self.current_image_path.clone() else {
+            return;
+        };
+        let Some(view) = self.pdf_views.get_mut(&path) else {
+            return;
+        };
+        let page = (view.page as isize + step).clamp(0, view.page_count as isize - 1) as usize;
+        if page == view.page {
+            return;
+        }
+        view.page = page;
+        view.scale = pdf::DEFAULT_SCALE;
+        self.texture_cache.remove(&path);
+        self.reset_view_on_load = true;
+        self.request_load(path);
+    }
+
+    /// Renders the current PDF page again once the view settles at a zoom where the
+    /// texture is noticeably too coarse or needlessly large.
+    #[cfg(feature = "pdf")]
+    fn update_pdf_resolution(&mut self, ctx: &egui::Context) {
+        let Some(path) = &self.current_image_path else {
+            return;
+        };
+        let Some(view) = self.pdf_views.get(path) else {
+            return;
+        };
+        let zoom = self.view_state.zoom;
+        if !self.texture_cache.contains_key(path)
+            || (zoom - self.view_state.target_zoom).abs() > zoom * 0.01
+        {
+            return;
+        }
+
+        // One texture pixel per screen pixel is ideal
+        let pixels_per_texel = zoom * ctx.pixels_per_point();
+        if (0.6..=1.5).contains(&pixels_per_texel) {
+            return;
+        }
+        let scale = (view.scale * pixels_per_texel).clamp(0.25, 16.0);
+        let request = (path.clone(), scale);
+        if self.pdf_rerender.as_ref() == Some(&request) {
+            return;
+        }
+        // The old texture stays on screen until the new one arrives
+        let command = ImageCommand::LoadPdfPage(path.clone(), view.page, scale);
+        self.loader.send(command);
+        self.pdf_rerender = Some(request);
+    }
+
+    /// Whether any listed image hasn't failed to decode yet.
+    fn has_readable_images(&self) -> bool {
+        self.current_folder_images
+            .iter()
+            .any(|p| !self.failed_paths.contains(p))
+    }
+
+    /// Moves `current_image_index` past files known to be unreadable, in the direction
+    /// of `step`, if skipping is enabled.
+    fn skip_failed(&mut self, step: isize) {
+        if !self.config.skip_unreadable || !self.has_readable_images() {
+            return;
+        }
+        let len = self.current_folder_images.len() as isize;
+        while self
+            .failed_paths
+            .contains(&self.current_folder_images[self.current_image_index])
+        {
+            self.current_image_index =
+                (self.current_image_index as isize + step).rem_euclid(len) as usize;
+        }
+    }
+
+    fn next_image(&mut self) {
+        if self.current_folder_images.is_empty() {
+            return;
+        }
+        let step = if self.spread_partner(self.current_image_index).is_some() {
+            2
+        } else {
+            1
+        };
+        if self.current_image_index + step >= self.current_folder_images.len()
+            && self.leave_folder(1)
+        {
+            return;
+        }
+        self.current_image_index =
+            (self.current_image_index + step) % self.current_folder_images.len();
+        self.skip_failed(1);
+        self.load_file(
+            self.current_folder_images[self.current_image_index].clone(),
+            false,
+        );
+        self.nav_step = 1;
+    }
+
+    /// Goes to the first image of the next (1) or current/previous (-1) session, like
+    /// chapters: back from inside a session returns to its start.
+    fn step_session(&mut self, step: isize) {
+        let starts = timeline::sessions(&self.current_folder_images, &self.capture_times);
+        let current = self.current_image_index;
+        let target = if step > 0 {
+            starts.iter().map(|(i, _)| *i).find(|&i| i > current)
+        } else {
+            starts.iter().map(|(i, _)| *i).rev().find(|&i| i < current)
+        };
+        if let Some(index) = target {
+            self.current_image_index = index;
+            self.load_file(self.current_folder_images[index].clone(), false);
+            self.nav_step = 0;
+        }
+    }
+
+    fn prev_image(&mut self) {
+        if self.current_folder_images.is_empty() {
+            return;
+        }
+        if self.current_image_index == 0 {
+            if self.leave_folder(-1) {
+                return;
+            }
+            self.current_image_index = self.current_folder_images.len() - 1;
+        } else if self.current_image_index >= 2
+            && self.spread_partner(self.current_image_index - 2) == Some(self.current_image_index - 1)
+        {
+            // Step back over the whole previous spread
+            self.current_image_index -= 2;
+        } else {
+            self.current_image_index -= 1;
+        }
+        self.skip_failed(-1);
+        self.load_file(
+            self.current_folder_images[self.current_image_index].clone(),
+            false,
+        );
+        self.nav_step = -1;
+    }
+
+    /// Copies the window position, size and fullscreen state into the config.
+    fn remember_window_state(&mut self, ctx: &egui::Context) {
+        let window_info = ctx.input(|i| i.viewport().clone());
+        let mut changed = false;
+
+        let is_maximized = window_info.maximized.unwrap_or(false);
+        let is_minimized = window_info.minimized.unwrap_or(false);
+        let is_fullscreen = window_info.fullscreen.unwrap_or(false);
+
+        // Only save position and size if not maximized, minimized or fullscreen
+        if !is_maximized && !is_minimized && !is_fullscreen {
+            // Usually it's better to save the outer position, but inner_rect is what eframe provides readily
+            if let Some(pos) = window_info.inner_rect.map(|r| r.min) {
+                // Ignore suspicious (0,0) or negative positions which might occur during window creation/snapping
+                if pos.x > -10000.0 && pos.y > -10000.0 {
+                    let new_pos = [pos.x, pos.y];
+                    if self.config.window_pos != Some(new_pos) {
+                        self.config.window_pos = Some(new_pos);
+                        changed = true;
+                    }
+                }
+            }
+
+            if let Some(size) = window_info.inner_rect.map(|r| r.size()) {
+                let new_size = [size.x, size.y];
+                if self.config.window_size != Some(new_size) {
+                    self.config.window_size = Some(new_size);
+                    changed = true;
+                }
+            }
+
+            if let Some(monitor) = window_info.monitor_size {
+                let monitor = [monitor.x, monitor.y];
+                if self.config.window_monitor != Some(monitor) {
+                    self.config.window_monitor = Some(monitor);
+                    changed = true;
+                }
+            }
+        }
+
+        // Slideshows go fullscreen on their own; that isn't the user's window state
+        if !self.slideshow.active && self.config.is_fullscreen != is_fullscreen {
+            self.config.is_fullscreen = is_fullscreen;
+            changed = true;
+        }
+
+        if let Some(is_max) = window_info.maximized
+            && !is_fullscreen
+            && self.config.is_maximized != Some(is_max)
+        {
+            self.config.is_maximized = Some(is_max);
+            changed = true;
+        }
+
+        // A maximized window covers the work area, so it shows where the monitor and taskbar are
+        if is_maximized
+            && !is_fullscreen
+            && let (Some(outer), Some(monitor)) = (window_info.outer_rect, window_info.monitor_size)
+        {
+            self.maximized_area = Some((monitor, outer));
+        }
+
+        if changed {
+            self.config.mark_dirty();
+        }
+    }
+}
+
+impl eframe::App for ImageViewer {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.first_frame {
+            self.first_frame = false;
+            self.restore_window(ctx);
+        }
+        if let Some(rx) = &self.startup_preview
+            && let Ok(image) = rx.try_recv()
+        {
+            self.startup_preview = None;
+            if let Some(path) = self.current_image_path.clone()
+                && self.is_loading()
+            {
+                let texture =
+                    ctx.load_texture("startup_preview", image, egui::TextureOptions::LINEAR);
+                self.preview = Some((path, texture));
+            }
+        }
+        if self.index_after_startup && !self.is_loading() && self.folder_scan.is_none() {
+            self.index_after_startup = false;
+            self.library
+                .start_indexing(ctx, &self.config.library_roots, &self.config.extensions);
+        }
+
+        // The image on screen when closing is never left through `load_file`
+        if self.config.view_memory == ViewMemory::Persistent
+            && ctx.input(|i| i.viewport().close_requested())
+        {
+            self.remember_view();
+            self.config.mark_dirty();
+        }
+
+        if let Some(config) = self.config_watcher.poll(&self.config) {
+            self.apply_config(config);
+        }
+        self.poll_folder_scan();
+        #[cfg(feature = "pdf")]
+        self.update_pdf_resolution(ctx);
+
+        // Process Thumbnail Loading
+        self.thumbnail_list.process_results(ctx, &self.current_folder_images, self.current_image_index);
+
+        // 1. Handle Async Results
+        while let Ok(result) = self.loader.rx.try_recv() {
+            match result {
+                ImageResult::Success(path, image) => {
+                    println!("UI: Received texture for {:?}", path);
+                    self.loading_paths.remove(&path);
+                    self.slow_paths.remove(&path);
+
+                    let previous = self.texture_cache.remove(&path);
+                    let texture = self.texture_pool.upload(
+                        ctx,
+                        path.to_string_lossy().to_string(),
+                        image.clone(),
+                        previous,
+                    );
+                    self.texture_cache.insert(path.clone(), texture);
+                    self.thumbnail_list.refresh_placeholder(&path);
+                    self.preview = None;
+
+                    #[cfg(feature = "pdf")]
+                    if let Some((rescaled, factor)) = self.pdf_rescale.take_if(|(p, _)| *p == path)
+                        && Some(&rescaled) == self.current_image_path.as_ref()
+                    {
+                        self.view_state.rescale(factor);
+                    }
+                    if Some(path.clone()) == self.current_image_path {
+                        self.last_loaded_path = Some(path.to_string_lossy().to_string());
+                        self.image_size = Some(image.size);
+                        if self.reset_view_on_load {
+                            self.view_state.reset();
+                            if !self.restore_view(&path) && self.config.fit_to_work_area {
+                                self.fit_to_work_area(ctx);
+                            }
+                            self.reset_view_on_load = false;
+                        }
+                    }
+                }
+                ImageResult::HighBitDepth(path) => {
+                    self.high_bit_depth.insert(path);
+                }
+                ImageResult::Format(path, info) => {
+                    self.format_info.insert(path, info);
+                }
+                ImageResult::Reduced(path, size) => {
+                    self.reduced.insert(path, size);
+                }
+                ImageResult::Slow(path) => {
+                    self.slow_paths.insert(path);
+                }
+                ImageResult::Corrupt(path) => {
+                    self.corrupt_paths.insert(path);
+                }
+                #[cfg(feature = "pdf")]
+                ImageResult::PdfPage { path, page, page_count, scale } => {
+                    let view = PdfView { page, page_count, scale };
+                    if let Some(old) = self.pdf_views.insert(path.clone(), view)
+                        && old.page == page
+                        && old.scale != scale
+                        && self.texture_cache.contains_key(&path)
+                    {
+                        self.pdf_rescale = Some((path, scale / old.scale));
+                    }
+                }
+                ImageResult::Missing(path) => {
+                    self.loading_paths.remove(&path);
+                    self.slow_paths.remove(&path);
+                    if Some(&path) == self.current_image_path.as_ref() {
+                        self.drop_missing(path);
+                    } else {
+                        // A neighbour that went away while preloading
+                        self.remove_paths(&[path]);
+                    }
+                }
+                ImageResult::Error(path, err) => {
+                    println!("UI: Received Error for {:?}: {}", path, err);
+                    self.loading_paths.remove(&path);
+                    self.slow_paths.remove(&path);
+                    self.failed_paths.insert(path.clone());
+                    if Some(&path) == self.current_image_path.as_ref() {
+                        let step = self.nav_step;
+                        if self.config.skip_unreadable
+                            && step != 0
+                            && self.has_readable_images()
+                        {
+                            let name = path.file_name().unwrap_or_default().to_string_lossy();
+                            osd::toast(ctx, format!("Skipped {} (unreadable)", name));
+                            if step > 0 {
+                                self.next_image();
+                            } else {
+                                self.prev_image();
+                            }
+                        } else {
+                            self.error_msg = Some(err);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Files can be deleted while the viewer is in the background
+        let focused = ctx.input(|i| i.viewport().focused.unwrap_or(true));
+        if focused
+            && !self.window_focused
+            && let Some(path) = self.current_image_path.clone()
+            && self.current_folder_images.contains(&path)
+            && !path.exists()
+        {
+            self.drop_missing(path);
+        }
+        self.window_focused = focused;
+
+        // 2. Handle File Drops
+        if self.kiosk.is_none() && !ctx.input(|i| i.raw.dropped_files.is_empty()) {
+            let dropped_files = ctx.input(|i| i.raw.dropped_files.clone());
+            // Paths are the norm on native; bytes and links are stored or downloaded first
+            let mut paths = Vec::new();
+            for file in &dropped_files {
+                match drop_import::resolve(file) {
+                    Ok(Dropped::Path(path)) => paths.push(path),
+                    Ok(Dropped::Url(url)) => self.downloader.download(ctx, url),
+                    Err(e) => self.downloader.set_status(e),
+                }
+            }
+            if paths.len() > 1 {
+                // Several files at once become an ad-hoc list, kept in drop order
+                let images: Vec<PathBuf> = paths
+                    .into_iter()
+                    .filter(|p| p.is_file() && is_supported_image(p, &self.config.extensions))
+                    .collect();
+                if !images.is_empty() {
+                    self.load_list(images);
+                }
+            } else if let Some(path) = paths.into_iter().next() {
+                self.load_path(path);
+            }
+        }
+
+        if let Some(path) = self.downloader.poll() {
+            self.load_path(path);
+        }
+
+        self.handle_remote(ctx);
+        #[cfg(feature = "tray")]
+        self.handle_tray(ctx);
+        #[cfg(all(windows, feature = "taskbar"))]
+        {
+            self.taskbar.set_recent(&self.config.recent_files);
+            self.taskbar.set_progress(self.batch.progress());
+        }
+        #[cfg(feature = "screenshot")]
+        self.handle_screen_capture(ctx);
+
+        // Handle Keyboard Navigation (unless a text field has focus)
+        if let Some(kiosk) = &mut self.kiosk {
+            kiosk.update(ctx);
+            if !self.slideshow.active && !self.current_folder_images.is_empty() {
+                self.toggle_slideshow(ctx);
+            }
+        } else if !ctx.wants_keyboard_input() {
+            self.handle_shortcuts(ctx);
+        }
+
+        // Slides advance once the current one (or its error) has been up for the interval
+        let shown = self
+            .current_image_path
+            .as_deref()
+            .filter(|p| self.texture_cache.contains_key(*p) || self.error_msg.is_some());
+        if self.slideshow.advance_due(ctx, shown, self.slide_interval()) {
+            self.next_image();
+        }
+        self.update_folder_watch(ctx);
+
+        // Fade overlays out once the mouse rests in fullscreen or a slideshow
+        let immersive =
+            self.slideshow.active || ctx.input(|i| i.viewport().fullscreen.unwrap_or(false));
+        if ctx.input(|i| i.pointer.delta() != egui::Vec2::ZERO || i.pointer.any_down()) {
+            self.last_activity = Instant::now();
+        }
+        let idle_for = self.last_activity.elapsed();
+        let idle = immersive && idle_for >= IDLE_HIDE;
+        let dt = ctx.input(|i| i.stable_dt).min(0.1);
+        if exp_decay(&mut self.ui_opacity, if idle { 0.0 } else { 1.0 }, dt, 6.0) {
+            ctx.request_repaint();
+        } else if immersive && !idle {
+            ctx.request_repaint_after(IDLE_HIDE - idle_for);
+        }
+
+        // Update Window Title based on current image
+        if let Some(path) = &self.current_image_path {
+            if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Title(format!(
+                    "{} - Better Image Viewer",
+                    file_name
+                )));
+            }
+        } else {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(
+                "Better Image Viewer".to_string(),
+            ));
+        }
+
+        // Overlays are skipped for the frame captured by a clean viewport export
+        let show_overlays = self.viewport_export.show_overlays() && self.kiosk.is_none();
+
+        if show_overlays {
+            // Shapes span both pages of a spread but export only redraws the current one
+            let spread = self.spread_partner(self.current_image_index).is_some();
+            if let Some(target) = self.annotations.toolbar(ctx, !spread)
+                && let Some(path) = self.current_image_path.clone()
+            {
+                self.annotations.export(ctx, path, target);
+            }
+            self.redaction.toolbar(ctx, self.current_image_path.as_deref());
+            self.straighten.toolbar(ctx, self.current_image_path.as_deref());
+            #[cfg(feature = "ocr")]
+            self.ocr.show_status(ctx);
+            #[cfg(feature = "cutout")]
+            self.cutout.show_status(ctx);
+        }
+        // Captured clipboard images live in a session folder, browsed like any other
+        if let Some(path) = self.clipboard_watcher.poll() {
+            self.load_path(path);
+        }
+        if show_overlays {
+            self.clipboard_watcher.show_status(ctx);
+            self.downloader.show_status(ctx);
+            self.clean_copy.show_status(ctx);
+
+            // View bookmarks and render modes of the current image. Exposure only applies to
+            // high-bit-depth images, so it's only shown for those
+            if self
+                .current_image_path
+                .as_ref()
+                .is_some_and(|p| self.corrupt_paths.contains(p))
+                && self.error_msg.is_none()
+            {
+                // Below the filter bar when that's open
+                let offset = if self.filter_bar.open { 56.0 } else { 10.0 };
+                egui::Area::new(egui::Id::new("corrupt_banner"))
+                    .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, offset))
+                    .interactable(false)
+                    .show(ctx, |ui| {
+                        ui.multiply_opacity(self.ui_opacity);
+                        egui::Frame::popup(ui.style())
+                            .fill(egui::Color32::from_rgb(110, 70, 0))
+                            .show(ui, |ui| {
+                                ui.colored_label(
+                                    egui::Color32::WHITE,
+                                    "⚠ This file is corrupt or truncated; gray areas couldn't be decoded",
+                                );
+                            });
+                    });
+            }
+
+            if let Some((badge, explanation)) = self.proxy_badge() {
+                let offset = if self.filter_bar.open { 56.0 } else { 10.0 };
+                egui::Area::new(egui::Id::new("proxy_badge"))
+                    .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10.0, offset))
+                    .show(ctx, |ui| {
+                        ui.multiply_opacity(self.ui_opacity);
+                        egui::Frame::popup(ui.style())
+                            .rounding(10.0)
+                            .inner_margin(egui::Margin::symmetric(8.0, 2.0))
+                            .show(ui, |ui| ui.weak(badge))
+                            .response
+                            .on_hover_text(explanation);
+                    });
+            }
+
+            let mut render_status = Vec::new();
+            #[cfg(feature = "pdf")]
+            if let Some(view) = self
+                .current_image_path
+                .as_ref()
+                .and_then(|p| self.pdf_views.get(p))
+                .filter(|v| v.page_count > 1)
+            {
+                render_status.push(format!("Page {} / {}", view.page + 1, view.page_count));
+            }
+            if let Some(path) = &self.current_image_path
+                && self.sequence_hint.as_ref().is_none_or(|(p, _)| p != path)
+            {
+                let frames = sequence::detect(path, &self.current_folder_images)
+                    .map_or(0, |frames| frames.len());
+                self.sequence_hint = Some((path.clone(), frames));
+            }
+            if let Some((_, frames)) = self.sequence_hint.as_ref().filter(|(_, n)| *n > 0) {
+                render_status.push(format!("Frame sequence: {} frames (P to play)", frames));
+            }
+            if self
+                .current_image_path
+                .as_ref()
+                .is_some_and(|p| video::is_video(p))
+            {
+                render_status.push("Video: first frame (Enter to play)".to_string());
+            }
+            if let Some(scan) = &self.folder_scan {
+                render_status.push(format!("Scanning folder… {} images", scan.found()));
+            }
+            if let Some(bookmarks) = self
+                .current_image_path
+                .as_ref()
+                .and_then(|p| self.view_bookmarks.get(p))
+                && bookmarks.iter().any(Option::is_some)
+            {
+                let slots: Vec<String> = (1..=9)
+                    .filter(|n| bookmarks[n - 1].is_some())
+                    .map(|n| n.to_string())
+                    .collect();
+                render_status.push(format!("Bookmarks: {}", slots.join(" ")));
+            }
+            if let Some(colormap) = self.false_color {
+                render_status.push(format!("False color: {}", colormap.label()));
+            } else if self.exposure != 0.0 && self.current_is_high_bit_depth() {
+                render_status.push(format!("Exposure {:+.1} EV", self.exposure));
+            }
+            if self.sort_order == SortOrder::CaptureDate
+                && let Some(time) = self
+                    .current_image_path
+                    .as_ref()
+                    .and_then(|p| self.capture_times.get(p))
+            {
+                render_status.push(format!("Captured {}", timeline::format_time(*time)));
+            }
+            // The zoom readout doubles as a menu of preset levels
+            let zoomable = !self.strip_mode
+                && self
+                    .current_image_path
+                    .as_ref()
+                    .is_some_and(|p| self.texture_cache.contains_key(p));
+            if !render_status.is_empty() || zoomable {
+                let mut preset = None;
+                egui::Area::new(egui::Id::new("render_status"))
+                    .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+                    .show(ctx, |ui| {
+                        ui.multiply_opacity(self.ui_opacity);
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            for line in &render_status {
+                                ui.label(line);
+                            }
+                            if zoomable {
+                                let scale = self.view_state.to_scale(self.view_state.target_zoom);
+                                preset = zoom_indicator::readout(
+                                    ui,
+                                    scale,
+                                    self.config.fit_to_work_area,
+                                );
+                            }
+                        });
+                    });
+                match preset {
+                    Some(ZoomPreset::Fit) => self.fit_requested = true,
+                    Some(ZoomPreset::SizeWindow) => self.size_window_to_image(ctx),
+                    Some(ZoomPreset::FitWorkArea) => self.toggle_fit_to_work_area(ctx),
+                    Some(ZoomPreset::Scale(scale)) => {
+                        let zoom = self.view_state.from_scale(scale);
+                        // Zoom about the middle of the window
+                        let pan = self.view_state.target_pan * (zoom / self.view_state.target_zoom);
+                        self.view_state.animate_to(zoom, pan);
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        osd::show(ctx, self.ui_opacity);
+        self.animated_export.show(ctx);
+        self.sequence_player.show(ctx);
+        let next_path = (!self.current_folder_images.is_empty()).then(|| {
+            let next = (self.current_image_index + 1) % self.current_folder_images.len();
+            self.current_folder_images[next].clone()
+        });
+        let slide = Slide {
+            current: self
+                .current_image_path
+                .as_ref()
+                .and_then(|p| self.texture_cache.get(p)),
+            next: next_path.as_ref().and_then(|p| self.texture_cache.get(p)),
+            next_path: next_path.as_deref(),
+            ken_burns: self.config.ken_burns,
+            interval: self.slide_interval(),
+        };
+        match self.presentation.show(ctx, &mut self.slideshow, slide) {
+            PresentationAction::Stop => self.stop_presentation(),
+            PresentationAction::DisplayChanged(display) => {
+                self.config.presentation_display = display;
+                self.config.mark_dirty();
+            }
+            PresentationAction::None => {}
+        }
+        let sharpness = self
+            .current_image_path
+            .as_deref()
+            .and_then(|p| self.sharpness.get(p));
+        let badges = self.config.sharpness_badges;
+        self.metadata_panel.show(
+            ctx,
+            self.current_image_path.as_deref(),
+            &self.current_folder_images,
+            sharpness,
+            self.current_image_path
+                .as_ref()
+                .and_then(|p| self.format_info.get(p)),
+            &mut self.config.sharpness_badges,
+        );
+        if self.config.sharpness_badges != badges {
+            self.config.mark_dirty();
+        }
+        if self.metadata_panel.open || self.config.sharpness_badges {
+            self.sharpness.update(
+                self.current_image_path.as_deref(),
+                &self.current_folder_images,
+                self.current_image_index,
+                self.config.sharpness_badges,
+            );
+        }
+        let tool_action = self.tools_menu.show(
+            ctx,
+            &self.config.tools,
+            self.current_image_path.as_deref(),
+        );
+        if let ToolAction::Finished(path) = tool_action
+            && self.current_image_path.as_ref() == Some(&path)
+        {
+            // Pick up in-place edits (optimizers, upscalers writing over the file)
+            self.texture_cache.remove(&path);
+            self.sharpness.invalidate(&path);
+            self.metadata_panel.invalidate();
+            self.load_file(path, false);
+        }
+        let current_texture = self
+            .current_image_path
+            .as_ref()
+            .and_then(|p| self.texture_cache.get(p));
+        if let UpscaleAction::Open(path) = self.upscaler.show(
+            ctx,
+            self.current_image_path.as_deref(),
+            current_texture,
+            &self.config.upscaler,
+        ) {
+            self.load_path(path);
+        }
+        self.enhance.show(ctx, self.current_image_path.as_deref());
+        if let Some(action) = self.eyedropper.show(ctx) {
+            self.eyedropper_action(ctx, action);
+        }
+        self.folder_compare.show(ctx, &self.config.extensions);
+        // Both run every frame; the tags are only compared after the tag store changed
+        let indexed = self.library.poll(&self.tags);
+        if self.library.sync_tags(&self.tags) || indexed {
+            self.search.invalidate();
+        }
+        match self
+            .library_window
+            .show(ctx, &self.library, &mut self.config.library_roots)
+        {
+            LibraryAction::RootsChanged => {
+                self.config.mark_dirty();
+                self.library
+                    .start_indexing(ctx, &self.config.library_roots, &self.config.extensions);
+            }
+            LibraryAction::ExportData => self.export_app_data(),
+            LibraryAction::ImportData => self.import_app_data(),
+            LibraryAction::Search => {
+                if !self.search.open {
+                    self.search.toggle();
+                }
+            }
+            LibraryAction::None => {}
+        }
+        match self.search.show(ctx, &self.library) {
+            SearchAction::Open(path) => self.load_path(path),
+            SearchAction::Browse(images) => self.load_list(images),
+            SearchAction::SaveAlbum(album) => {
+                osd::toast(ctx, format!("Saved album \"{}\"", album.name));
+                self.config.albums.retain(|a| a.name != album.name);
+                self.config.albums.push(album);
+                self.config.mark_dirty();
+            }
+            SearchAction::None => {}
+        }
+        if let TagAction::Browse(tag, images) =
+            self.tag_panel
+                .show(ctx, &mut self.tags, self.current_image_path.as_deref())
+        {
+            if images.is_empty() {
+                osd::toast(ctx, format!("No images tagged \"{}\" found", tag));
+            } else {
+                osd::toast(ctx, format!("{} images tagged \"{}\"", images.len(), tag));
+                self.load_list(images);
+            }
+        }
+        if let DuplicateAction::Deleted(paths) = self.duplicates.show(ctx) {
+            self.remove_paths(&paths);
+        }
+        let source = self
+            .unfiltered_images
+            .as_deref()
+            .unwrap_or(&self.current_folder_images);
+        match self.filter_bar.show(ctx, source, self.current_folder_images.len()) {
+            FilterAction::Apply(images) => {
+                if self.unfiltered_images.is_none() {
+                    self.unfiltered_images = Some(self.current_folder_images.clone());
+                }
+                self.show_filtered(images);
+            }
+            FilterAction::Clear => {
+                if let Some(all) = self.unfiltered_images.take() {
+                    self.show_filtered(all);
+                }
+            }
+            FilterAction::None => {}
+        }
+
+        let selected = self.selection.paths(&self.current_folder_images);
+        if let BatchAction::Removed(paths) = self.batch.show(ctx, selected) {
+            self.remove_paths(&paths);
+        }
+        let selected = self.selection.paths(&self.current_folder_images);
+        if let RenameAction::Renamed(renamed) =
+            self.rename.show(ctx, &self.current_folder_images, selected)
+        {
+            self.rename_paths(&renamed);
+        }
+
+        if let Some(sort) = &mut self.capture_sort {
+            if let Some((ordered, times)) = sort.poll() {
+                self.capture_sort = None;
+                self.capture_times = times;
+                self.apply_order(ordered);
+            } else if show_overlays {
+                let progress = sort.progress();
+                egui::Area::new(egui::Id::new("capture_sort_progress"))
+                    .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10.0, 10.0))
+                    .interactable(false)
+                    .show(ctx, |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.label("Reading capture dates…");
+                            ui.add(egui::ProgressBar::new(progress).desired_width(160.0));
+                        });
+                    });
+            }
+        }
+        if let Some(sort) = &mut self.similarity_sort {
+            if let Some(ordered) = sort.poll() {
+                self.similarity_sort = None;
+                self.apply_order(ordered);
+            } else if show_overlays {
+                let progress = sort.progress();
+                egui::Area::new(egui::Id::new("similarity_sort_progress"))
+                    .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10.0, 10.0))
+                    .interactable(false)
+                    .show(ctx, |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.label("Sorting by similarity…");
+                            ui.add(egui::ProgressBar::new(progress).desired_width(160.0));
+                        });
+                    });
+            }
+        }
+
+        // 3. UI Layout
+        if self.config.breadcrumb
+            && show_overlays
+            && !self.slideshow.active
+            && let Some(folder) = &self.current_folder
+            && let Some(folder) = folder_nav::breadcrumb(ctx, folder)
+        {
+            self.open_folder(folder);
+        }
+        if self.config.folder_tree
+            && show_overlays
+            && !self.slideshow.active
+            && let Some(folder) = self.folder_tree.show(
+                ctx,
+                self.current_folder.as_deref(),
+                &self.config.extensions,
+            )
+        {
+            self.open_folder(folder);
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if self.strip_mode && !self.current_folder_images.is_empty() {
+                // The strip streams its own textures; only keep the index in sync
+                if let StripAction::CurrentChanged(idx) =
+                    self.strip_view.display(ui, &self.current_folder_images)
+                    && idx != self.current_image_index
+                {
+                    self.current_image_index = idx;
+                    self.current_image_path = Some(self.current_folder_images[idx].clone());
+                    self.thumbnail_list
+                        .update_folder(&self.current_folder_images, self.current_image_index);
+                }
+                if show_overlays {
+                    self.show_thumbnail_list(ui);
+                }
+                return;
+            }
+
+            if self.is_loading() {
+                let slow = self
+                    .current_image_path
+                    .as_ref()
+                    .filter(|p| self.slow_paths.contains(*p));
+                if let Some(path) = slow {
+                    let mut cancel = false;
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(ui.available_height() / 2.0 - 40.0);
+                        ui.spinner();
+                        ui.label(format!("Loading slowly from {}", path.display()));
+                        cancel = ui.button("Cancel").clicked();
+                    });
+                    if cancel {
+                        self.loader.cancel();
+                    }
+                } else if let Some((_, texture)) = self
+                    .preview
+                    .as_ref()
+                    .filter(|(p, _)| self.current_image_path.as_ref() == Some(p))
+                {
+                    // The blurry embedded preview, fitted to the window, until the decode lands
+                    let area = ui.clip_rect();
+                    let size = texture.size_vec2();
+                    let scale = (area.width() / size.x).min(area.height() / size.y);
+                    let rect = egui::Rect::from_center_size(area.center(), size * scale);
+                    ui.painter().image(
+                        texture.id(),
+                        rect,
+                        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                        egui::Color32::WHITE,
+                    );
+                    let spinner = egui::Rect::from_min_size(
+                        area.min + egui::vec2(12.0, 12.0),
+                        egui::vec2(20.0, 20.0),
+                    );
+                    ui.put(spinner, egui::Spinner::new());
+                } else {
+                    ui.centered_and_justified(|ui| ui.spinner());
+                }
+                // IMPORTANT: Do NOT return here if you want debug overlays or other persistent UI
+                // But generally for a modal loading screen, returning is fine,
+                // provided we are sure is_loading will flip back.
+                return;
+            }
+
+            if let Some(err) = self.error_msg.clone() {
+                self.show_error(ctx, &err);
+                return;
+            }
+
+            let current_texture = self
+                .current_image_path
+                .as_ref()
+                .and_then(|p| self.texture_cache.get(p));
+
+            if let Some(texture) = current_texture {
+                // In spread mode the partner page is scaled to the same height and the
+                // pair is treated as a single image by the view math
+                let mut pages = vec![(texture.id(), texture.size_vec2())];
+                if let Some(partner) = self.spread_partner(self.current_image_index) {
+                    let partner_texture = &self.texture_cache[&self.current_folder_images[partner]];
+                    let partner_size = partner_texture.size_vec2();
+                    let scale = pages[0].1.y / partner_size.y;
+                    pages.push((partner_texture.id(), partner_size * scale));
+                    if self.spread_rtl {
+                        pages.reverse();
+                    }
+                }
+                let texture_size = egui::vec2(
+                    pages.iter().map(|(_, size)| size.x).sum(),
+                    pages[0].1.y,
+                );
+
+                // 4. Zoom & Pan Logic
+                if std::mem::take(&mut self.fit_requested) {
+                    self.view_state.fit_to_window(ui.clip_rect(), texture_size);
+                }
+                // While presenting, the Ken Burns motion plays on the other display
+                if self.slideshow.active && self.config.ken_burns && !self.presentation.active {
+                    let interval = self.slide_interval();
+                    self.slideshow
+                        .animate(ctx, &mut self.view_state, ui.clip_rect(), texture_size, interval);
+                }
+                self.loupe.process_input(ui);
+                self.view_state.process_input(ui, texture_size);
+                // Space+drag pans over the tools instead of measuring or drawing
+                if !ViewState::space_panning(ui) {
+                    self.measure.process_input(ui, &self.view_state, texture_size);
+                    self.annotations.process_input(ui, &self.view_state, texture_size);
+                    self.redaction.process_input(ui, &self.view_state, texture_size);
+                }
+                // Spreads are two files side by side; the eyedropper reads single images
+                let decoded = self.loader.decoded();
+                if pages.len() == 1
+                    && let Some(path) = &self.current_image_path
+                    && let Some(action) = self.eyedropper.process_input(
+                        ui,
+                        &self.view_state,
+                        texture_size,
+                        path,
+                        &decoded,
+                    )
+                {
+                    self.eyedropper_action(ctx, action);
+                }
+
+                // 5. Drawing
+                let image_rect = self.view_state.image_rect(ui.clip_rect(), texture_size);
+
+                let mut page_rects = Vec::with_capacity(pages.len());
+                let mut x = image_rect.min.x;
+                for (texture_id, size) in pages {
+                    let width = size.x * self.view_state.zoom;
+                    let page_rect = egui::Rect::from_min_size(
+                        egui::pos2(x, image_rect.min.y),
+                        egui::vec2(width, image_rect.height()),
+                    );
+                    if self.straighten.enabled {
+                        self.straighten.paint(ui, texture_id, page_rect);
+                    } else {
+                        ui.painter().image(
+                            texture_id,
+                            page_rect,
+                            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                            egui::Color32::WHITE,
+                        );
+                    }
+                    page_rects.push((texture_id, page_rect));
+                    x += width;
+                }
+
+                if let Some(path) = &self.current_image_path {
+                    self.redaction.paint(ui, &self.view_state, texture_size, path);
+                }
+                self.annotations.paint(ui, &self.view_state, texture_size);
+                self.measure.paint(ui, &self.view_state, texture_size);
+                self.eyedropper.paint(ui, &self.view_state, texture_size);
+                if show_overlays {
+                    self.loupe.paint(ui, &page_rects);
+                }
+                self.viewport_export.set_crop(image_rect.intersect(ui.clip_rect()));
+
+                self.view_state.paint_selection(ui);
+
+                // Debug overlay
+                if self.show_debug_info && show_overlays {
+                    ui.scope(|ui| {
+                        ui.multiply_opacity(self.ui_opacity);
+                        let mut debug_text = format!(
+                            "Zoom: {:.2}x\nPan: {:.0}, {:.0}\nSize: {}x{}\nMode: {:?}",
+                            self.view_state.to_scale(self.view_state.zoom),
+                            self.view_state.pan.x,
+                            self.view_state.pan.y,
+                            texture_size.x,
+                            texture_size.y,
+                            self.view_state.fit_mode
+                        );
+                        debug_text += &format!("\nDisplay: {}", self.config.display_profile.label());
+                        debug_text += &format!("\nView memory: {:?}", self.config.view_memory);
+                        debug_text += &format!(
+                            "\nSkip unreadable: {} ({} failed)",
+                            self.config.skip_unreadable,
+                            self.failed_paths.len()
+                        );
+                        if self.current_is_high_bit_depth() {
+                            debug_text += &format!("\nHigh bit depth, exposure {:+.1} EV", self.exposure);
+                        }
+                        if let Some(info) = self
+                            .current_image_path
+                            .as_ref()
+                            .and_then(|p| self.format_info.get(p))
+                        {
+                            debug_text += &format!("\nFormat: {}", info.summary());
+                            for (label, value) in &info.details {
+                                debug_text += &format!("\n{}: {}", label, value);
+                            }
+                        }
+
+                        let pos = ui.clip_rect().min + egui::vec2(10.0, 10.0);
+                        let font_id = egui::FontId::monospace(14.0);
+
+                        // Draw text shadow/outline for better visibility
+                        let offsets = [
+                            egui::vec2(-1.0, -1.0),
+                            egui::vec2(0.0, -1.0),
+                            egui::vec2(1.0, -1.0),
+                            egui::vec2(-1.0, 0.0),
+                            egui::vec2(1.0, 0.0),
+                            egui::vec2(-1.0, 1.0),
+                            egui::vec2(0.0, 1.0),
+                            egui::vec2(1.0, 1.0),
+                        ];
+
+                        for offset in offsets {
+                            ui.painter().text(
+                                pos + offset,
+                                egui::Align2::LEFT_TOP,
+                                &debug_text,
+                                font_id.clone(),
+                                egui::Color32::BLACK,
+                            );
+                        }
+
+                        // Draw main text
+                        ui.painter().text(
+                            pos,
+                            egui::Align2::LEFT_TOP,
+                            debug_text,
+                            font_id,
+                            egui::Color32::YELLOW,
+                        );
+                    });
+                }
+            } else if self.kiosk.is_some() {
+                // No opening, pasting or album edits in kiosk mode, nor recent files on show;
+                // the folder is watched until images arrive
+                ui.centered_and_justified(|ui| ui.weak("Waiting for images…"));
+            } else {
+                match self.start_screen.show(ui, &self.config.recent_files, &self.config.albums) {
+                    StartAction::None => {}
+                    StartAction::Open(path) => self.load_path(path),
+                    StartAction::OpenImage => self.open_dialog(false),
+                    StartAction::OpenFolder => self.open_dialog(true),
+                    StartAction::Paste => self.paste_clipboard_image(),
+                    StartAction::OpenAlbum(i) => self.open_album(i),
+                    StartAction::ExportAlbum(i) => {
+                        self.open_album(i);
+                        if !self.current_folder_images.is_empty() {
+                            self.selection.select_all(&self.current_folder_images);
+                            self.batch.open = true;
+                        }
+                    }
+                    StartAction::RemoveAlbum(i) => {
+                        self.config.albums.remove(i);
+                        self.config.mark_dirty();
+                    }
+                }
+            }
+
+            // Thumbnail List
+            if show_overlays {
+                self.show_thumbnail_list(ui);
+            }
+        });
+
+        if show_overlays && self.diagnostics.open {
+            // Full images are uploaded with mipmaps, a third on top of the base level
+            let texture_bytes = self
+                .texture_cache
+                .values()
+                .map(|t| t.size()[0] * t.size()[1] * 4 * 4 / 3)
+                .sum();
+            let (thumbnails, thumbnail_bytes) = self.thumbnail_list.texture_stats();
+            let stats = &self.loader.stats;
+            let snapshot = diagnostics::Snapshot {
+                textures: self.texture_cache.len(),
+                texture_bytes,
+                thumbnails,
+                thumbnail_bytes,
+                decoded_cache_bytes: stats.cache_bytes.load(Ordering::Relaxed),
+                pending_loads: self.loading_paths.len(),
+                queued_commands: stats.queued.load(Ordering::Relaxed),
+            };
+            // Below the F1 debug text when that's shown
+            let offset = if self.show_debug_info { 150.0 } else { 10.0 };
+            self.diagnostics.show(ctx, &snapshot, offset, self.ui_opacity);
+        }
+
+        let shown = self
+            .current_image_path
+            .as_deref()
+            .filter(|p| !self.strip_mode && self.texture_cache.contains_key(*p));
+        self.zoom_indicator.update(
+            ctx,
+            shown,
+            self.view_state.to_scale(self.view_state.target_zoom),
+            self.slideshow.active,
+            if show_overlays { self.ui_opacity } else { 0.0 },
+        );
+
+        self.viewport_export.update(ctx);
+
+        // Set last so widgets hovered this frame don't bring the cursor back
+        if idle {
+            ctx.set_cursor_icon(egui::CursorIcon::None);
+        }
+
+        // Kiosk mode forces its own fullscreen window; that isn't the user's window state
+        if self.kiosk.is_none() {
+            self.remember_window_state(ctx);
+        }
+        if let Some(wait) = self.config.save_if_due() {
+            ctx.request_repaint_after(wait);
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.config.flush();
+    }
+}
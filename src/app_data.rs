@@ -0,0 +1,73 @@
+//! Export and import of the viewer's own data (settings, recent files, albums and tags)
+//! as one file, to carry it to another machine or keep a backup.
+//!
+//! Ratings and other metadata are written into the images themselves and travel with
+//! them; the library index is rebuilt from its folders after an import.
+
+use crate::config::AppConfig;
+use crate::tags::{TagMap, TagStore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+pub const EXTENSION: &str = "json";
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    version: u32,
+    // Kept as plain JSON so the config's own migrations apply on import
+    config: Value,
+    #[serde(default)]
+    tags: TagMap,
+}
+
+pub fn export(path: &Path, config: &AppConfig, tags: &TagStore) -> Result<(), String> {
+    let bundle = Bundle {
+        version: BUNDLE_VERSION,
+        config: serde_json::to_value(config).map_err(|e| e.to_string())?,
+        tags: tags.all().clone(),
+    };
+    let content = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+/// Reads a bundle: the settings to switch to (see `AppConfig::imported`) and the tags
+/// to merge into the current ones.
+pub fn import(path: &Path, current: &AppConfig) -> Result<(AppConfig, TagMap), String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let bundle: Bundle =
+        serde_json::from_str(&content).map_err(|_| "Not an exported data file".to_string())?;
+    if bundle.version > BUNDLE_VERSION {
+        return Err("Exported by a newer version".to_string());
+    }
+    let config = current
+        .imported(&bundle.config.to_string())
+        .ok_or("The settings in the file are unreadable")?;
+    Ok((config, bundle.tags))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn bundle_round_trips_settings_and_tags() {
+        let mut config = AppConfig::default();
+        config.recent_files = vec![PathBuf::from("/photos/a.jpg")];
+        config.slideshow_interval = 7.0;
+        let mut tags = TagStore::default();
+        tags.add(Path::new("/photos/a.jpg"), "beach");
+
+        let path = std::env::temp_dir().join(format!("biv_app_data_{}.json", std::process::id()));
+        export(&path, &config, &tags).unwrap();
+        let (imported, imported_tags) = import(&path, &AppConfig::default()).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(imported.recent_files, config.recent_files);
+        assert_eq!(imported.slideshow_interval, 7.0);
+        assert_eq!(&imported_tags, tags.all());
+    }
+}
@@ -0,0 +1,377 @@
+//! Batch operations on the filmstrip selection: delete, move, copy, convert and contact
+//! sheets.
+
+use crate::image_loader::{decode_image, resize_linear};
+use eframe::egui;
+use image::RgbaImage;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+
+pub enum BatchAction {
+    None,
+    // The files left the folder (deleted or moved)
+    Removed(Vec<PathBuf>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ConvertFormat {
+    Png,
+    Jpeg,
+    WebP,
+}
+
+impl ConvertFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ConvertFormat::Png => "png",
+            ConvertFormat::Jpeg => "jpg",
+            ConvertFormat::WebP => "webp",
+        }
+    }
+}
+
+enum Progress {
+    Item(usize),
+    Done(Result<String, String>),
+}
+
+pub struct BatchWindow {
+    pub open: bool,
+    // Images the running job works on
+    paths: Vec<PathBuf>,
+    format: ConvertFormat,
+    columns: u32,
+    cell_size: u32,
+    rx: Option<Receiver<Progress>>,
+    processed: usize,
+    status: Option<String>,
+}
+
+impl Default for BatchWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            paths: Vec::new(),
+            format: ConvertFormat::Png,
+            columns: 5,
+            cell_size: 256,
+            rx: None,
+            processed: 0,
+            status: None,
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Saves `image` in the format of `target`'s extension, dropping alpha for JPEG.
+pub fn save_rgba(image: image::RgbaImage, target: &Path) -> image::ImageResult<()> {
+    if matches!(
+        target.extension().and_then(|e| e.to_str()),
+        Some("jpg" | "jpeg")
+    ) {
+        image::DynamicImage::ImageRgba8(image).to_rgb8().save(target)
+    } else {
+        image.save(target)
+    }
+}
+
+/// Moves a file, copying across file systems when a rename isn't possible.
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to)?;
+    std::fs::remove_file(from)
+}
+
+fn copy_all(
+    paths: &[PathBuf],
+    out_dir: &Path,
+    tx: &Sender<Progress>,
+    ctx: &egui::Context,
+) -> Result<String, String> {
+    let mut failed = Vec::new();
+    for (i, path) in paths.iter().enumerate() {
+        let target = out_dir.join(path.file_name().unwrap_or_default());
+        if target.exists() {
+            failed.push(format!("{} already exists", file_name(path)));
+        } else if let Err(e) = std::fs::copy(path, &target) {
+            failed.push(format!("{}: {}", file_name(path), e));
+        }
+        let _ = tx.send(Progress::Item(i + 1));
+        ctx.request_repaint();
+    }
+
+    if failed.is_empty() {
+        Ok(format!("Copied {} images to {}", paths.len(), out_dir.display()))
+    } else {
+        Err(format!("{} not copied ({})", failed.len(), failed.join("; ")))
+    }
+}
+
+fn convert_all(
+    paths: &[PathBuf],
+    format: ConvertFormat,
+    out_dir: &Path,
+    tx: &Sender<Progress>,
+    ctx: &egui::Context,
+) -> Result<String, String> {
+    let mut failed = Vec::new();
+    for (i, path) in paths.iter().enumerate() {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let target = out_dir.join(format!("{}.{}", stem, format.extension()));
+        if target.exists() {
+            // A source already in the target format is left as it is
+            if target != *path {
+                failed.push(format!("{} already exists", file_name(&target)));
+            }
+        } else {
+            let result = decode_image(path).and_then(|image| match format {
+                // JPEG has no alpha channel
+                ConvertFormat::Jpeg => {
+                    image::DynamicImage::ImageRgb8(image.to_rgb8()).save(&target)
+                }
+                _ => image.save(&target),
+            });
+            if let Err(e) = result {
+                failed.push(format!("{}: {}", file_name(path), e));
+            }
+        }
+        let _ = tx.send(Progress::Item(i + 1));
+        ctx.request_repaint();
+    }
+
+    if failed.is_empty() {
+        Ok(format!("Converted {} images", paths.len()))
+    } else {
+        Err(format!("{} failed ({})", failed.len(), failed.join("; ")))
+    }
+}
+
+/// Lays the images out in a grid of `cell_size` squares, each scaled to fit its cell.
+fn contact_sheet(
+    paths: &[PathBuf],
+    columns: u32,
+    cell_size: u32,
+    target: &Path,
+    tx: &Sender<Progress>,
+    ctx: &egui::Context,
+) -> Result<String, String> {
+    let gap = cell_size / 16;
+    let columns = columns.min(paths.len() as u32).max(1);
+    let rows = (paths.len() as u32).div_ceil(columns);
+    let mut sheet = RgbaImage::from_pixel(
+        columns * (cell_size + gap) + gap,
+        rows * (cell_size + gap) + gap,
+        image::Rgba([32, 32, 32, 255]),
+    );
+
+    for (i, path) in paths.iter().enumerate() {
+        // Unreadable images leave an empty cell rather than failing the sheet
+        if let Ok(image) = decode_image(path) {
+            let thumbnail = resize_linear(&image, cell_size, cell_size);
+            let (col, row) = (i as u32 % columns, i as u32 / columns);
+            let x = gap + col * (cell_size + gap) + (cell_size - thumbnail.width()) / 2;
+            let y = gap + row * (cell_size + gap) + (cell_size - thumbnail.height()) / 2;
+            image::imageops::overlay(&mut sheet, &thumbnail, x as i64, y as i64);
+        }
+        let _ = tx.send(Progress::Item(i + 1));
+        ctx.request_repaint();
+    }
+
+    save_rgba(sheet, target)
+        .map(|_| format!("Saved {}", target.display()))
+        .map_err(|e| e.to_string())
+}
+
+impl BatchWindow {
+    pub fn is_running(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    /// Images done and in total while a job runs.
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        self.is_running().then_some((self.processed, self.paths.len()))
+    }
+
+    fn start(
+        &mut self,
+        ctx: &egui::Context,
+        paths: Vec<PathBuf>,
+        job: impl FnOnce(&[PathBuf], &Sender<Progress>, &egui::Context) -> Result<String, String>
+        + Send
+        + 'static,
+    ) {
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.paths = paths.clone();
+        self.processed = 0;
+        self.status = None;
+
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = job(&paths, &tx, &ctx);
+            let _ = tx.send(Progress::Done(result));
+            ctx.request_repaint();
+        });
+    }
+
+    fn delete(&mut self, paths: &[PathBuf]) -> Option<Vec<PathBuf>> {
+        let confirmed = rfd::MessageDialog::new()
+            .set_title("Delete images")
+            .set_description(format!("Permanently delete {} files?", paths.len()))
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show();
+        if confirmed != rfd::MessageDialogResult::Yes {
+            return None;
+        }
+
+        let deleted: Vec<PathBuf> = paths
+            .iter()
+            .filter(|p| std::fs::remove_file(p).is_ok())
+            .cloned()
+            .collect();
+        self.status = Some(format!(
+            "Deleted {} of {} files",
+            deleted.len(),
+            paths.len()
+        ));
+        Some(deleted)
+    }
+
+    fn move_to(&mut self, paths: &[PathBuf]) -> Option<Vec<PathBuf>> {
+        let dir = rfd::FileDialog::new()
+            .set_title("Move to folder")
+            .pick_folder()?;
+        let mut moved = Vec::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            let target = dir.join(path.file_name().unwrap_or_default());
+            if target.exists() {
+                errors.push(format!("{} already exists", file_name(path)));
+                continue;
+            }
+            match move_file(path, &target) {
+                Ok(()) => moved.push(path.clone()),
+                Err(e) => errors.push(format!("{}: {}", file_name(path), e)),
+            }
+        }
+        self.status = Some(if errors.is_empty() {
+            format!("Moved {} files to {}", moved.len(), dir.display())
+        } else {
+            format!("Moved {} files; {}", moved.len(), errors.join("; "))
+        });
+        Some(moved)
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, selected: Vec<PathBuf>) -> BatchAction {
+        if let Some(rx) = &self.rx {
+            while let Ok(progress) = rx.try_recv() {
+                match progress {
+                    Progress::Item(n) => self.processed = n,
+                    Progress::Done(result) => {
+                        self.status = Some(match result {
+                            Ok(msg) | Err(msg) => msg,
+                        });
+                        self.rx = None;
+                        break;
+                    }
+                }
+            }
+        }
+
+        let mut action = BatchAction::None;
+        if !self.open {
+            return action;
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Batch Actions")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} images selected. Ctrl+click or Shift+click thumbnails to change the selection.",
+                    selected.len()
+                ));
+
+                ui.add_enabled_ui(!self.is_running() && !selected.is_empty(), |ui| {
+                    ui.horizontal(|ui| {
+                        if ui.button("Delete…").clicked()
+                            && let Some(deleted) = self.delete(&selected)
+                        {
+                            action = BatchAction::Removed(deleted);
+                        }
+                        if ui.button("Move to…").clicked()
+                            && let Some(moved) = self.move_to(&selected)
+                        {
+                            action = BatchAction::Removed(moved);
+                        }
+                        if ui.button("Copy to…").clicked()
+                            && let Some(dir) = rfd::FileDialog::new().set_title("Copy to folder").pick_folder()
+                        {
+                            self.start(ctx, selected.clone(), move |paths, tx, ctx| {
+                                copy_all(paths, &dir, tx, ctx)
+                            });
+                        }
+                    });
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Convert to:");
+                        ui.radio_value(&mut self.format, ConvertFormat::Png, "PNG");
+                        ui.radio_value(&mut self.format, ConvertFormat::Jpeg, "JPEG");
+                        ui.radio_value(&mut self.format, ConvertFormat::WebP, "WebP");
+                        if ui.button("Convert…").clicked()
+                            && let Some(dir) = rfd::FileDialog::new().set_title("Convert into folder").pick_folder()
+                        {
+                            let format = self.format;
+                            self.start(ctx, selected.clone(), move |paths, tx, ctx| {
+                                convert_all(paths, format, &dir, tx, ctx)
+                            });
+                        }
+                    });
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label("Contact sheet:");
+                        ui.add(egui::DragValue::new(&mut self.columns).range(1..=20).suffix(" columns"));
+                        ui.add(egui::DragValue::new(&mut self.cell_size).range(32..=1024).suffix(" px"));
+                        if ui.button("Export…").clicked()
+                            && let Some(target) = rfd::FileDialog::new()
+                                .add_filter("PNG", &["png"])
+                                .add_filter("JPEG", &["jpg", "jpeg"])
+                                .set_file_name("contact_sheet.png")
+                                .save_file()
+                        {
+                            let (columns, cell_size) = (self.columns, self.cell_size);
+                            self.start(ctx, selected.clone(), move |paths, tx, ctx| {
+                                contact_sheet(paths, columns, cell_size, &target, tx, ctx)
+                            });
+                        }
+                    });
+                });
+
+                if let Some((done, total)) = self.progress() {
+                    let total = total.max(1);
+                    ui.add(
+                        egui::ProgressBar::new(done as f32 / total as f32)
+                            .text(format!("{} / {}", done, total))
+                            .animate(true),
+                    );
+                } else if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+            });
+        self.open = open;
+
+        action
+    }
+}
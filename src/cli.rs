@@ -0,0 +1,118 @@
+//! Command line subcommands that run without opening a window.
+//!
+//! ```text
+//! better_image_viewer thumb <dir> [--size N] [--out <dir>]
+//! better_image_viewer convert <in> <out>
+//! ```
+//!
+//! `thumb` fills the viewer's thumbnail cache (and copies the results to `--out` when
+//! given); `convert` re-encodes an image, picking the format from the output extension.
+//! A file or folder named like a subcommand is opened in the viewer instead.
+
+use crate::config::AppConfig;
+use crate::image_loader::{decode_image, is_supported_image, make_thumbnail};
+use std::path::{Path, PathBuf};
+
+// Size used by the filmstrip, so pre-warmed thumbnails are picked up by the viewer
+const DEFAULT_THUMBNAIL_SIZE: u32 = 128;
+
+/// Runs a subcommand if `args` (without the program name) starts with one.
+/// Returns the process exit code, or `None` to start the GUI.
+pub fn run(args: &[String]) -> Option<i32> {
+    let first = args.first()?;
+    if Path::new(first).exists() {
+        return None;
+    }
+    let result = match first.as_str() {
+        "thumb" => thumb(&args[1..]),
+        "convert" => convert(&args[1..]),
+        _ => return None,
+    };
+    Some(match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    })
+}
+
+fn thumb(args: &[String]) -> Result<(), String> {
+    let usage = "usage: thumb <dir> [--size N] [--out <dir>]";
+    let mut dir = None;
+    let mut size = DEFAULT_THUMBNAIL_SIZE;
+    let mut out: Option<PathBuf> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--size" => {
+                size = args
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|s| *s > 0)
+                    .ok_or(usage)?;
+            }
+            "--out" => out = Some(args.next().ok_or(usage)?.into()),
+            _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+            _ => return Err(usage.to_string()),
+        }
+    }
+    let dir = dir.ok_or(usage)?;
+
+    let extensions = AppConfig::load().extensions;
+    let mut images: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .map_err(|e| format!("{}: {}", dir.display(), e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| p.is_file() && is_supported_image(p, &extensions))
+        .collect();
+    images.sort();
+
+    if let Some(out) = &out {
+        std::fs::create_dir_all(out).map_err(|e| format!("{}: {}", out.display(), e))?;
+    }
+
+    let mut failed = 0;
+    for (i, path) in images.iter().enumerate() {
+        let result = match &out {
+            Some(out) => {
+                // Keep the whole source name so `a.jpg` and `a.png` don't collide
+                let mut name = path.file_name().unwrap_or_default().to_os_string();
+                name.push(".png");
+                let target = out.join(name);
+                if target.exists() {
+                    Err(format!("{} already exists", target.display()))
+                } else {
+                    make_thumbnail(path, size)
+                        .and_then(|thumbnail| thumbnail.save(&target))
+                        .map_err(|e| e.to_string())
+                }
+            }
+            None => make_thumbnail(path, size)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        };
+        match result {
+            Ok(()) => println!("[{}/{}] {}", i + 1, images.len(), path.display()),
+            Err(e) => {
+                failed += 1;
+                eprintln!("[{}/{}] {}: {}", i + 1, images.len(), path.display(), e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        Err(format!("{} of {} images failed", failed, images.len()))
+    } else {
+        Ok(())
+    }
+}
+
+fn convert(args: &[String]) -> Result<(), String> {
+    let [input, output] = args else {
+        return Err("usage: convert <in> <out>".to_string());
+    };
+    let image = decode_image(Path::new(input)).map_err(|e| format!("{}: {}", input, e))?;
+    image.save(output).map_err(|e| format!("{}: {}", output, e))
+}
@@ -0,0 +1,36 @@
+//! Copying to the system clipboard, from any thread.
+//!
+//! On X11 a program serves what it copied for as long as it keeps its clipboard open, so
+//! one clipboard is opened on first use and kept for the life of the viewer. A clipboard
+//! dropped right after copying would take the copied content with it.
+
+use std::sync::Mutex;
+
+static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+fn with_clipboard(
+    f: impl FnOnce(&mut arboard::Clipboard) -> Result<(), arboard::Error>,
+) -> Result<(), String> {
+    let mut clipboard = CLIPBOARD.lock().unwrap_or_else(|e| e.into_inner());
+    let clipboard = match &mut *clipboard {
+        Some(clipboard) => clipboard,
+        None => clipboard
+            .insert(arboard::Clipboard::new().map_err(|e| format!("Clipboard error: {}", e))?),
+    };
+    f(clipboard).map_err(|e| format!("Clipboard error: {}", e))
+}
+
+pub fn copy_image(image: &image::RgbaImage) -> Result<(), String> {
+    with_clipboard(|clipboard| {
+        clipboard.set_image(arboard::ImageData {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            bytes: std::borrow::Cow::Borrowed(image.as_raw()),
+        })
+    })
+}
+
+#[cfg(feature = "ocr")]
+pub fn copy_text(text: String) -> Result<(), String> {
+    with_clipboard(|clipboard| clipboard.set_text(text))
+}
@@ -0,0 +1,181 @@
+use crate::config::private_temp_dir;
+use eframe::egui;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, channel};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The temp folder captures and pastes are written to, shared with the watcher thread so
+/// both number files from one counter and know the last image either of them stored.
+struct Session {
+    dir: PathBuf,
+    count: AtomicUsize,
+    last_hash: Mutex<Option<u64>>,
+}
+
+impl Session {
+    fn new() -> Result<Self, String> {
+        Ok(Self {
+            dir: private_temp_dir("biv_clipboard").map_err(|e| e.to_string())?,
+            count: AtomicUsize::new(0),
+            last_hash: Mutex::new(None),
+        })
+    }
+
+    /// Saves `data` as the next numbered file. With `skip_repeat`, nothing is saved when
+    /// it's the image stored last.
+    fn store(
+        &self,
+        data: arboard::ImageData,
+        skip_repeat: bool,
+    ) -> Result<Option<PathBuf>, String> {
+        let mut hasher = DefaultHasher::new();
+        (data.width, data.height, &data.bytes[..]).hash(&mut hasher);
+        let hash = hasher.finish();
+        {
+            let mut last_hash = self.last_hash.lock().unwrap_or_else(|e| e.into_inner());
+            if skip_repeat && *last_hash == Some(hash) {
+                return Ok(None);
+            }
+            *last_hash = Some(hash);
+        }
+
+        let image = image::RgbaImage::from_raw(
+            data.width as u32,
+            data.height as u32,
+            data.bytes.into_owned(),
+        )
+        .ok_or("Unsupported clipboard image")?;
+        let count = self.count.fetch_add(1, Ordering::Relaxed) + 1;
+        let path = self.dir.join(format!("clipboard_{:04}.png", count));
+        image.save(&path).map_err(|e| e.to_string())?;
+        Ok(Some(path))
+    }
+}
+
+/// Shows every image copied to the system clipboard while enabled.
+///
+/// Clipboard images are written to a per-session temp folder so they can be browsed
+/// like any other folder; `history` lists them oldest first.
+#[derive(Default)]
+pub struct ClipboardWatcher {
+    pub history: Vec<PathBuf>,
+    rx: Option<Receiver<PathBuf>>,
+    stop: Arc<AtomicBool>,
+    // Created on first use
+    session: Option<Arc<Session>>,
+}
+
+impl ClipboardWatcher {
+    pub fn is_enabled(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    fn session(&mut self) -> Result<Arc<Session>, String> {
+        if self.session.is_none() {
+            self.session = Some(Arc::new(Session::new()?));
+        }
+        Ok(self.session.clone().expect("session was just created"))
+    }
+
+    pub fn toggle(&mut self, ctx: &egui::Context) {
+        if self.is_enabled() {
+            self.stop.store(true, Ordering::Relaxed);
+            self.rx = None;
+            return;
+        }
+
+        let session = match self.session() {
+            Ok(session) => session,
+            Err(e) => {
+                eprintln!("Clipboard watcher: {}", e);
+                return;
+            }
+        };
+        let (tx, rx) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        self.rx = Some(rx);
+        self.stop = stop.clone();
+
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let Ok(mut clipboard) = arboard::Clipboard::new() else {
+                eprintln!("Clipboard watcher: clipboard unavailable");
+                return;
+            };
+
+            while !stop.load(Ordering::Relaxed) {
+                if let Ok(data) = clipboard.get_image() {
+                    match session.store(data, true) {
+                        Ok(Some(path)) => {
+                            if tx.send(path).is_err() {
+                                break;
+                            }
+                            ctx.request_repaint();
+                        }
+                        Ok(None) => {}
+                        Err(e) => eprintln!("Clipboard watcher: {}", e),
+                    }
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+    }
+
+    /// Stores the image currently on the clipboard in the session history.
+    pub fn paste(&mut self) -> Result<PathBuf, String> {
+        let data = arboard::Clipboard::new()
+            .and_then(|mut c| c.get_image())
+            .map_err(|_| "No image on the clipboard".to_string())?;
+        let path = self
+            .session()?
+            .store(data, false)?
+            .ok_or("Unsupported clipboard image")?;
+        self.history.push(path.clone());
+        Ok(path)
+    }
+
+    /// Returns the newest captured image, if one arrived since the last call.
+    pub fn poll(&mut self) -> Option<PathBuf> {
+        let mut newest = None;
+        if let Some(rx) = &self.rx {
+            while let Ok(path) = rx.try_recv() {
+                self.history.push(path.clone());
+                newest = Some(path);
+            }
+        }
+        newest
+    }
+
+    pub fn show_status(&self, ctx: &egui::Context) {
+        if !self.is_enabled() {
+            return;
+        }
+        egui::Area::new(egui::Id::new("clipboard_watch_status"))
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10.0, 10.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(format!(
+                        "📋 Watching clipboard ({} captured)",
+                        self.history.len()
+                    ));
+                });
+            });
+    }
+}
+
+impl Drop for ClipboardWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(session) = &self.session {
+            let _ = std::fs::remove_dir_all(&session.dir);
+        }
+    }
+}
@@ -1,56 +1,593 @@
-use directories::ProjectDirs;
-use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::PathBuf;
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AppConfig {
-    pub window_pos: Option<[f32; 2]>,
-    pub window_size: Option<[f32; 2]>,
-    pub is_maximized: Option<bool>,
-}
-
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            window_pos: None,
-            window_size: Some([800.0, 600.0]),
-            is_maximized: Some(false),
-        }
-    }
-}
-
-impl AppConfig {
-    fn get_config_path() -> Option<PathBuf> {
-        if let Some(proj_dirs) = ProjectDirs::from("com", "aniki", "better_image_viewer") {
-            let config_dir = proj_dirs.config_dir();
-            if !config_dir.exists() {
-                let _ = fs::create_dir_all(config_dir);
-            }
-            Some(config_dir.join("config.json"))
-        } else {
-            None
-        }
-    }
-
-    pub fn load() -> Self {
-        if let Some(path) = Self::get_config_path() {
-            if path.exists() {
-                if let Ok(content) = fs::read_to_string(&path) {
-                    if let Ok(config) = serde_json::from_str(&content) {
-                        return config;
-                    }
-                }
-            }
-        }
-        Self::default()
-    }
-
-    pub fn save(&self) {
-        if let Some(path) = Self::get_config_path() {
-            if let Ok(content) = serde_json::to_string_pretty(self) {
-                let _ = fs::write(path, content);
-            }
-        }
-    }
-}
+//! User settings, stored as JSON in the platform config directory. Fields added later
+//! carry serde defaults, so older config files keep loading.
+
+use crate::display_profile::DisplayProfile;
+use crate::image_loader::DEFAULT_EXTENSIONS;
+use crate::presentation::DisplaySide;
+use crate::thumbnail_list::FilmstripDock;
+use crate::video::VIDEO_EXTENSIONS;
+use crate::eyedropper::SampleSize;
+use crate::view_state::{PanScheme, ZoomSettings};
+use directories::ProjectDirs;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+const CONFIG_FILE: &str = "config.json";
+// Either file next to the executable switches to portable mode
+const PORTABLE_FLAG: &str = "portable.flag";
+// Changes are written this long after the last one, so dragging the window doesn't
+// write the file every frame
+const SAVE_DEBOUNCE: Duration = Duration::from_secs(1);
+// How often the config file is checked for outside edits
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+// Bumped when saved settings need converting for a new release (see `migrate`)
+const CONFIG_VERSION: u32 = 1;
+
+/// The executable's folder when running portable (from a USB stick, say): it holds a
+/// `config.json` or a `portable.flag`. Settings and caches then live there and nothing
+/// is written to the user profile.
+pub fn portable_dir() -> Option<&'static Path> {
+    static PORTABLE_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+    PORTABLE_DIR
+        .get_or_init(|| {
+            let dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+            (dir.join(CONFIG_FILE).is_file() || dir.join(PORTABLE_FLAG).exists()).then_some(dir)
+        })
+        .as_deref()
+}
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("com", "aniki", "better_image_viewer")
+}
+
+/// Folder for disposable data such as thumbnails.
+pub fn cache_dir() -> Option<PathBuf> {
+    match portable_dir() {
+        Some(dir) => Some(dir.join("cache")),
+        None => project_dirs().map(|dirs| dirs.cache_dir().to_path_buf()),
+    }
+}
+
+/// Folder for data the viewer keeps besides settings, such as the tag database.
+pub fn data_dir() -> Option<PathBuf> {
+    match portable_dir() {
+        Some(dir) => Some(dir.to_path_buf()),
+        None => project_dirs().map(|dirs| dirs.data_dir().to_path_buf()),
+    }
+}
+
+/// Creates a new folder in the temp dir that only this user can read, under a random
+/// name, so other users can neither predict it nor create it first.
+pub fn private_temp_dir(prefix: &str) -> std::io::Result<PathBuf> {
+    let mut bytes = [0u8; 8];
+    getrandom::fill(&mut bytes).map_err(std::io::Error::other)?;
+    let suffix: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let dir = std::env::temp_dir().join(format!("{}_{}", prefix, suffix));
+
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    // Not `create_dir_all`: an existing folder of that name is an error, not reused
+    builder.create(&dir)?;
+    Ok(dir)
+}
+
+/// A user command shown in the Tools menu.
+///
+/// `command` is a program followed by its arguments; `{path}`, `{dir}`, `{name}` and
+/// `{stem}` are replaced with the current image's path, folder, file name and file stem.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalTool {
+    pub name: String,
+    pub command: String,
+}
+
+/// Whether each image's zoom and pan are restored when returning to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ViewMemory {
+    #[default]
+    Off,
+    // Remembered until the viewer is closed
+    Session,
+    // Also kept in the config file
+    Persistent,
+}
+
+impl ViewMemory {
+    /// The setting after this one, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            ViewMemory::Off => ViewMemory::Session,
+            ViewMemory::Session => ViewMemory::Persistent,
+            ViewMemory::Persistent => ViewMemory::Off,
+        }
+    }
+}
+
+/// Zoom and pan last used for an image (see `ViewMemory::Persistent`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedView {
+    pub path: PathBuf,
+    // In device pixels, see `ViewState::to_scale`
+    pub zoom: f32,
+    pub pan: [f32; 2],
+}
+
+/// A saved library search, browsed like a folder (see `search`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Album {
+    pub name: String,
+    pub query: String,
+}
+
+/// Everything persisted between sessions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppConfig {
+    // Format of the file; files from before versioning count as 0
+    #[serde(default)]
+    pub version: u32,
+    pub window_pos: Option<[f32; 2]>,
+    pub window_size: Option<[f32; 2]>,
+    pub is_maximized: Option<bool>,
+    #[serde(default)]
+    pub is_fullscreen: bool,
+    // Size of the monitor the window was on, to notice when the monitor layout changed
+    #[serde(default)]
+    pub window_monitor: Option<[f32; 2]>,
+    #[serde(default)]
+    pub recent_files: Vec<PathBuf>,
+    #[serde(default)]
+    pub tools: Vec<ExternalTool>,
+    // Command for "Upscale"; {path}, {output} and {scale} (2 or 4) are filled in
+    #[serde(default = "default_upscaler")]
+    pub upscaler: String,
+    #[serde(default)]
+    pub display_profile: DisplayProfile,
+    #[serde(default)]
+    pub view_memory: ViewMemory,
+    #[serde(default)]
+    pub filmstrip_dock: FilmstripDock,
+    // Sharpness scores on filmstrip thumbnails (scores every image in the folder)
+    #[serde(default)]
+    pub sharpness_badges: bool,
+    // Path bar above the image (Ctrl+B)
+    #[serde(default)]
+    pub breadcrumb: bool,
+    // Folder tree beside the image (Ctrl+T)
+    #[serde(default)]
+    pub folder_tree: bool,
+    // Folders indexed by the library (Ctrl+L); empty leaves the library off
+    #[serde(default)]
+    pub library_roots: Vec<PathBuf>,
+    #[serde(default)]
+    pub albums: Vec<Album>,
+    // The only way to close the window in kiosk mode, e.g. "Ctrl+Shift+Q"
+    #[serde(default = "default_kiosk_exit")]
+    pub kiosk_exit: String,
+    // Most recently left first
+    #[serde(default)]
+    pub saved_views: Vec<SavedView>,
+    // Folder scanning: dotfiles / hidden-attribute files, and symlinked images
+    #[serde(default = "default_true")]
+    pub include_hidden: bool,
+    #[serde(default = "default_true")]
+    pub follow_symlinks: bool,
+    // Next/previous step over files that fail to decode instead of stopping on them
+    #[serde(default)]
+    pub skip_unreadable: bool,
+    // Next on the last image (previous on the first) continues into the neighbouring
+    // folder, as comic and scan readers do with chapter folders
+    #[serde(default)]
+    pub continue_folders: bool,
+    // File extensions treated as images when scanning folders
+    #[serde(default = "default_extensions")]
+    pub extensions: Vec<String>,
+    // Seconds each slide stays up, and whether slides slowly pan and zoom (Ken Burns)
+    #[serde(default = "default_slideshow_interval")]
+    pub slideshow_interval: f32,
+    #[serde(default)]
+    pub ken_burns: bool,
+    // Monitor the slideshow is presented on, relative to the main window's
+    #[serde(default)]
+    pub presentation_display: DisplaySide,
+    // Zoom limits and wheel steps
+    #[serde(default)]
+    pub zoom: ZoomSettings,
+    // Whether plain left-drag pans, or only middle-drag and Space+drag
+    #[serde(default)]
+    pub pan_scheme: PanScheme,
+    // New images open fitted, with the window sized around them within the monitor's work
+    // area (Alt+0); a maximized window just fits them
+    #[serde(default)]
+    pub fit_to_work_area: bool,
+    // Pixels the eyedropper (Shift+I) averages
+    #[serde(default)]
+    pub eyedropper_sample: SampleSize,
+    // Folder the open dialog starts in
+    #[serde(default)]
+    pub last_open_dir: Option<PathBuf>,
+
+    // File this config was loaded from and is saved back to
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    // Last change not yet written
+    #[serde(skip)]
+    changed_at: Option<Instant>,
+    // Settings this version doesn't know (written by a newer one), kept on save
+    #[serde(flatten)]
+    unknown: Map<String, Value>,
+}
+
+/// Brings a config file written as `version` up to date.
+fn migrate(file: &mut Map<String, Value>, version: u32) {
+    if version < 1 {
+        // Extension lists saved before video support was added lack the video types
+        if let Some(Value::Array(extensions)) = file.get_mut("extensions") {
+            for ext in VIDEO_EXTENSIONS {
+                if !extensions.iter().any(|e| e.as_str() == Some(ext)) {
+                    extensions.push(Value::from(*ext));
+                }
+            }
+        }
+    }
+}
+
+fn default_extensions() -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut extensions: Vec<String> = DEFAULT_EXTENSIONS
+        .iter()
+        .chain(VIDEO_EXTENSIONS)
+        .map(|e| e.to_string())
+        .collect();
+    #[cfg(feature = "pdf")]
+    extensions.push("pdf".to_string());
+    extensions
+}
+
+fn default_slideshow_interval() -> f32 {
+    5.0
+}
+
+fn default_kiosk_exit() -> String {
+    crate::kiosk::DEFAULT_EXIT.to_string()
+}
+
+fn default_upscaler() -> String {
+    "realesrgan-ncnn-vulkan -i {path} -o {output} -s {scale}".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+const MAX_RECENT_FILES: usize = 10;
+const MAX_SAVED_VIEWS: usize = 200;
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            window_pos: None,
+            window_size: Some([800.0, 600.0]),
+            is_maximized: Some(false),
+            is_fullscreen: false,
+            window_monitor: None,
+            recent_files: Vec::new(),
+            tools: Vec::new(),
+            upscaler: default_upscaler(),
+            display_profile: DisplayProfile::default(),
+            view_memory: ViewMemory::default(),
+            filmstrip_dock: FilmstripDock::default(),
+            sharpness_badges: false,
+            breadcrumb: false,
+            folder_tree: false,
+            library_roots: Vec::new(),
+            albums: Vec::new(),
+            kiosk_exit: default_kiosk_exit(),
+            saved_views: Vec::new(),
+            include_hidden: true,
+            follow_symlinks: true,
+            skip_unreadable: false,
+            continue_folders: false,
+            extensions: default_extensions(),
+            slideshow_interval: default_slideshow_interval(),
+            ken_burns: false,
+            presentation_display: DisplaySide::default(),
+            zoom: ZoomSettings::default(),
+            pan_scheme: PanScheme::default(),
+            fit_to_work_area: false,
+            eyedropper_sample: SampleSize::default(),
+            last_open_dir: None,
+            path: None,
+            changed_at: None,
+            unknown: Map::new(),
+        }
+    }
+}
+
+impl AppConfig {
+    fn get_config_path() -> Option<PathBuf> {
+        if let Some(dir) = portable_dir() {
+            return Some(dir.join(CONFIG_FILE));
+        }
+        let proj_dirs = project_dirs()?;
+        let config_dir = proj_dirs.config_dir();
+        if !config_dir.exists() {
+            let _ = fs::create_dir_all(config_dir);
+        }
+        Some(config_dir.join(CONFIG_FILE))
+    }
+
+    /// Reads the config file (next to the executable when portable), falling back to the
+    /// defaults if it is missing or unreadable.
+    pub fn load() -> Self {
+        match Self::get_config_path() {
+            Some(path) => Self::load_from(&path),
+            None => Self::default(),
+        }
+    }
+
+    /// Reads the config from `path`, which later saves also go to.
+    pub fn load_from(path: &Path) -> Self {
+        let mut config = match fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content).unwrap_or_else(|| {
+                // Keep the unreadable file around rather than overwriting it with defaults
+                let _ = fs::copy(path, path.with_extension("json.bak"));
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        config.path = Some(path.to_path_buf());
+        config
+    }
+
+    /// Parses a config file of any version. A value that doesn't fit (a wrong type, say)
+    /// falls back to its default alone instead of resetting every setting. `None` if the
+    /// content isn't a JSON object at all.
+    fn parse(content: &str) -> Option<Self> {
+        let Ok(Value::Object(mut file)) = serde_json::from_str(content) else {
+            return None;
+        };
+        let version = file.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+        migrate(&mut file, version);
+        // Saved back in the current format, unless a newer release wrote it
+        file.insert("version".into(), Value::from(version.max(CONFIG_VERSION)));
+
+        if let Ok(config) = serde_json::from_value(Value::Object(file.clone())) {
+            return Some(config);
+        }
+        let Ok(Value::Object(mut merged)) = serde_json::to_value(Self::default()) else {
+            return None;
+        };
+        for (key, value) in file {
+            let previous = merged.insert(key.clone(), value);
+            if serde_json::from_value::<Self>(Value::Object(merged.clone())).is_err() {
+                eprintln!("Config: ignoring the invalid value of \"{}\"", key);
+                match previous {
+                    Some(previous) => merged.insert(key, previous),
+                    None => merged.remove(&key),
+                };
+            }
+        }
+        serde_json::from_value(Value::Object(merged)).ok()
+    }
+
+    /// Settings read from an exported bundle (see `app_data`), to replace `self` and be
+    /// saved where it is.
+    pub fn imported(&self, content: &str) -> Option<Self> {
+        let mut imported = Self::parse(content)?;
+        imported.path = self.path.clone();
+        imported.mark_dirty();
+        Some(imported)
+    }
+
+    /// Moves `path` to the front of the recent files list.
+    pub fn add_recent_file(&mut self, path: &std::path::Path) {
+        self.recent_files.retain(|p| p != path);
+        self.recent_files.insert(0, path.to_path_buf());
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    /// Stores the view of `view.path`, replacing an older entry for it.
+    pub fn save_view(&mut self, view: SavedView) {
+        self.saved_views.retain(|v| v.path != view.path);
+        self.saved_views.insert(0, view);
+        self.saved_views.truncate(MAX_SAVED_VIEWS);
+    }
+
+    /// The stored view of `path`, if any.
+    pub fn saved_view(&self, path: &std::path::Path) -> Option<&SavedView> {
+        self.saved_views.iter().find(|v| v.path == path)
+    }
+
+    /// Schedules a save; call after changing any setting.
+    pub fn mark_dirty(&mut self) {
+        self.changed_at = Some(Instant::now());
+    }
+
+    /// Saves changes once they've settled. Returns how long until they will have, if
+    /// there are changes pending.
+    pub fn save_if_due(&mut self) -> Option<Duration> {
+        let wait = SAVE_DEBOUNCE.saturating_sub(self.changed_at?.elapsed());
+        if wait.is_zero() {
+            self.flush();
+            return None;
+        }
+        Some(wait)
+    }
+
+    /// Saves pending changes right away, e.g. on exit.
+    pub fn flush(&mut self) {
+        if self.changed_at.take().is_some() {
+            self.save();
+        }
+    }
+
+    /// Writes the config file. Failures are ignored; settings just won't persist.
+    pub fn save(&self) {
+        if let Some(path) = &self.path
+            && let Ok(content) = serde_json::to_string_pretty(self)
+        {
+            let _ = fs::write(path, content);
+        }
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        fs::metadata(self.path.as_ref()?).ok()?.modified().ok()
+    }
+}
+
+/// Picks up edits to the config file made outside the viewer, e.g. in a text editor.
+///
+/// A background thread checks the file's modification time and only wakes the viewer
+/// when it changes.
+pub struct ConfigWatcher {
+    rx: Option<Receiver<()>>,
+    // The settings as last read from the file, to tell outside edits from the viewer's own
+    on_disk: Value,
+}
+
+impl ConfigWatcher {
+    pub fn new(ctx: &egui::Context, config: &AppConfig) -> Self {
+        let rx = config.path.clone().map(|path| {
+            let (tx, rx) = channel();
+            let ctx = ctx.clone();
+            let mut modified = config.modified();
+            thread::spawn(move || {
+                loop {
+                    thread::sleep(WATCH_INTERVAL);
+                    let now = fs::metadata(&path).and_then(|m| m.modified()).ok();
+                    if now != modified {
+                        modified = now;
+                        if tx.send(()).is_err() {
+                            break;
+                        }
+                        ctx.request_repaint();
+                    }
+                }
+            });
+            rx
+        });
+        Self {
+            rx,
+            on_disk: serde_json::to_value(config).unwrap_or_default(),
+        }
+    }
+
+    /// The config as now on disk, if the file changed to something other than `current`
+    /// (the viewer's own saves don't count). Settings changed in the viewer but not saved
+    /// yet are kept unless the file changed them too.
+    pub fn poll(&mut self, current: &AppConfig) -> Option<AppConfig> {
+        if self.rx.as_ref()?.try_iter().count() == 0 {
+            return None;
+        }
+
+        // Half-written or broken files are left for the next edit to fix
+        let path = current.path.as_ref()?;
+        let reloaded = AppConfig::parse(&fs::read_to_string(path).ok()?)?;
+        let file = serde_json::to_value(&reloaded).ok()?;
+        let base = std::mem::replace(&mut self.on_disk, file.clone());
+        let current_value = serde_json::to_value(current).ok()?;
+        if file == current_value {
+            return None;
+        }
+
+        let mut config = if current.changed_at.is_some() {
+            let merged = merge_edits(&base, &file, current_value);
+            serde_json::from_value::<AppConfig>(merged).ok()?
+        } else {
+            reloaded
+        };
+        config.path = Some(path.clone());
+        config.changed_at = current.changed_at;
+        Some(config)
+    }
+}
+
+/// Applies the settings that differ between `base` and `edited` to `current`.
+fn merge_edits(base: &Value, edited: &Value, mut current: Value) -> Value {
+    if let (Value::Object(edited), Value::Object(current)) = (edited, &mut current) {
+        for (key, value) in edited {
+            if base.get(key) != Some(value) {
+                current.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_get_defaults() {
+        let config: AppConfig = serde_json::from_str(r#"{"window_size": [640.0, 480.0]}"#).unwrap();
+        assert_eq!(config.window_size, Some([640.0, 480.0]));
+        assert!(config.include_hidden);
+        assert_eq!(config.slideshow_interval, 5.0);
+        assert!(config.extensions.iter().any(|e| e == "jpg"));
+    }
+
+    #[test]
+    fn invalid_values_only_reset_themselves() {
+        let config = AppConfig::parse(r#"{"skip_unreadable": true, "slideshow_interval": "slow"}"#)
+            .unwrap();
+        assert!(config.skip_unreadable);
+        assert_eq!(config.slideshow_interval, 5.0);
+    }
+
+    #[test]
+    fn old_extension_lists_gain_video_types() {
+        let config = AppConfig::parse(r#"{"extensions": ["jpg"]}"#).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert!(config.extensions.iter().any(|e| e == "mp4"));
+        let config = AppConfig::parse(r#"{"version": 1, "extensions": ["jpg"]}"#).unwrap();
+        assert_eq!(config.extensions, ["jpg"]);
+    }
+
+    #[test]
+    fn unknown_settings_survive_a_save() {
+        let config = AppConfig::parse(r#"{"version": 7, "future_option": [1, 2]}"#).unwrap();
+        let saved = serde_json::to_value(&config).unwrap();
+        assert_eq!(saved["future_option"], serde_json::json!([1, 2]));
+        assert_eq!(saved["version"], 7);
+    }
+
+    #[test]
+    fn saves_go_back_to_the_loaded_file() {
+        let path = std::env::temp_dir().join(format!("biv_config_test_{}.json", std::process::id()));
+        let mut config = AppConfig::load_from(&path);
+        config.skip_unreadable = true;
+        config.save();
+        assert!(AppConfig::load_from(&path).skip_unreadable);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn recent_files_move_to_front_without_duplicates() {
+        let mut config = AppConfig::default();
+        for name in ["a.jpg", "b.jpg", "a.jpg"] {
+            config.add_recent_file(std::path::Path::new(name));
+        }
+        assert_eq!(config.recent_files, [PathBuf::from("a.jpg"), PathBuf::from("b.jpg")]);
+    }
+
+    #[test]
+    fn outside_edits_merge_with_unsaved_changes() {
+        let base = serde_json::json!({"include_hidden": true, "skip_unreadable": false});
+        let edited = serde_json::json!({"include_hidden": false, "skip_unreadable": false});
+        let current = serde_json::json!({"include_hidden": true, "skip_unreadable": true});
+        assert_eq!(
+            merge_edits(&base, &edited, current),
+            serde_json::json!({"include_hidden": false, "skip_unreadable": true})
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! "Remove background": cuts the subject of the current image out into a transparent PNG.
+//!
+//! Segmentation runs in the `rembg` command line tool (U²-Net and similar ONNX models),
+//! the same way OCR goes through tesseract, so no ML runtime is linked in; `rembg` only
+//! has to be on `PATH` (or be pointed to with `BIV_REMBG`) when the action is used.
+
+use crate::image_loader::decode_image;
+use crate::osd;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+pub struct Cutout {
+    // Result of the running job and when it started
+    job: Option<(Receiver<Result<String, String>>, Instant)>,
+    status: Option<String>,
+}
+
+fn rembg_command() -> Command {
+    Command::new(std::env::var_os("BIV_REMBG").unwrap_or_else(|| "rembg".into()))
+}
+
+fn default_file_name(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    format!("{}_cutout.png", stem)
+}
+
+/// Writes `path` with its background made transparent to `target` (a PNG).
+fn remove_background(path: &Path, target: &Path) -> Result<String, String> {
+    let image = decode_image(path).map_err(|e| format!("Background removal failed: {}", e))?;
+    // rembg reads through PIL, which knows fewer formats than we do
+    let input = std::env::temp_dir().join(format!("biv_cutout_{}.png", std::process::id()));
+    image
+        .save(&input)
+        .map_err(|e| format!("Background removal failed: {}", e))?;
+
+    let output = rembg_command()
+        .arg("i")
+        .arg(&input)
+        .arg(target)
+        .output()
+        .map_err(|e| format!("Could not run rembg ({}); is it installed?", e));
+    let _ = std::fs::remove_file(&input);
+    let output = output?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Background removal failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(format!("Saved {}", target.display()))
+}
+
+impl Cutout {
+    pub fn is_running(&self) -> bool {
+        self.job.is_some()
+    }
+
+    /// Asks where to save the cutout of `path`, then makes it on a worker thread.
+    pub fn start(&mut self, ctx: &egui::Context, path: PathBuf) {
+        if self.is_running() {
+            return;
+        }
+        let Some(target) = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .set_file_name(default_file_name(&path))
+            .save_file()
+        else {
+            return;
+        };
+
+        let ctx = ctx.clone();
+        let (tx, rx) = channel();
+        self.job = Some((rx, Instant::now()));
+        self.status = None;
+        thread::spawn(move || {
+            let _ = tx.send(remove_background(&path, &target));
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn show_status(&mut self, ctx: &egui::Context) {
+        if let Some((rx, _)) = &self.job
+            && let Ok(result) = rx.try_recv()
+        {
+            match result {
+                Ok(msg) => osd::toast(ctx, msg),
+                Err(msg) => self.status = Some(msg),
+            }
+            self.job = None;
+        }
+
+        if !self.is_running() && self.status.is_none() {
+            return;
+        }
+
+        egui::Area::new(egui::Id::new("cutout_status"))
+            .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(10.0, -40.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        if let Some((_, started)) = &self.job {
+                            // The model gives no progress, so the time spent stands in for it
+                            ui.spinner();
+                            let elapsed = started.elapsed().as_secs();
+                            ui.label(format!("Removing background… {}s", elapsed));
+                            ctx.request_repaint_after(Duration::from_secs(1));
+                        } else if let Some(status) = &self.status {
+                            ui.label(status);
+                        }
+                    });
+                });
+            });
+    }
+
+    pub fn dismiss_status(&mut self) {
+        self.status = None;
+    }
+}
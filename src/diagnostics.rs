@@ -0,0 +1,81 @@
+//! Diagnostics HUD (F3): frame rate, texture and cache memory, and loader backlog.
+
+use eframe::egui;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Frames are counted over this window
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+// Refresh rate of the readout while nothing else repaints
+const REFRESH: Duration = Duration::from_millis(250);
+
+/// Figures gathered by the app for one frame of the HUD.
+pub struct Snapshot {
+    pub textures: usize,
+    pub texture_bytes: usize,
+    pub thumbnails: usize,
+    pub thumbnail_bytes: usize,
+    pub decoded_cache_bytes: usize,
+    pub pending_loads: usize,
+    pub queued_commands: usize,
+}
+
+#[derive(Default)]
+pub struct Diagnostics {
+    pub open: bool,
+    frames: VecDeque<Instant>,
+}
+
+fn megabytes(bytes: usize) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+impl Diagnostics {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.frames.clear();
+    }
+
+    /// Draws the HUD `offset` points below the top left corner.
+    pub fn show(&mut self, ctx: &egui::Context, snapshot: &Snapshot, offset: f32, opacity: f32) {
+        if !self.open {
+            return;
+        }
+        let now = Instant::now();
+        self.frames.push_back(now);
+        while self.frames.front().is_some_and(|t| now - *t > FPS_WINDOW) {
+            self.frames.pop_front();
+        }
+        ctx.request_repaint_after(REFRESH);
+
+        let frame_time = ctx.input(|i| i.stable_dt) * 1000.0;
+        let lines = [
+            format!("FPS        {} ({:.1} ms)", self.frames.len(), frame_time),
+            format!(
+                "Textures   {} ({} VRAM)",
+                snapshot.textures,
+                megabytes(snapshot.texture_bytes)
+            ),
+            format!(
+                "Thumbnails {} ({} VRAM)",
+                snapshot.thumbnails,
+                megabytes(snapshot.thumbnail_bytes)
+            ),
+            format!("RAM cache  {}", megabytes(snapshot.decoded_cache_bytes)),
+            format!("Loading    {}", snapshot.pending_loads),
+            format!("Queued     {}", snapshot.queued_commands),
+        ];
+
+        egui::Area::new(egui::Id::new("diagnostics_hud"))
+            .anchor(egui::Align2::LEFT_TOP, egui::vec2(10.0, offset))
+            .interactable(false)
+            .show(ctx, |ui| {
+                ui.multiply_opacity(opacity);
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for line in &lines {
+                        ui.label(egui::RichText::new(line).monospace());
+                    }
+                });
+            });
+    }
+}
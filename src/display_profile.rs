@@ -0,0 +1,59 @@
+//! Color profile of the monitor the viewer is shown on.
+//!
+//! Images are treated as sRGB. Wide-gamut monitors that aren't color managed by the
+//! OS show sRGB values oversaturated, so for a Display P3 monitor the decoded pixels
+//! are re-encoded into P3 before they're uploaded.
+
+use crate::image_loader::{linear_to_srgb, srgb_to_linear};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DisplayProfile {
+    #[default]
+    Srgb,
+    DisplayP3,
+}
+
+// Linear sRGB to linear Display P3 (both D65 white)
+const SRGB_TO_P3: [[f32; 3]; 3] = [
+    [0.8225, 0.1774, 0.0000],
+    [0.0332, 0.9669, 0.0000],
+    [0.0171, 0.0724, 0.9108],
+];
+
+impl DisplayProfile {
+    pub fn label(self) -> &'static str {
+        match self {
+            DisplayProfile::Srgb => "sRGB",
+            DisplayProfile::DisplayP3 => "Display P3",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            DisplayProfile::Srgb => DisplayProfile::DisplayP3,
+            DisplayProfile::DisplayP3 => DisplayProfile::Srgb,
+        }
+    }
+
+    /// Converts sRGB pixels to this profile in place.
+    pub fn apply(self, image: &mut egui::ColorImage) {
+        if self == DisplayProfile::Srgb {
+            return;
+        }
+
+        let to_linear: Vec<f32> = (0..=255)
+            .map(|v| srgb_to_linear(v as f32 / 255.0))
+            .collect();
+        for pixel in &mut image.pixels {
+            let [r, g, b, a] = pixel.to_srgba_unmultiplied();
+            let rgb = [r, g, b].map(|v| to_linear[v as usize]);
+            let [r, g, b] = SRGB_TO_P3.map(|row| {
+                let v = row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2];
+                (linear_to_srgb(v) * 255.0).round() as u8
+            });
+            *pixel = egui::Color32::from_rgba_unmultiplied(r, g, b, a);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+//! Turns dropped content that isn't a plain file path into something loadable.
+//!
+//! Raw bytes (and downloaded URLs) are written to a private per-session temp folder. Web
+//! URLs are fetched with the `curl` command line tool, which ships with current Windows,
+//! macOS and most Linux distributions.
+
+use crate::config::private_temp_dir;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+pub enum Dropped {
+    Path(PathBuf),
+    Url(String),
+}
+
+/// The private temp folder for this session, created on first use.
+fn session_dir() -> std::io::Result<PathBuf> {
+    static DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+    let mut dir = DIR.lock().unwrap_or_else(|e| e.into_inner());
+    if dir.is_none() {
+        *dir = Some(private_temp_dir("biv_dropped")?);
+    }
+    Ok(dir.clone().expect("session dir was just created"))
+}
+
+/// Reduces a name taken from a URL or a drop to a plain file name of safe characters, so
+/// it can't point outside the session folder on any platform.
+fn safe_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(100)
+        .collect();
+    let name = name.trim_start_matches('.');
+    if name.is_empty() {
+        "dropped".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn is_web_url(text: &str) -> bool {
+    text.starts_with("http://") || text.starts_with("https://")
+}
+
+/// Minimal `file://` URL to path conversion (percent escapes included).
+fn file_url_to_path(url: &str) -> Option<PathBuf> {
+    let rest = url.strip_prefix("file://")?;
+    // "file:///C:/x" on Windows has no leading slash before the drive letter
+    let rest = match rest.as_bytes() {
+        [b'/', _, b':', ..] => &rest[1..],
+        _ => rest,
+    };
+
+    let mut bytes = Vec::with_capacity(rest.len());
+    let mut iter = rest.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex: Vec<u8> = iter.by_ref().take(2).collect();
+            let value = std::str::from_utf8(&hex)
+                .ok()
+                .and_then(|h| u8::from_str_radix(h, 16).ok())?;
+            bytes.push(value);
+        } else {
+            bytes.push(b);
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(bytes).ok()?))
+}
+
+/// Interprets a piece of text (pasted or dropped) as a path or URL.
+pub fn from_text(text: &str) -> Option<Dropped> {
+    let text = text.lines().next()?.trim().trim_matches('"');
+    if is_web_url(text) {
+        Some(Dropped::Url(text.to_string()))
+    } else if text.starts_with("file://") {
+        file_url_to_path(text).map(Dropped::Path)
+    } else {
+        let path = PathBuf::from(text);
+        path.exists().then_some(Dropped::Path(path))
+    }
+}
+
+/// Link files dropped from a browser (Windows `.url` internet shortcuts).
+fn read_internet_shortcut(path: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(path).ok()?;
+    text.lines()
+        .find_map(|line| line.trim().strip_prefix("URL="))
+        .map(str::to_string)
+}
+
+fn save_bytes(name: &str, bytes: &[u8]) -> std::io::Result<PathBuf> {
+    // Prefer the real format over whatever the name claims
+    let mut path = session_dir()?.join(safe_file_name(name));
+    if let Ok(format) = image::guess_format(bytes)
+        && let Some(ext) = format.extensions_str().first()
+    {
+        path.set_extension(ext);
+    }
+    std::fs::write(&path, bytes)?;
+    Ok(path)
+}
+
+/// Resolves one dropped item. Errors describe why the drop couldn't be used.
+pub fn resolve(file: &egui::DroppedFile) -> Result<Dropped, String> {
+    if let Some(path) = &file.path {
+        let path_str = path.to_string_lossy();
+        if let Some(dropped) = from_text(&path_str).filter(|_| !path.exists()) {
+            return Ok(dropped);
+        }
+        if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("url"))
+            && let Some(url) = read_internet_shortcut(path)
+        {
+            return from_text(&url).ok_or_else(|| format!("Unsupported link: {}", url));
+        }
+        return Ok(Dropped::Path(path.clone()));
+    }
+
+    if let Some(bytes) = &file.bytes {
+        return save_bytes(&file.name, bytes)
+            .map(Dropped::Path)
+            .map_err(|e| format!("Could not store dropped data: {}", e));
+    }
+
+    from_text(&file.name).ok_or_else(|| "Nothing usable was dropped".to_string())
+}
+
+#[derive(Default)]
+pub struct Downloader {
+    rx: Option<Receiver<Result<PathBuf, String>>>,
+    status: Option<String>,
+}
+
+impl Downloader {
+    pub fn is_running(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    pub fn set_status(&mut self, status: String) {
+        self.status = Some(status);
+    }
+
+    pub fn dismiss_status(&mut self) {
+        self.status = None;
+    }
+
+    pub fn download(&mut self, ctx: &egui::Context, url: String) {
+        if self.is_running() {
+            return;
+        }
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.status = None;
+
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = (|| {
+                let dir = session_dir().map_err(|e| e.to_string())?;
+                let name = url
+                    .split(['?', '#'])
+                    .next()
+                    .and_then(|u| u.rsplit('/').next())
+                    .filter(|n| !n.is_empty())
+                    .map_or_else(|| "download".to_string(), safe_file_name);
+                let temp = dir.join(format!("{}.part", name));
+
+                let output = Command::new("curl")
+                    .args(["--location", "--fail", "--silent", "--show-error", "--max-time", "60"])
+                    .arg("--output")
+                    .arg(&temp)
+                    .arg(&url)
+                    .output()
+                    .map_err(|e| format!("Could not run curl ({})", e))?;
+                if !output.status.success() {
+                    let _ = std::fs::remove_file(&temp);
+                    return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+                }
+
+                let bytes = std::fs::read(&temp).map_err(|e| e.to_string())?;
+                let _ = std::fs::remove_file(&temp);
+                if image::guess_format(&bytes).is_err() {
+                    return Err("The link does not point to an image".to_string());
+                }
+                save_bytes(&name, &bytes).map_err(|e| e.to_string())
+            })();
+            let _ = tx.send(result.map_err(|e| format!("Download failed: {}", e)));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Returns the downloaded file once it is ready.
+    pub fn poll(&mut self) -> Option<PathBuf> {
+        let result = self.rx.as_ref()?.try_recv().ok()?;
+        self.rx = None;
+        match result {
+            Ok(path) => Some(path),
+            Err(e) => {
+                self.status = Some(e);
+                None
+            }
+        }
+    }
+
+    pub fn show_status(&self, ctx: &egui::Context) {
+        if !self.is_running() && self.status.is_none() {
+            return;
+        }
+        egui::Area::new(egui::Id::new("download_status"))
+            .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(10.0, -120.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        if self.is_running() {
+                            ui.spinner();
+                            ui.label("Downloading…");
+                        } else if let Some(status) = &self.status {
+                            ui.label(status);
+                        }
+                    });
+                });
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_stay_inside_the_session_folder() {
+        assert_eq!(safe_file_name("photo.v2.jpg"), "photo.v2.jpg");
+        assert_eq!(safe_file_name("..\\..\\evil.png"), "_.._evil.png");
+        assert_eq!(safe_file_name("../x:y.png"), "_x_y.png");
+        assert_eq!(safe_file_name(".."), "dropped");
+        assert_eq!(safe_file_name(""), "dropped");
+    }
+}
@@ -0,0 +1,324 @@
+use crate::image_loader::{decode_image, resize_linear};
+use crate::perceptual_hash::{HashScanner, distance};
+use eframe::egui;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+// Hashes at most this many bits apart are considered the same picture
+const DUPLICATE_THRESHOLD: u32 = 6;
+const PREVIEW_SIZE: u32 = 160;
+
+pub enum DuplicateAction {
+    None,
+    Deleted(Vec<PathBuf>),
+}
+
+/// Sent by the grouping thread: the groups first, then a preview per grouped image.
+enum GroupingMessage {
+    Groups(Vec<Vec<PathBuf>>, HashMap<PathBuf, u64>),
+    Preview(PathBuf, egui::ColorImage),
+}
+
+/// Finds near-duplicate images in the current folder and lets the user delete copies.
+#[derive(Default)]
+pub struct DuplicateFinder {
+    pub open: bool,
+    scanner: Option<HashScanner>,
+    hashes: Vec<(PathBuf, u64)>,
+    grouping: Option<Receiver<GroupingMessage>>,
+    thumbnails: HashMap<PathBuf, egui::TextureHandle>,
+    groups: Vec<Vec<PathBuf>>,
+    grouped: bool,
+    // File sizes in bytes, read once when the groups arrive
+    sizes: HashMap<PathBuf, u64>,
+    marked: HashSet<PathBuf>,
+}
+
+/// Groups paths whose hashes are within `threshold` bits of each other (transitively).
+pub fn group_by_hash(hashes: &[(PathBuf, u64)], threshold: u32) -> Vec<Vec<PathBuf>> {
+    // Union-find over all close pairs
+    let mut parent: Vec<usize> = (0..hashes.len()).collect();
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    for i in 0..hashes.len() {
+        for j in i + 1..hashes.len() {
+            if distance(hashes[i].1, hashes[j].1) <= threshold {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                parent[a] = b;
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<PathBuf>> = HashMap::new();
+    for (i, (path, _)) in hashes.iter().enumerate() {
+        let root = find(&mut parent, i);
+        groups.entry(root).or_default().push(path.clone());
+    }
+
+    let mut groups: Vec<_> = groups.into_values().filter(|g| g.len() > 1).collect();
+    for group in &mut groups {
+        group.sort();
+    }
+    groups.sort();
+    groups
+}
+
+/// Index of the first group in which every image is marked, which deleting would empty.
+pub fn fully_marked(groups: &[Vec<PathBuf>], marked: &HashSet<PathBuf>) -> Option<usize> {
+    groups.iter().position(|g| g.iter().all(|p| marked.contains(p)))
+}
+
+impl DuplicateFinder {
+    pub fn start(&mut self, ctx: &egui::Context, folder_images: &[PathBuf]) {
+        self.open = true;
+        self.hashes.clear();
+        self.grouping = None;
+        self.thumbnails.clear();
+        self.groups.clear();
+        self.grouped = false;
+        self.sizes.clear();
+        self.marked.clear();
+        self.scanner = Some(HashScanner::start(ctx, folder_images.to_vec()));
+    }
+
+    /// Groups the hashes on a background thread, then decodes previews for the grouped
+    /// images only.
+    fn start_grouping(&mut self, ctx: &egui::Context) {
+        let (tx, rx) = channel();
+        self.grouping = Some(rx);
+
+        let hashes = std::mem::take(&mut self.hashes);
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let groups = group_by_hash(&hashes, DUPLICATE_THRESHOLD);
+            let sizes = groups
+                .iter()
+                .flatten()
+                .map(|p| (p.clone(), std::fs::metadata(p).map(|m| m.len()).unwrap_or(0)))
+                .collect();
+            let paths: Vec<PathBuf> = groups.iter().flatten().cloned().collect();
+            if tx.send(GroupingMessage::Groups(groups, sizes)).is_err() {
+                return;
+            }
+            ctx.request_repaint();
+
+            for path in paths {
+                let Ok(image) = decode_image(&path) else {
+                    continue;
+                };
+                let thumb = resize_linear(&image, PREVIEW_SIZE, PREVIEW_SIZE);
+                let preview = egui::ColorImage::from_rgba_unmultiplied(
+                    [thumb.width() as usize, thumb.height() as usize],
+                    thumb.as_raw(),
+                );
+                if tx.send(GroupingMessage::Preview(path, preview)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    fn process_results(&mut self, ctx: &egui::Context) {
+        if let Some(scanner) = &mut self.scanner {
+            while let Ok(result) = scanner.rx.try_recv() {
+                scanner.done += 1;
+                if let Some(hash) = result.hash {
+                    self.hashes.push((result.path, hash));
+                }
+            }
+            if scanner.is_finished() {
+                self.scanner = None;
+                self.start_grouping(ctx);
+            }
+        }
+
+        if let Some(rx) = &self.grouping {
+            while let Ok(message) = rx.try_recv() {
+                match message {
+                    GroupingMessage::Groups(groups, sizes) => {
+                        self.groups = groups;
+                        self.grouped = true;
+                        self.sizes = sizes;
+                    }
+                    GroupingMessage::Preview(path, preview) => {
+                        let texture = ctx.load_texture(
+                            format!("dup_{}", path.to_string_lossy()),
+                            preview,
+                            egui::TextureOptions::LINEAR,
+                        );
+                        self.thumbnails.insert(path, texture);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> DuplicateAction {
+        if !self.open {
+            self.scanner = None;
+            self.grouping = None;
+            return DuplicateAction::None;
+        }
+
+        self.process_results(ctx);
+
+        let mut action = DuplicateAction::None;
+        let mut open = self.open;
+        egui::Window::new("Duplicate Finder")
+            .open(&mut open)
+            .default_size([640.0, 480.0])
+            .show(ctx, |ui| {
+                if let Some(scanner) = &self.scanner {
+                    ui.label(format!("Hashing {} / {} images…", scanner.done, scanner.total));
+                    ui.add(egui::ProgressBar::new(scanner.progress()).show_percentage());
+                    return;
+                }
+
+                if self.grouping.is_some() && !self.grouped {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Grouping similar images…");
+                    });
+                    return;
+                }
+
+                if self.groups.is_empty() {
+                    ui.label("No duplicates found.");
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "{} groups of similar images. Tick the copies to delete.",
+                        self.groups.len()
+                    ));
+                    let emptied = fully_marked(&self.groups, &self.marked);
+                    let button = egui::Button::new(format!("Delete {} marked", self.marked.len()));
+                    let enabled = !self.marked.is_empty() && emptied.is_none();
+                    if ui.add_enabled(enabled, button).clicked()
+                        && let Some(deleted) = self.delete_marked()
+                    {
+                        action = DuplicateAction::Deleted(deleted);
+                    }
+                });
+                if let Some(group) = fully_marked(&self.groups, &self.marked) {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("Every image in group {} is marked; keep at least one.", group + 1),
+                    );
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                    for group in &self.groups {
+                        ui.horizontal(|ui| {
+                            for path in group {
+                                ui.vertical(|ui| {
+                                    ui.set_width(170.0);
+                                    if let Some(texture) = self.thumbnails.get(path) {
+                                        ui.add(
+                                            egui::Image::new(texture)
+                                                .max_size(egui::vec2(160.0, 160.0)),
+                                        );
+                                    }
+                                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                                    let size = self.sizes.get(path).copied().unwrap_or(0);
+                                    ui.label(format!("{}\n{} KB", name, size / 1024));
+
+                                    let mut marked = self.marked.contains(path);
+                                    if ui.checkbox(&mut marked, "Delete").changed() {
+                                        if marked {
+                                            self.marked.insert(path.clone());
+                                        } else {
+                                            self.marked.remove(path);
+                                        }
+                                    }
+                                });
+                            }
+                        });
+                        ui.separator();
+                    }
+                });
+            });
+        self.open = open;
+
+        action
+    }
+
+    /// Moves the marked files to the trash after confirmation, as long as every group
+    /// keeps an unmarked image. Returns the paths actually removed.
+    fn delete_marked(&mut self) -> Option<Vec<PathBuf>> {
+        if fully_marked(&self.groups, &self.marked).is_some() {
+            return None;
+        }
+        let confirmed = rfd::MessageDialog::new()
+            .set_title("Delete duplicates")
+            .set_description(format!("Move {} files to the trash?", self.marked.len()))
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show();
+        if confirmed != rfd::MessageDialogResult::Yes {
+            return None;
+        }
+
+        let deleted: Vec<PathBuf> = self
+            .marked
+            .drain()
+            .filter(|p| trash::delete(p).is_ok())
+            .collect();
+
+        for group in &mut self.groups {
+            group.retain(|p| !deleted.contains(p));
+        }
+        self.groups.retain(|g| g.len() > 1);
+        Some(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_close_hashes_transitively() {
+        let hashes: Vec<(PathBuf, u64)> = [
+            ("a", 0b0000),
+            ("b", 0xff00),
+            ("c", 0b0111),
+            ("d", 0xff01),
+            ("e", 0x0fff_0000),
+            ("f", 0b0111_1111),
+        ]
+        .into_iter()
+        .map(|(name, hash)| (PathBuf::from(name), hash))
+        .collect();
+
+        // c is 3 bits from a and 4 from f; a and f are 7 apart but still chained
+        let groups = group_by_hash(&hashes, 4);
+        let names: Vec<Vec<&str>> = groups
+            .iter()
+            .map(|g| g.iter().map(|p| p.to_str().unwrap()).collect())
+            .collect();
+        assert_eq!(names, [vec!["a", "c", "f"], vec!["b", "d"]]);
+    }
+
+    #[test]
+    fn refuses_to_mark_a_whole_group() {
+        let groups = vec![
+            vec![PathBuf::from("a"), PathBuf::from("b")],
+            vec![PathBuf::from("c"), PathBuf::from("d")],
+        ];
+        let mut marked: HashSet<PathBuf> = [PathBuf::from("a"), PathBuf::from("c")].into();
+        assert_eq!(fully_marked(&groups, &marked), None);
+        marked.insert(PathBuf::from("d"));
+        assert_eq!(fully_marked(&groups, &marked), Some(1));
+    }
+}
@@ -0,0 +1,345 @@
+//! Denoise and sharpen filters, previewed against the original with a split view and
+//! saved as a copy.
+//!
+//! Previews are made from a downscaled copy of the image on a worker thread; only one runs
+//! at a time, and settings changed meanwhile are picked up when it finishes.
+
+use crate::batch::save_rgba;
+use crate::image_loader::{decode_image, resize_linear};
+use crate::osd;
+use crate::split_compare;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+
+// Longest side of the copy previews are made from
+const PREVIEW_SIZE: u32 = 1024;
+// Neighbourhood the denoiser averages over, as a radius in pixels
+const DENOISE_RADIUS: i32 = 2;
+
+#[derive(Clone, Copy, PartialEq)]
+struct Settings {
+    // 0 leaves the image alone, 1 smooths differences of up to ~50 levels
+    denoise: f32,
+    // Unsharp mask: how much of the detail to add back, and the blur it is measured against
+    sharpen: f32,
+    radius: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            denoise: 0.0,
+            sharpen: 0.0,
+            radius: 1.5,
+        }
+    }
+}
+
+enum Event {
+    // Downscaled original, and the width of the full image
+    Source(PathBuf, Arc<image::RgbaImage>, u32),
+    Preview(PathBuf, Settings, egui::ColorImage),
+    Saved(String),
+    Failed(String),
+}
+
+pub struct Enhance {
+    pub open: bool,
+    settings: Settings,
+    tx: Sender<Event>,
+    rx: Receiver<Event>,
+    source: Option<(PathBuf, Arc<image::RgbaImage>, u32)>,
+    loading: Option<PathBuf>,
+    before: Option<egui::TextureHandle>,
+    after: Option<egui::TextureHandle>,
+    // Settings of the preview being made, and of the one shown
+    running: Option<Settings>,
+    shown: Option<Settings>,
+    split: f32,
+    saving: bool,
+    status: Option<String>,
+}
+
+impl Default for Enhance {
+    fn default() -> Self {
+        let (tx, rx) = channel();
+        Self {
+            open: false,
+            settings: Settings::default(),
+            tx,
+            rx,
+            source: None,
+            loading: None,
+            before: None,
+            after: None,
+            running: None,
+            shown: None,
+            split: 0.5,
+            saving: false,
+            status: None,
+        }
+    }
+}
+
+/// Edge-preserving smoothing (a small bilateral filter): neighbours count less the more
+/// their colour differs, so noise is averaged away while edges stay.
+fn denoise(image: &image::RgbaImage, strength: f32) -> image::RgbaImage {
+    let range_sigma = 50.0 * strength;
+    let range_factor = -1.0 / (2.0 * range_sigma * range_sigma);
+    let space_factor = -1.0 / (2.0 * (DENOISE_RADIUS as f32).powi(2));
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    image::RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let center = image.get_pixel(x, y).0.map(f32::from);
+        let mut sum = [0.0f32; 4];
+        let mut total = 0.0;
+        for dy in -DENOISE_RADIUS..=DENOISE_RADIUS {
+            for dx in -DENOISE_RADIUS..=DENOISE_RADIUS {
+                let nx = (x as i32 + dx).clamp(0, width - 1) as u32;
+                let ny = (y as i32 + dy).clamp(0, height - 1) as u32;
+                let pixel = image.get_pixel(nx, ny).0.map(f32::from);
+                let difference: f32 = (0..3).map(|i| (pixel[i] - center[i]).powi(2)).sum();
+                let distance = (dx * dx + dy * dy) as f32;
+                let weight = (distance * space_factor + difference * range_factor).exp();
+                for i in 0..4 {
+                    sum[i] += pixel[i] * weight;
+                }
+                total += weight;
+            }
+        }
+        image::Rgba(sum.map(|v| (v / total).round() as u8))
+    })
+}
+
+/// Adds `amount` times the difference from a blur of `sigma` pixels back to the image.
+fn unsharp_mask(image: &image::RgbaImage, amount: f32, sigma: f32) -> image::RgbaImage {
+    let blurred = image::imageops::blur(image, sigma);
+    let mut out = image.clone();
+    for (pixel, blurred) in out.pixels_mut().zip(blurred.pixels()) {
+        for i in 0..3 {
+            let value = f32::from(pixel[i]);
+            pixel[i] = (value + (value - f32::from(blurred[i])) * amount).round() as u8;
+        }
+    }
+    out
+}
+
+/// `image` with `settings` applied; `scale` is the size of `image` relative to the full
+/// one, so that the sharpening radius looks the same on previews.
+fn apply(image: &image::RgbaImage, settings: Settings, scale: f32) -> image::RgbaImage {
+    let denoised = if settings.denoise > 0.0 {
+        denoise(image, settings.denoise)
+    } else {
+        image.clone()
+    };
+    if settings.sharpen > 0.0 {
+        unsharp_mask(&denoised, settings.sharpen, settings.radius * scale)
+    } else {
+        denoised
+    }
+}
+
+fn to_color_image(image: &image::RgbaImage) -> egui::ColorImage {
+    let size = [image.width() as usize, image.height() as usize];
+    egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw())
+}
+
+fn default_file_name(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map_or("png".into(), |e| e.to_string_lossy());
+    format!("{}_enhanced.{}", stem, extension)
+}
+
+impl Enhance {
+    pub fn show(&mut self, ctx: &egui::Context, current: Option<&Path>) {
+        while let Ok(event) = self.rx.try_recv() {
+            self.handle(ctx, event);
+        }
+        if !self.open {
+            return;
+        }
+
+        let Some(current) = current else {
+            return;
+        };
+        let is_current = self.source.as_ref().is_some_and(|(p, ..)| p == current);
+        if !is_current && self.loading.as_deref() != Some(current) {
+            self.load_source(ctx, current.to_path_buf());
+        }
+        if is_current && self.running.is_none() && self.shown != Some(self.settings) {
+            self.start_preview(ctx);
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Denoise & sharpen")
+            .open(&mut open)
+            .default_width(540.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("enhance_settings")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Denoise");
+                        ui.add(egui::Slider::new(&mut self.settings.denoise, 0.0..=1.0));
+                        ui.end_row();
+                        ui.label("Sharpen");
+                        ui.add(egui::Slider::new(&mut self.settings.sharpen, 0.0..=3.0));
+                        ui.end_row();
+                        ui.label("Radius");
+                        ui.add(
+                            egui::Slider::new(&mut self.settings.radius, 0.5..=5.0).suffix(" px"),
+                        );
+                        ui.end_row();
+                    });
+
+                if let (true, Some(before), Some(after)) = (is_current, &self.before, &self.after) {
+                    let max_size = egui::vec2(ui.available_width(), 400.0);
+                    split_compare::show(ui, before, after, &mut self.split, max_size);
+                } else {
+                    ui.spinner();
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("Reset").clicked() {
+                        self.settings = Settings::default();
+                    }
+                    let can_save = self.settings != Settings::default() && !self.saving;
+                    if ui
+                        .add_enabled(can_save, egui::Button::new("Save As…"))
+                        .clicked()
+                        && let Some(target) = rfd::FileDialog::new()
+                            .add_filter("PNG", &["png"])
+                            .add_filter("JPEG", &["jpg", "jpeg"])
+                            .set_file_name(default_file_name(current))
+                            .save_file()
+                    {
+                        self.save(ctx, current.to_path_buf(), target);
+                    }
+                    if self.running.is_some() || self.saving {
+                        ui.spinner();
+                    } else if let Some(status) = &self.status {
+                        ui.label(status);
+                    }
+                });
+            });
+        self.open = open;
+    }
+
+    fn handle(&mut self, ctx: &egui::Context, event: Event) {
+        match event {
+            Event::Source(path, image, full_width) => {
+                if self.loading.as_ref() != Some(&path) {
+                    return;
+                }
+                self.loading = None;
+                let texture = to_color_image(&image);
+                self.before =
+                    Some(ctx.load_texture("enhance_before", texture, egui::TextureOptions::LINEAR));
+                self.after = None;
+                self.shown = None;
+                self.source = Some((path, image, full_width));
+            }
+            Event::Preview(path, settings, image) => {
+                self.running = None;
+                // Made from an image that has been replaced since
+                if self.source.as_ref().is_none_or(|(p, ..)| *p != path) {
+                    return;
+                }
+                self.after =
+                    Some(ctx.load_texture("enhance_after", image, egui::TextureOptions::LINEAR));
+                self.shown = Some(settings);
+            }
+            Event::Saved(msg) => {
+                self.saving = false;
+                osd::toast(ctx, msg);
+            }
+            Event::Failed(msg) => {
+                self.saving = false;
+                self.loading = None;
+                self.running = None;
+                self.status = Some(msg);
+            }
+        }
+    }
+
+    fn load_source(&mut self, ctx: &egui::Context, path: PathBuf) {
+        self.loading = Some(path.clone());
+        self.status = None;
+        let tx = self.tx.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let event = match decode_image(&path) {
+                Ok(image) => {
+                    let preview = resize_linear(&image, PREVIEW_SIZE, PREVIEW_SIZE);
+                    Event::Source(path, Arc::new(preview), image.width())
+                }
+                Err(e) => Event::Failed(format!("Could not load image: {}", e)),
+            };
+            let _ = tx.send(event);
+            ctx.request_repaint();
+        });
+    }
+
+    fn start_preview(&mut self, ctx: &egui::Context) {
+        let Some((path, image, full_width)) = &self.source else {
+            return;
+        };
+        let settings = self.settings;
+        self.running = Some(settings);
+        let path = path.clone();
+        let image = Arc::clone(image);
+        let scale = image.width() as f32 / (*full_width).max(1) as f32;
+        let tx = self.tx.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let preview = apply(&image, settings, scale);
+            let _ = tx.send(Event::Preview(path, settings, to_color_image(&preview)));
+            ctx.request_repaint();
+        });
+    }
+
+    fn save(&mut self, ctx: &egui::Context, path: PathBuf, target: PathBuf) {
+        self.saving = true;
+        self.status = None;
+        let settings = self.settings;
+        let tx = self.tx.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let event = decode_image(&path)
+                .and_then(|image| save_rgba(apply(&image.to_rgba8(), settings, 1.0), &target))
+                .map(|_| Event::Saved(format!("Saved {}", target.display())))
+                .unwrap_or_else(|e| Event::Failed(format!("Saving failed: {}", e)));
+            let _ = tx.send(event);
+            ctx.request_repaint();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn denoise_keeps_edges_and_flattens_noise() {
+        let image = image::RgbaImage::from_fn(10, 10, |x, y| {
+            let base = if x < 5 { 20 } else { 230 };
+            let noise = if (x + y) % 2 == 0 { 6 } else { 0 };
+            image::Rgba([base + noise, base + noise, base + noise, 255])
+        });
+        let out = denoise(&image, 0.5);
+        // Both sides end up close to their average, and the edge stays sharp
+        assert!(out.get_pixel(2, 2)[0].abs_diff(23) <= 1);
+        assert!(out.get_pixel(7, 2)[0].abs_diff(233) <= 1);
+        assert!(out.get_pixel(4, 5)[0] < 40 && out.get_pixel(5, 5)[0] > 210);
+    }
+
+    #[test]
+    fn default_settings_leave_the_image_alone() {
+        let image =
+            image::RgbaImage::from_fn(8, 8, |x, y| image::Rgba([x as u8 * 30, y as u8, 7, 255]));
+        assert_eq!(apply(&image, Settings::default(), 1.0), image);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::config::ExternalTool;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+pub enum ToolAction {
+    None,
+    // The tool exited; the image may have been modified in place
+    Finished(PathBuf),
+}
+
+/// Splits a command template into arguments (double quotes group words) and fills in
+/// the placeholders. Substitution happens per argument, so paths with spaces stay whole.
+pub fn expand(template: &str, path: &Path) -> Vec<String> {
+    let dir = path.parent().unwrap_or(Path::new("")).to_string_lossy();
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    for c in template.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(current);
+    }
+
+    args.into_iter()
+        .map(|arg| {
+            arg.replace("{path}", &path.to_string_lossy())
+                .replace("{dir}", &dir)
+                .replace("{name}", &name)
+                .replace("{stem}", &stem)
+        })
+        .collect()
+}
+
+fn run(tool: &ExternalTool, path: &Path) -> Result<String, String> {
+    let args = expand(&tool.command, path);
+    let (program, rest) = args.split_first().ok_or("Empty command")?;
+    let output = Command::new(program)
+        .args(rest)
+        .output()
+        .map_err(|e| format!("Could not run {} ({})", program, e))?;
+
+    if output.status.success() {
+        Ok(format!("{} finished", tool.name))
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let last_line = stderr.lines().last().unwrap_or_default();
+        Err(format!("{} failed ({}): {}", tool.name, output.status, last_line))
+    }
+}
+
+/// Opens `path` in the operating system's default application for its type.
+pub fn open_with_system(path: &Path) -> std::io::Result<()> {
+    // Explorer takes the path as-is, where `cmd /C start` would parse `&` and `^` in it
+    #[cfg(windows)]
+    let mut command = Command::new("explorer.exe");
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let mut command = Command::new("xdg-open");
+
+    command.arg(path).spawn().map(|_| ())
+}
+
+/// The Tools window: runs user-configured commands on the current image.
+#[derive(Default)]
+pub struct ToolsMenu {
+    pub open: bool,
+    rx: Option<Receiver<(PathBuf, Result<String, String>)>>,
+    running: Option<String>,
+    status: Option<String>,
+}
+
+impl ToolsMenu {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        tools: &[ExternalTool],
+        current: Option<&Path>,
+    ) -> ToolAction {
+        let mut action = ToolAction::None;
+        if let Some(rx) = &self.rx
+            && let Ok((path, result)) = rx.try_recv()
+        {
+            self.status = Some(match result {
+                Ok(msg) | Err(msg) => msg,
+            });
+            self.rx = None;
+            self.running = None;
+            action = ToolAction::Finished(path);
+        }
+
+        if !self.open {
+            return action;
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Tools")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if tools.is_empty() {
+                    ui.label("No tools configured.");
+                    ui.label("Add entries to \"tools\" in config.json, e.g.");
+                    ui.code(r#"{ "name": "Optimize", "command": "oxipng -o 4 {path}" }"#);
+                    return;
+                }
+
+                for tool in tools {
+                    let enabled = current.is_some() && self.rx.is_none();
+                    let button = ui
+                        .add_enabled(enabled, egui::Button::new(&tool.name))
+                        .on_hover_text(&tool.command);
+                    if button.clicked()
+                        && let Some(path) = current
+                    {
+                        self.start(ctx, tool.clone(), path.to_path_buf());
+                    }
+                }
+
+                ui.separator();
+                if let Some(name) = &self.running {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!("Running {}…", name));
+                    });
+                } else if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+            });
+        self.open = open;
+
+        action
+    }
+
+    fn start(&mut self, ctx: &egui::Context, tool: ExternalTool, path: PathBuf) {
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.running = Some(tool.name.clone());
+        self.status = None;
+
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = run(&tool, &path);
+            let _ = tx.send((path, result));
+            ctx.request_repaint();
+        });
+    }
+}
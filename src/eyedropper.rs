@@ -0,0 +1,335 @@
+//! Eyedropper (Shift+I): the color under the cursor, averaged over 1×1, 3×3 or 5×5 image
+//! pixels, with a live readout in RGB, HSV, hex and linear light. Colors come from the
+//! decoded file rather than the texture on screen, so exposure, false color and the
+//! display profile don't change them. Clicking the image copies the hex value.
+
+use crate::image_loader::{SharedDecodedCache, decode_image, linear_to_srgb, srgb_to_linear};
+use crate::view_state::ViewState;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+/// Square of image pixels a reading averages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SampleSize {
+    #[default]
+    Point,
+    Three,
+    Five,
+}
+
+impl SampleSize {
+    pub const ALL: [SampleSize; 3] = [SampleSize::Point, SampleSize::Three, SampleSize::Five];
+
+    fn radius(self) -> u32 {
+        match self {
+            SampleSize::Point => 0,
+            SampleSize::Three => 1,
+            SampleSize::Five => 2,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SampleSize::Point => "1×1",
+            SampleSize::Three => "3×3",
+            SampleSize::Five => "5×5",
+        }
+    }
+}
+
+/// An averaged reading; channels are 0..1 in sRGB and in linear light.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    // Center pixel
+    pub x: u32,
+    pub y: u32,
+    pub srgb: [f32; 3],
+    pub linear: [f32; 3],
+    pub alpha: Option<f32>,
+}
+
+impl Sample {
+    pub fn rgb8(&self) -> [u8; 3] {
+        self.srgb.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    pub fn hex(&self) -> String {
+        let [r, g, b] = self.rgb8();
+        format!("#{:02X}{:02X}{:02X}", r, g, b)
+    }
+}
+
+/// Averages the pixels within `size` of (`x`, `y`), cut off at the image edges. Float
+/// images hold linear light; the others are taken to be sRGB.
+pub fn sample(image: &image::DynamicImage, x: u32, y: u32, size: SampleSize) -> Option<Sample> {
+    if x >= image.width() || y >= image.height() {
+        return None;
+    }
+    let r = size.radius();
+    let (x0, y0) = (x.saturating_sub(r), y.saturating_sub(r));
+    let x1 = (x + r + 1).min(image.width());
+    let y1 = (y + r + 1).min(image.height());
+    let region = image.crop_imm(x0, y0, x1 - x0, y1 - y0).to_rgba32f();
+
+    let mut sum = [0.0f32; 4];
+    for pixel in region.pixels() {
+        for (total, v) in sum.iter_mut().zip(pixel.0) {
+            *total += v;
+        }
+    }
+    let count = region.pixels().len() as f32;
+    let [r, g, b, a] = sum.map(|v| v / count);
+    let float = matches!(
+        image.color(),
+        image::ColorType::Rgb32F | image::ColorType::Rgba32F
+    );
+    let (srgb, linear) = if float {
+        ([r, g, b].map(linear_to_srgb), [r, g, b])
+    } else {
+        ([r, g, b], [r, g, b].map(srgb_to_linear))
+    };
+    Some(Sample {
+        x,
+        y,
+        srgb,
+        linear,
+        alpha: image.color().has_alpha().then_some(a),
+    })
+}
+
+/// Hue in degrees, saturation and value (0..1) of an sRGB color.
+pub fn hsv([r, g, b]: [f32; 3]) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    let hue = if delta <= 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let saturation = if max > 0.0 { delta / max } else { 0.0 };
+    (hue, saturation, max)
+}
+
+pub enum EyedropperAction {
+    SetSize(SampleSize),
+    Copy(String),
+}
+
+#[derive(Default)]
+pub struct Eyedropper {
+    pub enabled: bool,
+    pub size: SampleSize,
+    // Pixels read from, when no longer in the loader's cache; None if the file can't be read
+    source: Option<(PathBuf, Option<Arc<image::DynamicImage>>)>,
+    pending: Option<(PathBuf, Receiver<Option<Arc<image::DynamicImage>>>)>,
+    // Latest reading, kept while the cursor is off the image
+    reading: Option<Sample>,
+    // Pixels the reading under the cursor averages, in texture pixels
+    outline: Option<egui::Rect>,
+}
+
+impl Eyedropper {
+    /// Decoded pixels of `path`: the loader's if it still has them, otherwise decoded again
+    /// on a worker (None until that finishes).
+    fn pixels(
+        &mut self,
+        ctx: &egui::Context,
+        path: &Path,
+        decoded: &SharedDecodedCache,
+    ) -> Option<Arc<image::DynamicImage>> {
+        if let Some(image) = decoded.lock().unwrap().get(path) {
+            return Some(image);
+        }
+        if let Some((source_path, image)) = &self.source
+            && source_path == path
+        {
+            return image.clone();
+        }
+        match &self.pending {
+            Some((pending_path, rx)) if pending_path == path => {
+                let image = rx.try_recv().ok()?;
+                self.pending = None;
+                self.source = Some((path.to_path_buf(), image.clone()));
+                image
+            }
+            _ => {
+                let (tx, rx) = channel();
+                let worker_path = path.to_path_buf();
+                let ctx = ctx.clone();
+                thread::spawn(move || {
+                    let _ = tx.send(decode_image(&worker_path).ok().map(Arc::new));
+                    ctx.request_repaint();
+                });
+                self.pending = Some((path.to_path_buf(), rx));
+                None
+            }
+        }
+    }
+
+    /// Reads the color under the cursor. `image_size` is the size of the texture the view
+    /// shows, which may be smaller than the file.
+    pub fn process_input(
+        &mut self,
+        ui: &egui::Ui,
+        view_state: &ViewState,
+        image_size: egui::Vec2,
+        path: &Path,
+        decoded: &SharedDecodedCache,
+    ) -> Option<EyedropperAction> {
+        self.outline = None;
+        if !self.enabled {
+            return None;
+        }
+        let viewport = ui.clip_rect();
+        let over_image = ui.rect_contains_pointer(viewport) && !ui.ctx().is_pointer_over_area();
+        let pos = ui.input(|i| i.pointer.hover_pos()).filter(|_| over_image)?;
+        let image = self.pixels(ui.ctx(), path, decoded)?;
+
+        let scale = image.width() as f32 / image_size.x.max(1.0);
+        let p = view_state
+            .screen_to_image(viewport, image_size, pos)
+            .to_vec2()
+            * scale;
+        if p.x < 0.0 || p.y < 0.0 {
+            return None;
+        }
+        let reading = sample(&image, p.x as u32, p.y as u32, self.size)?;
+        self.reading = Some(reading);
+        let r = self.size.radius() as f32;
+        let center = egui::pos2(reading.x as f32 + 0.5, reading.y as f32 + 0.5);
+        self.outline = Some(egui::Rect::from_center_size(
+            (center.to_vec2() / scale).to_pos2(),
+            egui::Vec2::splat((2.0 * r + 1.0) / scale),
+        ));
+        ui.ctx().set_cursor_icon(egui::CursorIcon::Crosshair);
+
+        ui.input(|i| i.pointer.primary_clicked())
+            .then(|| EyedropperAction::Copy(reading.hex()))
+    }
+
+    /// Outlines the pixels being read.
+    pub fn paint(&self, ui: &egui::Ui, view_state: &ViewState, image_size: egui::Vec2) {
+        let Some(outline) = self.outline else {
+            return;
+        };
+        let viewport = ui.clip_rect();
+        let outline = egui::Rect::from_min_max(
+            view_state.image_to_screen(viewport, image_size, outline.min),
+            view_state.image_to_screen(viewport, image_size, outline.max),
+        );
+        let painter = ui.painter();
+        painter.rect_stroke(
+            outline.expand(1.0),
+            0.0,
+            egui::Stroke::new(1.0, egui::Color32::BLACK),
+        );
+        painter.rect_stroke(outline, 0.0, egui::Stroke::new(1.0, egui::Color32::WHITE));
+    }
+
+    /// The readout panel.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<EyedropperAction> {
+        let mut action = None;
+        let mut open = self.enabled;
+        egui::Window::new("Eyedropper")
+            .open(&mut open)
+            .resizable(false)
+            .default_pos(egui::pos2(16.0, 80.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Average");
+                    for size in SampleSize::ALL {
+                        if ui
+                            .selectable_label(self.size == size, size.label())
+                            .clicked()
+                            && self.size != size
+                        {
+                            self.size = size;
+                            action = Some(EyedropperAction::SetSize(size));
+                        }
+                    }
+                });
+                ui.separator();
+                let Some(reading) = self.reading else {
+                    ui.weak("Point at the image");
+                    return;
+                };
+
+                let [r, g, b] = reading.rgb8();
+                let (swatch, _) = ui.allocate_exact_size(
+                    egui::vec2(ui.available_width(), 28.0),
+                    egui::Sense::hover(),
+                );
+                ui.painter()
+                    .rect_filled(swatch, 4.0, egui::Color32::from_rgb(r, g, b));
+
+                let (h, s, v) = hsv(reading.srgb);
+                let [lr, lg, lb] = reading.linear;
+                egui::Grid::new("eyedropper_values")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Pixel");
+                        ui.monospace(format!("{}, {}", reading.x, reading.y));
+                        ui.end_row();
+                        ui.label("RGB");
+                        ui.monospace(format!("{}, {}, {}", r, g, b));
+                        ui.end_row();
+                        ui.label("HSV");
+                        ui.monospace(format!("{:.0}°, {:.0}%, {:.0}%", h, s * 100.0, v * 100.0));
+                        ui.end_row();
+                        ui.label("Hex");
+                        ui.horizontal(|ui| {
+                            ui.monospace(reading.hex());
+                            if ui.small_button("Copy").clicked() {
+                                action = Some(EyedropperAction::Copy(reading.hex()));
+                            }
+                        });
+                        ui.end_row();
+                        ui.label("Linear");
+                        ui.monospace(format!("{:.3}, {:.3}, {:.3}", lr, lg, lb));
+                        ui.end_row();
+                        if let Some(alpha) = reading.alpha {
+                            ui.label("Alpha");
+                            ui.monospace(format!("{:.0}%", alpha * 100.0));
+                            ui.end_row();
+                        }
+                    });
+            });
+        self.enabled = open;
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_the_region_within_the_image() {
+        let mut image = image::RgbImage::new(4, 4);
+        image.put_pixel(0, 0, image::Rgb([255, 0, 0]));
+        image.put_pixel(1, 0, image::Rgb([0, 0, 255]));
+        let image = image::DynamicImage::ImageRgb8(image);
+
+        let point = sample(&image, 0, 0, SampleSize::Point).unwrap();
+        assert_eq!(point.hex(), "#FF0000");
+        // The corner keeps 2×2 of the 3×3 square: one red, one blue, two black
+        let three = sample(&image, 0, 0, SampleSize::Three).unwrap();
+        assert_eq!(three.rgb8(), [64, 0, 64]);
+        assert!(sample(&image, 4, 0, SampleSize::Point).is_none());
+    }
+
+    #[test]
+    fn converts_to_hsv() {
+        assert_eq!(hsv([1.0, 0.0, 0.0]), (0.0, 1.0, 1.0));
+        assert_eq!(hsv([0.0, 0.0, 1.0]), (240.0, 1.0, 1.0));
+        assert_eq!(hsv([0.5, 0.5, 0.5]), (0.0, 0.0, 0.5));
+    }
+}
@@ -0,0 +1,80 @@
+//! False-color view: maps luminance to a color gradient, which makes exposure maps,
+//! depth maps and heightmaps much easier to read than plain gray.
+
+use eframe::egui;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colormap {
+    Viridis,
+    Turbo,
+}
+
+impl Colormap {
+    pub fn label(self) -> &'static str {
+        match self {
+            Colormap::Viridis => "Viridis",
+            Colormap::Turbo => "Turbo",
+        }
+    }
+
+    /// Off → Viridis → Turbo → off.
+    pub fn cycle(current: Option<Self>) -> Option<Self> {
+        match current {
+            None => Some(Colormap::Viridis),
+            Some(Colormap::Viridis) => Some(Colormap::Turbo),
+            Some(Colormap::Turbo) => None,
+        }
+    }
+
+    /// Color for `t` in 0..1 (polynomial fits of the reference gradients).
+    fn color(self, t: f32) -> [f32; 3] {
+        let t = t.clamp(0.0, 1.0);
+        let poly = |c: &[f32]| c.iter().rev().fold(0.0, |acc, c| acc * t + c);
+        match self {
+            Colormap::Viridis => [
+                poly(&[0.2777, 0.1051, -0.3309, -4.6342, 6.2283, 4.7764, -5.4355]),
+                poly(&[0.0054, 1.4046, 0.2148, -5.7991, 14.1799, -13.7451, 4.6459]),
+                poly(&[0.3341, 1.3846, 0.0951, -19.3324, 56.6906, -65.3530, 26.3124]),
+            ],
+            Colormap::Turbo => [
+                poly(&[0.1357, 4.6154, -42.6603, 132.1311, -152.9424, 59.2864]),
+                poly(&[0.0914, 2.1942, 4.8430, -14.1850, 4.2773, 2.8296]),
+                poly(&[0.1067, 12.6419, -60.5820, 110.3628, -89.9031, 27.3483]),
+            ],
+        }
+    }
+
+    /// Renders the luminance of `image` through this gradient.
+    ///
+    /// Float images (HDR, EXR, depth) have no fixed white point, so their range is
+    /// stretched to the darkest and brightest pixel.
+    pub fn apply(self, image: &image::DynamicImage) -> egui::ColorImage {
+        let luma = image.to_luma32f();
+        let (mut low, mut high) = (0.0, 1.0);
+        if matches!(
+            image.color(),
+            image::ColorType::Rgb32F | image::ColorType::Rgba32F
+        ) {
+            low = luma.iter().copied().fold(f32::INFINITY, f32::min);
+            high = luma.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        }
+        let range = (high - low).max(f32::EPSILON);
+
+        let lut: Vec<egui::Color32> = (0..=255)
+            .map(|i| {
+                let [r, g, b] = self.color(i as f32 / 255.0);
+                let encode = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+                egui::Color32::from_rgb(encode(r), encode(g), encode(b))
+            })
+            .collect();
+        let pixels = luma
+            .iter()
+            .map(|v| lut[(((v - low) / range).clamp(0.0, 1.0) * 255.0).round() as usize])
+            .collect();
+
+        egui::ColorImage {
+            size: [image.width() as usize, image.height() as usize],
+            pixels,
+        }
+    }
+}
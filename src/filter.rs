@@ -0,0 +1,246 @@
+//! Filter bar (Ctrl+F) that narrows the folder list by name, extension, resolution or rating.
+//!
+//! Name and extension filters apply immediately. Resolution and rating need to read every
+//! file, so those run on a worker and the list is swapped in when it's done.
+
+use crate::metadata;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+pub enum FilterAction {
+    None,
+    // Show only these images (in the order of the unfiltered list)
+    Apply(Vec<PathBuf>),
+    // Back to the whole folder
+    Clear,
+}
+
+#[derive(Clone, Default, PartialEq)]
+struct Criteria {
+    // Substring of the file name, or a glob when it contains `*` or `?`
+    name: String,
+    // Comma separated, e.g. "jpg, png"
+    extensions: String,
+    min_width: u32,
+    min_height: u32,
+    min_rating: u8,
+}
+
+/// Case-insensitive `*`/`?` wildcard match against the whole text.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        Some((p, rest)) => text
+            .split_first()
+            .is_some_and(|(t, text)| (*p == '?' || p == t) && glob_match(rest, text)),
+    }
+}
+
+impl Criteria {
+    fn is_active(&self) -> bool {
+        !self.name.trim().is_empty() || !self.extensions.trim().is_empty() || self.needs_file_info()
+    }
+
+    fn needs_file_info(&self) -> bool {
+        self.min_width > 0 || self.min_height > 0 || self.min_rating > 0
+    }
+
+    fn matches_name(&self, path: &Path) -> bool {
+        let file_name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase();
+        let name = self.name.trim().to_lowercase();
+        let name_ok = if name.contains(['*', '?']) {
+            let pattern: Vec<char> = name.chars().collect();
+            let text: Vec<char> = file_name.chars().collect();
+            glob_match(&pattern, &text)
+        } else {
+            file_name.contains(&name)
+        };
+
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let mut wanted = self
+            .extensions
+            .split(',')
+            .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+            .filter(|e| !e.is_empty())
+            .peekable();
+        let extension_ok = wanted.peek().is_none() || wanted.any(|e| e == extension);
+
+        name_ok && extension_ok
+    }
+
+    fn matches_file_info(&self, path: &Path) -> bool {
+        if (self.min_width > 0 || self.min_height > 0)
+            && !image::image_dimensions(path)
+                .is_ok_and(|(w, h)| w >= self.min_width && h >= self.min_height)
+        {
+            return false;
+        }
+        self.min_rating == 0 || metadata::read_rating(path) >= self.min_rating
+    }
+}
+
+#[derive(Default)]
+pub struct FilterBar {
+    pub open: bool,
+    criteria: Criteria,
+    // Criteria of the last applied (or running) filter
+    applied: Criteria,
+    rx: Option<Receiver<Vec<PathBuf>>>,
+    cancel: Arc<AtomicBool>,
+    focus_requested: bool,
+}
+
+impl FilterBar {
+    pub fn is_active(&self) -> bool {
+        self.applied.is_active()
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.focus_requested = self.open;
+    }
+
+    /// Forgets the filter without reporting it, e.g. when another folder is opened.
+    pub fn reset(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.rx = None;
+        self.criteria = Criteria::default();
+        self.applied = Criteria::default();
+    }
+
+    fn start(&mut self, ctx: &egui::Context, source: &[PathBuf]) -> FilterAction {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.rx = None;
+        self.applied = self.criteria.clone();
+
+        let criteria = self.criteria.clone();
+        let candidates: Vec<PathBuf> = source
+            .iter()
+            .filter(|p| criteria.matches_name(p))
+            .cloned()
+            .collect();
+        if !criteria.needs_file_info() {
+            return FilterAction::Apply(candidates);
+        }
+
+        let (tx, rx) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.rx = Some(rx);
+        self.cancel = cancel.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let mut matches = Vec::new();
+            for path in candidates {
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                if criteria.matches_file_info(&path) {
+                    matches.push(path);
+                }
+            }
+            let _ = tx.send(matches);
+            ctx.request_repaint();
+        });
+        FilterAction::None
+    }
+
+    /// Shows the bar. `source` is the unfiltered list, `shown` the number of images listed.
+    pub fn show(&mut self, ctx: &egui::Context, source: &[PathBuf], shown: usize) -> FilterAction {
+        let mut action = FilterAction::None;
+        if let Some(rx) = &self.rx
+            && let Ok(matches) = rx.try_recv()
+        {
+            self.rx = None;
+            action = FilterAction::Apply(matches);
+        }
+
+        if !self.open {
+            if self.is_active() {
+                self.reset();
+                return FilterAction::Clear;
+            }
+            return action;
+        }
+
+        let mut close = false;
+        egui::Area::new(egui::Id::new("filter_bar"))
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 10.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        let name = ui.add(
+                            egui::TextEdit::singleline(&mut self.criteria.name)
+                                .hint_text("Name or *.glob")
+                                .desired_width(160.0),
+                        );
+                        if std::mem::take(&mut self.focus_requested) {
+                            name.request_focus();
+                        }
+                        if name.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                            close = true;
+                        }
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.criteria.extensions)
+                                .hint_text("jpg, png")
+                                .desired_width(70.0),
+                        );
+                        ui.label("Min");
+                        ui.add(
+                            egui::DragValue::new(&mut self.criteria.min_width)
+                                .range(0..=20000)
+                                .speed(10)
+                                .suffix(" w"),
+                        );
+                        ui.add(
+                            egui::DragValue::new(&mut self.criteria.min_height)
+                                .range(0..=20000)
+                                .speed(10)
+                                .suffix(" h"),
+                        );
+                        ui.add(
+                            egui::DragValue::new(&mut self.criteria.min_rating)
+                                .range(0..=5)
+                                .suffix(" ★"),
+                        );
+
+                        if self.rx.is_some() {
+                            ui.spinner();
+                        } else if self.is_active() {
+                            ui.label(format!("{} / {}", shown, source.len()));
+                        }
+                        if ui.button("✖").on_hover_text("Close filter (Esc)").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            });
+
+        if close {
+            self.open = false;
+            if self.is_active() {
+                self.reset();
+                return FilterAction::Clear;
+            }
+        } else if self.criteria != self.applied {
+            action = if self.criteria.is_active() {
+                self.start(ctx, source)
+            } else {
+                self.reset();
+                FilterAction::Clear
+            };
+        }
+        action
+    }
+}
@@ -0,0 +1,513 @@
+//! Compares two folders of renders or exports: files with the same name are paired up,
+//! checked for pixel differences in the background, and stepped through side by side,
+//! flipping between A and B, or as a difference image.
+
+use crate::image_loader::{decode_image, is_supported_image};
+use crate::split_compare;
+use eframe::egui;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+// Longest side of the images shown for the current pair
+const VIEW_SIZE: u32 = 2048;
+// Channel differences up to this are treated as encoder noise
+const TOLERANCE: u8 = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CompareView {
+    Split,
+    Flip,
+    Difference,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Verdict {
+    Identical,
+    // Share of pixels that differ, and the largest channel difference
+    Differs(f32, u8),
+    SizeDiffers,
+    Unreadable,
+}
+
+struct Pair {
+    name: String,
+    a: PathBuf,
+    b: PathBuf,
+    verdict: Option<Verdict>,
+}
+
+struct PairView {
+    index: usize,
+    a: egui::TextureHandle,
+    b: egui::TextureHandle,
+    difference: Option<egui::TextureHandle>,
+}
+
+type Decoded = Result<(egui::ColorImage, egui::ColorImage, Option<egui::ColorImage>), String>;
+
+pub struct FolderCompare {
+    pub open: bool,
+    folders: [Option<PathBuf>; 2],
+    pairs: Vec<Pair>,
+    // Files found in only one of the folders
+    unmatched: [usize; 2],
+    verdicts: Option<Receiver<(usize, Verdict)>>,
+    current: usize,
+    view: CompareView,
+    show_b: bool,
+    differing_only: bool,
+    split: f32,
+    loading: Option<(usize, Receiver<Decoded>)>,
+    shown: Option<PairView>,
+    status: Option<String>,
+}
+
+impl Default for FolderCompare {
+    fn default() -> Self {
+        Self {
+            open: false,
+            folders: [None, None],
+            pairs: Vec::new(),
+            unmatched: [0, 0],
+            verdicts: None,
+            current: 0,
+            view: CompareView::Split,
+            show_b: false,
+            differing_only: false,
+            split: 0.5,
+            loading: None,
+            shown: None,
+            status: None,
+        }
+    }
+}
+
+fn list_images(dir: &Path, extensions: &[String]) -> HashMap<String, PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && is_supported_image(p, extensions))
+        .filter_map(|p| Some((p.file_stem()?.to_string_lossy().to_lowercase(), p)))
+        .collect()
+}
+
+/// Pairs files of `a` and `b` by name, ignoring case and extension (so a PNG render can
+/// be checked against its JPEG export). Returns the pairs by name and how many files of
+/// each folder had no partner.
+fn match_files(
+    a: HashMap<String, PathBuf>,
+    mut b: HashMap<String, PathBuf>,
+) -> (Vec<Pair>, [usize; 2]) {
+    let mut pairs = Vec::new();
+    let mut only_a = 0;
+    for (name, a) in a {
+        match b.remove(&name) {
+            Some(b) => pairs.push(Pair {
+                name: a
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+                a,
+                b,
+                verdict: None,
+            }),
+            None => only_a += 1,
+        }
+    }
+    pairs.sort_by(|x, y| x.name.cmp(&y.name));
+    (pairs, [only_a, b.len()])
+}
+
+fn compare_pixels(a: &image::RgbaImage, b: &image::RgbaImage) -> Verdict {
+    if a.dimensions() != b.dimensions() {
+        return Verdict::SizeDiffers;
+    }
+    let mut changed = 0usize;
+    let mut max = 0u8;
+    for (pa, pb) in a.pixels().zip(b.pixels()) {
+        let delta = (0..4).map(|i| pa[i].abs_diff(pb[i])).max().unwrap_or(0);
+        max = max.max(delta);
+        if delta > TOLERANCE {
+            changed += 1;
+        }
+    }
+    if changed == 0 {
+        Verdict::Identical
+    } else {
+        Verdict::Differs(changed as f32 / (a.width() * a.height()) as f32, max)
+    }
+}
+
+fn compare(a: &Path, b: &Path) -> Verdict {
+    match (decode_image(a), decode_image(b)) {
+        (Ok(a), Ok(b)) => compare_pixels(&a.to_rgba8(), &b.to_rgba8()),
+        _ => Verdict::Unreadable,
+    }
+}
+
+/// Differing pixels in red over a dimmed grey copy of `a`.
+fn difference_image(a: &image::RgbaImage, b: &image::RgbaImage) -> image::RgbaImage {
+    image::RgbaImage::from_fn(a.width(), a.height(), |x, y| {
+        let (pa, pb) = (a.get_pixel(x, y), b.get_pixel(x, y));
+        let delta = (0..4).map(|i| pa[i].abs_diff(pb[i])).max().unwrap_or(0);
+        if delta > TOLERANCE {
+            image::Rgba([255, 255 - delta, 255 - delta, 255])
+        } else {
+            let grey = ((pa[0] as u32 + pa[1] as u32 + pa[2] as u32) / 9) as u8;
+            image::Rgba([grey, grey, grey, 255])
+        }
+    })
+}
+
+fn to_color_image(image: &image::RgbaImage) -> egui::ColorImage {
+    let size = [image.width() as usize, image.height() as usize];
+    egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw())
+}
+
+fn decode_pair(a: &Path, b: &Path) -> Decoded {
+    let fit = |path: &Path| -> Result<image::RgbaImage, String> {
+        let image = decode_image(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(if image.width() > VIEW_SIZE || image.height() > VIEW_SIZE {
+            image.thumbnail(VIEW_SIZE, VIEW_SIZE).to_rgba8()
+        } else {
+            image.to_rgba8()
+        })
+    };
+    let (a, b) = (fit(a)?, fit(b)?);
+    let difference = (a.dimensions() == b.dimensions()).then(|| difference_image(&a, &b));
+    Ok((
+        to_color_image(&a),
+        to_color_image(&b),
+        difference.as_ref().map(to_color_image),
+    ))
+}
+
+impl Verdict {
+    fn differs(self) -> bool {
+        !matches!(self, Verdict::Identical)
+    }
+
+    fn describe(self) -> String {
+        match self {
+            Verdict::Identical => "Identical".to_string(),
+            Verdict::Differs(share, max) => {
+                format!("{:.2}% of pixels differ (up to {})", share * 100.0, max)
+            }
+            Verdict::SizeDiffers => "Sizes differ".to_string(),
+            Verdict::Unreadable => "Could not be read".to_string(),
+        }
+    }
+}
+
+impl FolderCompare {
+    fn pick_folder(&mut self, side: usize, ctx: &egui::Context, extensions: &[String]) {
+        let Some(dir) = rfd::FileDialog::new()
+            .set_title(if side == 0 { "Folder A" } else { "Folder B" })
+            .pick_folder()
+        else {
+            return;
+        };
+        self.folders[side] = Some(dir);
+        if let [Some(a), Some(b)] = &self.folders {
+            let (pairs, unmatched) =
+                match_files(list_images(a, extensions), list_images(b, extensions));
+            self.pairs = pairs;
+            self.unmatched = unmatched;
+            self.current = 0;
+            self.shown = None;
+            self.loading = None;
+            self.start_verdicts(ctx);
+        }
+    }
+
+    fn start_verdicts(&mut self, ctx: &egui::Context) {
+        let jobs: Vec<(usize, PathBuf, PathBuf)> = self
+            .pairs
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.verdict.is_none())
+            .map(|(i, p)| (i, p.a.clone(), p.b.clone()))
+            .collect();
+        let (tx, rx) = channel();
+        self.verdicts = Some(rx);
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            for (i, a, b) in jobs {
+                if tx.send((i, compare(&a, &b))).is_err() {
+                    // The folders were changed or the window closed
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    fn load_current(&mut self, ctx: &egui::Context) {
+        let Some(pair) = self.pairs.get(self.current) else {
+            return;
+        };
+        let (a, b) = (pair.a.clone(), pair.b.clone());
+        let (tx, rx) = channel();
+        self.loading = Some((self.current, rx));
+        self.status = None;
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(decode_pair(&a, &b));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Index of the next pair in `direction` (1 or -1) that is listed.
+    fn step(&self, direction: isize) -> Option<usize> {
+        let mut i = self.current as isize + direction;
+        while i >= 0 && (i as usize) < self.pairs.len() {
+            let pair = &self.pairs[i as usize];
+            if !self.differing_only || pair.verdict.is_none_or(Verdict::differs) {
+                return Some(i as usize);
+            }
+            i += direction;
+        }
+        None
+    }
+
+    fn poll(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.verdicts {
+            while let Ok((i, verdict)) = rx.try_recv() {
+                if let Some(pair) = self.pairs.get_mut(i) {
+                    pair.verdict = Some(verdict);
+                }
+            }
+            if self.pairs.iter().all(|p| p.verdict.is_some()) {
+                self.verdicts = None;
+            }
+        }
+
+        if let Some((index, rx)) = &self.loading
+            && let Ok(result) = rx.try_recv()
+        {
+            let index = *index;
+            self.loading = None;
+            match result {
+                Ok((a, b, difference)) => {
+                    let load = |name: &str, image| {
+                        ctx.load_texture(name, image, egui::TextureOptions::LINEAR)
+                    };
+                    self.shown = Some(PairView {
+                        index,
+                        a: load("compare_a", a),
+                        b: load("compare_b", b),
+                        difference: difference.map(|d| load("compare_difference", d)),
+                    });
+                }
+                Err(msg) => self.status = Some(msg),
+            }
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, extensions: &[String]) {
+        if !self.open {
+            self.verdicts = None;
+            self.loading = None;
+            return;
+        }
+        self.poll(ctx);
+        if self.verdicts.is_none() && self.pairs.iter().any(|p| p.verdict.is_none()) {
+            // Reopened after closing the window mid-check
+            self.start_verdicts(ctx);
+        }
+        let current_shown = self.shown.as_ref().is_some_and(|s| s.index == self.current);
+        if !current_shown && self.loading.is_none() && !self.pairs.is_empty() {
+            self.load_current(ctx);
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Compare Folders")
+            .open(&mut open)
+            .default_size([820.0, 560.0])
+            .show(ctx, |ui| {
+                for side in 0..2 {
+                    ui.horizontal(|ui| {
+                        let label = if side == 0 { "A:" } else { "B:" };
+                        ui.label(label);
+                        if ui.button("Choose…").clicked() {
+                            self.pick_folder(side, ctx, extensions);
+                        }
+                        match &self.folders[side] {
+                            Some(dir) => ui.label(dir.display().to_string()),
+                            None => ui.weak("No folder"),
+                        };
+                    });
+                }
+                if self.pairs.is_empty() {
+                    if self.folders.iter().all(Option::is_some) {
+                        ui.label("No files with matching names.");
+                    }
+                    return;
+                }
+
+                let checked = self.pairs.iter().filter(|p| p.verdict.is_some()).count();
+                let differing = self
+                    .pairs
+                    .iter()
+                    .filter(|p| p.verdict.is_some_and(Verdict::differs))
+                    .count();
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "{} pairs, {} differ; {} only in A, {} only in B",
+                        self.pairs.len(),
+                        differing,
+                        self.unmatched[0],
+                        self.unmatched[1]
+                    ));
+                    if checked < self.pairs.len() {
+                        ui.spinner();
+                        ui.weak(format!("checking {} / {}", checked, self.pairs.len()));
+                    }
+                });
+                ui.separator();
+
+                egui::SidePanel::left("compare_pairs")
+                    .resizable(true)
+                    .default_width(220.0)
+                    .show_inside(ui, |ui| {
+                        ui.checkbox(&mut self.differing_only, "Differing only");
+                        egui::ScrollArea::vertical().show(ui, |ui| {
+                            for (i, pair) in self.pairs.iter().enumerate() {
+                                let differs = pair.verdict.is_none_or(Verdict::differs);
+                                if self.differing_only && !differs {
+                                    continue;
+                                }
+                                let mut text = egui::RichText::new(&pair.name);
+                                if pair.verdict.is_some_and(Verdict::differs) {
+                                    text = text.color(ui.visuals().warn_fg_color);
+                                } else if pair.verdict.is_none() {
+                                    text = text.weak();
+                                }
+                                let response = ui.selectable_label(self.current == i, text);
+                                let response = match pair.verdict {
+                                    Some(v) => response.on_hover_text(v.describe()),
+                                    None => response,
+                                };
+                                if response.clicked() {
+                                    self.current = i;
+                                }
+                            }
+                        });
+                    });
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(self.step(-1).is_some(), egui::Button::new("◀"))
+                        .clicked()
+                        && let Some(i) = self.step(-1)
+                    {
+                        self.current = i;
+                    }
+                    if ui
+                        .add_enabled(self.step(1).is_some(), egui::Button::new("▶"))
+                        .clicked()
+                        && let Some(i) = self.step(1)
+                    {
+                        self.current = i;
+                    }
+                    ui.separator();
+                    ui.selectable_value(&mut self.view, CompareView::Split, "Split");
+                    ui.selectable_value(&mut self.view, CompareView::Flip, "A/B")
+                        .on_hover_text("Click the image to flip");
+                    ui.selectable_value(&mut self.view, CompareView::Difference, "Difference");
+                    ui.separator();
+                    let pair = &self.pairs[self.current];
+                    match pair.verdict {
+                        Some(verdict) => ui.label(verdict.describe()),
+                        None => ui.weak("Checking…"),
+                    };
+                });
+
+                let Some(shown) = self.shown.as_ref().filter(|s| s.index == self.current) else {
+                    match &self.status {
+                        Some(status) => ui.label(status),
+                        None => ui.spinner(),
+                    };
+                    return;
+                };
+                let max_size = ui.available_size();
+                match self.view {
+                    CompareView::Split => {
+                        split_compare::show(ui, &shown.a, &shown.b, &mut self.split, max_size);
+                    }
+                    CompareView::Flip => {
+                        let texture = if self.show_b { &shown.b } else { &shown.a };
+                        let response = ui.add(
+                            egui::Image::new(texture)
+                                .max_size(max_size)
+                                .sense(egui::Sense::click()),
+                        );
+                        if response.clicked() {
+                            self.show_b = !self.show_b;
+                        }
+                        ui.weak(if self.show_b {
+                            "Showing B"
+                        } else {
+                            "Showing A"
+                        });
+                    }
+                    CompareView::Difference => match &shown.difference {
+                        Some(difference) => {
+                            ui.add(egui::Image::new(difference).max_size(max_size));
+                        }
+                        None => {
+                            ui.label("The images have different sizes.");
+                        }
+                    },
+                }
+            });
+        self.open = open;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_by_name_ignoring_case_and_extension() {
+        let files = |names: &[&str], dir: &str| -> HashMap<String, PathBuf> {
+            names
+                .iter()
+                .map(|n| {
+                    let path = Path::new(dir).join(n);
+                    (
+                        path.file_stem().unwrap().to_string_lossy().to_lowercase(),
+                        path,
+                    )
+                })
+                .collect()
+        };
+        let a = files(&["frame_002.png", "Frame_001.png", "extra.png"], "/v1");
+        let b = files(&["frame_001.jpg", "frame_002.png"], "/v2");
+        let (pairs, unmatched) = match_files(a, b);
+        let names: Vec<&str> = pairs.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Frame_001.png", "frame_002.png"]);
+        assert_eq!(pairs[0].b, Path::new("/v2/frame_001.jpg"));
+        assert_eq!(unmatched, [1, 0]);
+    }
+
+    #[test]
+    fn tolerates_encoder_noise() {
+        let a = image::RgbaImage::from_pixel(4, 4, image::Rgba([100, 100, 100, 255]));
+        let mut b = image::RgbaImage::from_pixel(4, 4, image::Rgba([101, 100, 99, 255]));
+        assert_eq!(compare_pixels(&a, &b), Verdict::Identical);
+        b.put_pixel(0, 0, image::Rgba([200, 100, 100, 255]));
+        assert_eq!(compare_pixels(&a, &b), Verdict::Differs(1.0 / 16.0, 100));
+        assert_eq!(
+            compare_pixels(&a, &image::RgbaImage::new(2, 2)),
+            Verdict::SizeDiffers
+        );
+    }
+}
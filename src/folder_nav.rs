@@ -0,0 +1,117 @@
+//! Moving between folders: listing subfolders, stepping to the parent or a sibling
+//! folder, and the breadcrumb bar (Ctrl+B) with the current folder's path.
+
+use crate::image_loader::is_supported_image;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+
+/// Visible subfolders of `dir`, sorted by name (case-insensitively).
+pub fn subfolders(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut folders: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    folders.sort_by_key(|p| name(p).to_lowercase());
+    folders
+}
+
+/// Last component of `path`, or the whole path for roots like `/` and `C:\`.
+pub fn name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    )
+}
+
+/// The folder next to `folder` in its parent, `step` places on (1 next, -1 previous).
+pub fn sibling(folder: &Path, step: isize) -> Option<PathBuf> {
+    let siblings = subfolders(folder.parent()?);
+    let index = siblings.iter().position(|p| p == folder)?;
+    let target = index.checked_add_signed(step)?;
+    siblings.get(target).cloned()
+}
+
+/// The nearest folder next to `folder` in the direction of `step` that holds images,
+/// skipping empty ones (like chapter folders with only a cover subfolder).
+pub fn sibling_with_images(folder: &Path, step: isize, extensions: &[String]) -> Option<PathBuf> {
+    let siblings = subfolders(folder.parent()?);
+    let index = siblings.iter().position(|p| p == folder)?;
+    let has_images = |dir: &&PathBuf| {
+        std::fs::read_dir(dir).is_ok_and(|entries| {
+            entries
+                .flatten()
+                .any(|e| is_supported_image(&e.path(), extensions))
+        })
+    };
+    if step > 0 {
+        siblings[index + 1..].iter().find(has_images).cloned()
+    } else {
+        siblings[..index].iter().rev().find(has_images).cloned()
+    }
+}
+
+/// The breadcrumb bar: a button per folder on the path, each followed by a menu of its
+/// subfolders. Returns the folder picked.
+pub fn breadcrumb(ctx: &egui::Context, folder: &Path) -> Option<PathBuf> {
+    let mut picked = None;
+    egui::TopBottomPanel::top("breadcrumb").show(ctx, |ui| {
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 2.0;
+            let segments: Vec<&Path> = folder.ancestors().collect();
+            for segment in segments.into_iter().rev() {
+                let current = segment == folder;
+                if ui.selectable_label(current, name(segment)).clicked() && !current {
+                    picked = Some(segment.to_path_buf());
+                }
+                ui.menu_button("›", |ui| {
+                    // Listed only while the menu is open
+                    let children = subfolders(segment);
+                    if children.is_empty() {
+                        ui.weak("No subfolders");
+                    }
+                    egui::ScrollArea::vertical()
+                        .max_height(400.0)
+                        .show(ui, |ui| {
+                            for child in children {
+                                if ui.button(name(&child)).clicked() {
+                                    picked = Some(child);
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                });
+            }
+        });
+    });
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_between_sibling_folders() {
+        let root = std::env::temp_dir().join(format!("biv_folders_{}", std::process::id()));
+        for name in ["b", "A", "c", ".hidden"] {
+            std::fs::create_dir_all(root.join(name)).unwrap();
+        }
+        std::fs::write(root.join("file.jpg"), b"").unwrap();
+
+        let folders = subfolders(&root);
+        let next = sibling(&root.join("b"), 1);
+        let before_first = sibling(&root.join("A"), -1);
+        std::fs::write(root.join("c").join("page.png"), b"").unwrap();
+        let with_images = sibling_with_images(&root.join("A"), 1, &["png".to_string()]);
+        let _ = std::fs::remove_dir_all(&root);
+        assert_eq!(folders, [root.join("A"), root.join("b"), root.join("c")]);
+        assert_eq!(next, Some(root.join("c")));
+        assert_eq!(before_first, None);
+        assert_eq!(with_images, Some(root.join("c")));
+    }
+}
@@ -0,0 +1,162 @@
+//! Background folder scanning.
+//!
+//! Listing a folder on a network share or with 100k files can take seconds, so it runs
+//! on a worker that sends the images it finds in batches while the viewer stays usable.
+
+use crate::image_loader::is_supported_image;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// A batch is sent when it's this large or this old, whichever comes first
+const BATCH_SIZE: usize = 512;
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Which directory entries count as images.
+#[derive(Clone)]
+pub struct ScanOptions {
+    pub extensions: Vec<String>,
+    pub include_hidden: bool,
+    pub follow_symlinks: bool,
+}
+
+pub enum ScanEvent {
+    // More images, unsorted
+    Found(Vec<PathBuf>),
+    // The folder has been read completely (or couldn't be read)
+    Done,
+}
+
+pub struct FolderScan {
+    rx: Receiver<Vec<PathBuf>>,
+    cancel: Arc<AtomicBool>,
+    found: usize,
+}
+
+/// Dotfiles everywhere, plus files with the hidden attribute on Windows.
+fn is_hidden(entry: &std::fs::DirEntry) -> bool {
+    if entry.file_name().to_string_lossy().starts_with('.') {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if entry
+            .metadata()
+            .is_ok_and(|m| m.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+        {
+            return true;
+        }
+    }
+    false
+}
+
+fn is_image(entry: &std::fs::DirEntry, options: &ScanOptions) -> bool {
+    let path = entry.path();
+    // The extension is checked first so other files never cost a metadata call
+    if !is_supported_image(&path, &options.extensions) {
+        return false;
+    }
+    let is_symlink = entry.file_type().is_ok_and(|t| t.is_symlink());
+    if (is_symlink && !options.follow_symlinks) || (!options.include_hidden && is_hidden(entry)) {
+        return false;
+    }
+    path.is_file()
+}
+
+/// Adds newly found images to `images`, keeping it sorted by path and free of duplicates.
+pub fn merge_found(images: &mut Vec<PathBuf>, found: Vec<PathBuf>) {
+    images.extend(found);
+    images.sort();
+    images.dedup();
+}
+
+impl FolderScan {
+    pub fn start(ctx: &egui::Context, folder: &Path, options: ScanOptions) -> Self {
+        let (tx, rx) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let worker_cancel = cancel.clone();
+        let worker_folder = folder.to_path_buf();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let Ok(entries) = std::fs::read_dir(&worker_folder) else {
+                return;
+            };
+            let mut batch = Vec::new();
+            let mut last_sent = Instant::now();
+            for entry in entries.flatten() {
+                if worker_cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                if is_image(&entry, &options) {
+                    batch.push(entry.path());
+                }
+                if !batch.is_empty()
+                    && (batch.len() >= BATCH_SIZE || last_sent.elapsed() >= BATCH_INTERVAL)
+                {
+                    if tx.send(std::mem::take(&mut batch)).is_err() {
+                        return;
+                    }
+                    last_sent = Instant::now();
+                    ctx.request_repaint();
+                }
+            }
+            if !batch.is_empty() {
+                let _ = tx.send(batch);
+            }
+            // Dropping the sender tells the UI the scan is done
+            ctx.request_repaint();
+        });
+
+        Self {
+            rx,
+            cancel,
+            found: 0,
+        }
+    }
+
+    /// Number of images received so far.
+    pub fn found(&self) -> usize {
+        self.found
+    }
+
+    /// Returns the next pending event, if any.
+    pub fn poll(&mut self) -> Option<ScanEvent> {
+        match self.rx.try_recv() {
+            Ok(batch) => {
+                self.found += batch.len();
+                Some(ScanEvent::Found(batch))
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(ScanEvent::Done),
+        }
+    }
+}
+
+impl Drop for FolderScan {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merged_batches_stay_sorted_and_unique() {
+        let mut images = vec![PathBuf::from("b.jpg"), PathBuf::from("d.jpg")];
+        merge_found(
+            &mut images,
+            vec![PathBuf::from("c.jpg"), PathBuf::from("a.jpg"), PathBuf::from("b.jpg")],
+        );
+        let names: Vec<_> = images.iter().map(|p| p.to_str().unwrap()).collect();
+        assert_eq!(names, ["a.jpg", "b.jpg", "c.jpg", "d.jpg"]);
+    }
+}
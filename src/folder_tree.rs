@@ -0,0 +1,185 @@
+//! Folder tree side panel (Ctrl+T): the file system as a collapsible tree for changing
+//! folders without the OS dialog. Folders are listed when first expanded, and the images
+//! in each shown folder are counted on a worker.
+
+use crate::folder_nav::{name, subfolders};
+use crate::image_loader::is_supported_image;
+use eframe::egui;
+use egui::collapsing_header::CollapsingState;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+
+/// Drives of the machine, or the file system root.
+fn roots() -> Vec<PathBuf> {
+    #[cfg(windows)]
+    {
+        ('A'..='Z')
+            .map(|drive| PathBuf::from(format!("{}:\\", drive)))
+            .filter(|p| p.exists())
+            .collect()
+    }
+    #[cfg(not(windows))]
+    {
+        vec![PathBuf::from("/")]
+    }
+}
+
+fn node_id(folder: &Path) -> egui::Id {
+    egui::Id::new(("folder_tree", folder))
+}
+
+struct Counter {
+    tx: Sender<PathBuf>,
+    rx: Receiver<(PathBuf, usize)>,
+}
+
+impl Counter {
+    fn start(ctx: &egui::Context, extensions: &[String]) -> Self {
+        let (tx, rx_worker) = channel::<PathBuf>();
+        let (tx_worker, rx) = channel();
+        let extensions = extensions.to_vec();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            while let Ok(folder) = rx_worker.recv() {
+                let count = std::fs::read_dir(&folder).map_or(0, |entries| {
+                    entries
+                        .flatten()
+                        .filter(|e| is_supported_image(&e.path(), &extensions))
+                        .count()
+                });
+                if tx_worker.send((folder, count)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+        Self { tx, rx }
+    }
+}
+
+#[derive(Default)]
+pub struct FolderTree {
+    // Subfolders of folders that have been expanded
+    children: HashMap<PathBuf, Vec<PathBuf>>,
+    counts: HashMap<PathBuf, usize>,
+    requested: HashSet<PathBuf>,
+    // Started when the panel is first shown
+    counter: Option<Counter>,
+    // Folder the tree was last opened up to
+    revealed: Option<PathBuf>,
+    scroll_to_current: bool,
+}
+
+impl FolderTree {
+    /// Expands the tree down to `folder`.
+    fn reveal(&mut self, ctx: &egui::Context, folder: &Path) {
+        for ancestor in folder.ancestors().skip(1) {
+            let mut state = CollapsingState::load_with_default_open(ctx, node_id(ancestor), false);
+            state.set_open(true);
+            state.store(ctx);
+        }
+        self.revealed = Some(folder.to_path_buf());
+        self.scroll_to_current = true;
+    }
+
+    /// Shows the panel; returns the folder clicked.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        current: Option<&Path>,
+        extensions: &[String],
+    ) -> Option<PathBuf> {
+        let counter = self
+            .counter
+            .get_or_insert_with(|| Counter::start(ctx, extensions));
+        while let Ok((folder, count)) = counter.rx.try_recv() {
+            self.counts.insert(folder, count);
+        }
+        if let Some(current) = current
+            && self.revealed.as_deref() != Some(current)
+        {
+            self.reveal(ctx, current);
+        }
+
+        let mut picked = None;
+        egui::SidePanel::left("folder_tree")
+            .resizable(true)
+            .default_width(240.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.strong("Folders");
+                    if ui
+                        .small_button("⟳")
+                        .on_hover_text("Read the folders again")
+                        .clicked()
+                    {
+                        self.children.clear();
+                        self.counts.clear();
+                        self.requested.clear();
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::both()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for root in roots() {
+                            self.node(ui, &root, current, &mut picked);
+                        }
+                    });
+            });
+        self.scroll_to_current = false;
+        picked
+    }
+
+    fn node(
+        &mut self,
+        ui: &mut egui::Ui,
+        folder: &Path,
+        current: Option<&Path>,
+        picked: &mut Option<PathBuf>,
+    ) {
+        if self.requested.insert(folder.to_path_buf())
+            && let Some(counter) = &self.counter
+        {
+            let _ = counter.tx.send(folder.to_path_buf());
+        }
+        let label = match self.counts.get(folder) {
+            Some(count) if *count > 0 => format!("{}  ({})", name(folder), count),
+            _ => name(folder),
+        };
+        let is_current = current == Some(folder);
+        let scroll = is_current && self.scroll_to_current;
+        let row = |ui: &mut egui::Ui| {
+            let response = ui.selectable_label(is_current, label);
+            if scroll {
+                response.scroll_to_me(Some(egui::Align::Center));
+            }
+            if response.clicked() && !is_current {
+                *picked = Some(folder.to_path_buf());
+            }
+        };
+
+        // Folders known to have no subfolders get no expander
+        if self.children.get(folder).is_some_and(Vec::is_empty) {
+            ui.horizontal(|ui| {
+                ui.add_space(ui.spacing().icon_width + ui.spacing().item_spacing.x);
+                row(ui);
+            });
+            return;
+        }
+        CollapsingState::load_with_default_open(ui.ctx(), node_id(folder), false)
+            .show_header(ui, row)
+            .body(|ui| {
+                let children = self
+                    .children
+                    .entry(folder.to_path_buf())
+                    .or_insert_with(|| subfolders(folder))
+                    .clone();
+                for child in &children {
+                    self.node(ui, child, current, picked);
+                }
+            });
+    }
+}
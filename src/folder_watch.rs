@@ -0,0 +1,60 @@
+//! Watch folder: while a slideshow (or kiosk) runs over a folder, the folder is listed
+//! again every few seconds so files dropped into it join the rotation and deleted ones
+//! drop out. Listing rather than OS notifications also works on network shares.
+
+use crate::folder_scan::{FolderScan, ScanEvent, ScanOptions};
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(3);
+
+pub struct FolderWatch {
+    folder: PathBuf,
+    options: ScanOptions,
+    last_check: Instant,
+    // Listing in progress and the images it found so far
+    scan: Option<(FolderScan, Vec<PathBuf>)>,
+}
+
+impl FolderWatch {
+    pub fn new(folder: PathBuf, options: ScanOptions) -> Self {
+        Self {
+            folder,
+            options,
+            last_check: Instant::now(),
+            scan: None,
+        }
+    }
+
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// The images now in the folder, once a new listing completes. Call once per frame.
+    pub fn poll(&mut self, ctx: &egui::Context) -> Option<Vec<PathBuf>> {
+        let Some((scan, found)) = &mut self.scan else {
+            let wait = WATCH_INTERVAL.saturating_sub(self.last_check.elapsed());
+            if wait.is_zero() {
+                let scan = FolderScan::start(ctx, &self.folder, self.options.clone());
+                self.scan = Some((scan, Vec::new()));
+            } else {
+                ctx.request_repaint_after(wait);
+            }
+            return None;
+        };
+        while let Some(event) = scan.poll() {
+            match event {
+                ScanEvent::Found(batch) => found.extend(batch),
+                ScanEvent::Done => {
+                    let (_, mut images) = self.scan.take()?;
+                    self.last_check = Instant::now();
+                    ctx.request_repaint_after(WATCH_INTERVAL);
+                    images.sort();
+                    return Some(images);
+                }
+            }
+        }
+        None
+    }
+}
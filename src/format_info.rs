@@ -0,0 +1,229 @@
+//! Technical details of an image file for the info panel and HUD: bit depth and color
+//! model, plus what the header tells about the encoding (progressive JPEGs, chroma
+//! subsampling, estimated JPEG quality, PNG interlacing and deflate level, lossy or
+//! lossless WebP). Read by the image worker from the bytes it decodes.
+
+// Luminance quantization table of the JPEG standard (Annex K), which encoders scale
+// for their quality setting
+const STANDARD_LUMA_TABLE: [u8; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatInfo {
+    // e.g. "JPEG"
+    pub format: String,
+    pub bits_per_channel: u32,
+    pub float: bool,
+    // e.g. "RGB", "YCbCr", "Indexed"
+    pub color_model: String,
+    // Encoding details from the header, like ("Encoding", "Progressive")
+    pub details: Vec<(&'static str, String)>,
+}
+
+impl FormatInfo {
+    /// Format, bit depth and color model, like "JPEG, 8-bit YCbCr".
+    pub fn summary(&self) -> String {
+        format!(
+            "{}, {}-bit{} {}",
+            self.format,
+            self.bits_per_channel,
+            if self.float { " float" } else { "" },
+            self.color_model
+        )
+    }
+}
+
+fn color_model(color: image::ColorType) -> &'static str {
+    match color {
+        image::ColorType::L8 | image::ColorType::L16 => "Grayscale",
+        image::ColorType::La8 | image::ColorType::La16 => "Grayscale + alpha",
+        image::ColorType::Rgb8 | image::ColorType::Rgb16 | image::ColorType::Rgb32F => "RGB",
+        _ => "RGBA",
+    }
+}
+
+/// Details of the file in `bytes`, decoded to pixels of type `color`.
+pub fn inspect(bytes: &[u8], color: image::ColorType) -> FormatInfo {
+    let mut info = FormatInfo {
+        format: image::guess_format(bytes).map_or_else(
+            |_| "Unknown".to_string(),
+            |f| format!("{:?}", f).to_uppercase(),
+        ),
+        bits_per_channel: u32::from(color.bytes_per_pixel()) * 8 / u32::from(color.channel_count()),
+        float: matches!(color, image::ColorType::Rgb32F | image::ColorType::Rgba32F),
+        color_model: color_model(color).to_string(),
+        details: Vec::new(),
+    };
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        inspect_jpeg(bytes, &mut info);
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        inspect_png(bytes, &mut info);
+    } else if bytes.get(8..12) == Some(b"WEBP") {
+        let lossless = bytes.get(12..16) == Some(b"VP8L")
+            || (bytes.get(12..16) == Some(b"VP8X") && bytes.windows(4).any(|w| w == b"VP8L"));
+        let compression = if lossless { "Lossless" } else { "Lossy" };
+        info.details.push(("Compression", compression.to_string()));
+    }
+    info
+}
+
+/// Quality (1-100) a libjpeg-style encoder would have used for this luminance table.
+fn jpeg_quality(table: &[u8]) -> u32 {
+    let sum = |t: &[u8]| t.iter().map(|&q| u32::from(q)).sum::<u32>() as f32;
+    let scale = sum(table) * 100.0 / sum(&STANDARD_LUMA_TABLE);
+    let quality = if scale <= 100.0 {
+        (200.0 - scale) / 2.0
+    } else {
+        5000.0 / scale
+    };
+    quality.round().clamp(1.0, 100.0) as u32
+}
+
+fn inspect_jpeg(bytes: &[u8], info: &mut FormatInfo) {
+    let mut i = 2;
+    while i + 4 <= bytes.len() && bytes[i] == 0xFF {
+        let marker = bytes[i + 1];
+        if marker == 0xFF {
+            // Fill byte
+            i += 1;
+            continue;
+        }
+        let length = usize::from(u16::from_be_bytes([bytes[i + 2], bytes[i + 3]]));
+        let Some(segment) = bytes.get(i + 4..i + 2 + length) else {
+            return;
+        };
+        match marker {
+            // Quantization tables: 8 or 16-bit entries, table 0 is luminance
+            0xDB => {
+                let mut rest = segment;
+                while let Some(&header) = rest.first() {
+                    let size = 64 * (1 + usize::from(header >> 4));
+                    let Some(table) = rest.get(1..1 + size) else {
+                        break;
+                    };
+                    if header == 0 {
+                        info.details
+                            .push(("Quality", format!("≈{}", jpeg_quality(table))));
+                    }
+                    rest = &rest[1 + size..];
+                }
+            }
+            // Start of frame (C4, C8 and CC are other markers)
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                let encoding = match marker {
+                    0xC2 | 0xC6 | 0xCA | 0xCE => "Progressive",
+                    0xC3 | 0xC7 | 0xCB | 0xCF => "Lossless",
+                    _ => "Baseline",
+                };
+                info.details.insert(0, ("Encoding", encoding.to_string()));
+                let Some(&[precision, _, _, _, _, components]) = segment.get(..6) else {
+                    return;
+                };
+                info.bits_per_channel = u32::from(precision);
+                info.color_model = match components {
+                    1 => "Grayscale",
+                    3 => "YCbCr",
+                    4 => "CMYK",
+                    _ => "Unknown",
+                }
+                .to_string();
+                // Sampling factors of luma and the first chroma component
+                if components == 3
+                    && let (Some(&luma), Some(&chroma)) = (segment.get(7), segment.get(10))
+                {
+                    let h = (luma >> 4) / (chroma >> 4).max(1);
+                    let v = (luma & 0xF) / (chroma & 0xF).max(1);
+                    let subsampling = match (h, v) {
+                        (1, 1) => "4:4:4".to_string(),
+                        (2, 1) => "4:2:2".to_string(),
+                        (2, 2) => "4:2:0".to_string(),
+                        (1, 2) => "4:4:0".to_string(),
+                        (4, 1) => "4:1:1".to_string(),
+                        _ => format!("{}×{}", h, v),
+                    };
+                    info.details.push(("Chroma subsampling", subsampling));
+                }
+            }
+            // Start of scan: compressed data follows
+            0xDA => return,
+            _ => {}
+        }
+        i += 2 + length;
+    }
+}
+
+fn inspect_png(bytes: &[u8], info: &mut FormatInfo) {
+    // IHDR: width, height, bit depth, color type, compression, filter, interlace
+    let Some(&[depth, color, _, _, interlace]) = bytes.get(24..29) else {
+        return;
+    };
+    info.bits_per_channel = u32::from(depth);
+    if color == 3 {
+        info.color_model = "Indexed".to_string();
+    }
+    let interlacing = if interlace == 1 { "Adam7" } else { "None" };
+    info.details.push(("Interlacing", interlacing.to_string()));
+
+    // The zlib header of the first data chunk records the deflate level the encoder used
+    let mut i = 8;
+    while let Some(header) = bytes.get(i..i + 8) {
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        if &header[4..] == b"IDAT" {
+            if let Some(&flags) = bytes.get(i + 9) {
+                let level = ["Fastest", "Fast", "Default", "Maximum"][usize::from(flags >> 6)];
+                info.details.push(("Deflate level", level.to_string()));
+            }
+            return;
+        }
+        i += 12 + length;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_jpeg_headers() {
+        let mut bytes = Vec::new();
+        image::RgbImage::from_pixel(32, 32, image::Rgb([200, 100, 50]))
+            .write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(
+                &mut bytes, 75,
+            ))
+            .unwrap();
+        let info = inspect(&bytes, image::ColorType::Rgb8);
+        assert_eq!(info.format, "JPEG");
+        assert_eq!(info.color_model, "YCbCr");
+        assert_eq!(
+            info.details,
+            [
+                ("Encoding", "Baseline".to_string()),
+                ("Chroma subsampling", "4:4:4".to_string()),
+                ("Quality", "≈75".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_png_headers() {
+        let mut bytes = Vec::new();
+        image::GrayImage::new(4, 4)
+            .write_to(
+                &mut std::io::Cursor::new(&mut bytes),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+        let info = inspect(&bytes, image::ColorType::L8);
+        assert_eq!(info.summary(), "PNG, 8-bit Grayscale");
+        assert_eq!(
+            info.details,
+            [
+                ("Interlacing", "None".to_string()),
+                ("Deflate level", "Fastest".to_string())
+            ]
+        );
+    }
+}
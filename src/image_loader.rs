@@ -1,150 +1,887 @@
-use eframe::egui;
-use std::path::PathBuf;
-use std::sync::mpsc::{Receiver, Sender, channel};
-use std::thread;
-
-pub enum ImageCommand {
-    Load(PathBuf),
-}
-
-pub enum ImageResult {
-    Success(PathBuf, egui::ColorImage),
-    Error(PathBuf, String),
-}
-
-pub struct ImageLoader {
-    pub tx: Sender<ImageCommand>,
-    pub rx: Receiver<ImageResult>,
-}
-
-pub enum ThumbnailCommand {
-    Load(PathBuf, u32), // Path, max dimension
-}
-
-pub enum ThumbnailResult {
-    Success(PathBuf, egui::ColorImage),
-    Error(PathBuf, String),
-}
-
-pub struct ThumbnailLoader {
-    pub tx: Sender<ThumbnailCommand>,
-    pub rx: Receiver<ThumbnailResult>,
-}
-
-impl ImageLoader {
-    pub fn new(ctx: egui::Context) -> Self {
-        let (tx_ui, rx_worker) = channel::<ImageCommand>();
-        let (tx_worker, rx_ui) = channel::<ImageResult>();
-
-        // Background Loader Thread
-        thread::spawn(move || {
-            while let Ok(cmd) = rx_worker.recv() {
-                match cmd {
-                    ImageCommand::Load(path) => {
-                        println!("Thread: Start loading {:?}", path);
-                        // image::open attempts to infer the format from the file extension.
-                        // Sometimes files have incorrect extensions (e.g., a PNG named .jpg).
-                        // By using image::io::Reader, we can tell it to guess the format from the file content headers instead.
-                        let result = image::ImageReader::open(&path)
-                            .map_err(|e| image::ImageError::IoError(e))
-                            .and_then(|reader| {
-                                reader
-                                    .with_guessed_format()
-                                    .map_err(|e| image::ImageError::IoError(e))
-                            })
-                            .and_then(|reader| reader.decode());
-
-                        match result {
-                            Ok(dynamic_image) => {
-                                let width = dynamic_image.width() as usize;
-                                let height = dynamic_image.height() as usize;
-                                println!("Thread: Image decoded {}x{}", width, height);
-
-                                // Convert to rgba8 for egui
-                                let image_buffer = dynamic_image.to_rgba8();
-                                let pixels = image_buffer.into_raw();
-                                let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                                    [width, height],
-                                    &pixels,
-                                );
-
-                                // Send back
-                                if let Err(e) =
-                                    tx_worker.send(ImageResult::Success(path.clone(), color_image))
-                                {
-                                    println!("Thread: Failed to send Success result: {}", e);
-                                } else {
-                                    println!("Thread: Sent Success result");
-                                }
-                            }
-                            Err(err) => {
-                                println!("Thread: Error decoding image: {}", err);
-                                let _ = tx_worker.send(ImageResult::Error(
-                                    path.clone(),
-                                    format!("Load error: {}", err),
-                                ));
-                            }
-                        }
-                        // Request repaint to update UI
-                        ctx.request_repaint();
-                    }
-                }
-            }
-        });
-
-        Self {
-            tx: tx_ui,
-            rx: rx_ui,
-        }
-    }
-}
-
-impl ThumbnailLoader {
-    pub fn new(ctx: egui::Context) -> Self {
-        let (tx_ui, rx_worker) = channel::<ThumbnailCommand>();
-        let (tx_worker, rx_ui) = channel::<ThumbnailResult>();
-
-        thread::spawn(move || {
-            while let Ok(cmd) = rx_worker.recv() {
-                match cmd {
-                    ThumbnailCommand::Load(path, max_dim) => {
-                        let result = image::ImageReader::open(&path)
-                            .map_err(|e| image::ImageError::IoError(e))
-                            .and_then(|reader| {
-                                reader
-                                    .with_guessed_format()
-                                    .map_err(|e| image::ImageError::IoError(e))
-                            })
-                            .and_then(|reader| reader.decode());
-
-                        match result {
-                            Ok(dynamic_image) => {
-                                // Compute thumbnail
-                                let thumbnail = dynamic_image.thumbnail(max_dim, max_dim);
-                                let width = thumbnail.width() as usize;
-                                let height = thumbnail.height() as usize;
-                                let image_buffer = thumbnail.to_rgba8();
-                                let pixels = image_buffer.into_raw();
-                                let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                                    [width, height],
-                                    &pixels,
-                                );
-
-                                let _ = tx_worker.send(ThumbnailResult::Success(path.clone(), color_image));
-                            }
-                            Err(err) => {
-                                let _ = tx_worker.send(ThumbnailResult::Error(path.clone(), err.to_string()));
-                            }
-                        }
-                        ctx.request_repaint();
-                    }
-                }
-            }
-        });
-
-        Self {
-            tx: tx_ui,
-            rx: rx_ui,
-        }
-    }
-}
+//! Image decoding off the UI thread.
+//!
+//! [`ImageLoader`] decodes full images on a worker thread and sends back textures ready for
+//! egui, [`ThumbnailLoader`] does the same for filmstrip thumbnails through an on-disk
+//! cache. Both are driven by commands on `tx` and answer on `rx`. A thumbnail loader given
+//! the image loader's [`SharedDecodedCache`] scales down images already decoded for viewing
+//! instead of decoding them again.
+
+use crate::display_profile::DisplayProfile;
+use crate::false_color::Colormap;
+use crate::format_info::{self, FormatInfo};
+use crate::partial_decode;
+use crate::video;
+#[cfg(feature = "pdf")]
+use crate::pdf;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Requests to the image worker. Setters apply to loads sent after them.
+#[derive(Clone)]
+pub enum ImageCommand {
+    Load(PathBuf),
+    // Exposure in stops applied to high-bit-depth images from now on
+    SetExposure(f32),
+    // Re-encode decoded images for this monitor profile from now on
+    SetDisplayProfile(DisplayProfile),
+    // Show luminance through a color gradient instead of the image's colors
+    SetFalseColor(Option<Colormap>),
+    // A PDF page (0-based) at a given scale in pixels per point; `Load` renders the first
+    // page at the default scale
+    #[cfg(feature = "pdf")]
+    LoadPdfPage(PathBuf, usize, f32),
+}
+
+/// Answers from the image worker; one `Success`, `Missing` or `Error` per load.
+pub enum ImageResult {
+    Success(PathBuf, egui::ColorImage),
+    // Sent ahead of `Success` for images with more than 8 bits per channel
+    HighBitDepth(PathBuf),
+    // Sent ahead of `Success` when the file was read and decoded (not taken from the cache)
+    Format(PathBuf, FormatInfo),
+    // Sent ahead of `Success` for images too large for one texture, with their full size
+    Reduced(PathBuf, [usize; 2]),
+    // The file is taking long to read, typically from a network share
+    Slow(PathBuf),
+    // The file no longer exists
+    Missing(PathBuf),
+    // Sent ahead of `Success` when only part of a damaged file could be decoded
+    Corrupt(PathBuf),
+    // Sent ahead of `Success` for PDFs: the page shown and the scale it was rendered at
+    #[cfg(feature = "pdf")]
+    PdfPage {
+        path: PathBuf,
+        page: usize,
+        page_count: usize,
+        scale: f32,
+    },
+    Error(PathBuf, String),
+}
+
+/// Texture options for full images. Textures are uploaded as sRGB, so the GPU filters
+/// and builds the mip levels in linear light; mipmaps keep zoomed-out photos from aliasing.
+pub const IMAGE_TEXTURE_OPTIONS: egui::TextureOptions = egui::TextureOptions {
+    mipmap_mode: Some(egui::TextureFilter::Linear),
+    ..egui::TextureOptions::LINEAR
+};
+
+// Bumped when thumbnails are generated differently, so stale cache entries are ignored
+const THUMBNAIL_CACHE_VERSION: u32 = 2;
+// The thumbnail cache is trimmed back to this, least recently used files first
+const THUMBNAIL_CACHE_LIMIT: u64 = 256 * 1024 * 1024;
+// Thumbnails written between checks of the cache size (the first write checks too)
+const THUMBNAIL_TRIM_EVERY: usize = 500;
+
+// Decoded images kept around so exposure or view mode changes don't decode again
+const DECODED_CACHE_SIZE: usize = 3;
+
+/// The last few decoded images, least recently used first.
+pub struct DecodedCache {
+    entries: Vec<(PathBuf, Arc<image::DynamicImage>)>,
+    capacity: usize,
+}
+
+impl DecodedCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Removes and returns the image of `path`; `insert` it again to keep it cached.
+    pub fn take(&mut self, path: &Path) -> Option<Arc<image::DynamicImage>> {
+        let index = self.entries.iter().position(|(p, _)| p == path)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    /// The image of `path`, leaving it where it is in the eviction order.
+    pub fn get(&self, path: &Path) -> Option<Arc<image::DynamicImage>> {
+        self.entries
+            .iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, image)| image.clone())
+    }
+
+    /// Adds `image` as the most recently used entry, evicting the oldest beyond capacity.
+    pub fn insert(&mut self, path: PathBuf, image: impl Into<Arc<image::DynamicImage>>) {
+        self.entries.retain(|(p, _)| *p != path);
+        self.entries.push((path, image.into()));
+        if self.entries.len() > self.capacity {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Memory held by the cached pixels.
+    pub fn bytes(&self) -> usize {
+        self.entries.iter().map(|(_, image)| image.as_bytes().len()).sum()
+    }
+}
+
+/// The image worker's decoded images, readable by thumbnail workers.
+pub type SharedDecodedCache = Arc<Mutex<DecodedCache>>;
+
+/// Worker counters shown in the diagnostics HUD.
+#[derive(Default)]
+pub struct LoaderStats {
+    // Commands sent but not yet picked up by the worker
+    pub queued: AtomicUsize,
+    // Size of the worker's decoded image cache
+    pub cache_bytes: AtomicUsize,
+}
+
+// Reads taking longer than this are reported as slow, and given up on after the timeout.
+// A hung SMB/NFS share can otherwise block the loader forever.
+const SLOW_READ: Duration = Duration::from_secs(2);
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+// Transient IO errors (dropped connections, busy files) are retried this often
+const READ_ATTEMPTS: u32 = 3;
+
+/// Full-size image decoding on a background thread.
+pub struct ImageLoader {
+    tx: Sender<ImageCommand>,
+    pub rx: Receiver<ImageResult>,
+    // Abandons the read in progress
+    cancel: Arc<AtomicBool>,
+    pub stats: Arc<LoaderStats>,
+    decoded: SharedDecodedCache,
+}
+
+/// Requests to the thumbnail worker.
+pub enum ThumbnailCommand {
+    Load(PathBuf, u32), // Path, max dimension
+}
+
+/// Answers from the thumbnail worker.
+pub enum ThumbnailResult {
+    Success(PathBuf, egui::ColorImage),
+    // Online-only cloud file, left alone so browsing doesn't download the folder
+    Placeholder(PathBuf),
+    Error(PathBuf, String),
+}
+
+/// Thumbnail generation on a background thread.
+pub struct ThumbnailLoader {
+    pub tx: Sender<ThumbnailCommand>,
+    pub rx: Receiver<ThumbnailResult>,
+}
+
+/// Decodes an image file, detecting the format from its content. Videos give their
+/// first frame.
+pub fn decode_image(path: &Path) -> image::ImageResult<image::DynamicImage> {
+    if video::is_video(path) {
+        return video_frame(path);
+    }
+    // image::open attempts to infer the format from the file extension.
+    // Sometimes files have incorrect extensions (e.g., a PNG named .jpg).
+    // By using image::io::Reader, we can tell it to guess the format from the file content headers instead.
+    image::ImageReader::open(path)
+        .map_err(image::ImageError::IoError)
+        .and_then(|reader| {
+            reader
+                .with_guessed_format()
+                .map_err(image::ImageError::IoError)
+        })
+        .and_then(|reader| reader.decode())
+}
+
+fn video_frame(path: &Path) -> image::ImageResult<image::DynamicImage> {
+    video::first_frame(path).map_err(|e| image::ImageError::IoError(std::io::Error::other(e)))
+}
+
+/// Decodes an image already read into memory. The format is detected from the content,
+/// falling back to the extension of `path` (TGA has no signature to detect).
+pub fn decode_bytes(path: &Path, bytes: &[u8]) -> image::ImageResult<image::DynamicImage> {
+    let mut reader = image::ImageReader::new(std::io::Cursor::new(bytes));
+    if let Ok(format) = image::ImageFormat::from_path(path) {
+        reader.set_format(format);
+    }
+    reader
+        .with_guessed_format()
+        .map_err(image::ImageError::IoError)?
+        .decode()
+}
+
+/// Reads the whole file, retrying errors that may go away on their own.
+fn read_with_retries(path: &Path) -> std::io::Result<Vec<u8>> {
+    use std::io::ErrorKind;
+
+    let mut attempt = 1;
+    loop {
+        match std::fs::read(path) {
+            Ok(bytes) => return Ok(bytes),
+            Err(e)
+                if attempt < READ_ATTEMPTS
+                    && !matches!(
+                        e.kind(),
+                        ErrorKind::NotFound | ErrorKind::PermissionDenied | ErrorKind::InvalidInput
+                    ) =>
+            {
+                thread::sleep(Duration::from_millis(250 * attempt as u64));
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Reads `path` on a helper thread so a hanging read can time out or be cancelled.
+/// `on_slow` is called once if the read takes longer than `SLOW_READ`.
+fn read_file(
+    path: &Path,
+    cancel: &AtomicBool,
+    on_slow: impl FnOnce(),
+) -> std::io::Result<Vec<u8>> {
+    use std::io::{Error, ErrorKind};
+
+    let (tx, rx) = channel();
+    let reader_path = path.to_path_buf();
+    // A read stuck in the kernel can't be interrupted; the thread is left to finish on its own
+    thread::spawn(move || {
+        let _ = tx.send(read_with_retries(&reader_path));
+    });
+
+    let started = Instant::now();
+    let mut on_slow = Some(on_slow);
+    loop {
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(result) => return result,
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(Error::other("the reader thread stopped"));
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        if cancel.swap(false, Ordering::Relaxed) {
+            return Err(Error::new(ErrorKind::Interrupted, "cancelled"));
+        }
+        let elapsed = started.elapsed();
+        if elapsed >= READ_TIMEOUT {
+            return Err(Error::new(
+                ErrorKind::TimedOut,
+                format!("no data after {} seconds", READ_TIMEOUT.as_secs()),
+            ));
+        }
+        if elapsed >= SLOW_READ
+            && let Some(on_slow) = on_slow.take()
+        {
+            on_slow();
+        }
+    }
+}
+
+/// Extensions picked up from folders unless the config lists its own.
+pub const DEFAULT_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "jfif", "png", "gif", "webp", "bmp", "ico", "tif", "tiff", "avif", "tga",
+    "pbm", "pgm", "ppm", "pnm", "pam", "qoi", "hdr", "exr",
+];
+
+/// Whether `path` has one of `extensions` (lowercase, without the dot).
+pub fn is_supported_image(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| {
+            let ext = ext.to_lowercase();
+            extensions.iter().any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(&ext))
+        })
+}
+
+/// Whether `path` is an online-only cloud file (OneDrive, Dropbox, ...) whose content
+/// would be downloaded on first read. Only reads attributes, which doesn't hydrate it.
+pub fn is_cloud_placeholder(path: &Path) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+        const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x40000;
+        const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x400000;
+        std::fs::symlink_metadata(path).is_ok_and(|m| {
+            m.file_attributes()
+                & (FILE_ATTRIBUTE_OFFLINE
+                    | FILE_ATTRIBUTE_RECALL_ON_OPEN
+                    | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+                != 0
+        })
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` is the same in every build, so cache
+/// entries survive updates.
+struct Fnv64(u64);
+
+impl Fnv64 {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> &mut Self {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+        self
+    }
+}
+
+/// Location of the cached thumbnail for `path` at `max_dim`.
+///
+/// The key covers the file's size and modification time, so edited files get a new entry.
+fn thumbnail_cache_path(path: &Path, max_dim: u32) -> Option<PathBuf> {
+    let cache_dir = crate::config::cache_dir()?;
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+    let hash = Fnv64::new()
+        .write(std::fs::canonicalize(path).ok()?.as_os_str().as_encoded_bytes())
+        .write(&metadata.len().to_le_bytes())
+        .write(&modified.as_nanos().to_le_bytes())
+        .write(&max_dim.to_le_bytes())
+        .write(&THUMBNAIL_CACHE_VERSION.to_le_bytes())
+        .0;
+    Some(cache_dir.join("thumbnails").join(format!("{:016x}.png", hash)))
+}
+
+/// Deletes the least recently used files in `dir` until the rest fit in `limit` bytes.
+/// Cache hits refresh a file's modification time, so that is when it was last used.
+fn trim_cache(dir: &Path, limit: u64) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(std::time::SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let used = metadata.modified().ok()?;
+            metadata.is_file().then(|| (used, metadata.len(), entry.path()))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort();
+    for (_, len, path) in files {
+        if total <= limit {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= len;
+        }
+    }
+}
+
+/// Produces a thumbnail no larger than `max_dim`, going through the on-disk cache.
+pub fn make_thumbnail(path: &Path, max_dim: u32) -> image::ImageResult<image::RgbaImage> {
+    make_thumbnail_from(path, max_dim, None)
+}
+
+/// Like [`make_thumbnail`], scaling down `decoded` (the file's pixels, already decoded)
+/// rather than decoding the file when there's no cached thumbnail.
+pub fn make_thumbnail_from(
+    path: &Path,
+    max_dim: u32,
+    decoded: Option<&image::DynamicImage>,
+) -> image::ImageResult<image::RgbaImage> {
+    let cache_path = thumbnail_cache_path(path, max_dim);
+    if let Some(cache_path) = &cache_path
+        && let Ok(cached) = image::open(cache_path)
+    {
+        if let Ok(file) = std::fs::File::options().write(true).open(cache_path) {
+            let _ = file.set_modified(std::time::SystemTime::now());
+        }
+        return Ok(cached.to_rgba8());
+    }
+
+    let thumbnail = match decoded {
+        Some(image) => resize_linear(image, max_dim, max_dim),
+        None => resize_linear(&decode_image(path)?, max_dim, max_dim),
+    };
+    if let Some(cache_path) = cache_path
+        && let Some(dir) = cache_path.parent()
+        && std::fs::create_dir_all(dir).is_ok()
+        && thumbnail.save(&cache_path).is_ok()
+    {
+        static WRITES: AtomicUsize = AtomicUsize::new(0);
+        if WRITES
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(THUMBNAIL_TRIM_EVERY)
+        {
+            trim_cache(dir, THUMBNAIL_CACHE_LIMIT);
+        }
+    }
+    Ok(thumbnail)
+}
+
+/// Scales `image` to fit within `max_width`x`max_height`, keeping its aspect ratio.
+///
+/// Downscaling averages pixels in linear light (and with premultiplied alpha), so fine
+/// detail and thin bright lines don't darken the way sRGB averaging does.
+pub fn resize_linear(image: &image::DynamicImage, max_width: u32, max_height: u32) -> image::RgbaImage {
+    let (src_w, src_h) = (image.width(), image.height());
+    let scale = (max_width as f64 / src_w as f64).min(max_height as f64 / src_h as f64);
+    if scale >= 1.0 {
+        // Enlarging doesn't average anything, the usual filter is fine
+        return image
+            .resize(max_width, max_height, image::imageops::FilterType::Triangle)
+            .to_rgba8();
+    }
+    let dst_w = ((src_w as f64 * scale).round() as u32).max(1);
+    let dst_h = ((src_h as f64 * scale).round() as u32).max(1);
+
+    let to_linear: Vec<f32> = (0..=255).map(|v| srgb_to_linear(v as f32 / 255.0)).collect();
+    let source = image.to_rgba8();
+
+    // Box filter: every source pixel lands in exactly one destination pixel
+    let mut sums = vec![[0f32; 5]; (dst_w * dst_h) as usize];
+    for (x, y, p) in source.enumerate_pixels() {
+        let dx = (x as u64 * dst_w as u64 / src_w as u64) as u32;
+        let dy = (y as u64 * dst_h as u64 / src_h as u64) as u32;
+        let sum = &mut sums[(dy * dst_w + dx) as usize];
+        let alpha = p[3] as f32 / 255.0;
+        for c in 0..3 {
+            sum[c] += to_linear[p[c] as usize] * alpha;
+        }
+        sum[3] += alpha;
+        sum[4] += 1.0;
+    }
+
+    image::RgbaImage::from_fn(dst_w, dst_h, |x, y| {
+        let [r, g, b, alpha, count] = sums[(y * dst_w + x) as usize];
+        if alpha <= 0.0 {
+            return image::Rgba([0, 0, 0, 0]);
+        }
+        let encode = |v: f32| (linear_to_srgb(v / alpha) * 255.0).round() as u8;
+        image::Rgba([
+            encode(r),
+            encode(g),
+            encode(b),
+            (alpha / count * 255.0).round() as u8,
+        ])
+    })
+}
+
+/// `image` scaled down so neither side exceeds `max_side`.
+fn fit_texture_limit(image: &egui::ColorImage, max_side: usize) -> egui::ColorImage {
+    let [width, height] = image.size;
+    let pixels = image
+        .pixels
+        .iter()
+        .flat_map(|c| c.to_srgba_unmultiplied())
+        .collect();
+    let rgba = image::RgbaImage::from_raw(width as u32, height as u32, pixels)
+        .expect("buffer matches the image size");
+    let side = max_side as u32;
+    let resized = resize_linear(&image::DynamicImage::ImageRgba8(rgba), side, side);
+    egui::ColorImage::from_rgba_unmultiplied(
+        [resized.width() as usize, resized.height() as usize],
+        resized.as_raw(),
+    )
+}
+
+/// Whether pixels of this type carry more than 8 bits per channel.
+pub fn is_high_bit_depth(color: image::ColorType) -> bool {
+    color.bytes_per_pixel() > color.channel_count()
+}
+
+/// sRGB transfer function, decoding a 0..1 value to linear light.
+pub fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Inverse of [`srgb_to_linear`], clamping to 0..1 first.
+pub fn linear_to_srgb(v: f32) -> f32 {
+    let v = v.clamp(0.0, 1.0);
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Quantizes `v` (0..1) to 8 bits with triangular noise of ±1 step.
+///
+/// Without dithering, smooth 16-bit gradients would show banding that isn't in the file.
+fn dither(v: f32, x: u32, y: u32, channel: u32) -> u8 {
+    // Cheap per-pixel hash as the noise source; stable between frames
+    let mut h = x.wrapping_mul(0x8da6b343) ^ y.wrapping_mul(0xd8163841) ^ channel.wrapping_mul(0xcb1ab31f);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b3c6d);
+    h ^= h >> 12;
+    let a = (h & 0xffff) as f32 / 65535.0;
+    let b = (h >> 16) as f32 / 65535.0;
+    (v * 255.0 + a - b).round().clamp(0.0, 255.0) as u8
+}
+
+/// Maps a high-bit-depth image to 8-bit for display, applying `exposure` (in stops).
+///
+/// Float images (HDR, EXR) hold linear light; 16-bit images are sRGB encoded and are
+/// only linearized when the exposure changes their values.
+pub fn to_display_image(image: &image::DynamicImage, exposure: f32) -> egui::ColorImage {
+    let linear_source = matches!(
+        image.color(),
+        image::ColorType::Rgb32F | image::ColorType::Rgba32F
+    );
+    let gain = 2f32.powf(exposure);
+    let map = |v: f32| {
+        if linear_source {
+            linear_to_srgb(v * gain)
+        } else if gain != 1.0 {
+            linear_to_srgb(srgb_to_linear(v) * gain)
+        } else {
+            v
+        }
+    };
+
+    let (width, height) = (image.width(), image.height());
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    let mut push = |x: u32, y: u32, rgba: [f32; 4]| {
+        for (c, v) in rgba[..3].iter().enumerate() {
+            pixels.push(dither(map(*v), x, y, c as u32));
+        }
+        pixels.push(dither(rgba[3], x, y, 3));
+    };
+    if linear_source {
+        for (x, y, p) in image.to_rgba32f().enumerate_pixels() {
+            push(x, y, p.0);
+        }
+    } else {
+        for (x, y, p) in image.to_rgba16().enumerate_pixels() {
+            push(x, y, p.0.map(|v| v as f32 / 65535.0));
+        }
+    }
+    egui::ColorImage::from_rgba_unmultiplied([width as usize, height as usize], &pixels)
+}
+
+impl ImageLoader {
+    /// Starts the worker; `ctx` is asked to repaint whenever a result arrives.
+    pub fn new(ctx: egui::Context) -> Self {
+        let (tx_ui, rx_worker) = channel::<ImageCommand>();
+        let (tx_worker, rx_ui) = channel::<ImageResult>();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let worker_cancel = cancel.clone();
+        let stats = Arc::new(LoaderStats::default());
+        let worker_stats = stats.clone();
+        let decoded = Arc::new(Mutex::new(DecodedCache::new(DECODED_CACHE_SIZE)));
+        let decoded_cache = decoded.clone();
+
+        // Background Loader Thread
+        thread::spawn(move || {
+            let mut exposure = 0.0;
+            let mut display_profile = DisplayProfile::default();
+            let mut false_color: Option<Colormap> = None;
+            #[cfg(feature = "pdf")]
+            let mut pdf_renderer = pdf::PdfRenderer::default();
+
+            while let Ok(cmd) = rx_worker.recv() {
+                worker_stats.queued.fetch_sub(1, Ordering::Relaxed);
+                #[cfg(feature = "pdf")]
+                let mut pdf_request = None;
+                let path = match cmd {
+                    ImageCommand::SetExposure(value) => {
+                        exposure = value;
+                        continue;
+                    }
+                    ImageCommand::SetDisplayProfile(profile) => {
+                        display_profile = profile;
+                        continue;
+                    }
+                    ImageCommand::SetFalseColor(colormap) => {
+                        false_color = colormap;
+                        continue;
+                    }
+                    ImageCommand::Load(path) => path,
+                    #[cfg(feature = "pdf")]
+                    ImageCommand::LoadPdfPage(path, page, scale) => {
+                        pdf_request = Some((page, scale));
+                        path
+                    }
+                };
+
+                println!("Thread: Start loading {:?}", path);
+                let cached = decoded_cache.lock().unwrap().take(&path).map(Ok);
+                // PDFs are rendered rather than decoded, at the requested page and scale
+                #[cfg(feature = "pdf")]
+                let cached = if pdf::is_pdf(&path) {
+                    let (page, scale) = pdf_request.unwrap_or((0, pdf::DEFAULT_SCALE));
+                    let rendered = pdf_renderer.render(&path, page, scale).map(|rendered| {
+                        let _ = tx_worker.send(ImageResult::PdfPage {
+                            path: path.clone(),
+                            page: rendered.page,
+                            page_count: rendered.page_count,
+                            scale: rendered.scale,
+                        });
+                        Arc::new(rendered.image)
+                    });
+                    Some(rendered.map_err(|e| image::ImageError::IoError(std::io::Error::other(e))))
+                } else {
+                    cached
+                };
+                let result = match cached {
+                    Some(result) => result,
+                    // Videos are never read whole; ffmpeg only needs the start
+                    None if video::is_video(&path) => video_frame(&path).map(Arc::new),
+                    None => {
+                        // Cancel presses from before this load don't count
+                        worker_cancel.store(false, Ordering::Relaxed);
+                        read_file(&path, &worker_cancel, || {
+                            let _ = tx_worker.send(ImageResult::Slow(path.clone()));
+                            ctx.request_repaint();
+                        })
+                        .map_err(image::ImageError::IoError)
+                        .and_then(|bytes| {
+                            let image = decode_bytes(&path, &bytes).or_else(|err| {
+                                match partial_decode::decode(&bytes) {
+                                    Some(image) => {
+                                        let _ = tx_worker
+                                            .send(ImageResult::Corrupt(path.clone()));
+                                        Ok(image)
+                                    }
+                                    None => Err(err),
+                                }
+                            })?;
+                            let info = format_info::inspect(&bytes, image.color());
+                            let _ = tx_worker.send(ImageResult::Format(path.clone(), info));
+                            Ok(image)
+                        })
+                        .map(Arc::new)
+                    }
+                };
+
+                match result {
+                    Ok(dynamic_image) => {
+                        let width = dynamic_image.width() as usize;
+                        let height = dynamic_image.height() as usize;
+                        println!("Thread: Image decoded {}x{}", width, height);
+
+                        let high_bit_depth = is_high_bit_depth(dynamic_image.color());
+                        let mut color_image = if let Some(colormap) = false_color {
+                            colormap.apply(&dynamic_image)
+                        } else if high_bit_depth {
+                            to_display_image(&dynamic_image, exposure)
+                        } else {
+                            // Convert to rgba8 for egui
+                            let image_buffer = dynamic_image.to_rgba8();
+                            let pixels = image_buffer.into_raw();
+                            egui::ColorImage::from_rgba_unmultiplied([width, height], &pixels)
+                        };
+                        // Shown scaled down when the GPU can't take it as one texture
+                        let max_side = ctx.input(|i| i.max_texture_side);
+                        if width.max(height) > max_side {
+                            color_image = fit_texture_limit(&color_image, max_side);
+                            let _ = tx_worker
+                                .send(ImageResult::Reduced(path.clone(), [width, height]));
+                        }
+                        display_profile.apply(&mut color_image);
+
+                        if high_bit_depth {
+                            let _ = tx_worker.send(ImageResult::HighBitDepth(path.clone()));
+                        }
+
+                        // Send back
+                        if let Err(e) =
+                            tx_worker.send(ImageResult::Success(path.clone(), color_image))
+                        {
+                            println!("Thread: Failed to send Success result: {}", e);
+                        } else {
+                            println!("Thread: Sent Success result");
+                        }
+
+                        decoded_cache.lock().unwrap().insert(path.clone(), dynamic_image);
+                    }
+                    Err(image::ImageError::IoError(e))
+                        if e.kind() == std::io::ErrorKind::NotFound =>
+                    {
+                        let _ = tx_worker.send(ImageResult::Missing(path.clone()));
+                    }
+                    Err(err) => {
+                        println!("Thread: Error decoding image: {}", err);
+                        let _ = tx_worker.send(ImageResult::Error(
+                            path.clone(),
+                            format!("Load error: {}", err),
+                        ));
+                    }
+                }
+                worker_stats
+                    .cache_bytes
+                    .store(decoded_cache.lock().unwrap().bytes(), Ordering::Relaxed);
+                // Request repaint to update UI
+                ctx.request_repaint();
+            }
+        });
+
+        Self {
+            tx: tx_ui,
+            rx: rx_ui,
+            cancel,
+            stats,
+            decoded,
+        }
+    }
+
+    /// The worker's decoded images, to hand to [`ThumbnailLoader::with_decoded`].
+    pub fn decoded(&self) -> SharedDecodedCache {
+        self.decoded.clone()
+    }
+
+    /// Queues a command for the worker.
+    pub fn send(&self, command: ImageCommand) {
+        self.stats.queued.fetch_add(1, Ordering::Relaxed);
+        let _ = self.tx.send(command);
+    }
+
+    /// Gives up on the file currently being read, which then fails with "cancelled".
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl ThumbnailLoader {
+    /// Starts the worker; `ctx` is asked to repaint whenever a result arrives.
+    pub fn new(ctx: egui::Context) -> Self {
+        Self::start(ctx, None)
+    }
+
+    /// Like [`ThumbnailLoader::new`], taking images the image loader already decoded
+    /// from `decoded` instead of decoding them again.
+    pub fn with_decoded(ctx: egui::Context, decoded: SharedDecodedCache) -> Self {
+        Self::start(ctx, Some(decoded))
+    }
+
+    fn start(ctx: egui::Context, decoded: Option<SharedDecodedCache>) -> Self {
+        let (tx_ui, rx_worker) = channel::<ThumbnailCommand>();
+        let (tx_worker, rx_ui) = channel::<ThumbnailResult>();
+
+        thread::spawn(move || {
+            while let Ok(cmd) = rx_worker.recv() {
+                match cmd {
+                    ThumbnailCommand::Load(path, max_dim) => {
+                        if is_cloud_placeholder(&path) {
+                            let _ = tx_worker.send(ThumbnailResult::Placeholder(path));
+                            ctx.request_repaint();
+                            continue;
+                        }
+                        let image = decoded
+                            .as_ref()
+                            .and_then(|cache| cache.lock().unwrap().get(&path));
+                        let result = make_thumbnail_from(&path, max_dim, image.as_deref());
+
+                        match result {
+                            Ok(thumbnail) => {
+                                let width = thumbnail.width() as usize;
+                                let height = thumbnail.height() as usize;
+                                let pixels = thumbnail.into_raw();
+                                let color_image = egui::ColorImage::from_rgba_unmultiplied(
+                                    [width, height],
+                                    &pixels,
+                                );
+
+                                let _ = tx_worker.send(ThumbnailResult::Success(path.clone(), color_image));
+                            }
+                            Err(err) => {
+                                let _ = tx_worker.send(ThumbnailResult::Error(path.clone(), err.to_string()));
+                            }
+                        }
+                        ctx.request_repaint();
+                    }
+                }
+            }
+        });
+
+        Self {
+            tx: tx_ui,
+            rx: rx_ui,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32) -> image::DynamicImage {
+        image::DynamicImage::new_rgba8(width, 1)
+    }
+
+    #[test]
+    fn decoded_cache_evicts_least_recently_used() {
+        let mut cache = DecodedCache::new(2);
+        cache.insert("a.png".into(), image(1));
+        cache.insert("b.png".into(), image(2));
+        // Using "a" again makes "b" the oldest
+        let a = cache.take(Path::new("a.png")).unwrap();
+        cache.insert("a.png".into(), a);
+        cache.insert("c.png".into(), image(3));
+
+        assert!(cache.take(Path::new("b.png")).is_none());
+        assert_eq!(cache.take(Path::new("a.png")).unwrap().width(), 1);
+        assert_eq!(cache.take(Path::new("c.png")).unwrap().width(), 3);
+    }
+
+    #[test]
+    fn decoded_cache_replaces_entries_of_the_same_path() {
+        let mut cache = DecodedCache::new(2);
+        cache.insert("a.png".into(), image(1));
+        cache.insert("a.png".into(), image(2));
+        cache.insert("b.png".into(), image(3));
+        assert_eq!(cache.take(Path::new("a.png")).unwrap().width(), 2);
+        assert!(cache.take(Path::new("a.png")).is_none());
+    }
+
+    #[test]
+    fn extensions_match_regardless_of_case_and_dot() {
+        let extensions = vec!["jpg".to_string(), ".PNG".to_string()];
+        assert!(is_supported_image(Path::new("photo.JPG"), &extensions));
+        assert!(is_supported_image(Path::new("shot.png"), &extensions));
+        assert!(!is_supported_image(Path::new("notes.txt"), &extensions));
+        assert!(!is_supported_image(Path::new("jpg"), &extensions));
+    }
+
+    #[test]
+    fn srgb_transfer_round_trips() {
+        for i in 0..=20 {
+            let v = i as f32 / 20.0;
+            assert!((linear_to_srgb(srgb_to_linear(v)) - v).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn cache_keys_use_a_stable_hash() {
+        assert_eq!(Fnv64::new().write(b"").0, 0xcbf2_9ce4_8422_2325);
+        assert_eq!(Fnv64::new().write(b"a").0, 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn trimming_drops_least_recently_used_files() {
+        let dir = std::env::temp_dir().join(format!("biv_thumbs_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let now = std::time::SystemTime::now();
+        for (i, name) in ["old", "middle", "new"].into_iter().enumerate() {
+            let path = dir.join(name);
+            std::fs::write(&path, [0u8; 100]).unwrap();
+            let used = now - std::time::Duration::from_secs(60 * (3 - i as u64));
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(used)
+                .unwrap();
+        }
+
+        trim_cache(&dir, 250);
+        let mut left: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["middle", "new"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
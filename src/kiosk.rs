@@ -0,0 +1,101 @@
+//! Kiosk mode (`--kiosk`) for exhibitions and signage: a fullscreen slideshow that ignores
+//! the keyboard, so nothing can be deleted, renamed, moved or saved over and no settings
+//! can be opened. The window can only be closed with the configured exit shortcut (or the
+//! remote control's `quit`).
+
+use eframe::egui;
+
+pub const DEFAULT_EXIT: &str = "Ctrl+Shift+Q";
+
+/// A key with modifiers, written like `Ctrl+Shift+Q` or `Alt+F12`.
+#[derive(Debug, PartialEq)]
+pub struct Shortcut {
+    modifiers: egui::Modifiers,
+    key: egui::Key,
+}
+
+impl Shortcut {
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut modifiers = egui::Modifiers::NONE;
+        let mut parts = text.split('+').map(str::trim).peekable();
+        while let Some(part) = parts.next() {
+            if parts.peek().is_none() {
+                let key = egui::Key::from_name(part)
+                    .or_else(|| egui::Key::from_name(&part.to_uppercase()))?;
+                return Some(Self { modifiers, key });
+            }
+            match part.to_lowercase().as_str() {
+                "ctrl" | "cmd" => modifiers = modifiers | egui::Modifiers::COMMAND,
+                "shift" => modifiers = modifiers | egui::Modifiers::SHIFT,
+                "alt" => modifiers = modifiers | egui::Modifiers::ALT,
+                _ => return None,
+            }
+        }
+        None
+    }
+}
+
+pub struct Kiosk {
+    exit: Shortcut,
+    // Set once closing is allowed
+    leaving: bool,
+}
+
+impl Kiosk {
+    pub fn new(exit: &str) -> Self {
+        let exit = Shortcut::parse(exit).unwrap_or_else(|| {
+            eprintln!(
+                "Kiosk: can't read the exit shortcut \"{}\", using {}",
+                exit, DEFAULT_EXIT
+            );
+            Shortcut::parse(DEFAULT_EXIT).expect("default shortcut parses")
+        });
+        Self {
+            exit,
+            leaving: false,
+        }
+    }
+
+    /// Lets the next close request through.
+    pub fn leave(&mut self) {
+        self.leaving = true;
+    }
+
+    /// Keeps the window fullscreen and open; closes it when the exit shortcut is pressed.
+    /// Call once per frame.
+    pub fn update(&mut self, ctx: &egui::Context) {
+        if ctx.input_mut(|i| i.consume_key(self.exit.modifiers, self.exit.key)) {
+            self.leaving = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            return;
+        }
+        if ctx.input(|i| i.viewport().close_requested()) && !self.leaving {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+        }
+        if !ctx.input(|i| i.viewport().fullscreen.unwrap_or(false)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_shortcuts() {
+        assert_eq!(
+            Shortcut::parse("Ctrl+Shift+Q"),
+            Some(Shortcut {
+                modifiers: egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                key: egui::Key::Q,
+            })
+        );
+        assert_eq!(
+            Shortcut::parse("alt + f12").map(|s| s.key),
+            Some(egui::Key::F12)
+        );
+        assert!(Shortcut::parse("Ctrl+").is_none());
+        assert!(Shortcut::parse("Hyper+Q").is_none());
+    }
+}
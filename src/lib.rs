@@ -0,0 +1,76 @@
+//! Better Image Viewer as a library: the viewer app itself, plus the parts that are useful
+//! on their own (background image loading, the zoom/pan model, the filmstrip and the
+//! settings file). `main.rs` only parses the command line and starts [`ImageViewer`].
+
+mod animated_export;
+mod animation;
+mod annotations;
+mod app;
+mod app_data;
+mod batch;
+pub mod cli;
+mod clipboard;
+mod clipboard_watch;
+#[cfg(feature = "cutout")]
+mod cutout;
+mod diagnostics;
+mod drop_import;
+mod duplicates;
+mod enhance;
+mod external_tools;
+mod eyedropper;
+mod false_color;
+mod filter;
+mod folder_compare;
+mod format_info;
+mod folder_nav;
+mod folder_scan;
+mod folder_tree;
+mod folder_watch;
+pub mod config;
+mod display_profile;
+pub mod image_loader;
+mod kiosk;
+mod library;
+mod loupe;
+mod measure;
+mod metadata;
+mod monitor;
+mod partial_decode;
+#[cfg(feature = "pdf")]
+mod pdf;
+mod perceptual_hash;
+mod playlist;
+mod presentation;
+#[cfg(feature = "ocr")]
+mod ocr;
+mod osd;
+mod redaction;
+pub mod remote;
+mod rename;
+mod search;
+mod selection;
+mod sequence;
+mod sharpness;
+mod slideshow;
+mod split_compare;
+mod start_screen;
+#[cfg(feature = "screenshot")]
+mod screen_capture;
+mod straighten;
+mod strip_view;
+mod tags;
+pub mod thumbnail_list;
+mod texture_pool;
+mod timeline;
+mod upscale;
+#[cfg(all(windows, feature = "taskbar"))]
+mod taskbar;
+#[cfg(feature = "tray")]
+mod tray;
+mod video;
+pub mod view_state;
+mod viewport_export;
+mod zoom_indicator;
+
+pub use app::ImageViewer;
@@ -0,0 +1,565 @@
+//! Library: an index of every image under a few chosen root folders (size, dimensions,
+//! perceptual hash, capture date, rating, tags), kept in an SQLite database in the data
+//! folder so the whole collection can be searched without reading a single image.
+//!
+//! Indexing runs on a worker and is incremental: files whose size and modification time
+//! haven't changed since the last run are not read again, and only the rows of changed
+//! files are written.
+
+use crate::config::data_dir;
+use crate::image_loader::{decode_image, is_supported_image};
+use crate::metadata;
+use crate::perceptual_hash::dhash;
+use crate::tags::TagStore;
+use crate::timeline;
+use eframe::egui;
+use rusqlite::{Connection, params};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+const LIBRARY_FILE: &str = "library.sqlite3";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS images (
+        path TEXT PRIMARY KEY,
+        size INTEGER NOT NULL,
+        modified INTEGER NOT NULL,
+        width INTEGER NOT NULL,
+        height INTEGER NOT NULL,
+        hash INTEGER,
+        captured INTEGER,
+        rating INTEGER NOT NULL,
+        camera TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS tags (
+        path TEXT NOT NULL REFERENCES images (path) ON DELETE CASCADE,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (path, tag)
+    );
+    PRAGMA foreign_keys = ON;
+";
+
+#[derive(Clone, Default)]
+pub struct Entry {
+    pub size: u64,
+    // Seconds since 1970
+    pub modified: u64,
+    pub width: u32,
+    pub height: u32,
+    // dHash, None when the image couldn't be decoded
+    pub hash: Option<u64>,
+    // Camera time as seconds since 1970, see `timeline`
+    pub captured: Option<i64>,
+    pub rating: u8,
+    // EXIF camera model, empty when not recorded
+    pub camera: String,
+    // Copied from the tag store, see `Library::sync_tags`
+    pub tags: BTreeSet<String>,
+}
+
+enum IndexEvent {
+    // Number of images found under the roots
+    Found(usize),
+    // An image that was checked; the entry is set when it was (re)read
+    Checked(Option<(PathBuf, Entry)>),
+    // Every image under the roots, to drop entries of files that are gone
+    Finished(Vec<PathBuf>),
+}
+
+struct Indexing {
+    rx: Receiver<IndexEvent>,
+    cancel: Arc<AtomicBool>,
+    total: usize,
+    done: usize,
+}
+
+impl Drop for Indexing {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn size_and_modified(path: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((meta.len(), modified.as_secs()))
+}
+
+fn read_entry(path: &Path, size: u64, modified: u64) -> Entry {
+    let (width, height, hash) = match decode_image(path) {
+        Ok(image) => (image.width(), image.height(), Some(dhash(&image))),
+        Err(_) => (0, 0, None),
+    };
+    let exif = metadata::read_exif(path);
+    Entry {
+        size,
+        modified,
+        width,
+        height,
+        hash,
+        captured: exif
+            .as_ref()
+            .and_then(metadata::exif_capture_time)
+            .map(|t| timeline::timestamp(&t)),
+        rating: metadata::read_rating(path),
+        camera: exif
+            .as_ref()
+            .map(metadata::camera_model)
+            .unwrap_or_default(),
+        tags: BTreeSet::new(),
+    }
+}
+
+fn open_database(path: &Path) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+fn read_entries(conn: &Connection) -> rusqlite::Result<BTreeMap<PathBuf, Entry>> {
+    let mut entries = BTreeMap::new();
+    let mut images = conn.prepare(
+        "SELECT path, size, modified, width, height, hash, captured, rating, camera FROM images",
+    )?;
+    let rows = images.query_map([], |row| {
+        let entry = Entry {
+            size: row.get::<_, i64>(1)? as u64,
+            modified: row.get::<_, i64>(2)? as u64,
+            width: row.get(3)?,
+            height: row.get(4)?,
+            // Stored signed, as SQLite integers are
+            hash: row.get::<_, Option<i64>>(5)?.map(|h| h as u64),
+            captured: row.get(6)?,
+            rating: row.get(7)?,
+            camera: row.get(8)?,
+            tags: BTreeSet::new(),
+        };
+        Ok((PathBuf::from(row.get::<_, String>(0)?), entry))
+    })?;
+    for row in rows {
+        let (path, entry) = row?;
+        entries.insert(path, entry);
+    }
+
+    let mut tags = conn.prepare("SELECT path, tag FROM tags")?;
+    let rows = tags.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?;
+    for row in rows {
+        let (path, tag) = row?;
+        if let Some(entry) = entries.get_mut(Path::new(&path)) {
+            entry.tags.insert(tag);
+        }
+    }
+    Ok(entries)
+}
+
+/// Inserts or replaces the row of `path` and its tags.
+fn upsert(conn: &Connection, path: &str, entry: &Entry) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO images (path, size, modified, width, height, hash, captured, rating, camera)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT (path) DO UPDATE SET size = ?2, modified = ?3, width = ?4, height = ?5,
+             hash = ?6, captured = ?7, rating = ?8, camera = ?9",
+        params![
+            path,
+            entry.size as i64,
+            entry.modified as i64,
+            entry.width,
+            entry.height,
+            entry.hash.map(|h| h as i64),
+            entry.captured,
+            entry.rating,
+            entry.camera,
+        ],
+    )?;
+    write_tags(conn, path, &entry.tags)
+}
+
+fn write_tags(conn: &Connection, path: &str, tags: &BTreeSet<String>) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM tags WHERE path = ?1", [path])?;
+    for tag in tags {
+        conn.execute("INSERT INTO tags (path, tag) VALUES (?1, ?2)", [path, tag])?;
+    }
+    Ok(())
+}
+
+/// Runs `write` in one transaction, so a batch of rows costs a single disk sync.
+fn write_batch(
+    conn: &mut Option<Connection>,
+    write: impl FnOnce(&Connection) -> rusqlite::Result<()>,
+) {
+    let Some(conn) = conn else {
+        return;
+    };
+    let result = conn.transaction().and_then(|tx| {
+        write(&tx)?;
+        tx.commit()
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to save library: {}", e);
+    }
+}
+
+fn tags_of(tags: &TagStore, path: &Path) -> BTreeSet<String> {
+    tags.tags_of(path).cloned().collect()
+}
+
+/// Every supported image below `root`, skipping hidden folders.
+fn collect_images(root: &Path, extensions: &[String], cancel: &AtomicBool, out: &mut Vec<PathBuf>) {
+    let mut folders = vec![root.to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(entries) = fs::read_dir(&folder) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if !entry.file_name().to_string_lossy().starts_with('.') {
+                    folders.push(path);
+                }
+            } else if is_supported_image(&path, extensions) {
+                out.push(path);
+            }
+        }
+    }
+}
+
+/// The indexed images of the library roots, read into memory for searching and written
+/// back row by row as they change.
+#[derive(Default)]
+pub struct Library {
+    conn: Option<Connection>,
+    entries: BTreeMap<PathBuf, Entry>,
+    indexing: Option<Indexing>,
+    // Tag store revision the entries' tags were last copied from
+    tags_revision: Option<u64>,
+}
+
+impl Library {
+    /// Opens the index; a missing database is created, an unreadable one gives an empty
+    /// library that isn't saved.
+    pub fn load() -> Self {
+        let Some(dir) = data_dir() else {
+            return Self::default();
+        };
+        let _ = fs::create_dir_all(&dir);
+        let opened = open_database(&dir.join(LIBRARY_FILE))
+            .and_then(|conn| Ok((read_entries(&conn)?, conn)));
+        match opened {
+            Ok((entries, conn)) => Self {
+                conn: Some(conn),
+                entries,
+                ..Self::default()
+            },
+            Err(e) => {
+                eprintln!("Failed to open library: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Copies tag edits into the index; returns true when any indexed image's tags changed.
+    pub fn sync_tags(&mut self, tags: &TagStore) -> bool {
+        if self.tags_revision == Some(tags.revision()) {
+            return false;
+        }
+        self.tags_revision = Some(tags.revision());
+        let mut changed = Vec::new();
+        for (path, entry) in &mut self.entries {
+            let current = tags_of(tags, path);
+            if entry.tags != current {
+                entry.tags = current;
+                changed.push(path.clone());
+            }
+        }
+        if changed.is_empty() {
+            return false;
+        }
+        let entries = &self.entries;
+        write_batch(&mut self.conn, |conn| {
+            for path in &changed {
+                if let Some(name) = path.to_str() {
+                    write_tags(conn, name, &entries[path].tags)?;
+                }
+            }
+            Ok(())
+        });
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&PathBuf, &Entry)> {
+        self.entries.iter()
+    }
+
+    /// Brings the index up to date with `roots` in the background.
+    pub fn start_indexing(
+        &mut self,
+        ctx: &egui::Context,
+        roots: &[PathBuf],
+        extensions: &[String],
+    ) {
+        let (tx, rx) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let worker_cancel = cancel.clone();
+        let roots = roots.to_vec();
+        let extensions = extensions.to_vec();
+        let known: HashMap<PathBuf, (u64, u64)> = self
+            .entries
+            .iter()
+            .map(|(path, entry)| (path.clone(), (entry.size, entry.modified)))
+            .collect();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let mut images = Vec::new();
+            for root in &roots {
+                collect_images(root, &extensions, &worker_cancel, &mut images);
+            }
+            if tx.send(IndexEvent::Found(images.len())).is_err() {
+                return;
+            }
+            for path in &images {
+                if worker_cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                let entry = size_and_modified(path)
+                    .filter(|stamp| known.get(path) != Some(stamp))
+                    .map(|(size, modified)| (path.clone(), read_entry(path, size, modified)));
+                if tx.send(IndexEvent::Checked(entry)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+            let _ = tx.send(IndexEvent::Finished(images));
+            ctx.request_repaint();
+        });
+
+        self.indexing = Some(Indexing {
+            rx,
+            cancel,
+            total: 0,
+            done: 0,
+        });
+    }
+
+    /// Images checked and found so far while indexing.
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        self.indexing.as_ref().map(|i| (i.done, i.total))
+    }
+
+    /// Takes indexing results and stores them; returns true when the index changed. Call
+    /// once per frame.
+    pub fn poll(&mut self, tags: &TagStore) -> bool {
+        let Some(indexing) = &mut self.indexing else {
+            return false;
+        };
+        let mut updated = Vec::new();
+        let mut finished = None;
+        while let Ok(event) = indexing.rx.try_recv() {
+            match event {
+                IndexEvent::Found(total) => indexing.total = total,
+                IndexEvent::Checked(entry) => {
+                    indexing.done += 1;
+                    if let Some((path, mut entry)) = entry {
+                        entry.tags = tags_of(tags, &path);
+                        updated.push((path, entry));
+                    }
+                }
+                IndexEvent::Finished(images) => finished = Some(images),
+            }
+        }
+
+        let removed: Vec<PathBuf> = match finished {
+            Some(images) => {
+                self.indexing = None;
+                let present: HashSet<PathBuf> = images.into_iter().collect();
+                self.entries
+                    .keys()
+                    .filter(|path| !present.contains(*path))
+                    .cloned()
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        if updated.is_empty() && removed.is_empty() {
+            return false;
+        }
+
+        // Paths that aren't valid Unicode can't be stored and are only kept for this session
+        write_batch(&mut self.conn, |conn| {
+            for (path, entry) in &updated {
+                if let Some(name) = path.to_str() {
+                    upsert(conn, name, entry)?;
+                }
+            }
+            for path in &removed {
+                if let Some(name) = path.to_str() {
+                    conn.execute("DELETE FROM images WHERE path = ?1", [name])?;
+                }
+            }
+            Ok(())
+        });
+        for path in &removed {
+            self.entries.remove(path);
+        }
+        self.entries.extend(updated);
+        true
+    }
+}
+
+pub enum LibraryAction {
+    None,
+    // The root folders were edited; the config needs saving and the index updating
+    RootsChanged,
+    // Open the library search
+    Search,
+    ExportData,
+    ImportData,
+}
+
+#[derive(Default)]
+pub struct LibraryWindow {
+    pub open: bool,
+}
+
+impl LibraryWindow {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        library: &Library,
+        roots: &mut Vec<PathBuf>,
+    ) -> LibraryAction {
+        let mut action = LibraryAction::None;
+        if !self.open {
+            return action;
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Library")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.label("Folders");
+                let mut removed = None;
+                for (i, root) in roots.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .small_button("✕")
+                            .on_hover_text("Remove from library")
+                            .clicked()
+                        {
+                            removed = Some(i);
+                        }
+                        ui.label(root.display().to_string());
+                    });
+                }
+                if let Some(i) = removed {
+                    roots.remove(i);
+                    action = LibraryAction::RootsChanged;
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Add folder…").clicked()
+                        && let Some(folder) = rfd::FileDialog::new().pick_folder()
+                        && !roots.contains(&folder)
+                    {
+                        roots.push(folder);
+                        action = LibraryAction::RootsChanged;
+                    }
+                    let idle = library.progress().is_none() && !roots.is_empty();
+                    if ui.add_enabled(idle, egui::Button::new("Rescan")).clicked() {
+                        action = LibraryAction::RootsChanged;
+                    }
+                });
+                match library.progress() {
+                    Some((done, total)) => {
+                        ui.label(format!("Indexing {} / {} images…", done, total));
+                        ui.add(
+                            egui::ProgressBar::new(done as f32 / total.max(1) as f32)
+                                .show_percentage(),
+                        );
+                    }
+                    None => {
+                        ui.weak(format!("{} images indexed", library.len()));
+                    }
+                }
+
+                ui.separator();
+                if ui
+                    .add_enabled(library.len() > 0, egui::Button::new("Search…"))
+                    .clicked()
+                {
+                    action = LibraryAction::Search;
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Settings, albums and tags:");
+                    if ui.button("Export…").clicked() {
+                        action = LibraryAction::ExportData;
+                    }
+                    if ui.button("Import…").clicked() {
+                        action = LibraryAction::ImportData;
+                    }
+                });
+            });
+        self.open = open;
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_round_trip_and_update_in_place() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        let mut entry = Entry {
+            size: 1234,
+            modified: 1_700_000_000,
+            width: 640,
+            height: 480,
+            hash: Some(u64::MAX),
+            captured: Some(-86_400),
+            rating: 3,
+            camera: "X100V".to_string(),
+            tags: ["beach".to_string(), "holiday".to_string()].into(),
+        };
+        upsert(&conn, "/a/beach.jpg", &entry).unwrap();
+        entry.rating = 5;
+        entry.tags = ["beach".to_string()].into();
+        upsert(&conn, "/a/beach.jpg", &entry).unwrap();
+
+        let entries = read_entries(&conn).unwrap();
+        assert_eq!(entries.len(), 1);
+        let read = &entries[Path::new("/a/beach.jpg")];
+        assert_eq!(read.hash, Some(u64::MAX));
+        assert_eq!(read.captured, Some(-86_400));
+        assert_eq!(read.rating, 5);
+        assert_eq!(read.camera, "X100V");
+        assert_eq!(read.tags, entry.tags);
+
+        conn.execute("DELETE FROM images WHERE path = ?1", ["/a/beach.jpg"])
+            .unwrap();
+        let tags: i64 = conn
+            .query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tags, 0);
+    }
+}
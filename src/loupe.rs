@@ -0,0 +1,78 @@
+use eframe::egui;
+
+/// A square magnifier that follows the cursor and shows a zoomed region of the image.
+pub struct Loupe {
+    pub enabled: bool,
+    pub magnification: f32,
+    size: f32,
+}
+
+impl Default for Loupe {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            magnification: 4.0,
+            size: 220.0,
+        }
+    }
+}
+
+impl Loupe {
+    /// Uses the mouse wheel to change the magnification while the loupe is active.
+    ///
+    /// The scroll delta is consumed so the main view doesn't zoom at the same time.
+    pub fn process_input(&mut self, ui: &egui::Ui) {
+        if !self.enabled {
+            return;
+        }
+
+        let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+        if scroll != 0.0 {
+            self.magnification = (self.magnification * 1.2_f32.powf(scroll / 50.0)).clamp(2.0, 8.0);
+            ui.ctx().input_mut(|i| i.smooth_scroll_delta = egui::Vec2::ZERO);
+        }
+    }
+
+    /// Draws the loupe over `pages`, given as (texture, on-screen rect) pairs.
+    pub fn paint(&self, ui: &egui::Ui, pages: &[(egui::TextureId, egui::Rect)]) {
+        if !self.enabled {
+            return;
+        }
+        let Some(cursor) = ui.input(|i| i.pointer.hover_pos()) else {
+            return;
+        };
+
+        let loupe_rect = egui::Rect::from_center_size(cursor, egui::Vec2::splat(self.size));
+        let painter = ui.painter();
+        painter.rect_filled(loupe_rect, 0.0, egui::Color32::from_gray(20));
+
+        for &(texture_id, page_rect) in pages {
+            // Magnify the page about the cursor, then crop it to the loupe with adjusted UVs
+            let magnified = egui::Rect::from_min_max(
+                cursor + (page_rect.min - cursor) * self.magnification,
+                cursor + (page_rect.max - cursor) * self.magnification,
+            );
+            let visible = magnified.intersect(loupe_rect);
+            if !visible.is_positive() {
+                continue;
+            }
+
+            let to_uv = |p: egui::Pos2| ((p - magnified.min) / magnified.size()).to_pos2();
+            painter.image(
+                texture_id,
+                visible,
+                egui::Rect::from_min_max(to_uv(visible.min), to_uv(visible.max)),
+                egui::Color32::WHITE,
+            );
+        }
+
+        painter.rect_stroke(loupe_rect, 0.0, egui::Stroke::new(2.0, egui::Color32::WHITE));
+        painter.text(
+            loupe_rect.left_bottom() + egui::vec2(6.0, -6.0),
+            egui::Align2::LEFT_BOTTOM,
+            format!("{:.1}x", self.magnification),
+            egui::FontId::monospace(12.0),
+            egui::Color32::WHITE,
+        );
+    }
+}
@@ -1,26 +1,61 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod animation;
-mod app;
-mod config;
-mod image_loader;
-mod thumbnail_list;
-mod view_state;
-
-use app::ImageViewer;
-use config::AppConfig;
+use better_image_viewer::config::AppConfig;
+use better_image_viewer::remote::{self, RemoteOptions};
+use better_image_viewer::{ImageViewer, cli};
 use std::path::PathBuf;
 
 fn main() -> eframe::Result<()> {
-    let config = AppConfig::load();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
+    let mut config = AppConfig::load();
 
     // Parse command line arguments to get the initial image path
-    let args: Vec<String> = std::env::args().collect();
-    let initial_path = if args.len() > 1 {
-        Some(PathBuf::from(&args[1]))
-    } else {
-        None
-    };
+    let mut initial_path = None;
+    let mut remote = RemoteOptions::default();
+    let mut kiosk = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--remote" => remote.enabled = true,
+            "--stdin" => remote.stdin = true,
+            // Locked-down fullscreen slideshow, see `kiosk`
+            "--kiosk" => kiosk = true,
+            // `--config <file>` uses (and saves to) another settings file
+            "--config" => match args.next() {
+                Some(path) => config = AppConfig::load_from(std::path::Path::new(&path)),
+                None => {
+                    eprintln!("usage: --config <file>");
+                    std::process::exit(1);
+                }
+            },
+            // `--send next` drives a running viewer and exits
+            "--send" => {
+                let line = args.collect::<Vec<_>>().join(" ");
+                match remote::send(&line) {
+                    Ok(reply) => println!("{}", reply),
+                    Err(e) => {
+                        eprintln!("No running viewer to send to: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                return Ok(());
+            }
+            _ => initial_path = Some(PathBuf::from(arg)),
+        }
+    }
+
+    // Single instance: hand the file over to a viewer that is already listening
+    if remote.enabled
+        && let Some(path) = &initial_path
+        && let Ok(path) = std::path::absolute(path)
+        && remote::send(&format!("open {}", path.display())).is_ok_and(|reply| reply == "ok")
+    {
+        return Ok(());
+    }
 
     let mut viewport = eframe::egui::ViewportBuilder::default().with_drag_and_drop(true);
 
@@ -42,6 +77,6 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "Better Image Viewer",
         options,
-        Box::new(|cc| Ok(Box::new(ImageViewer::new(cc, config, initial_path)))),
+        Box::new(|cc| Ok(Box::new(ImageViewer::new(cc, config, initial_path, remote, kiosk)))),
     )
 }
@@ -0,0 +1,130 @@
+use crate::view_state::ViewState;
+use eframe::egui;
+
+/// Measure tool: drag out a rectangle and read its size and corners in image pixels.
+///
+/// The rectangle is stored in image coordinates so it stays attached to the image
+/// while panning and zooming.
+#[derive(Default)]
+pub struct Measure {
+    pub enabled: bool,
+    start: Option<egui::Pos2>,
+    end: Option<egui::Pos2>,
+    dragging: bool,
+}
+
+impl Measure {
+    pub fn clear(&mut self) {
+        self.start = None;
+        self.end = None;
+        self.dragging = false;
+    }
+
+    /// The measured region in image pixels, snapped to whole pixels.
+    pub fn region(&self) -> Option<egui::Rect> {
+        Some(egui::Rect::from_two_pos(self.start?, self.end?))
+    }
+
+    fn snap(view_state: &ViewState, viewport: egui::Rect, image_size: egui::Vec2, pos: egui::Pos2) -> egui::Pos2 {
+        let p = view_state.screen_to_image(viewport, image_size, pos);
+        egui::pos2(
+            p.x.round().clamp(0.0, image_size.x),
+            p.y.round().clamp(0.0, image_size.y),
+        )
+    }
+
+    pub fn process_input(
+        &mut self,
+        ui: &egui::Ui,
+        view_state: &ViewState,
+        image_size: egui::Vec2,
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        let viewport = ui.clip_rect();
+        let wants_pointer = ui.ctx().wants_pointer_input() || ui.ctx().is_pointer_over_area();
+        let (pressed, down, pos, ctrl) = ui.input(|i| {
+            (
+                i.pointer.button_pressed(egui::PointerButton::Primary),
+                i.pointer.button_down(egui::PointerButton::Primary),
+                i.pointer.interact_pos(),
+                i.modifiers.command,
+            )
+        });
+
+        if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.clear();
+        }
+
+        // Ctrl + drag stays reserved for zoom-to-selection
+        if pressed
+            && !ctrl
+            && !wants_pointer
+            && let Some(pos) = pos
+        {
+            let p = Self::snap(view_state, viewport, image_size, pos);
+            self.start = Some(p);
+            self.end = Some(p);
+            self.dragging = true;
+        }
+
+        if self.dragging {
+            if let Some(pos) = pos {
+                self.end = Some(Self::snap(view_state, viewport, image_size, pos));
+            }
+            if !down {
+                self.dragging = false;
+            }
+            ui.ctx().request_repaint();
+        }
+    }
+
+    pub fn paint(&self, ui: &egui::Ui, view_state: &ViewState, image_size: egui::Vec2) {
+        if !self.enabled {
+            return;
+        }
+        let Some(region) = self.region() else {
+            return;
+        };
+
+        let viewport = ui.clip_rect();
+        let screen_rect = egui::Rect::from_min_max(
+            view_state.image_to_screen(viewport, image_size, region.min),
+            view_state.image_to_screen(viewport, image_size, region.max),
+        );
+
+        let painter = ui.painter();
+        painter.rect(
+            screen_rect,
+            0.0,
+            egui::Color32::from_rgba_unmultiplied(255, 200, 0, 30),
+            egui::Stroke::new(1.0, egui::Color32::YELLOW),
+        );
+
+        let font_id = egui::FontId::monospace(12.0);
+        let label = |pos: egui::Pos2, anchor: egui::Align2, text: String| {
+            let galley = painter.layout_no_wrap(text, font_id.clone(), egui::Color32::WHITE);
+            let rect = anchor.anchor_size(pos, galley.size()).expand(3.0);
+            painter.rect_filled(rect, 3.0, egui::Color32::from_black_alpha(180));
+            painter.galley(rect.min + egui::vec2(3.0, 3.0), galley, egui::Color32::WHITE);
+        };
+
+        label(
+            screen_rect.center_top() - egui::vec2(0.0, 6.0),
+            egui::Align2::CENTER_BOTTOM,
+            format!("{} × {} px", region.width(), region.height()),
+        );
+        label(
+            screen_rect.left_top() - egui::vec2(6.0, 6.0),
+            egui::Align2::RIGHT_BOTTOM,
+            format!("({}, {})", region.min.x, region.min.y),
+        );
+        label(
+            screen_rect.right_bottom() + egui::vec2(6.0, 6.0),
+            egui::Align2::LEFT_TOP,
+            format!("({}, {})", region.max.x, region.max.y),
+        );
+    }
+}
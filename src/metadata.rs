@@ -0,0 +1,744 @@
+//! EXIF reading and XMP editing for the info panel.
+//!
+//! The editable fields (title, description, copyright, keywords, capture date) are stored
+//! as XMP, either embedded in the file (JPEG and PNG) or in a `<name>.xmp` sidecar next to
+//! it. Writing replaces the whole XMP packet with the fields edited here.
+
+use crate::format_info::FormatInfo;
+use crate::sharpness;
+use eframe::egui;
+use img_parts::Bytes;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+const XMP_JPEG_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const XMP_PNG_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+#[derive(Clone, Default, PartialEq)]
+pub struct MetadataFields {
+    pub title: String,
+    pub description: String,
+    pub copyright: String,
+    // Comma separated
+    pub keywords: String,
+    // "YYYY-MM-DD HH:MM:SS"
+    pub capture_date: String,
+}
+
+impl MetadataFields {
+    /// Copies every non-empty field of `other` over this one.
+    fn overlay(&mut self, other: &MetadataFields) {
+        for (dst, src) in [
+            (&mut self.title, &other.title),
+            (&mut self.description, &other.description),
+            (&mut self.copyright, &other.copyright),
+            (&mut self.keywords, &other.keywords),
+            (&mut self.capture_date, &other.capture_date),
+        ] {
+            if !src.trim().is_empty() {
+                dst.clone_from(src);
+            }
+        }
+    }
+}
+
+pub struct ImageMetadata {
+    // Tag name and displayable value
+    pub exif: Vec<(String, String)>,
+    pub fields: MetadataFields,
+    pub has_gps: bool,
+    // Latitude and longitude in degrees, north and east positive
+    pub gps: Option<(f64, f64)>,
+}
+
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    path.with_extension("xmp")
+}
+
+pub fn read_exif(path: &Path) -> Option<exif::Exif> {
+    let file = File::open(path).ok()?;
+    exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()
+}
+
+/// The small JPEG preview cameras embed in the EXIF data, decoded.
+pub fn embedded_thumbnail(path: &Path) -> Option<image::DynamicImage> {
+    let exif = read_exif(path)?;
+    let field = |tag| exif.get_field(tag, exif::In::THUMBNAIL)?.value.get_uint(0);
+    let offset = field(exif::Tag::JPEGInterchangeFormat)? as usize;
+    let length = field(exif::Tag::JPEGInterchangeFormatLength)? as usize;
+    let bytes = exif.buf().get(offset..offset.checked_add(length)?)?;
+    image::load_from_memory_with_format(bytes, image::ImageFormat::Jpeg).ok()
+}
+
+fn gps_degrees(
+    exif: &exif::Exif,
+    tag: exif::Tag,
+    ref_tag: exif::Tag,
+    negative: &str,
+) -> Option<f64> {
+    let exif::Value::Rational(parts) = &exif.get_field(tag, exif::In::PRIMARY)?.value else {
+        return None;
+    };
+    let degrees = parts
+        .iter()
+        .zip([1.0, 60.0, 3600.0])
+        .map(|(r, scale)| r.to_f64() / scale)
+        .sum::<f64>();
+    if !degrees.is_finite() {
+        return None;
+    }
+    let negative = exif_string(exif, ref_tag).eq_ignore_ascii_case(negative);
+    Some(if negative { -degrees } else { degrees })
+}
+
+pub fn gps_coordinates(exif: &exif::Exif) -> Option<(f64, f64)> {
+    let lat = gps_degrees(exif, exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef, "S")?;
+    let lon = gps_degrees(
+        exif,
+        exif::Tag::GPSLongitude,
+        exif::Tag::GPSLongitudeRef,
+        "W",
+    )?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// When the photo was taken (EXIF DateTimeOriginal), in the camera's local time.
+pub fn capture_time(path: &Path) -> Option<exif::DateTime> {
+    exif_capture_time(&read_exif(path)?)
+}
+
+/// Capture time from already read EXIF data.
+pub fn exif_capture_time(exif: &exif::Exif) -> Option<exif::DateTime> {
+    let field = exif.get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY)?;
+    let exif::Value::Ascii(parts) = &field.value else {
+        return None;
+    };
+    exif::DateTime::from_ascii(parts.first()?).ok()
+}
+
+/// Camera model (EXIF Model), empty when not recorded.
+pub fn camera_model(exif: &exif::Exif) -> String {
+    exif_string(exif, exif::Tag::Model)
+}
+
+fn exif_string(exif: &exif::Exif, tag: exif::Tag) -> String {
+    exif.get_field(tag, exif::In::PRIMARY)
+        .map(|f| match &f.value {
+            exif::Value::Ascii(parts) => parts
+                .iter()
+                .map(|p| String::from_utf8_lossy(p).trim().to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => f.display_value().to_string(),
+        })
+        .unwrap_or_default()
+}
+
+pub fn read(path: &Path) -> ImageMetadata {
+    let exif = read_exif(path);
+    let exif_fields = exif
+        .as_ref()
+        .map(|exif| {
+            exif.fields()
+                .filter(|f| f.ifd_num == exif::In::PRIMARY)
+                .map(|f| {
+                    (
+                        f.tag.to_string(),
+                        f.display_value().with_unit(exif).to_string(),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let fields = match read_xmp(path) {
+        Some(xmp) => parse_xmp(&xmp),
+        None => exif
+            .as_ref()
+            .map(|exif| MetadataFields {
+                description: exif_string(exif, exif::Tag::ImageDescription),
+                copyright: exif_string(exif, exif::Tag::Copyright),
+                capture_date: exif_string(exif, exif::Tag::DateTimeOriginal).replacen(':', "-", 2),
+                ..Default::default()
+            })
+            .unwrap_or_default(),
+    };
+
+    ImageMetadata {
+        exif: exif_fields,
+        fields,
+        has_gps: exif.as_ref().is_some_and(|e| {
+            e.get_field(exif::Tag::GPSLatitude, exif::In::PRIMARY)
+                .is_some()
+        }),
+        gps: exif.as_ref().and_then(gps_coordinates),
+    }
+}
+
+/// The XMP packet of the sidecar, or else the one embedded in the file.
+fn read_xmp(path: &Path) -> Option<String> {
+    if let Ok(xmp) = std::fs::read_to_string(sidecar_path(path)) {
+        return Some(xmp);
+    }
+    let data = std::fs::read(path).ok()?;
+    let start = find(&data, b"<x:xmpmeta")?;
+    let end = find(&data[start..], b"</x:xmpmeta>")? + start + b"</x:xmpmeta>".len();
+    Some(String::from_utf8_lossy(&data[start..end]).into_owned())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Text of the element `tag`, or of the `rdf:li` items inside it.
+fn xmp_values(xmp: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let Some(start) = xmp.find(&open) else {
+        return Vec::new();
+    };
+    let inner_start = start + open.len();
+    let Some(len) = xmp[inner_start..].find(&close) else {
+        return Vec::new();
+    };
+    let inner = &xmp[inner_start..inner_start + len];
+
+    if !inner.contains("<rdf:li") {
+        return vec![unescape(inner.trim())];
+    }
+    inner
+        .split("<rdf:li")
+        .skip(1)
+        .filter_map(|item| {
+            let text = &item[item.find('>')? + 1..];
+            Some(unescape(text[..text.find("</rdf:li>")?].trim()))
+        })
+        .collect()
+}
+
+fn parse_xmp(xmp: &str) -> MetadataFields {
+    let first = |tag| xmp_values(xmp, tag).into_iter().next().unwrap_or_default();
+    MetadataFields {
+        title: first("dc:title"),
+        description: first("dc:description"),
+        copyright: first("dc:rights"),
+        keywords: xmp_values(xmp, "dc:subject").join(", "),
+        capture_date: first("photoshop:DateCreated").replacen('T', " ", 1),
+    }
+}
+
+/// The star rating (`xmp:Rating`, 0 to 5) of the image; 0 when unrated or rejected.
+pub fn read_rating(path: &Path) -> u8 {
+    let Some(xmp) = read_xmp(path) else {
+        return 0;
+    };
+    let attribute = xmp
+        .find("xmp:Rating=\"")
+        .and_then(|i| xmp[i + "xmp:Rating=\"".len()..].split('"').next())
+        .map(str::to_string);
+    attribute
+        .or_else(|| xmp_values(&xmp, "xmp:Rating").into_iter().next())
+        .and_then(|v| v.trim().parse::<i32>().ok())
+        .map_or(0, |r| r.clamp(0, 5) as u8)
+}
+
+pub fn xmp_packet(fields: &MetadataFields) -> String {
+    let alt = |text: &str| {
+        format!(
+            "<rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt>",
+            escape(text)
+        )
+    };
+    let mut body = String::new();
+    if !fields.title.is_empty() {
+        body += &format!("   <dc:title>{}</dc:title>\n", alt(&fields.title));
+    }
+    if !fields.description.is_empty() {
+        body += &format!(
+            "   <dc:description>{}</dc:description>\n",
+            alt(&fields.description)
+        );
+    }
+    if !fields.copyright.is_empty() {
+        body += &format!("   <dc:rights>{}</dc:rights>\n", alt(&fields.copyright));
+    }
+    let keywords: Vec<&str> = fields
+        .keywords
+        .split(',')
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .collect();
+    if !keywords.is_empty() {
+        let items: String = keywords
+            .iter()
+            .map(|k| format!("<rdf:li>{}</rdf:li>", escape(k)))
+            .collect();
+        body += &format!("   <dc:subject><rdf:Bag>{}</rdf:Bag></dc:subject>\n", items);
+    }
+    if !fields.capture_date.trim().is_empty() {
+        body += &format!(
+            "   <photoshop:DateCreated>{}</photoshop:DateCreated>\n",
+            escape(&fields.capture_date.trim().replacen(' ', "T", 1))
+        );
+    }
+
+    format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n \
+         <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n  \
+         <rdf:Description rdf:about=\"\"\n    \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n    \
+         xmlns:photoshop=\"http://ns.adobe.com/photoshop/1.0/\">\n\
+         {}  </rdf:Description>\n \
+         </rdf:RDF>\n\
+         </x:xmpmeta>\n\
+         <?xpacket end=\"w\"?>",
+        body
+    )
+}
+
+/// Embeds `xmp` into a JPEG or PNG file, replacing any existing packet.
+fn embed_xmp(path: &Path, xmp: &str) -> Result<(), String> {
+    let data = Bytes::from(std::fs::read(path).map_err(|e| e.to_string())?);
+    let encoded = match image::guess_format(&data) {
+        Ok(image::ImageFormat::Jpeg) => {
+            let mut jpeg = img_parts::jpeg::Jpeg::from_bytes(data).map_err(|e| e.to_string())?;
+            let segments = jpeg.segments_mut();
+            segments.retain(|s| {
+                !(s.marker() == img_parts::jpeg::markers::APP1
+                    && s.contents().starts_with(XMP_JPEG_HEADER))
+            });
+            let mut contents = XMP_JPEG_HEADER.to_vec();
+            contents.extend_from_slice(xmp.as_bytes());
+            // Right after SOI/APP0 (and EXIF), where readers expect it
+            let position = segments
+                .iter()
+                .position(|s| {
+                    s.marker() != img_parts::jpeg::markers::APP0
+                        && s.marker() != img_parts::jpeg::markers::APP1
+                })
+                .unwrap_or(segments.len());
+            segments.insert(
+                position,
+                img_parts::jpeg::JpegSegment::new_with_contents(
+                    img_parts::jpeg::markers::APP1,
+                    Bytes::from(contents),
+                ),
+            );
+            jpeg.encoder().bytes()
+        }
+        Ok(image::ImageFormat::Png) => {
+            let mut png = img_parts::png::Png::from_bytes(data).map_err(|e| e.to_string())?;
+            let chunks = png.chunks_mut();
+            chunks.retain(|c| !(&c.kind() == b"iTXt" && c.contents().starts_with(XMP_PNG_KEYWORD)));
+            // Keyword, null separator, uncompressed, no language tag or translated keyword
+            let mut contents = XMP_PNG_KEYWORD.to_vec();
+            contents.extend_from_slice(&[0, 0, 0, 0, 0]);
+            contents.extend_from_slice(xmp.as_bytes());
+            // Directly after IHDR
+            chunks.insert(
+                1.min(chunks.len()),
+                img_parts::png::PngChunk::new(*b"iTXt", Bytes::from(contents)),
+            );
+            png.encoder().bytes()
+        }
+        _ => return Err("Embedding is only supported for JPEG and PNG; use a sidecar".to_string()),
+    };
+    std::fs::write(path, encoded).map_err(|e| e.to_string())
+}
+
+pub fn write(path: &Path, fields: &MetadataFields, sidecar: bool) -> Result<(), String> {
+    let xmp = xmp_packet(fields);
+    if sidecar {
+        std::fs::write(sidecar_path(path), xmp).map_err(|e| e.to_string())
+    } else {
+        embed_xmp(path, &xmp)
+    }
+}
+
+/// Re-encodes `path` as `<stem>_clean.<ext>` next to it, without EXIF, GPS or XMP data.
+///
+/// The EXIF orientation is applied to the pixels first, since the tag itself is dropped.
+pub fn save_clean_copy(path: &Path) -> Result<PathBuf, String> {
+    let mut image = crate::image_loader::decode_image(path).map_err(|e| e.to_string())?;
+    if let Some(orientation) = read_exif(path)
+        .and_then(|e| {
+            e.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?
+                .value
+                .get_uint(0)
+        })
+        .and_then(|o| image::metadata::Orientation::from_exif(o as u8))
+    {
+        image.apply_orientation(orientation);
+    }
+
+    let format = image::ImageFormat::from_path(path).unwrap_or(image::ImageFormat::Png);
+    let format = if format.can_write() {
+        format
+    } else {
+        image::ImageFormat::Png
+    };
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let out = path.with_file_name(format!("{}_clean.{}", stem, format.extensions_str()[0]));
+
+    let mut writer = std::io::BufWriter::new(File::create(&out).map_err(|e| e.to_string())?);
+    let result = if format == image::ImageFormat::Jpeg {
+        // The default quality (75) is noticeably lossy for a copy meant to replace the original
+        image
+            .to_rgb8()
+            .write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(
+                &mut writer,
+                95,
+            ))
+    } else {
+        image.write_to(&mut writer, format)
+    };
+    result.map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+/// Runs [`save_clean_copy`] in the background and reports the outcome.
+#[derive(Default)]
+pub struct CleanCopy {
+    rx: Option<Receiver<Result<PathBuf, String>>>,
+    status: Option<String>,
+}
+
+impl CleanCopy {
+    pub fn start(&mut self, ctx: &egui::Context, path: PathBuf) {
+        if self.rx.is_some() {
+            return;
+        }
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.status = None;
+
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(save_clean_copy(&path));
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn dismiss_status(&mut self) {
+        self.status = None;
+    }
+
+    pub fn show_status(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.rx
+            && let Ok(result) = rx.try_recv()
+        {
+            self.status = Some(match result {
+                Ok(path) => format!(
+                    "Saved clean copy {}",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ),
+                Err(e) => format!("Clean copy failed: {}", e),
+            });
+            self.rx = None;
+        }
+
+        if self.rx.is_none() && self.status.is_none() {
+            return;
+        }
+        egui::Area::new(egui::Id::new("clean_copy_status"))
+            .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(10.0, -160.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        if self.rx.is_some() {
+                            ui.spinner();
+                            ui.label("Saving clean copy…");
+                        } else if let Some(status) = &self.status {
+                            ui.label(status);
+                        }
+                    });
+                });
+            });
+    }
+}
+
+/// A tiny offline locator: an equirectangular graticule with the position marked.
+fn paint_locator(ui: &mut egui::Ui, lat: f64, lon: f64) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(240.0, 120.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, egui::Color32::from_rgb(24, 40, 64));
+
+    let to_screen = |lat: f64, lon: f64| {
+        egui::pos2(
+            rect.min.x + ((lon + 180.0) / 360.0) as f32 * rect.width(),
+            rect.min.y + ((90.0 - lat) / 180.0) as f32 * rect.height(),
+        )
+    };
+    let grid = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(30));
+    for lon_line in (-150..=150).step_by(30) {
+        let x = to_screen(0.0, lon_line as f64).x;
+        painter.vline(x, rect.y_range(), grid);
+    }
+    for lat_line in (-60..=60).step_by(30) {
+        let stroke = if lat_line == 0 {
+            egui::Stroke::new(1.0, egui::Color32::from_white_alpha(80))
+        } else {
+            grid
+        };
+        painter.hline(rect.x_range(), to_screen(lat_line as f64, 0.0).y, stroke);
+    }
+
+    let pos = to_screen(lat, lon);
+    painter.hline(
+        rect.x_range(),
+        pos.y,
+        egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(255, 80, 80, 90)),
+    );
+    painter.vline(
+        pos.x,
+        rect.y_range(),
+        egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(255, 80, 80, 90)),
+    );
+    painter.circle(
+        pos,
+        4.0,
+        egui::Color32::RED,
+        egui::Stroke::new(1.5, egui::Color32::WHITE),
+    );
+}
+
+/// Info window: file details, EXIF tags and the XMP field editor.
+#[derive(Default)]
+pub struct MetadataPanel {
+    pub open: bool,
+    path: Option<PathBuf>,
+    metadata: Option<ImageMetadata>,
+    edited: MetadataFields,
+    sidecar: bool,
+    batch_rx: Option<Receiver<(usize, usize)>>,
+    status: Option<String>,
+}
+
+impl MetadataPanel {
+    /// Re-reads the metadata on the next frame (after the file was changed elsewhere).
+    pub fn invalidate(&mut self) {
+        self.path = None;
+    }
+
+    fn load(&mut self, path: Option<&Path>) {
+        self.path = path.map(Path::to_path_buf);
+        self.metadata = path.map(read);
+        self.edited = self
+            .metadata
+            .as_ref()
+            .map(|m| m.fields.clone())
+            .unwrap_or_default();
+        self.status = None;
+    }
+
+    fn apply_to_folder(&mut self, ctx: &egui::Context, images: &[PathBuf]) {
+        let (tx, rx) = channel();
+        self.batch_rx = Some(rx);
+
+        let images = images.to_vec();
+        let edited = self.edited.clone();
+        let sidecar = self.sidecar;
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let mut failed = 0;
+            for (i, path) in images.iter().enumerate() {
+                // Keep each image's own values for the fields left empty here
+                let mut fields = read(path).fields;
+                fields.overlay(&edited);
+                if write(path, &fields, sidecar).is_err() {
+                    failed += 1;
+                }
+                let _ = tx.send((i + 1, failed));
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    /// `sharpness_badges` is the option to show sharpness scores on the filmstrip.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        current: Option<&Path>,
+        folder_images: &[PathBuf],
+        sharpness: Option<f32>,
+        format: Option<&FormatInfo>,
+        sharpness_badges: &mut bool,
+    ) {
+        if let Some(rx) = &self.batch_rx {
+            let mut finished = false;
+            while let Ok((done, failed)) = rx.try_recv() {
+                self.status = Some(if failed > 0 {
+                    format!(
+                        "Updated {} / {} images ({} failed)",
+                        done - failed,
+                        folder_images.len(),
+                        failed
+                    )
+                } else {
+                    format!("Updated {} / {} images", done, folder_images.len())
+                });
+                finished = done >= folder_images.len();
+            }
+            if finished {
+                self.batch_rx = None;
+                self.invalidate();
+            }
+        }
+
+        if !self.open {
+            return;
+        }
+        if self.path.as_deref() != current {
+            self.load(current);
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Info")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                let Some(path) = self.path.clone() else {
+                    ui.label("No image loaded.");
+                    return;
+                };
+
+                ui.label(path.file_name().unwrap_or_default().to_string_lossy());
+                if let Ok((w, h)) = image::image_dimensions(&path) {
+                    let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                    ui.label(format!("{} × {} px, {} KB", w, h, size / 1024));
+                }
+                if let Some(format) = format {
+                    ui.label(format.summary());
+                    if !format.details.is_empty() {
+                        egui::Grid::new("format_details").num_columns(2).show(ui, |ui| {
+                            for (label, value) in &format.details {
+                                ui.weak(*label);
+                                ui.label(value);
+                                ui.end_row();
+                            }
+                        });
+                    }
+                }
+                ui.horizontal(|ui| {
+                    match sharpness {
+                        Some(score) => {
+                            let (word, color) = sharpness::verdict(score);
+                            ui.label(format!("Sharpness {:.0}", score))
+                                .on_hover_text("Variance of the Laplacian; higher is sharper");
+                            ui.colored_label(color, word);
+                        }
+                        None => {
+                            ui.label("Sharpness");
+                            ui.spinner();
+                        }
+                    }
+                    ui.checkbox(sharpness_badges, "On thumbnails");
+                });
+                if self.metadata.as_ref().is_some_and(|m| m.has_gps) {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 170, 60),
+                        "⚠ Contains GPS location (Ctrl+Shift+K saves a clean copy)",
+                    );
+                }
+                if let Some((lat, lon)) = self.metadata.as_ref().and_then(|m| m.gps) {
+                    let coordinates = format!("{:.6}, {:.6}", lat, lon);
+                    ui.horizontal(|ui| {
+                        ui.label(format!("📍 {}", coordinates));
+                        if ui.small_button("Copy").clicked() {
+                            ui.ctx().copy_text(coordinates.clone());
+                        }
+                        if ui.small_button("Open in maps").clicked() {
+                            ui.ctx().open_url(egui::OpenUrl::new_tab(format!(
+                                "https://www.openstreetmap.org/?mlat={lat:.6}&mlon={lon:.6}#map=15/{lat:.6}/{lon:.6}"
+                            )));
+                        }
+                    });
+                    paint_locator(ui, lat, lon);
+                }
+                ui.separator();
+
+                egui::Grid::new("metadata_fields").num_columns(2).show(ui, |ui| {
+                    ui.label("Title");
+                    ui.text_edit_singleline(&mut self.edited.title);
+                    ui.end_row();
+                    ui.label("Description");
+                    ui.text_edit_multiline(&mut self.edited.description);
+                    ui.end_row();
+                    ui.label("Copyright");
+                    ui.text_edit_singleline(&mut self.edited.copyright);
+                    ui.end_row();
+                    ui.label("Keywords");
+                    ui.text_edit_singleline(&mut self.edited.keywords)
+                        .on_hover_text("Comma separated");
+                    ui.end_row();
+                    ui.label("Captured");
+                    ui.text_edit_singleline(&mut self.edited.capture_date)
+                        .on_hover_text("YYYY-MM-DD HH:MM:SS");
+                    ui.end_row();
+                });
+
+                ui.checkbox(&mut self.sidecar, "Write to an XMP sidecar instead of the file");
+                ui.horizontal(|ui| {
+                    let busy = self.batch_rx.is_some();
+                    if ui.add_enabled(!busy, egui::Button::new("Save")).clicked() {
+                        self.status = Some(match write(&path, &self.edited, self.sidecar) {
+                            Ok(()) => "Saved".to_string(),
+                            Err(e) => format!("Save failed: {}", e),
+                        });
+                    }
+                    let batch = egui::Button::new(format!("Apply to {} images", folder_images.len()));
+                    if ui
+                        .add_enabled(!busy && folder_images.len() > 1, batch)
+                        .on_hover_text("Writes the filled-in fields to every image in the folder")
+                        .clicked()
+                    {
+                        self.apply_to_folder(ctx, folder_images);
+                    }
+                });
+                if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+
+                if let Some(metadata) = &self.metadata
+                    && !metadata.exif.is_empty()
+                {
+                    ui.separator();
+                    egui::CollapsingHeader::new(format!("EXIF ({} tags)", metadata.exif.len()))
+                        .show(ui, |ui| {
+                            egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                                egui::Grid::new("exif_tags").striped(true).show(ui, |ui| {
+                                    for (tag, value) in &metadata.exif {
+                                        ui.label(tag);
+                                        ui.label(value);
+                                        ui.end_row();
+                                    }
+                                });
+                            });
+                        });
+                }
+            });
+        self.open = open;
+    }
+}
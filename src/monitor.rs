@@ -0,0 +1,77 @@
+//! Monitor geometry egui doesn't report: where monitors are on the desktop and which part
+//! of them the taskbar leaves free. Only Windows answers; elsewhere the window manager
+//! keeps windows on screen itself, and every query returns `None`.
+//!
+//! Points are in egui points, in the same desktop coordinates as `ViewportInfo::outer_rect`.
+
+use eframe::egui;
+
+/// The monitor showing `point` (or the one nearest to it with `nearest`): its full rect
+/// and its work area.
+#[cfg(windows)]
+fn monitor_at(
+    point: egui::Pos2,
+    pixels_per_point: f32,
+    nearest: bool,
+) -> Option<(egui::Rect, egui::Rect)> {
+    use windows::Win32::Foundation::{POINT, RECT};
+    use windows::Win32::Graphics::Gdi::{
+        GetMonitorInfoW, MONITOR_DEFAULTTONEAREST, MONITOR_DEFAULTTONULL, MONITORINFO,
+        MonitorFromPoint,
+    };
+
+    let to_rect = |r: RECT| {
+        egui::Rect::from_min_max(
+            egui::pos2(r.left as f32, r.top as f32),
+            egui::pos2(r.right as f32, r.bottom as f32),
+        ) / pixels_per_point
+    };
+    let pixel = point * pixels_per_point;
+    let point = POINT {
+        x: pixel.x.round() as i32,
+        y: pixel.y.round() as i32,
+    };
+    let flags = if nearest {
+        MONITOR_DEFAULTTONEAREST
+    } else {
+        MONITOR_DEFAULTTONULL
+    };
+
+    // SAFETY: plain Win32 calls; `info` is sized as the API requires
+    unsafe {
+        let monitor = MonitorFromPoint(point, flags);
+        if monitor.0.is_null() {
+            return None;
+        }
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        GetMonitorInfoW(monitor, &mut info)
+            .as_bool()
+            .then(|| (to_rect(info.rcMonitor), to_rect(info.rcWork)))
+    }
+}
+
+#[cfg(not(windows))]
+fn monitor_at(
+    _point: egui::Pos2,
+    _pixels_per_point: f32,
+    _nearest: bool,
+) -> Option<(egui::Rect, egui::Rect)> {
+    None
+}
+
+/// Whether any monitor shows `point`, if the platform says.
+pub fn is_on_screen(point: egui::Pos2, pixels_per_point: f32) -> Option<bool> {
+    if cfg!(windows) {
+        Some(monitor_at(point, pixels_per_point, false).is_some())
+    } else {
+        None
+    }
+}
+
+/// The work area (the monitor less taskbars and docks) of the monitor nearest to `point`.
+pub fn work_area_near(point: egui::Pos2, pixels_per_point: f32) -> Option<egui::Rect> {
+    monitor_at(point, pixels_per_point, true).map(|(_, work)| work)
+}
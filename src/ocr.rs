@@ -0,0 +1,123 @@
+//! "Copy text from image" using the Tesseract OCR engine.
+//!
+//! Tesseract is driven through its command line interface so no native library is needed
+//! at build time; the `tesseract` executable only has to be on `PATH` (or be pointed to
+//! with `BIV_TESSERACT`) when the action is used.
+
+use crate::clipboard;
+use crate::image_loader::decode_image;
+use crate::osd;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+#[derive(Default)]
+pub struct Ocr {
+    rx: Option<Receiver<Result<String, String>>>,
+    status: Option<String>,
+}
+
+fn tesseract_command() -> Command {
+    Command::new(std::env::var_os("BIV_TESSERACT").unwrap_or_else(|| "tesseract".into()))
+}
+
+/// Runs OCR on `path`, optionally restricted to `region` (in image pixels).
+fn recognize(path: &Path, region: Option<egui::Rect>) -> Result<String, String> {
+    let mut image = decode_image(path).map_err(|e| format!("OCR failed: {}", e))?;
+    if let Some(region) = region {
+        let x = region.min.x.max(0.0) as u32;
+        let y = region.min.y.max(0.0) as u32;
+        image = image.crop_imm(x, y, region.width() as u32, region.height() as u32);
+    }
+
+    // Tesseract's own format support varies between builds, so always hand it a PNG
+    let input = std::env::temp_dir().join(format!("biv_ocr_{}.png", std::process::id()));
+    image
+        .save(&input)
+        .map_err(|e| format!("OCR failed: {}", e))?;
+
+    let output = tesseract_command()
+        .arg(&input)
+        .arg("stdout")
+        .output()
+        .map_err(|e| format!("Could not run tesseract ({}); is it installed?", e));
+    let _ = std::fs::remove_file(&input);
+    let output = output?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "OCR failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+impl Ocr {
+    pub fn is_running(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    /// Recognizes text on a worker thread and puts it on the clipboard.
+    pub fn copy_text(&mut self, ctx: &egui::Context, path: PathBuf, region: Option<egui::Rect>) {
+        if self.is_running() {
+            return;
+        }
+
+        let ctx = ctx.clone();
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.status = None;
+
+        thread::spawn(move || {
+            let result = recognize(&path, region).and_then(|text| {
+                if text.is_empty() {
+                    return Err("No text recognized".to_string());
+                }
+                let count = text.chars().count();
+                clipboard::copy_text(text)?;
+                Ok(format!("Copied {} characters of text", count))
+            });
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn show_status(&mut self, ctx: &egui::Context) {
+        if let Some(rx) = &self.rx
+            && let Ok(result) = rx.try_recv()
+        {
+            match result {
+                Ok(msg) => osd::toast(ctx, msg),
+                Err(msg) => self.status = Some(msg),
+            }
+            self.rx = None;
+        }
+
+        if !self.is_running() && self.status.is_none() {
+            return;
+        }
+
+        egui::Area::new(egui::Id::new("ocr_status"))
+            .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(10.0, -40.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        if self.is_running() {
+                            ui.spinner();
+                            ui.label("Recognizing text…");
+                        } else if let Some(status) = &self.status {
+                            ui.label(status);
+                        }
+                    });
+                });
+            });
+    }
+
+    pub fn dismiss_status(&mut self) {
+        self.status = None;
+    }
+}
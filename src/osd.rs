@@ -0,0 +1,87 @@
+//! On-screen display: short toasts confirming actions ("Copied to clipboard", "Zoom 100%").
+//!
+//! Toasts are kept in the egui context, so any module with a `Context` can post one
+//! without threading a handle through the app.
+
+use crate::animation::exp_decay;
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+const TOAST_DURATION: Duration = Duration::from_millis(1800);
+// Older toasts are dropped when more pile up
+const MAX_TOASTS: usize = 4;
+
+#[derive(Clone)]
+struct Toast {
+    text: String,
+    posted: Instant,
+    opacity: f32,
+}
+
+fn toasts_id() -> egui::Id {
+    egui::Id::new("osd_toasts")
+}
+
+/// Shows `text` briefly. Posting the same text again restarts its timer.
+pub fn toast(ctx: &egui::Context, text: impl Into<String>) {
+    let text = text.into();
+    ctx.data_mut(|d| {
+        let toasts = d.get_temp_mut_or_default::<Vec<Toast>>(toasts_id());
+        let opacity = match toasts.iter().position(|t| t.text == text) {
+            Some(index) => toasts.remove(index).opacity,
+            None => 0.0,
+        };
+        toasts.push(Toast {
+            text,
+            posted: Instant::now(),
+            opacity,
+        });
+        if toasts.len() > MAX_TOASTS {
+            toasts.remove(0);
+        }
+    });
+    ctx.request_repaint();
+}
+
+/// Draws the pending toasts, fading them in and out; `opacity` scales all of them.
+pub fn show(ctx: &egui::Context, opacity: f32) {
+    let mut toasts: Vec<Toast> = ctx.data(|d| d.get_temp(toasts_id())).unwrap_or_default();
+    if toasts.is_empty() {
+        return;
+    }
+
+    let dt = ctx.input(|i| i.stable_dt).min(0.1);
+    let mut animating = false;
+    for toast in &mut toasts {
+        let target = if toast.posted.elapsed() < TOAST_DURATION {
+            1.0
+        } else {
+            0.0
+        };
+        animating |= exp_decay(&mut toast.opacity, target, dt, 12.0);
+    }
+    toasts.retain(|t| t.opacity > 0.0 || t.posted.elapsed() < TOAST_DURATION);
+    if animating {
+        ctx.request_repaint();
+    } else if let Some(next) = toasts.iter().map(|t| t.posted + TOAST_DURATION).min() {
+        ctx.request_repaint_after(next.saturating_duration_since(Instant::now()));
+    }
+
+    egui::Area::new(toasts_id())
+        .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -140.0))
+        .order(egui::Order::Tooltip)
+        .interactable(false)
+        .show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                for toast in &toasts {
+                    ui.scope(|ui| {
+                        ui.multiply_opacity(toast.opacity * opacity);
+                        egui::Frame::popup(ui.style())
+                            .rounding(12.0)
+                            .show(ui, |ui| ui.label(&toast.text));
+                    });
+                }
+            });
+        });
+    ctx.data_mut(|d| d.insert_temp(toasts_id(), toasts));
+}
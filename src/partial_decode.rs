@@ -0,0 +1,70 @@
+//! Best-effort decoding of truncated or damaged files.
+//!
+//! Half-downloaded JPEGs and PNGs usually still hold most of the picture. When the
+//! regular decoder gives up, whatever can be read is shown and the rest is left gray.
+
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use std::io::Cursor;
+
+const MISSING: Rgba<u8> = Rgba([128, 128, 128, 255]);
+
+/// Decodes as much of `bytes` as possible, or `None` if nothing is recoverable.
+pub fn decode(bytes: &[u8]) -> Option<DynamicImage> {
+    match image::guess_format(bytes).ok()? {
+        ImageFormat::Jpeg => decode_jpeg(bytes),
+        ImageFormat::Png => decode_png(bytes),
+        _ => None,
+    }
+}
+
+/// Truncated JPEGs decode once they're terminated again: the missing blocks have no
+/// coefficients, which the decoder renders as flat gray.
+fn decode_jpeg(bytes: &[u8]) -> Option<DynamicImage> {
+    const END_OF_IMAGE: [u8; 2] = [0xFF, 0xD9];
+    if bytes.ends_with(&END_OF_IMAGE) {
+        return None;
+    }
+    let mut terminated = bytes.to_vec();
+    terminated.extend_from_slice(&END_OF_IMAGE);
+    image::load_from_memory_with_format(&terminated, ImageFormat::Jpeg).ok()
+}
+
+/// Reads PNG rows until the data runs out or stops making sense.
+fn decode_png(bytes: &[u8]) -> Option<DynamicImage> {
+    let mut decoder = png::Decoder::new(Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    // Interlaced rows arrive in passes spread over the whole image; not worth piecing together
+    if reader.info().interlaced {
+        return None;
+    }
+
+    let (width, height) = (reader.info().width, reader.info().height);
+    let channels = match reader.output_color_type().0 {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => return None,
+    };
+
+    let mut image = RgbaImage::from_pixel(width, height, MISSING);
+    let mut y = 0;
+    while y < height
+        && let Ok(Some(row)) = reader.next_row()
+    {
+        for (x, pixel) in row.data().chunks_exact(channels).enumerate() {
+            let rgba = match *pixel {
+                [l] => [l, l, l, 255],
+                [l, a] => [l, l, l, a],
+                [r, g, b] => [r, g, b, 255],
+                [r, g, b, a] => [r, g, b, a],
+                _ => unreachable!(),
+            };
+            image.put_pixel(x as u32, y, Rgba(rgba));
+        }
+        y += 1;
+    }
+
+    (y > 0).then_some(DynamicImage::ImageRgba8(image))
+}
@@ -0,0 +1,88 @@
+//! PDF pages rendered through pdfium (`pdf` feature).
+//!
+//! pdfium is loaded at runtime, from next to the executable first and then from the
+//! system library path. Without it PDFs fail to open with an explanatory error.
+
+use image::DynamicImage;
+use pdfium_render::prelude::*;
+use std::path::Path;
+
+// Pixels per PDF point for the first render of a page (144 dpi)
+pub const DEFAULT_SCALE: f32 = 2.0;
+// Renders are kept below this size on the long side, however far the view zooms in
+const MAX_RENDER_SIZE: f32 = 8192.0;
+
+pub fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"))
+}
+
+/// Page and rendering scale of an open PDF, as last rendered.
+#[derive(Clone, Copy)]
+pub struct PdfView {
+    pub page: usize,
+    pub page_count: usize,
+    pub scale: f32,
+}
+
+pub struct RenderedPage {
+    pub image: DynamicImage,
+    pub page: usize,
+    pub page_count: usize,
+    // Pixels per point actually used, which the size cap may have lowered
+    pub scale: f32,
+}
+
+/// Renders single pages, binding to pdfium on first use. Lives on a loader thread.
+#[derive(Default)]
+pub struct PdfRenderer {
+    pdfium: Option<Pdfium>,
+}
+
+impl PdfRenderer {
+    fn pdfium(&mut self) -> Result<&Pdfium, String> {
+        if self.pdfium.is_none() {
+            let local = std::env::current_exe()
+                .ok()
+                .and_then(|exe| {
+                    exe.parent()
+                        .map(Pdfium::pdfium_platform_library_name_at_path)
+                })
+                .and_then(|library| Pdfium::bind_to_library(library).ok());
+            let bindings = match local {
+                Some(bindings) => bindings,
+                None => Pdfium::bind_to_system_library()
+                    .map_err(|e| format!("PDF support needs the pdfium library ({})", e))?,
+            };
+            self.pdfium = Some(Pdfium::new(bindings));
+        }
+        Ok(self.pdfium.as_ref().unwrap())
+    }
+
+    /// Renders `page` (0-based, clamped to the last page) at `scale` pixels per point.
+    pub fn render(&mut self, path: &Path, page: usize, scale: f32) -> Result<RenderedPage, String> {
+        let document = self
+            .pdfium()?
+            .load_pdf_from_file(path, None)
+            .map_err(|e| e.to_string())?;
+        let pages = document.pages();
+        let page_count = pages.len() as usize;
+        let index = page.min(page_count.saturating_sub(1));
+        let page = pages
+            .get(index as PdfPageIndex)
+            .map_err(|e| e.to_string())?;
+
+        let long_side = page.width().value.max(page.height().value).max(1.0);
+        let scale = scale.min(MAX_RENDER_SIZE / long_side);
+        let bitmap = page
+            .render_with_config(&PdfRenderConfig::new().scale_page_by_factor(scale))
+            .map_err(|e| e.to_string())?;
+
+        Ok(RenderedPage {
+            image: bitmap.as_image(),
+            page: index,
+            page_count,
+            scale,
+        })
+    }
+}
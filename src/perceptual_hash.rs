@@ -0,0 +1,198 @@
+use crate::image_loader::decode_image;
+use eframe::egui;
+use image::DynamicImage;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, channel};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// 64-bit difference hash (dHash) of an image.
+///
+/// The image is reduced to a 9x8 grayscale grid and each bit records whether a cell is
+/// brighter than its right neighbour. Resizing, recompression and small colour shifts
+/// barely change the hash, so visually similar images have a small Hamming distance.
+pub fn dhash(image: &DynamicImage) -> u64 {
+    let small = image
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y).0[0];
+            let right = small.get_pixel(x + 1, y).0[0];
+            hash = (hash << 1) | (left > right) as u64;
+        }
+    }
+    hash
+}
+
+/// Number of differing bits between two hashes (0 = identical, 64 = opposite).
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+pub struct HashResult {
+    pub path: PathBuf,
+    pub hash: Option<u64>,
+}
+
+/// Hashes a list of images on a small pool of background threads.
+///
+/// Results arrive on `rx` in completion order; `total` is the number of paths queued.
+pub struct HashScanner {
+    pub rx: Receiver<HashResult>,
+    pub total: usize,
+    pub done: usize,
+    cancel: Arc<AtomicBool>,
+}
+
+impl HashScanner {
+    /// Starts hashing `paths`.
+    pub fn start(ctx: &egui::Context, paths: Vec<PathBuf>) -> Self {
+        let (tx, rx) = channel();
+        let total = paths.len();
+        let queue = Arc::new(Mutex::new(paths));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let workers = thread::available_parallelism().map_or(2, |n| n.get()).min(8);
+
+        for _ in 0..workers {
+            let queue = queue.clone();
+            let cancel = cancel.clone();
+            let tx = tx.clone();
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                loop {
+                    let Some(path) = queue.lock().unwrap().pop() else {
+                        break;
+                    };
+                    if cancel.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    // A decoder panic still counts as processed, or the scan would never finish
+                    let hash = std::panic::catch_unwind(|| {
+                        decode_image(&path).ok().map(|image| dhash(&image))
+                    })
+                    .ok()
+                    .flatten();
+                    if tx.send(HashResult { path, hash }).is_err() {
+                        break;
+                    }
+                    ctx.request_repaint();
+                }
+            });
+        }
+
+        Self {
+            rx,
+            total,
+            done: 0,
+            cancel,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.done >= self.total
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.done as f32 / self.total as f32
+        }
+    }
+}
+
+impl Drop for HashScanner {
+    fn drop(&mut self) {
+        // Let the workers wind down when the scan is abandoned
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Orders images so each one is followed by the most similar remaining image.
+///
+/// This is a greedy nearest-neighbour walk starting at the first entry; images that
+/// couldn't be hashed keep their relative order at the end.
+pub fn similarity_order(hashes: &[(PathBuf, u64)], unhashed: &[PathBuf]) -> Vec<PathBuf> {
+    let mut remaining: Vec<&(PathBuf, u64)> = hashes.iter().collect();
+    let mut ordered = Vec::with_capacity(hashes.len() + unhashed.len());
+
+    if !remaining.is_empty() {
+        let mut current = remaining.remove(0);
+        ordered.push(current.0.clone());
+        while !remaining.is_empty() {
+            let (idx, _) = remaining
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (_, h))| distance(current.1, *h))
+                .unwrap();
+            current = remaining.remove(idx);
+            ordered.push(current.0.clone());
+        }
+    }
+
+    ordered.extend(unhashed.iter().cloned());
+    ordered
+}
+
+/// Hashes a folder in the background and produces a visual similarity ordering.
+pub struct SimilaritySort {
+    ctx: egui::Context,
+    scanner: HashScanner,
+    hashes: Vec<(PathBuf, u64)>,
+    unhashed: Vec<PathBuf>,
+    // The ordering walk is quadratic, so it runs on its own thread once hashing is done
+    order: Option<Receiver<Vec<PathBuf>>>,
+}
+
+impl SimilaritySort {
+    pub fn start(ctx: &egui::Context, paths: &[PathBuf]) -> Self {
+        Self {
+            ctx: ctx.clone(),
+            scanner: HashScanner::start(ctx, paths.to_vec()),
+            hashes: Vec::new(),
+            unhashed: Vec::new(),
+            order: None,
+        }
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.scanner.progress()
+    }
+
+    /// Collects finished hashes; returns the new order once every image is processed.
+    pub fn poll(&mut self) -> Option<Vec<PathBuf>> {
+        while let Ok(result) = self.scanner.rx.try_recv() {
+            self.scanner.done += 1;
+            match result.hash {
+                Some(hash) => self.hashes.push((result.path, hash)),
+                None => self.unhashed.push(result.path),
+            }
+        }
+
+        if let Some(rx) = &self.order {
+            return rx.try_recv().ok();
+        }
+        if !self.scanner.is_finished() {
+            return None;
+        }
+
+        let (tx, rx) = channel();
+        self.order = Some(rx);
+        let mut hashes = std::mem::take(&mut self.hashes);
+        let mut unhashed = std::mem::take(&mut self.unhashed);
+        let ctx = self.ctx.clone();
+        thread::spawn(move || {
+            // Workers finish in arbitrary order; start the walk from the alphabetically first image
+            hashes.sort();
+            unhashed.sort();
+            let _ = tx.send(similarity_order(&hashes, &unhashed));
+            ctx.request_repaint();
+        });
+        None
+    }
+}
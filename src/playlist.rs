@@ -0,0 +1,38 @@
+//! Plain text playlists: one image path per line, absolute or relative to the playlist.
+//!
+//! Empty lines and lines starting with `#` are ignored, so simple `.m3u` files work too.
+
+use std::path::{Path, PathBuf};
+
+pub const EXTENSIONS: &[&str] = &["m3u", "m3u8", "txt", "lst"];
+
+pub fn is_playlist(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Reads the playlist, skipping entries that don't point to an existing file.
+pub fn read(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let text = std::fs::read_to_string(path)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| base.join(line))
+        .filter(|p| p.is_file())
+        .collect())
+}
+
+/// Writes `images` to `path`, relative to the playlist's folder where possible.
+pub fn write(path: &Path, images: &[PathBuf]) -> std::io::Result<()> {
+    let base = path.parent().unwrap_or(Path::new(""));
+    let mut text = String::new();
+    for image in images {
+        let entry = image.strip_prefix(base).unwrap_or(image);
+        text.push_str(&entry.to_string_lossy());
+        text.push('\n');
+    }
+    std::fs::write(path, text)
+}
@@ -0,0 +1,222 @@
+//! "Present on display 2": the slideshow runs fullscreen in a borderless window on another
+//! monitor while the main window stays a normal viewer to steer it from.
+//!
+//! egui can't list monitors, so the display is picked by where it sits relative to the
+//! main window's monitor; the presentation window is opened there and made fullscreen.
+
+use crate::slideshow::Slideshow;
+use crate::view_state::ViewState;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Where the presentation monitor is, seen from the monitor of the main window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DisplaySide {
+    #[default]
+    Right,
+    Left,
+    Above,
+    Below,
+}
+
+impl DisplaySide {
+    const ALL: [DisplaySide; 4] = [
+        DisplaySide::Right,
+        DisplaySide::Left,
+        DisplaySide::Above,
+        DisplaySide::Below,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            DisplaySide::Right => "Display to the right",
+            DisplaySide::Left => "Display to the left",
+            DisplaySide::Above => "Display above",
+            DisplaySide::Below => "Display below",
+        }
+    }
+
+    fn direction(self) -> egui::Vec2 {
+        match self {
+            DisplaySide::Right => egui::vec2(1.0, 0.0),
+            DisplaySide::Left => egui::vec2(-1.0, 0.0),
+            DisplaySide::Above => egui::vec2(0.0, -1.0),
+            DisplaySide::Below => egui::vec2(0.0, 1.0),
+        }
+    }
+}
+
+pub enum PresentationAction {
+    None,
+    Stop,
+    // The display was changed in the control window and should be remembered
+    DisplayChanged(DisplaySide),
+}
+
+/// What the presentation shows this frame.
+pub struct Slide<'a> {
+    pub current: Option<&'a egui::TextureHandle>,
+    pub next_path: Option<&'a Path>,
+    pub next: Option<&'a egui::TextureHandle>,
+    pub ken_burns: bool,
+    pub interval: Duration,
+}
+
+#[derive(Default)]
+pub struct Presentation {
+    pub active: bool,
+    display: DisplaySide,
+    // Center of the window before it went fullscreen, in desktop points
+    position: Option<egui::Pos2>,
+    // Ken Burns motion runs here rather than in the main window's view
+    view_state: ViewState,
+}
+
+impl Presentation {
+    fn viewport_id() -> egui::ViewportId {
+        egui::ViewportId::from_hash_of("presentation")
+    }
+
+    pub fn start(&mut self, ctx: &egui::Context, display: DisplaySide) {
+        self.active = true;
+        self.display = display;
+        self.position = Self::target_center(ctx, display);
+        self.view_state = ViewState::default();
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    /// A point one monitor away from the main window, in the direction of `display`.
+    fn target_center(ctx: &egui::Context, display: DisplaySide) -> Option<egui::Pos2> {
+        ctx.input(|i| {
+            let viewport = i.viewport();
+            let window = viewport.outer_rect?;
+            let monitor = viewport.monitor_size?;
+            Some(window.center() + display.direction() * monitor)
+        })
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        slideshow: &mut Slideshow,
+        slide: Slide,
+    ) -> PresentationAction {
+        if !self.active {
+            return PresentationAction::None;
+        }
+        let mut action = PresentationAction::None;
+
+        let size = egui::vec2(640.0, 360.0);
+        let mut builder = egui::ViewportBuilder::default()
+            .with_title("Better Image Viewer - Presentation")
+            .with_decorations(false)
+            .with_inner_size(size)
+            .with_fullscreen(true);
+        if let Some(center) = self.position {
+            builder = builder.with_position(center - size / 2.0);
+        }
+
+        ctx.show_viewport_immediate(Self::viewport_id(), builder, |ctx, _class| {
+            egui::CentralPanel::default()
+                .frame(egui::Frame::none().fill(egui::Color32::BLACK))
+                .show(ctx, |ui| {
+                    let Some(texture) = slide.current else {
+                        return;
+                    };
+                    let viewport = ui.max_rect();
+                    let image_size = texture.size_vec2();
+                    if slide.ken_burns {
+                        slideshow.animate(
+                            ctx,
+                            &mut self.view_state,
+                            viewport,
+                            image_size,
+                            slide.interval,
+                        );
+                        self.view_state.process_input(ui, image_size);
+                    } else {
+                        // Fill the screen, upscaling small images too
+                        let zoom = (viewport.width() / image_size.x.max(1.0))
+                            .min(viewport.height() / image_size.y.max(1.0));
+                        self.view_state.restore(zoom, egui::Vec2::ZERO);
+                    }
+                    ui.painter().image(
+                        texture.id(),
+                        self.view_state.image_rect(viewport, image_size),
+                        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                        egui::Color32::WHITE,
+                    );
+                });
+            if ctx.input(|i| i.viewport().close_requested() || i.key_pressed(egui::Key::Escape)) {
+                action = PresentationAction::Stop;
+            }
+        });
+
+        // Control surface in the main window
+        let mut open = true;
+        egui::Window::new("Presenting")
+            .open(&mut open)
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10.0, 10.0))
+            .resizable(false)
+            .show(ctx, |ui| {
+                let mut display = self.display;
+                egui::ComboBox::from_id_salt("presentation_display")
+                    .selected_text(display.label())
+                    .show_ui(ui, |ui| {
+                        for side in DisplaySide::ALL {
+                            ui.selectable_value(&mut display, side, side.label());
+                        }
+                    });
+                if display != self.display {
+                    self.move_to(ctx, display);
+                    action = PresentationAction::DisplayChanged(display);
+                }
+
+                ui.separator();
+                ui.label("Next up");
+                match slide.next {
+                    Some(texture) => {
+                        ui.add(egui::Image::new(texture).max_width(200.0).max_height(150.0));
+                    }
+                    None => {
+                        ui.allocate_ui(egui::vec2(200.0, 150.0), |ui| {
+                            ui.centered_and_justified(|ui| ui.spinner());
+                        });
+                    }
+                }
+                if let Some(name) = slide.next_path.and_then(|p| p.file_name()) {
+                    ui.weak(name.to_string_lossy());
+                }
+
+                ui.separator();
+                if ui.button("Stop presenting").clicked() {
+                    action = PresentationAction::Stop;
+                }
+            });
+        if !open {
+            action = PresentationAction::Stop;
+        }
+        action
+    }
+
+    /// Moves the presentation to another display, leaving fullscreen while it travels.
+    fn move_to(&mut self, ctx: &egui::Context, display: DisplaySide) {
+        self.display = display;
+        self.position = Self::target_center(ctx, display);
+        let Some(center) = self.position else {
+            return;
+        };
+        let id = Self::viewport_id();
+        ctx.send_viewport_cmd_to(id, egui::ViewportCommand::Fullscreen(false));
+        ctx.send_viewport_cmd_to(
+            id,
+            egui::ViewportCommand::OuterPosition(center - egui::vec2(320.0, 180.0)),
+        );
+        ctx.send_viewport_cmd_to(id, egui::ViewportCommand::Fullscreen(true));
+    }
+}
@@ -0,0 +1,399 @@
+use crate::annotations::ExportTarget;
+use crate::clipboard;
+use crate::image_loader::decode_image;
+use crate::osd;
+use crate::view_state::ViewState;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RedactMode {
+    Pixelate,
+    Blur,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum RedactShape {
+    Rect,
+    Brush,
+}
+
+#[derive(Clone)]
+struct RedactJob {
+    path: PathBuf,
+    // Regions in image pixels
+    regions: Vec<egui::Rect>,
+    mode: RedactMode,
+    strength: u32,
+}
+
+enum RedactCommand {
+    Preview(RedactJob),
+    Export(RedactJob, ExportTarget),
+}
+
+enum RedactResult {
+    Preview(PathBuf, egui::ColorImage),
+    Exported(Result<String, String>),
+}
+
+/// Pixelate/blur redaction of image regions, for sharing screenshots safely.
+///
+/// The effect is computed on the decoded pixels by a worker thread; the UI shows the
+/// worker's preview on top of the image and exports exactly that buffer.
+pub struct Redaction {
+    pub enabled: bool,
+    mode: RedactMode,
+    shape: RedactShape,
+    strength: u32,
+    // Brush diameter in screen points
+    brush_size: f32,
+
+    regions: Vec<egui::Rect>,
+    drag_start: Option<egui::Pos2>,
+
+    tx: Sender<RedactCommand>,
+    rx: Receiver<RedactResult>,
+    preview: Option<(PathBuf, egui::TextureHandle)>,
+    dirty: bool,
+    status: Option<String>,
+}
+
+impl Redaction {
+    pub fn new(ctx: &egui::Context) -> Self {
+        let (tx_ui, rx_worker) = channel::<RedactCommand>();
+        let (tx_worker, rx_ui) = channel::<RedactResult>();
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            // Keep the last decode around so re-previewing while editing is cheap
+            let mut cached: Option<(PathBuf, image::RgbaImage)> = None;
+
+            while let Ok(mut cmd) = rx_worker.recv() {
+                // Only the newest preview matters when several queued up during a drag
+                while let RedactCommand::Preview(_) = cmd {
+                    match rx_worker.try_recv() {
+                        Ok(next) => cmd = next,
+                        Err(_) => break,
+                    }
+                }
+
+                let job = match &cmd {
+                    RedactCommand::Preview(job) | RedactCommand::Export(job, _) => job.clone(),
+                };
+                if cached.as_ref().is_none_or(|(p, _)| p != &job.path) {
+                    match decode_image(&job.path) {
+                        Ok(image) => cached = Some((job.path.clone(), image.to_rgba8())),
+                        Err(e) => {
+                            let _ = tx_worker.send(RedactResult::Exported(Err(format!(
+                                "Redaction failed: {}",
+                                e
+                            ))));
+                            continue;
+                        }
+                    }
+                }
+                let Some((_, original)) = &cached else {
+                    continue;
+                };
+
+                let mut image = original.clone();
+                for region in &job.regions {
+                    apply(&mut image, original, *region, job.mode, job.strength);
+                }
+
+                let result = match cmd {
+                    RedactCommand::Preview(_) => {
+                        let size = [image.width() as usize, image.height() as usize];
+                        RedactResult::Preview(
+                            job.path,
+                            egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()),
+                        )
+                    }
+                    RedactCommand::Export(_, ExportTarget::File(path)) => RedactResult::Exported(
+                        image
+                            .save(&path)
+                            .map(|_| format!("Saved {}", path.display()))
+                            .map_err(|e| format!("Export failed: {}", e)),
+                    ),
+                    RedactCommand::Export(_, ExportTarget::Clipboard) => RedactResult::Exported(
+                        clipboard::copy_image(&image).map(|_| "Copied to clipboard".to_string()),
+                    ),
+                };
+                let _ = tx_worker.send(result);
+                ctx.request_repaint();
+            }
+        });
+
+        Self {
+            enabled: false,
+            mode: RedactMode::Pixelate,
+            shape: RedactShape::Rect,
+            strength: 12,
+            brush_size: 30.0,
+            regions: Vec::new(),
+            drag_start: None,
+            tx: tx_ui,
+            rx: rx_ui,
+            preview: None,
+            dirty: false,
+            status: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.regions.clear();
+        self.drag_start = None;
+        self.preview = None;
+        self.dirty = false;
+    }
+
+    fn job(&self, path: PathBuf) -> RedactJob {
+        RedactJob {
+            path,
+            regions: self.regions.clone(),
+            mode: self.mode,
+            strength: self.strength,
+        }
+    }
+
+    pub fn process_input(
+        &mut self,
+        ui: &egui::Ui,
+        view_state: &ViewState,
+        image_size: egui::Vec2,
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        let viewport = ui.clip_rect();
+        let wants_pointer = ui.ctx().wants_pointer_input() || ui.ctx().is_pointer_over_area();
+        let (pressed, down, pos, ctrl) = ui.input(|i| {
+            (
+                i.pointer.button_pressed(egui::PointerButton::Primary),
+                i.pointer.button_down(egui::PointerButton::Primary),
+                i.pointer.interact_pos(),
+                i.modifiers.command,
+            )
+        });
+
+        if ctrl && ui.input(|i| i.key_pressed(egui::Key::Z)) && self.regions.pop().is_some() {
+            self.dirty = true;
+        }
+
+        let Some(pos) = pos else {
+            return;
+        };
+        let p = view_state.screen_to_image(viewport, image_size, pos);
+
+        if pressed && !ctrl && !wants_pointer {
+            self.drag_start = Some(p);
+        }
+
+        if let Some(start) = self.drag_start {
+            if self.shape == RedactShape::Brush {
+                // Stamp a square whenever the cursor moved far enough along the stroke
+                let size = self.brush_size / view_state.zoom;
+                if self.regions.last().is_none_or(|r| r.center().distance(p) > size / 4.0) {
+                    self.regions.push(egui::Rect::from_center_size(p, egui::Vec2::splat(size)));
+                    self.dirty = true;
+                }
+            }
+            if !down {
+                self.drag_start = None;
+                if self.shape == RedactShape::Rect {
+                    let rect = egui::Rect::from_two_pos(start, p);
+                    if rect.width() >= 1.0 && rect.height() >= 1.0 {
+                        self.regions.push(rect);
+                        self.dirty = true;
+                    }
+                }
+            }
+            ui.ctx().request_repaint();
+        }
+    }
+
+    /// Draws the worker's redacted preview over `image_rect` and outlines pending regions.
+    pub fn paint(
+        &mut self,
+        ui: &egui::Ui,
+        view_state: &ViewState,
+        image_size: egui::Vec2,
+        current_path: &Path,
+    ) {
+        while let Ok(result) = self.rx.try_recv() {
+            match result {
+                RedactResult::Preview(path, image) => {
+                    let texture =
+                        ui.ctx()
+                            .load_texture("redaction_preview", image, egui::TextureOptions::LINEAR);
+                    self.preview = Some((path, texture));
+                }
+                RedactResult::Exported(result) => {
+                    match result {
+                        Ok(msg) => osd::toast(ui.ctx(), msg),
+                        Err(msg) => self.status = Some(msg),
+                    }
+                }
+            }
+        }
+
+        if !self.enabled && self.regions.is_empty() {
+            return;
+        }
+
+        if std::mem::take(&mut self.dirty) {
+            let _ = self.tx.send(RedactCommand::Preview(self.job(current_path.to_path_buf())));
+        }
+
+        let viewport = ui.clip_rect();
+        if let Some((path, texture)) = &self.preview
+            && path == current_path
+            && !self.regions.is_empty()
+        {
+            ui.painter().image(
+                texture.id(),
+                view_state.image_rect(viewport, image_size),
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE,
+            );
+        }
+
+        if let Some(start) = self.drag_start
+            && self.shape == RedactShape::Rect
+            && let Some(pos) = ui.input(|i| i.pointer.latest_pos())
+        {
+            ui.painter().rect_stroke(
+                egui::Rect::from_two_pos(
+                    view_state.image_to_screen(viewport, image_size, start),
+                    pos,
+                ),
+                0.0,
+                egui::Stroke::new(1.0, egui::Color32::RED),
+            );
+        }
+    }
+
+    /// Toolbar for redaction settings and export.
+    pub fn toolbar(&mut self, ctx: &egui::Context, current_path: Option<&Path>) {
+        if !self.enabled {
+            return;
+        }
+
+        egui::Area::new(egui::Id::new("redaction_toolbar"))
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 10.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.shape, RedactShape::Rect, "Rect");
+                        ui.selectable_value(&mut self.shape, RedactShape::Brush, "Brush");
+                        ui.separator();
+                        let mut changed = ui
+                            .selectable_value(&mut self.mode, RedactMode::Pixelate, "Pixelate")
+                            .changed();
+                        changed |= ui
+                            .selectable_value(&mut self.mode, RedactMode::Blur, "Blur")
+                            .changed();
+                        changed |= ui
+                            .add(egui::Slider::new(&mut self.strength, 2..=64).text("strength"))
+                            .changed();
+                        if self.shape == RedactShape::Brush {
+                            ui.add(egui::Slider::new(&mut self.brush_size, 5.0..=120.0).text("brush"));
+                        }
+                        self.dirty |= changed;
+                        ui.separator();
+                        if ui.add_enabled(!self.regions.is_empty(), egui::Button::new("Undo")).clicked() {
+                            self.regions.pop();
+                            self.dirty = true;
+                        }
+
+                        let can_export = !self.regions.is_empty() && current_path.is_some();
+                        if let Some(path) = current_path {
+                            if ui.add_enabled(can_export, egui::Button::new("Save PNG…")).clicked()
+                                && let Some(target) = rfd::FileDialog::new()
+                                    .add_filter("PNG", &["png"])
+                                    .set_file_name("redacted.png")
+                                    .save_file()
+                            {
+                                let _ = self.tx.send(RedactCommand::Export(
+                                    self.job(path.to_path_buf()),
+                                    ExportTarget::File(target),
+                                ));
+                            }
+                            if ui.add_enabled(can_export, egui::Button::new("Copy")).clicked() {
+                                let _ = self.tx.send(RedactCommand::Export(
+                                    self.job(path.to_path_buf()),
+                                    ExportTarget::Clipboard,
+                                ));
+                            }
+                        }
+                        if let Some(status) = &self.status {
+                            ui.label(status);
+                        }
+                    });
+                });
+            });
+    }
+}
+
+/// Clamps an image-space rect to whole pixels inside the image.
+fn pixel_bounds(image: &image::RgbaImage, region: egui::Rect) -> Option<(u32, u32, u32, u32)> {
+    let x0 = region.min.x.floor().max(0.0) as u32;
+    let y0 = region.min.y.floor().max(0.0) as u32;
+    let x1 = (region.max.x.ceil().max(0.0) as u32).min(image.width());
+    let y1 = (region.max.y.ceil().max(0.0) as u32).min(image.height());
+    (x0 < x1 && y0 < y1).then_some((x0, y0, x1, y1))
+}
+
+fn apply(
+    image: &mut image::RgbaImage,
+    original: &image::RgbaImage,
+    region: egui::Rect,
+    mode: RedactMode,
+    strength: u32,
+) {
+    let Some((x0, y0, x1, y1)) = pixel_bounds(image, region) else {
+        return;
+    };
+
+    match mode {
+        RedactMode::Pixelate => {
+            // Blocks are aligned to the image grid so overlapping brush stamps agree
+            let block = strength.max(2);
+            let mut by = y0 / block * block;
+            while by < y1 {
+                let mut bx = x0 / block * block;
+                while bx < x1 {
+                    let (ex, ey) = ((bx + block).min(image.width()), (by + block).min(image.height()));
+                    let mut sum = [0u64; 4];
+                    for y in by..ey {
+                        for x in bx..ex {
+                            for (s, c) in sum.iter_mut().zip(original.get_pixel(x, y).0) {
+                                *s += c as u64;
+                            }
+                        }
+                    }
+                    let count = ((ex - bx) * (ey - by)) as u64;
+                    let avg = image::Rgba(sum.map(|s| (s / count) as u8));
+                    for y in by.max(y0)..ey.min(y1) {
+                        for x in bx.max(x0)..ex.min(x1) {
+                            image.put_pixel(x, y, avg);
+                        }
+                    }
+                    bx += block;
+                }
+                by += block;
+            }
+        }
+        RedactMode::Blur => {
+            // Blur the untouched original so overlapping brush stamps don't compound
+            let mut sub = image::imageops::crop_imm(original, x0, y0, x1 - x0, y1 - y0).to_image();
+            let radius = strength as f32 / 2.0;
+            sub = image::imageops::blur(&sub, radius);
+            image::imageops::replace(image, &sub, x0 as i64, y0 as i64);
+        }
+    }
+}
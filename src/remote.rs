@@ -0,0 +1,284 @@
+//! Remote control of a running viewer over a per-user socket (and optionally stdin).
+//!
+//! On Unix the viewer listens on a socket in the user's data folder; elsewhere it listens
+//! on a localhost TCP port. Either way a client must first send the token the viewer
+//! wrote to `remote.token` next to it, so other users and web pages can't drive it.
+//!
+//! After that, one command per line, either plain text (`open <path>`, `next`, `prev`,
+//! `fullscreen`, `quit`) or JSON (`{"command": "open", "path": "..."}`). Every line gets
+//! an `ok` or `error: ...` reply, and the first line that doesn't parse ends the connection.
+
+use crate::config;
+use eframe::egui;
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(not(unix))]
+pub const DEFAULT_PORT: u16 = 47811;
+
+const TOKEN_FILE: &str = "remote.token";
+#[cfg(unix)]
+const SOCKET_FILE: &str = "remote.sock";
+
+pub enum RemoteCommand {
+    Open(PathBuf),
+    Next,
+    Prev,
+    Fullscreen,
+    Quit,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct RemoteOptions {
+    /// Listen on the local socket.
+    pub enabled: bool,
+    /// Also read commands from standard input.
+    pub stdin: bool,
+}
+
+#[derive(Deserialize)]
+struct JsonCommand {
+    command: String,
+    path: Option<PathBuf>,
+}
+
+/// Port used by the viewer, overridable with `BIV_REMOTE_PORT`.
+#[cfg(not(unix))]
+pub fn port() -> u16 {
+    std::env::var("BIV_REMOTE_PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(DEFAULT_PORT)
+}
+
+pub fn parse(line: &str) -> Result<RemoteCommand, String> {
+    let line = line.trim();
+    let (command, path) = if line.starts_with('{') {
+        let json: JsonCommand = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        (json.command, json.path)
+    } else {
+        let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+        let arg = arg.trim();
+        (command.to_string(), (!arg.is_empty()).then(|| PathBuf::from(arg)))
+    };
+
+    match command.to_lowercase().as_str() {
+        "open" => path
+            .map(RemoteCommand::Open)
+            .ok_or_else(|| "open needs a path".to_string()),
+        "next" => Ok(RemoteCommand::Next),
+        "prev" => Ok(RemoteCommand::Prev),
+        "fullscreen" => Ok(RemoteCommand::Fullscreen),
+        "quit" => Ok(RemoteCommand::Quit),
+        other => Err(format!("unknown command '{}'", other)),
+    }
+}
+
+fn token_path() -> std::io::Result<PathBuf> {
+    config::data_dir()
+        .map(|dir| dir.join(TOKEN_FILE))
+        .ok_or_else(|| std::io::Error::other("no data folder for the remote token"))
+}
+
+/// Writes a fresh random token that clients must send before any command.
+fn write_token() -> std::io::Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(std::io::Error::other)?;
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    let path = token_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(&path)?.write_all(token.as_bytes())?;
+    Ok(token)
+}
+
+/// Answers command lines until the peer hangs up.
+///
+/// With a `token`, the first line must match it and any line that fails to parse drops
+/// the connection; without one (stdin) errors are only reported.
+fn serve_lines(
+    reader: impl BufRead,
+    mut reply: impl Write,
+    tx: &Sender<RemoteCommand>,
+    ctx: &egui::Context,
+    token: Option<&str>,
+) {
+    let mut lines = reader.lines();
+    if let Some(token) = token {
+        match lines.next() {
+            Some(Ok(line)) if line.trim() == token => {}
+            _ => {
+                let _ = writeln!(reply, "error: bad token");
+                return;
+            }
+        }
+    }
+
+    for line in lines {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (response, keep) = match parse(&line) {
+            Ok(command) => {
+                if tx.send(command).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+                ("ok".to_string(), true)
+            }
+            Err(e) => (format!("error: {}", e), token.is_none()),
+        };
+        if writeln!(reply, "{}", response).is_err() || !keep {
+            break;
+        }
+    }
+}
+
+#[cfg(unix)]
+fn socket_path() -> std::io::Result<PathBuf> {
+    config::data_dir()
+        .map(|dir| dir.join(SOCKET_FILE))
+        .ok_or_else(|| std::io::Error::other("no data folder for the remote socket"))
+}
+
+#[cfg(unix)]
+type Listener = std::os::unix::net::UnixListener;
+#[cfg(not(unix))]
+type Listener = std::net::TcpListener;
+
+#[cfg(unix)]
+type Stream = std::os::unix::net::UnixStream;
+#[cfg(not(unix))]
+type Stream = std::net::TcpStream;
+
+#[cfg(unix)]
+fn listen() -> std::io::Result<Listener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = socket_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // A socket file nobody answers on is left over from a crash
+    if path.exists() {
+        if Stream::connect(&path).is_ok() {
+            return Err(std::io::ErrorKind::AddrInUse.into());
+        }
+        std::fs::remove_file(&path)?;
+    }
+    let listener = Listener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+#[cfg(not(unix))]
+fn listen() -> std::io::Result<Listener> {
+    Listener::bind((std::net::Ipv4Addr::LOCALHOST, port()))
+}
+
+#[cfg(unix)]
+fn connect() -> std::io::Result<Stream> {
+    Stream::connect(socket_path()?)
+}
+
+#[cfg(not(unix))]
+fn connect() -> std::io::Result<Stream> {
+    Stream::connect((std::net::Ipv4Addr::LOCALHOST, port()))
+}
+
+pub struct RemoteServer {
+    rx: Receiver<RemoteCommand>,
+}
+
+impl RemoteServer {
+    pub fn start(ctx: &egui::Context, options: RemoteOptions) -> std::io::Result<Self> {
+        let (tx, rx) = channel();
+
+        if options.enabled {
+            let listener = listen()?;
+            let token = write_token()?;
+            let tx = tx.clone();
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let tx = tx.clone();
+                    let ctx = ctx.clone();
+                    let token = token.clone();
+                    thread::spawn(move || {
+                        if let Ok(reader) = stream.try_clone() {
+                            serve_lines(BufReader::new(reader), stream, &tx, &ctx, Some(&token));
+                        }
+                    });
+                }
+            });
+        }
+
+        if options.stdin {
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                serve_lines(std::io::stdin().lock(), std::io::stdout(), &tx, &ctx, None);
+            });
+        }
+
+        Ok(Self { rx })
+    }
+
+    pub fn poll(&self) -> Option<RemoteCommand> {
+        self.rx.try_recv().ok()
+    }
+}
+
+/// Sends one command line to a running viewer and returns its reply.
+pub fn send(line: &str) -> std::io::Result<String> {
+    let token = std::fs::read_to_string(token_path()?)?;
+    let mut stream = connect()?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    writeln!(stream, "{}", token.trim())?;
+    writeln!(stream, "{}", line)?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(reply.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serve(input: &str, token: Option<&str>) -> (String, usize) {
+        let (tx, rx) = channel();
+        let mut reply = Vec::new();
+        serve_lines(input.as_bytes(), &mut reply, &tx, &egui::Context::default(), token);
+        (String::from_utf8(reply).unwrap(), rx.try_iter().count())
+    }
+
+    #[test]
+    fn socket_needs_token_first() {
+        let (reply, sent) = serve("POST / HTTP/1.1\nopen /x\nquit\n", Some("secret"));
+        assert_eq!(reply, "error: bad token\n");
+        assert_eq!(sent, 0);
+
+        let (reply, sent) = serve("secret\nnext\nbogus\nquit\n", Some("secret"));
+        assert_eq!(reply, "ok\nerror: unknown command 'bogus'\n");
+        assert_eq!(sent, 1);
+    }
+
+    #[test]
+    fn stdin_keeps_going_after_errors() {
+        let (reply, sent) = serve("bogus\nnext\n", None);
+        assert_eq!(reply, "error: unknown command 'bogus'\nok\n");
+        assert_eq!(sent, 1);
+    }
+}
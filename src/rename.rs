@@ -0,0 +1,419 @@
+//! Batch rename of the folder or the selection from a pattern such as
+//! `{date}_{counter:04}_{name}`, with a live preview and undo of the last run.
+
+use crate::metadata::capture_time;
+use crate::timeline::civil_from_days;
+use eframe::egui;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const TOKENS: &str = "{name} {date} {time} {counter} {counter:04}";
+
+pub enum RenameAction {
+    None,
+    // Old and new paths of the files renamed
+    Renamed(Vec<(PathBuf, PathBuf)>),
+}
+
+pub struct RenameWindow {
+    pub open: bool,
+    pattern: String,
+    start: usize,
+    selection_only: bool,
+    // Capture date and time of each file, read once for the preview
+    dates: HashMap<PathBuf, (String, String)>,
+    // What the last run did, new paths first, for undo
+    last: Vec<(PathBuf, PathBuf)>,
+    status: Option<String>,
+}
+
+impl Default for RenameWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            pattern: "{date}_{counter:04}_{name}".to_string(),
+            start: 1,
+            selection_only: true,
+            dates: HashMap::new(),
+            last: Vec::new(),
+            status: None,
+        }
+    }
+}
+
+struct Planned {
+    from: PathBuf,
+    to: PathBuf,
+    // Another file already has (or would get) the new name
+    conflict: bool,
+}
+
+/// Capture date (`2024-05-31`) and time (`142501`) from EXIF, or else the file's
+/// modification time (UTC).
+fn capture_date(path: &Path) -> (String, String) {
+    if let Some(d) = capture_time(path) {
+        return (
+            format!("{:04}-{:02}-{:02}", d.year, d.month, d.day),
+            format!("{:02}{:02}{:02}", d.hour, d.minute, d.second),
+        );
+    }
+
+    let seconds = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs() as i64);
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let time = seconds.rem_euclid(86_400);
+    (
+        format!("{:04}-{:02}-{:02}", year, month, day),
+        format!("{:02}{:02}{:02}", time / 3600, time / 60 % 60, time % 60),
+    )
+}
+
+/// Fills in the pattern for one file. `{counter:N}` pads the counter to N digits.
+fn render(
+    pattern: &str,
+    name: &str,
+    (date, time): &(String, String),
+    counter: usize,
+) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = pattern;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let close = rest[open..].find('}').ok_or("Unclosed { in the pattern")?;
+        let token = &rest[open + 1..open + close];
+        match token.split_once(':') {
+            None if token == "name" => out.push_str(name),
+            None if token == "date" => out.push_str(date),
+            None if token == "time" => out.push_str(time),
+            None if token == "counter" => out.push_str(&counter.to_string()),
+            Some(("counter", width)) => {
+                let width: usize = width
+                    .parse()
+                    .map_err(|_| format!("Bad counter width in {{{}}}", token))?;
+                out.push_str(&format!("{:0width$}", counter, width = width));
+            }
+            _ => return Err(format!("Unknown token {{{}}}", token)),
+        }
+        rest = &rest[open + close + 1..];
+    }
+    out.push_str(rest);
+
+    if out.trim().is_empty() {
+        return Err("The pattern gives empty names".to_string());
+    }
+    if out.contains(['/', '\\']) {
+        return Err("Names can't contain path separators".to_string());
+    }
+    Ok(out)
+}
+
+/// New names for `paths` (keeping extensions), each checked against the others and
+/// against files already in the folder.
+fn plan(
+    pattern: &str,
+    paths: &[PathBuf],
+    start: usize,
+    dates: &HashMap<PathBuf, (String, String)>,
+    exists: impl Fn(&Path) -> bool,
+) -> Result<Vec<Planned>, String> {
+    let mut planned = Vec::with_capacity(paths.len());
+    for (i, path) in paths.iter().enumerate() {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let date = dates.get(path).cloned().unwrap_or_default();
+        let mut name = render(pattern, &stem, &date, start + i)?;
+        if let Some(extension) = path.extension() {
+            name = format!("{}.{}", name, extension.to_string_lossy());
+        }
+        planned.push(Planned {
+            from: path.clone(),
+            to: path.with_file_name(name),
+            conflict: false,
+        });
+    }
+
+    let sources: HashSet<&PathBuf> = paths.iter().collect();
+    let mut counts: HashMap<PathBuf, usize> = HashMap::new();
+    for p in &planned {
+        *counts.entry(p.to.clone()).or_default() += 1;
+    }
+    for p in &mut planned {
+        // Files being renamed themselves free up their names
+        p.conflict = counts[&p.to] > 1 || (!sources.contains(&p.to) && exists(&p.to));
+    }
+    Ok(planned)
+}
+
+/// Renames `from` to `to` unless something already has that name.
+fn rename_free(from: &Path, to: &Path) -> std::io::Result<()> {
+    if to.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "already exists",
+        ));
+    }
+    std::fs::rename(from, to)
+}
+
+/// Renames `pairs` (from, to) through temporary names, so names can be swapped or
+/// shifted along without one rename overwriting the next file. Returns what was done.
+///
+/// The batch is all or nothing: if any rename fails, every file is put back under its
+/// old name, and files that can't be put back are reported with where they were left.
+fn rename_all(pairs: &[(PathBuf, PathBuf)]) -> (Vec<(PathBuf, PathBuf)>, Vec<String>) {
+    let mut errors = Vec::new();
+    let mut staged = Vec::new();
+    for (i, (from, to)) in pairs.iter().enumerate() {
+        if from == to {
+            continue;
+        }
+        let temp = from.with_file_name(format!(".biv_rename_{}_{}", std::process::id(), i));
+        match rename_free(from, &temp) {
+            Ok(()) => staged.push((from, temp, to)),
+            Err(e) => {
+                errors.push(format!("{}: {}", from.display(), e));
+                break;
+            }
+        }
+    }
+
+    // The target may have appeared since the preview
+    let mut moved = 0;
+    if errors.is_empty() {
+        for (_, temp, to) in &staged {
+            if let Err(e) = rename_free(temp, to) {
+                errors.push(format!("{}: {}", to.display(), e));
+                break;
+            }
+            moved += 1;
+        }
+    }
+    if errors.is_empty() {
+        let done = staged
+            .into_iter()
+            .map(|(from, _, to)| (from.clone(), to.clone()))
+            .collect();
+        return (done, errors);
+    }
+
+    // Undo in reverse so every old name is free again before it's reused
+    for (_, temp, to) in staged[..moved].iter().rev() {
+        if let Err(e) = rename_free(to, temp) {
+            errors.push(format!("could not undo {}: {}", to.display(), e));
+        }
+    }
+    for (from, temp, _) in staged.iter().rev() {
+        if temp.exists()
+            && let Err(e) = rename_free(temp, from)
+        {
+            errors.push(format!(
+                "could not restore {} (left as {}): {}",
+                from.display(),
+                temp.display(),
+                e
+            ));
+        }
+    }
+    (Vec::new(), errors)
+}
+
+impl RenameWindow {
+    fn run(&mut self, pairs: &[(PathBuf, PathBuf)], verb: &str) -> RenameAction {
+        let (done, errors) = rename_all(pairs);
+        self.status = Some(if errors.is_empty() {
+            format!("{} {} files", verb, done.len())
+        } else {
+            format!("{} {} files; {}", verb, done.len(), errors.join("; "))
+        });
+        for (from, to) in &done {
+            if let Some(date) = self.dates.remove(from) {
+                self.dates.insert(to.clone(), date);
+            }
+        }
+        if done.is_empty() {
+            RenameAction::None
+        } else {
+            RenameAction::Renamed(done)
+        }
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        folder_images: &[PathBuf],
+        selected: Vec<PathBuf>,
+    ) -> RenameAction {
+        let mut action = RenameAction::None;
+        if !self.open {
+            return action;
+        }
+
+        let paths = if self.selection_only && !selected.is_empty() {
+            selected
+        } else {
+            folder_images.to_vec()
+        };
+        if self.pattern.contains("{date") || self.pattern.contains("{time") {
+            for path in &paths {
+                if !self.dates.contains_key(path) {
+                    self.dates.insert(path.clone(), capture_date(path));
+                }
+            }
+        }
+        let planned = plan(&self.pattern, &paths, self.start, &self.dates, Path::exists);
+
+        let mut open = self.open;
+        egui::Window::new("Batch Rename")
+            .open(&mut open)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Pattern:");
+                    ui.add(egui::TextEdit::singleline(&mut self.pattern).desired_width(260.0))
+                        .on_hover_text(TOKENS);
+                    ui.label("from");
+                    ui.add(egui::DragValue::new(&mut self.start).range(0..=999_999));
+                });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.selection_only, "Selected images only");
+                    ui.weak(format!("{} files", paths.len()));
+                });
+                ui.weak(format!("Tokens: {}", TOKENS));
+                ui.separator();
+
+                let conflicts = match &planned {
+                    Ok(planned) => {
+                        egui::ScrollArea::vertical()
+                            .max_height(300.0)
+                            .show(ui, |ui| {
+                                egui::Grid::new("rename_preview")
+                                    .striped(true)
+                                    .show(ui, |ui| {
+                                        for p in planned {
+                                            ui.label(
+                                                p.from
+                                                    .file_name()
+                                                    .unwrap_or_default()
+                                                    .to_string_lossy(),
+                                            );
+                                            let name =
+                                                p.to.file_name()
+                                                    .unwrap_or_default()
+                                                    .to_string_lossy();
+                                            if p.conflict {
+                                                ui.colored_label(ui.visuals().error_fg_color, name)
+                                                    .on_hover_text("Name already taken");
+                                            } else {
+                                                ui.label(name);
+                                            }
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                        planned.iter().filter(|p| p.conflict).count()
+                    }
+                    Err(msg) => {
+                        ui.colored_label(ui.visuals().error_fg_color, msg);
+                        0
+                    }
+                };
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let ready = planned.as_ref().is_ok_and(|p| !p.is_empty()) && conflicts == 0;
+                    if ui.add_enabled(ready, egui::Button::new("Rename")).clicked()
+                        && let Ok(planned) = &planned
+                    {
+                        let pairs: Vec<(PathBuf, PathBuf)> = planned
+                            .iter()
+                            .map(|p| (p.from.clone(), p.to.clone()))
+                            .collect();
+                        action = self.run(&pairs, "Renamed");
+                        if let RenameAction::Renamed(done) = &action {
+                            self.last = done
+                                .iter()
+                                .map(|(from, to)| (to.clone(), from.clone()))
+                                .collect();
+                        }
+                    }
+                    if ui
+                        .add_enabled(!self.last.is_empty(), egui::Button::new("Undo"))
+                        .clicked()
+                    {
+                        let pairs = std::mem::take(&mut self.last);
+                        action = self.run(&pairs, "Restored");
+                    }
+                    if conflicts > 0 {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            format!("{} name conflicts", conflicts),
+                        );
+                    } else if let Some(status) = &self.status {
+                        ui.label(status);
+                    }
+                });
+            });
+        self.open = open;
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_tokens() {
+        let date = ("2024-05-31".to_string(), "142501".to_string());
+        assert_eq!(
+            render("{date}_{counter:04}_{name}", "IMG_1", &date, 7).unwrap(),
+            "2024-05-31_0007_IMG_1"
+        );
+        assert_eq!(
+            render("{time}-{counter}", "x", &date, 12).unwrap(),
+            "142501-12"
+        );
+        assert!(render("{nope}", "x", &date, 1).is_err());
+        assert!(render("{name", "x", &date, 1).is_err());
+    }
+
+    #[test]
+    fn detects_collisions() {
+        let paths = [PathBuf::from("/f/a.jpg"), PathBuf::from("/f/b.jpg")];
+        let dates = HashMap::new();
+        let existing = |p: &Path| p == Path::new("/f/b.jpg") || p == Path::new("/f/taken.jpg");
+
+        let same = plan("photo", &paths, 1, &dates, existing).unwrap();
+        assert!(same.iter().all(|p| p.conflict));
+        // A name held by a file in the run itself is free
+        let swap = plan("{name}", &paths[..1], 1, &dates, existing).unwrap();
+        assert!(!swap[0].conflict);
+        let shifted = plan("b", &paths[..1], 1, &dates, existing).unwrap();
+        assert!(shifted[0].conflict);
+        let taken = plan("taken", &paths[1..], 1, &dates, existing).unwrap();
+        assert!(taken[0].conflict);
+    }
+
+    #[test]
+    fn failed_rename_restores_the_whole_batch() {
+        let dir = std::env::temp_dir().join(format!("biv_rename_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let [a, b, x] = ["a", "b", "x"].map(|name| dir.join(name));
+        for path in [&a, &b, &x] {
+            std::fs::write(path, path.file_name().unwrap().as_encoded_bytes()).unwrap();
+        }
+
+        // `x` appeared after the preview, so b -> x fails after a -> b went through
+        let (done, errors) = rename_all(&[(a.clone(), b.clone()), (b.clone(), x.clone())]);
+        assert!(done.is_empty());
+        assert_eq!(errors.len(), 1);
+        for (path, content) in [(&a, "a"), (&b, "b"), (&x, "x")] {
+            assert_eq!(std::fs::read_to_string(path).unwrap(), content);
+        }
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+//! Global screenshot hotkey (Ctrl+Alt+S, works while the viewer is in the background).
+//!
+//! The primary monitor is captured and opened right away; dragging with the measure
+//! tool (enabled automatically) and pressing Enter crops the capture to that region.
+
+use eframe::egui;
+use global_hotkey::hotkey::{Code, HotKey, Modifiers};
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+pub struct ScreenCapture {
+    // Unregisters the hotkey when dropped
+    _manager: GlobalHotKeyManager,
+    rx: Receiver<Result<PathBuf, String>>,
+    /// The most recent capture, until it has been cropped or left.
+    pub pending: Option<PathBuf>,
+}
+
+fn session_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("biv_screenshots_{}", std::process::id()))
+}
+
+fn capture_primary_monitor() -> Result<PathBuf, String> {
+    let monitors = xcap::Monitor::all().map_err(|e| e.to_string())?;
+    let monitor = monitors
+        .iter()
+        .find(|m| m.is_primary().unwrap_or(false))
+        .or(monitors.first())
+        .ok_or("No monitor found")?;
+    let image = monitor.capture_image().map_err(|e| e.to_string())?;
+
+    let dir = session_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    let path = dir.join(format!("screenshot_{}.png", stamp));
+    // xcap brings its own `image` version, so go through raw pixels
+    let (width, height) = image.dimensions();
+    image::RgbaImage::from_raw(width, height, image.into_raw())
+        .ok_or("Invalid capture")?
+        .save(&path)
+        .map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Crops the capture at `path` to `region` (image pixels), overwriting it.
+pub fn crop(path: &Path, region: egui::Rect) -> Result<(), String> {
+    let image = image::open(path).map_err(|e| e.to_string())?;
+    let x = region.min.x.max(0.0) as u32;
+    let y = region.min.y.max(0.0) as u32;
+    image
+        .crop_imm(x, y, region.width() as u32, region.height() as u32)
+        .save(path)
+        .map_err(|e| e.to_string())
+}
+
+impl ScreenCapture {
+    pub fn new(ctx: &egui::Context) -> Result<Self, String> {
+        let manager = GlobalHotKeyManager::new().map_err(|e| e.to_string())?;
+        let hotkey = HotKey::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyS);
+        let hotkey_id = hotkey.id();
+        manager.register(hotkey).map_err(|e| e.to_string())?;
+
+        let (tx, rx) = channel();
+        let ctx = ctx.clone();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+            if event.id == hotkey_id && event.state == HotKeyState::Pressed {
+                let tx = tx.clone();
+                let ctx = ctx.clone();
+                thread::spawn(move || {
+                    let _ = tx.send(capture_primary_monitor());
+                    ctx.request_repaint();
+                });
+            }
+        }));
+
+        Ok(Self {
+            _manager: manager,
+            rx,
+            pending: None,
+        })
+    }
+
+    /// Returns a finished capture.
+    pub fn poll(&mut self) -> Option<Result<PathBuf, String>> {
+        let result = self.rx.try_recv().ok()?;
+        if let Ok(path) = &result {
+            self.pending = Some(path.clone());
+        }
+        Some(result)
+    }
+}
+
+impl Drop for ScreenCapture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(session_dir());
+    }
+}
@@ -0,0 +1,396 @@
+//! Library search (Ctrl+Shift+F): one query box over the whole library index, matching
+//! file names, tags, camera models, ratings and capture date ranges, with the results
+//! shown as a thumbnail grid.
+//!
+//! Query syntax: plain words must each appear in the file name, a tag or the camera
+//! model; `tag:x`, `camera:x`, `rating:4` (at least), `date:2024-05`, `after:2023` and
+//! `before:2024-01-15` narrow further. Values with spaces go in double quotes.
+
+use crate::config::Album;
+use crate::image_loader::{ThumbnailCommand, ThumbnailLoader, ThumbnailResult};
+use crate::library::{Entry, Library};
+use crate::timeline;
+use eframe::egui;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// Grid cell and thumbnail size
+const CELL: f32 = 120.0;
+const THUMBNAIL_SIZE: u32 = 112;
+
+/// A parsed search.
+#[derive(Default, Debug, PartialEq)]
+pub struct Query {
+    words: Vec<String>,
+    tags: Vec<String>,
+    cameras: Vec<String>,
+    // Capture time range in seconds since 1970, end exclusive
+    after: Option<i64>,
+    before: Option<i64>,
+    min_rating: u8,
+}
+
+/// Splits on whitespace outside double quotes, dropping the quotes.
+fn tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut query = Self::default();
+        for token in tokens(&text.to_lowercase()) {
+            let Some((key, value)) = token.split_once(':') else {
+                query.words.push(token);
+                continue;
+            };
+            let period =
+                || timeline::period(value).ok_or_else(|| format!("Not a date: \"{}\"", value));
+            match key {
+                "tag" => query.tags.push(value.trim().to_string()),
+                "camera" => query.cameras.push(value.to_string()),
+                "rating" => {
+                    query.min_rating = value
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|r| *r <= 5)
+                        .ok_or_else(|| format!("Rating must be 0 to 5, not \"{}\"", value))?;
+                }
+                "date" => {
+                    let (start, end) = period()?;
+                    query.after = Some(start);
+                    query.before = Some(end);
+                }
+                "after" => query.after = Some(period()?.1),
+                "before" => query.before = Some(period()?.0),
+                // Not a known field, e.g. a time in a file name
+                _ => query.words.push(token),
+            }
+        }
+        Ok(query)
+    }
+
+    pub fn matches(&self, path: &Path, entry: &Entry) -> bool {
+        if entry.rating < self.min_rating {
+            return false;
+        }
+        if self.after.is_some() || self.before.is_some() {
+            let Some(captured) = entry.captured else {
+                return false;
+            };
+            if self.after.is_some_and(|t| captured < t)
+                || self.before.is_some_and(|t| captured >= t)
+            {
+                return false;
+            }
+        }
+        let camera = entry.camera.to_lowercase();
+        if !self.cameras.iter().all(|c| camera.contains(c.as_str())) {
+            return false;
+        }
+        if !self.tags.iter().all(|t| entry.tags.contains(t)) {
+            return false;
+        }
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase();
+        self.words.iter().all(|word| {
+            name.contains(word.as_str())
+                || camera.contains(word.as_str())
+                || entry.tags.iter().any(|tag| tag.contains(word.as_str()))
+        })
+    }
+}
+
+/// Library images matching `query`, oldest shot first (undated ones last, by path).
+pub fn search<'a>(
+    entries: impl Iterator<Item = (&'a PathBuf, &'a Entry)>,
+    query: &Query,
+) -> Vec<PathBuf> {
+    let mut results: Vec<(&PathBuf, Option<i64>)> = entries
+        .filter(|(path, entry)| query.matches(path, entry))
+        .map(|(path, entry)| (path, entry.captured))
+        .collect();
+    results.sort_by(|(a, ta), (b, tb)| match (ta, tb) {
+        (Some(ta), Some(tb)) => ta.cmp(tb).then_with(|| a.cmp(b)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.cmp(b),
+    });
+    results.into_iter().map(|(path, _)| path.clone()).collect()
+}
+
+/// Library images matching the query text of an album.
+pub fn album_images(library: &Library, album: &Album) -> Result<Vec<PathBuf>, String> {
+    Query::parse(&album.query).map(|query| search(library.entries(), &query))
+}
+
+pub enum SearchAction {
+    None,
+    Open(PathBuf),
+    // Show the results as the navigation list
+    Browse(Vec<PathBuf>),
+    SaveAlbum(Album),
+}
+
+#[derive(Default)]
+pub struct SearchWindow {
+    pub open: bool,
+    text: String,
+    album_name: String,
+    focus: bool,
+    // Results of `text`, recomputed when it or the index changes
+    results: Option<Result<Vec<PathBuf>, String>>,
+    // Started when the window first shows thumbnails
+    thumbnails: Option<ThumbnailLoader>,
+    textures: HashMap<PathBuf, egui::TextureHandle>,
+    requested: HashSet<PathBuf>,
+}
+
+impl SearchWindow {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.focus = self.open;
+    }
+
+    /// Drops the cached results, e.g. after the index changed.
+    pub fn invalidate(&mut self) {
+        self.results = None;
+    }
+
+    fn receive_thumbnails(&mut self, ctx: &egui::Context) {
+        let Some(loader) = &self.thumbnails else {
+            return;
+        };
+        while let Ok(result) = loader.rx.try_recv() {
+            // Failed and online-only files stay requested and show their name instead
+            if let ThumbnailResult::Success(path, image) = result {
+                let texture = ctx.load_texture(
+                    format!("search:{}", path.display()),
+                    image,
+                    Default::default(),
+                );
+                self.textures.insert(path, texture);
+            }
+        }
+    }
+
+    /// One grid cell; returns true when clicked.
+    fn cell(&mut self, ui: &mut egui::Ui, path: &Path) -> bool {
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(CELL, CELL), egui::Sense::click());
+        let inner = rect.shrink(4.0);
+        if response.hovered() {
+            ui.painter()
+                .rect_filled(rect, 4.0, ui.visuals().widgets.hovered.bg_fill);
+        }
+        match self.textures.get(path) {
+            Some(texture) => {
+                let size = texture.size_vec2();
+                let scale = (inner.width() / size.x).min(inner.height() / size.y);
+                let image_rect = egui::Rect::from_center_size(inner.center(), size * scale);
+                let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                ui.painter()
+                    .image(texture.id(), image_rect, uv, egui::Color32::WHITE);
+            }
+            None => {
+                if ui.is_rect_visible(rect)
+                    && self.requested.insert(path.to_path_buf())
+                    && let Some(loader) = &self.thumbnails
+                {
+                    let _ = loader
+                        .tx
+                        .send(ThumbnailCommand::Load(path.to_path_buf(), THUMBNAIL_SIZE));
+                }
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                ui.painter().text(
+                    inner.center(),
+                    egui::Align2::CENTER_CENTER,
+                    name,
+                    egui::FontId::proportional(11.0),
+                    ui.visuals().weak_text_color(),
+                );
+            }
+        }
+        response.on_hover_text(path.display().to_string()).clicked()
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, library: &Library) -> SearchAction {
+        let mut action = SearchAction::None;
+        if !self.open {
+            // Thumbnails are only kept while the window is open
+            self.thumbnails = None;
+            self.textures.clear();
+            self.requested.clear();
+            return action;
+        }
+        if self.thumbnails.is_none() {
+            self.thumbnails = Some(ThumbnailLoader::new(ctx.clone()));
+        }
+        self.receive_thumbnails(ctx);
+
+        let mut open = self.open;
+        egui::Window::new("Search Library")
+            .open(&mut open)
+            .default_size([560.0, 480.0])
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.text)
+                        .hint_text("beach tag:holiday camera:x100 rating:4 date:2024-05")
+                        .desired_width(f32::INFINITY),
+                );
+                if std::mem::take(&mut self.focus) {
+                    response.request_focus();
+                }
+                if response.changed() {
+                    self.results = None;
+                }
+                let results = self.results.get_or_insert_with(|| {
+                    Query::parse(&self.text).map(|query| search(library.entries(), &query))
+                });
+                let results = match results {
+                    Ok(results) => results.clone(),
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e.as_str());
+                        return;
+                    }
+                };
+
+                ui.horizontal(|ui| {
+                    if library.len() == 0 {
+                        ui.label("The library is empty; add folders to it with Ctrl+L.");
+                    } else {
+                        ui.label(format!("{} of {} images", results.len(), library.len()));
+                    }
+                    if ui
+                        .add_enabled(!results.is_empty(), egui::Button::new("Browse all"))
+                        .clicked()
+                    {
+                        action = SearchAction::Browse(results.clone());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.album_name)
+                            .hint_text("Album name")
+                            .desired_width(160.0),
+                    );
+                    let can_save =
+                        !self.album_name.trim().is_empty() && !self.text.trim().is_empty();
+                    if ui
+                        .add_enabled(can_save, egui::Button::new("Save as album"))
+                        .on_hover_text("Keep this search on the start screen")
+                        .clicked()
+                    {
+                        action = SearchAction::SaveAlbum(Album {
+                            name: self.album_name.trim().to_string(),
+                            query: self.text.trim().to_string(),
+                        });
+                        self.album_name.clear();
+                    }
+                });
+                ui.separator();
+
+                let columns = ((ui.available_width() / CELL) as usize).max(1);
+                let rows = results.len().div_ceil(columns);
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show_rows(ui, CELL, rows, |ui, range| {
+                        ui.spacing_mut().item_spacing = egui::Vec2::ZERO;
+                        for row in range {
+                            let start = row * columns;
+                            let end = (start + columns).min(results.len());
+                            ui.horizontal(|ui| {
+                                for path in &results[start..end] {
+                                    if self.cell(ui, path) {
+                                        action = SearchAction::Open(path.clone());
+                                    }
+                                }
+                            });
+                        }
+                    });
+            });
+        self.open = open;
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn entry(rating: u8, captured: Option<i64>, camera: &str) -> Entry {
+        Entry {
+            size: 0,
+            modified: 0,
+            width: 10,
+            height: 10,
+            hash: None,
+            captured,
+            rating,
+            camera: camera.to_string(),
+            tags: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn parses_fields_and_quoted_values() {
+        let query = Query::parse(r#"Beach camera:"EOS R5" rating:4 after:2023"#).unwrap();
+        assert_eq!(query.words, ["beach"]);
+        assert_eq!(query.cameras, ["eos r5"]);
+        assert_eq!(query.min_rating, 4);
+        assert_eq!(
+            query.after,
+            timeline::period("2024").map(|(start, _)| start)
+        );
+        assert!(Query::parse("rating:9").is_err());
+        assert!(Query::parse("date:someday").is_err());
+    }
+
+    #[test]
+    fn searches_names_tags_cameras_and_dates_in_capture_order() {
+        let (may, june) = (
+            timeline::period("2024-05").unwrap().0,
+            timeline::period("2024-06").unwrap().0,
+        );
+        let mut cat = entry(4, None, "");
+        cat.tags.insert("beach".to_string());
+        let entries = BTreeMap::from([
+            (PathBuf::from("/a/beach.jpg"), entry(2, Some(june), "X100V")),
+            (PathBuf::from("/a/dog.jpg"), entry(5, Some(may), "EOS R5")),
+            (PathBuf::from("/b/cat.jpg"), cat),
+        ]);
+
+        let find = |text: &str| search(entries.iter(), &Query::parse(text).unwrap());
+        assert_eq!(
+            find("BEACH"),
+            [PathBuf::from("/a/beach.jpg"), PathBuf::from("/b/cat.jpg")]
+        );
+        assert_eq!(find("tag:beach"), [PathBuf::from("/b/cat.jpg")]);
+        assert_eq!(
+            find("rating:4"),
+            [PathBuf::from("/a/dog.jpg"), PathBuf::from("/b/cat.jpg")]
+        );
+        assert_eq!(find("date:2024-05"), [PathBuf::from("/a/dog.jpg")]);
+        assert_eq!(find("before:2024-06 eos"), [PathBuf::from("/a/dog.jpg")]);
+        assert_eq!(find("x100"), [PathBuf::from("/a/beach.jpg")]);
+    }
+}
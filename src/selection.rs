@@ -0,0 +1,97 @@
+use eframe::egui;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Multi-selection of folder images, shared by the filmstrip and the batch actions.
+///
+/// Ctrl+click toggles an image, Shift+click extends from the last clicked one.
+#[derive(Default)]
+pub struct Selection {
+    selected: HashSet<PathBuf>,
+    anchor: Option<usize>,
+}
+
+impl Selection {
+    pub fn contains(&self, path: &Path) -> bool {
+        self.selected.contains(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.selected.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selected.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.selected.clear();
+    }
+
+    /// Selected images in folder order.
+    pub fn paths(&self, folder_images: &[PathBuf]) -> Vec<PathBuf> {
+        folder_images
+            .iter()
+            .filter(|p| self.selected.contains(*p))
+            .cloned()
+            .collect()
+    }
+
+    pub fn select_all(&mut self, folder_images: &[PathBuf]) {
+        self.selected = folder_images.iter().cloned().collect();
+        self.anchor = None;
+    }
+
+    /// Makes `index` the only selected image.
+    pub fn select_only(&mut self, folder_images: &[PathBuf], index: usize) {
+        self.selected.clear();
+        self.selected.insert(folder_images[index].clone());
+        self.anchor = Some(index);
+    }
+
+    /// Applies a click on `index`. Returns true for a plain click, which opens the image.
+    pub fn click(
+        &mut self,
+        folder_images: &[PathBuf],
+        index: usize,
+        modifiers: egui::Modifiers,
+    ) -> bool {
+        if modifiers.command {
+            let path = &folder_images[index];
+            if !self.selected.remove(path) {
+                self.selected.insert(path.clone());
+            }
+            self.anchor = Some(index);
+            false
+        } else if modifiers.shift {
+            let anchor = self.anchor.unwrap_or(index).min(folder_images.len() - 1);
+            let range = anchor.min(index)..=anchor.max(index);
+            self.selected.extend(folder_images[range].iter().cloned());
+            false
+        } else {
+            self.selected.clear();
+            self.anchor = Some(index);
+            true
+        }
+    }
+
+    /// Keeps renamed images (old, new) selected under their new names.
+    pub fn rename(&mut self, renamed: &[(PathBuf, PathBuf)]) {
+        for (old, new) in renamed {
+            if self.selected.remove(old) {
+                self.selected.insert(new.clone());
+            }
+        }
+    }
+
+    /// Drops images that are no longer in the folder list (deleted, moved, filtered out).
+    pub fn retain(&mut self, folder_images: &[PathBuf]) {
+        if !self.selected.is_empty() {
+            let folder: HashSet<&PathBuf> = folder_images.iter().collect();
+            self.selected.retain(|p| folder.contains(p));
+        }
+        if self.anchor.is_some_and(|i| i >= folder_images.len()) {
+            self.anchor = None;
+        }
+    }
+}
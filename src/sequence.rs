@@ -0,0 +1,237 @@
+//! Numbered frame sequences (render_0001.png, render_0002.png, …) played back as a flipbook.
+//!
+//! Frames are decoded on a worker at preview size and kept as textures, so playback runs
+//! at the chosen frame rate once they are in.
+
+use crate::image_loader::{decode_image, resize_linear};
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+// Shorter runs of numbered files are more likely unrelated photos than an animation
+const MIN_FRAMES: usize = 3;
+// Longest side of the preview frames
+const PREVIEW_SIZE: u32 = 1024;
+
+/// Splits a file name into the text before the last run of digits, the digits and the rest.
+fn split_number(path: &Path) -> Option<(String, &str, String)> {
+    let name = path.file_name()?.to_str()?;
+    let stem_len = path.file_stem()?.to_str()?.len();
+    let stem = &name[..stem_len];
+    let end = stem.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let start = stem[..end]
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |i| i + 1);
+    Some((
+        name[..start].to_lowercase(),
+        &stem[start..end],
+        name[end..].to_lowercase(),
+    ))
+}
+
+/// The numbered sequence `path` belongs to, in frame order, if it has enough frames.
+///
+/// Frames share the text around the number and, for zero-padded numbers, its width.
+pub fn detect(path: &Path, folder_images: &[PathBuf]) -> Option<Vec<PathBuf>> {
+    let (prefix, digits, suffix) = split_number(path)?;
+    let padded = digits.starts_with('0');
+    let mut frames: Vec<(u64, PathBuf)> = folder_images
+        .iter()
+        .filter(|p| p.parent() == path.parent())
+        .filter_map(|p| {
+            let (p_prefix, p_digits, p_suffix) = split_number(p)?;
+            let same_width = !padded || p_digits.len() == digits.len();
+            (p_prefix == prefix && p_suffix == suffix && same_width)
+                .then(|| Some((p_digits.parse().ok()?, p.clone())))
+                .flatten()
+        })
+        .collect();
+    if frames.len() < MIN_FRAMES {
+        return None;
+    }
+    frames.sort();
+    Some(frames.into_iter().map(|(_, p)| p).collect())
+}
+
+pub struct SequencePlayer {
+    pub open: bool,
+    frames: Vec<PathBuf>,
+    textures: Vec<Option<egui::TextureHandle>>,
+    rx: Option<Receiver<(usize, egui::ColorImage)>>,
+    cancel: Arc<AtomicBool>,
+    fps: f32,
+    playing: bool,
+    // Fractional frame position, advanced by the frame time while playing
+    position: f32,
+}
+
+impl Default for SequencePlayer {
+    fn default() -> Self {
+        Self {
+            open: false,
+            frames: Vec::new(),
+            textures: Vec::new(),
+            rx: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            fps: 24.0,
+            playing: false,
+            position: 0.0,
+        }
+    }
+}
+
+impl SequencePlayer {
+    /// Opens the player on `frames`, starting at `start`.
+    pub fn open(&mut self, ctx: &egui::Context, frames: Vec<PathBuf>, start: usize) {
+        self.open = true;
+        self.playing = true;
+        self.position = start as f32;
+        if frames == self.frames {
+            return;
+        }
+
+        self.cancel.store(true, Ordering::Relaxed);
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.cancel = cancel.clone();
+        self.textures = vec![None; frames.len()];
+        self.frames = frames.clone();
+
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            // Decode outward from the starting frame so playback can begin right away
+            let order = (start..frames.len()).chain(0..start);
+            for index in order {
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
+                let Ok(image) = decode_image(&frames[index]) else {
+                    continue;
+                };
+                let preview = resize_linear(&image, PREVIEW_SIZE, PREVIEW_SIZE);
+                let size = [preview.width() as usize, preview.height() as usize];
+                let color_image = egui::ColorImage::from_rgba_unmultiplied(size, preview.as_raw());
+                if tx.send((index, color_image)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    fn close(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+        self.rx = None;
+        self.frames.clear();
+        self.textures.clear();
+        self.playing = false;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+        if let Some(rx) = &self.rx {
+            while let Ok((index, image)) = rx.try_recv() {
+                let name = format!("sequence_{}", index);
+                self.textures[index] =
+                    Some(ctx.load_texture(name, image, egui::TextureOptions::LINEAR));
+            }
+        }
+
+        let len = self.frames.len();
+        let loaded = self.textures.iter().filter(|t| t.is_some()).count();
+        if self.playing && len > 0 {
+            let dt = ctx.input(|i| i.stable_dt).min(0.1);
+            self.position = (self.position + dt * self.fps) % len as f32;
+            ctx.request_repaint();
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Frame Sequence")
+            .open(&mut open)
+            .default_width(660.0)
+            .show(ctx, |ui| {
+                let index = (self.position as usize).min(len.saturating_sub(1));
+                let frame_height = (ui.available_width() * 9.0 / 16.0).max(120.0);
+                match self.textures.get(index).and_then(Option::as_ref) {
+                    Some(texture) => {
+                        ui.add(
+                            egui::Image::new(texture)
+                                .max_height(frame_height)
+                                .max_width(ui.available_width()),
+                        );
+                    }
+                    None => {
+                        ui.allocate_ui(egui::vec2(ui.available_width(), frame_height), |ui| {
+                            ui.centered_and_justified(|ui| ui.spinner());
+                        });
+                    }
+                }
+
+                let mut frame = index;
+                let scrub = ui.add(
+                    egui::Slider::new(&mut frame, 0..=len.saturating_sub(1))
+                        .show_value(false)
+                        .trailing_fill(true),
+                );
+                if scrub.changed() {
+                    self.position = frame as f32;
+                }
+                // Dragging pauses playback so the frame stays where it was dropped
+                if scrub.drag_started() {
+                    self.playing = false;
+                }
+
+                ui.horizontal(|ui| {
+                    let label = if self.playing { "⏸" } else { "▶" };
+                    if ui.button(label).clicked() {
+                        self.playing = !self.playing;
+                    }
+                    ui.add(
+                        egui::DragValue::new(&mut self.fps)
+                            .range(1.0..=120.0)
+                            .speed(0.5)
+                            .suffix(" fps"),
+                    );
+                    ui.label(format!("Frame {} / {}", index + 1, len));
+                    if loaded < len {
+                        ui.weak(format!("({} loaded)", loaded));
+                    }
+                    if let Some(name) = self.frames.get(index).and_then(|p| p.file_name()) {
+                        ui.weak(name.to_string_lossy());
+                    }
+                });
+            });
+        if !open {
+            self.open = false;
+            self.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(|n| Path::new("shots").join(n)).collect()
+    }
+
+    #[test]
+    fn frames_are_ordered_numerically() {
+        let folder = paths(&["f10.png", "f9.png", "f11.png", "other.png", "f1.jpg"]);
+        let frames = detect(&folder[0], &folder).unwrap();
+        assert_eq!(frames, paths(&["f9.png", "f10.png", "f11.png"]));
+    }
+
+    #[test]
+    fn padded_numbers_must_match_width() {
+        let folder = paths(&["render_001.png", "render_002.png", "render_0003.png"]);
+        assert!(detect(&folder[0], &folder).is_none());
+    }
+}
@@ -0,0 +1,151 @@
+//! Sharpness score (variance of the Laplacian) for culling out-of-focus shots, computed
+//! on a background thread for the current image and, with badges on, the whole folder.
+
+use crate::image_loader::decode_image;
+use eframe::egui;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+
+// Images are scored at this size at most, so scores compare across resolutions
+const SCORE_SIZE: u32 = 1024;
+// Below these scores an image counts as blurry or soft
+const BLURRY: f32 = 50.0;
+const SOFT: f32 = 150.0;
+
+/// Variance of the 4-neighbour Laplacian over the luma of `image`; in-focus detail gives
+/// strong responses, blur flattens them.
+pub fn score(image: &image::DynamicImage) -> f32 {
+    let luma = if image.width() > SCORE_SIZE || image.height() > SCORE_SIZE {
+        image.thumbnail(SCORE_SIZE, SCORE_SIZE).to_luma8()
+    } else {
+        image.to_luma8()
+    };
+    let (width, height) = luma.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let value = |x: u32, y: u32| f64::from(luma.get_pixel(x, y)[0]);
+    let (mut sum, mut sum_squares) = (0.0, 0.0);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let laplacian = value(x - 1, y) + value(x + 1, y) + value(x, y - 1) + value(x, y + 1)
+                - 4.0 * value(x, y);
+            sum += laplacian;
+            sum_squares += laplacian * laplacian;
+        }
+    }
+    let count = f64::from((width - 2) * (height - 2));
+    let mean = sum / count;
+    (sum_squares / count - mean * mean) as f32
+}
+
+/// Word and colour for a score.
+pub fn verdict(score: f32) -> (&'static str, egui::Color32) {
+    if score < BLURRY {
+        ("Blurry", egui::Color32::from_rgb(255, 100, 100))
+    } else if score < SOFT {
+        ("Soft", egui::Color32::from_rgb(255, 190, 80))
+    } else {
+        ("Sharp", egui::Color32::from_rgb(120, 220, 120))
+    }
+}
+
+/// Scores images one at a time on a worker, current image first.
+pub struct SharpnessScores {
+    tx: Sender<PathBuf>,
+    rx: Receiver<(PathBuf, Option<f32>)>,
+    // None for images that could not be decoded
+    scores: HashMap<PathBuf, Option<f32>>,
+    pending: Option<PathBuf>,
+}
+
+impl SharpnessScores {
+    pub fn new(ctx: &egui::Context) -> Self {
+        let (tx, rx_worker) = channel::<PathBuf>();
+        let (tx_worker, rx) = channel();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            while let Ok(path) = rx_worker.recv() {
+                let score = decode_image(&path).ok().map(|image| score(&image));
+                if tx_worker.send((path, score)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+        Self {
+            tx,
+            rx,
+            scores: HashMap::new(),
+            pending: None,
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<f32> {
+        self.scores.get(path).copied().flatten()
+    }
+
+    /// Forgets the score of a file that changed on disk.
+    pub fn invalidate(&mut self, path: &Path) {
+        self.scores.remove(path);
+    }
+
+    /// Takes finished scores and queues the next image: `current`, then (with `folder`)
+    /// the rest of `folder_images` nearest the current one first. Call once per frame.
+    pub fn update(
+        &mut self,
+        current: Option<&Path>,
+        folder_images: &[PathBuf],
+        current_index: usize,
+        folder: bool,
+    ) {
+        while let Ok((path, score)) = self.rx.try_recv() {
+            self.scores.insert(path, score);
+            self.pending = None;
+        }
+        if self.pending.is_some() {
+            return;
+        }
+
+        let next = current
+            .filter(|p| !self.scores.contains_key(*p))
+            .map(Path::to_path_buf)
+            .or_else(|| {
+                if !folder {
+                    return None;
+                }
+                folder_images
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| !self.scores.contains_key(*p))
+                    .min_by_key(|(i, _)| i.abs_diff(current_index))
+                    .map(|(_, p)| p.clone())
+            });
+        if let Some(path) = next {
+            self.pending = Some(path.clone());
+            let _ = self.tx.send(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blur_lowers_the_score() {
+        let checkerboard = image::GrayImage::from_fn(64, 64, |x, y| {
+            image::Luma([if (x / 4 + y / 4) % 2 == 0 { 0 } else { 255 }])
+        });
+        let sharp = image::DynamicImage::ImageLuma8(checkerboard);
+        let blurred = sharp.blur(3.0);
+        let flat = image::DynamicImage::new_luma8(64, 64);
+
+        assert!(score(&sharp) > score(&blurred) * 4.0);
+        assert_eq!(score(&flat), 0.0);
+        assert_eq!(verdict(score(&sharp)).0, "Sharp");
+    }
+}
@@ -0,0 +1,149 @@
+//! Slideshow: steps through the folder on a timer, optionally with a slow Ken Burns pan
+//! and zoom across each slide.
+
+use crate::view_state::ViewState;
+use eframe::egui;
+use std::hash::BuildHasher;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// Ken Burns zoom range, relative to the zoom at which the image just covers the screen
+const MIN_ZOOM_IN: f32 = 1.05;
+const MAX_ZOOM_IN: f32 = 1.3;
+// Panoramas covering the screen would be cropped to a sliver; they zoom from fit instead
+const MAX_COVER_RATIO: f32 = 2.0;
+
+/// Start and end view of a slide's pan and zoom.
+struct Framing {
+    start: (f32, egui::Vec2),
+    end: (f32, egui::Vec2),
+}
+
+pub struct Slideshow {
+    pub active: bool,
+    // Slide on screen and when it appeared; the timer only runs once an image is showing
+    slide: Option<(PathBuf, Instant)>,
+    framing: Option<Framing>,
+    // State of the xorshift generator picking framings
+    rng: u64,
+}
+
+impl Default for Slideshow {
+    fn default() -> Self {
+        Self {
+            active: false,
+            slide: None,
+            framing: None,
+            // Seeded from the hasher's per-process random keys
+            rng: std::collections::hash_map::RandomState::new().hash_one(0u8) | 1,
+        }
+    }
+}
+
+impl Slideshow {
+    pub fn toggle(&mut self) {
+        self.active = !self.active;
+        self.slide = None;
+        self.framing = None;
+    }
+
+    /// Whether `shown` (the image on screen, `None` while loading) has been up for
+    /// `interval` and the next slide is due.
+    pub fn advance_due(
+        &mut self,
+        ctx: &egui::Context,
+        shown: Option<&Path>,
+        interval: Duration,
+    ) -> bool {
+        if !self.active {
+            return false;
+        }
+        let Some(shown) = shown else {
+            return false;
+        };
+        if self.slide.as_ref().is_none_or(|(path, _)| path != shown) {
+            self.slide = Some((shown.to_path_buf(), Instant::now()));
+            self.framing = None;
+        }
+        let elapsed = self
+            .slide
+            .as_ref()
+            .map_or(Duration::ZERO, |(_, at)| at.elapsed());
+        if elapsed >= interval {
+            return true;
+        }
+        ctx.request_repaint_after(interval - elapsed);
+        false
+    }
+
+    /// Moves the view along the current slide's Ken Burns path.
+    pub fn animate(
+        &mut self,
+        ctx: &egui::Context,
+        view_state: &mut ViewState,
+        viewport: egui::Rect,
+        image_size: egui::Vec2,
+        interval: Duration,
+    ) {
+        let Some((_, started)) = self.slide else {
+            return;
+        };
+        let framing = match self.framing.take() {
+            Some(framing) => framing,
+            None => {
+                let framing = self.random_framing(viewport, image_size);
+                view_state.restore(framing.start.0, framing.start.1);
+                framing
+            }
+        };
+
+        let t = (started.elapsed().as_secs_f32() / interval.as_secs_f32().max(0.1)).min(1.0);
+        let (start_zoom, start_pan) = framing.start;
+        let (end_zoom, end_pan) = framing.end;
+        let zoom = start_zoom + (end_zoom - start_zoom) * t;
+        let limit = ViewState::pan_limit(viewport, image_size, zoom);
+        let pan = (start_pan + (end_pan - start_pan) * t).clamp(-limit, limit);
+        view_state.animate_to(zoom, pan);
+        self.framing = Some(framing);
+        ctx.request_repaint();
+    }
+
+    fn random_unit(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A random pan at `zoom_in` times the base zoom, keeping the screen inside the image
+    /// wherever the image is large enough to fill it.
+    fn random_view(
+        &mut self,
+        viewport: egui::Rect,
+        image_size: egui::Vec2,
+        zoom_in: f32,
+    ) -> (f32, egui::Vec2) {
+        let image_size = image_size.max(egui::vec2(1.0, 1.0));
+        let fit = (viewport.width() / image_size.x).min(viewport.height() / image_size.y);
+        let cover = (viewport.width() / image_size.x).max(viewport.height() / image_size.y);
+        let zoom = cover.min(fit * MAX_COVER_RATIO) * zoom_in;
+        let limit = ViewState::pan_limit(viewport, image_size, zoom);
+        let pan = egui::vec2(
+            limit.x * (self.random_unit() * 2.0 - 1.0),
+            limit.y * (self.random_unit() * 2.0 - 1.0),
+        );
+        (zoom, pan)
+    }
+
+    /// Slowly zooms in or out (at random) between two random points of the image.
+    fn random_framing(&mut self, viewport: egui::Rect, image_size: egui::Vec2) -> Framing {
+        let (mut from, mut to) = (MIN_ZOOM_IN, MAX_ZOOM_IN);
+        if self.random_unit() < 0.5 {
+            std::mem::swap(&mut from, &mut to);
+        }
+        Framing {
+            start: self.random_view(viewport, image_size, from),
+            end: self.random_view(viewport, image_size, to),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Before/after comparison of two renderings of the same image, split by a handle that
+//! is dragged across.
+
+use eframe::egui;
+
+/// Shows `before` left of the split and `after` right of it, fitted into `max_size`.
+/// Dragging or clicking moves `split` (0..1 across the width).
+pub fn show(
+    ui: &mut egui::Ui,
+    before: &egui::TextureHandle,
+    after: &egui::TextureHandle,
+    split: &mut f32,
+    max_size: egui::Vec2,
+) {
+    let image_size = after.size_vec2().max(egui::vec2(1.0, 1.0));
+    let scale = (max_size.x / image_size.x).min(max_size.y / image_size.y);
+    let (rect, response) = ui.allocate_exact_size(image_size * scale, egui::Sense::drag());
+    if let Some(pos) = response.interact_pointer_pos() {
+        *split = ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+    }
+
+    let x = rect.left() + rect.width() * *split;
+    let painter = ui.painter_at(rect);
+    painter.image(
+        before.id(),
+        egui::Rect::from_min_max(rect.min, egui::pos2(x, rect.max.y)),
+        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(*split, 1.0)),
+        egui::Color32::WHITE,
+    );
+    painter.image(
+        after.id(),
+        egui::Rect::from_min_max(egui::pos2(x, rect.min.y), rect.max),
+        egui::Rect::from_min_max(egui::pos2(*split, 0.0), egui::pos2(1.0, 1.0)),
+        egui::Color32::WHITE,
+    );
+    painter.vline(
+        x,
+        rect.y_range(),
+        egui::Stroke::new(2.0, egui::Color32::WHITE),
+    );
+
+    let font = egui::FontId::proportional(12.0);
+    let shadow = egui::Color32::from_black_alpha(200);
+    for (text, pos, align) in [
+        (
+            "Before",
+            rect.left_top() + egui::vec2(6.0, 6.0),
+            egui::Align2::LEFT_TOP,
+        ),
+        (
+            "After",
+            rect.right_top() + egui::vec2(-6.0, 6.0),
+            egui::Align2::RIGHT_TOP,
+        ),
+    ] {
+        painter.text(
+            pos + egui::vec2(1.0, 1.0),
+            align,
+            text,
+            font.clone(),
+            shadow,
+        );
+        painter.text(pos, align, text, font.clone(), egui::Color32::WHITE);
+    }
+    if response.hovered() || response.dragged() {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+    }
+}
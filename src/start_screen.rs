@@ -0,0 +1,222 @@
+//! What an empty window shows: the recently opened images as thumbnails, their folders,
+//! and the ways to open something.
+
+use crate::config::Album;
+use crate::image_loader::{ThumbnailCommand, ThumbnailLoader, ThumbnailResult};
+use eframe::egui;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+const THUMBNAIL_SIZE: u32 = 96;
+const TILE_SIZE: egui::Vec2 = egui::vec2(104.0, 124.0);
+const COLUMN_WIDTH: f32 = 580.0;
+const MAX_RECENT_FOLDERS: usize = 5;
+
+pub enum StartAction {
+    None,
+    Open(PathBuf),
+    OpenImage,
+    OpenFolder,
+    Paste,
+    // Index into the albums
+    OpenAlbum(usize),
+    ExportAlbum(usize),
+    RemoveAlbum(usize),
+}
+
+#[derive(Default)]
+pub struct StartScreen {
+    // Started the first time the screen is shown, so launching with a file costs nothing
+    loader: Option<ThumbnailLoader>,
+    requested: HashSet<PathBuf>,
+    // `None` for files that can't be read anymore
+    thumbnails: HashMap<PathBuf, Option<egui::TextureHandle>>,
+}
+
+/// Folders of `recent_files`, most recent first.
+pub fn recent_folders(recent_files: &[PathBuf]) -> Vec<&Path> {
+    let mut folders: Vec<&Path> = Vec::new();
+    for folder in recent_files.iter().filter_map(|p| p.parent()) {
+        if !folders.contains(&folder) && folders.len() < MAX_RECENT_FOLDERS {
+            folders.push(folder);
+        }
+    }
+    folders
+}
+
+impl StartScreen {
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        recent_files: &[PathBuf],
+        albums: &[Album],
+    ) -> StartAction {
+        self.load_thumbnails(ui.ctx(), recent_files);
+        let mut action = StartAction::None;
+
+        let area = ui.max_rect();
+        let rows = if recent_files.is_empty() { 0.0 } else { 2.0 };
+        let height = 140.0 + rows * TILE_SIZE.y + albums.len() as f32 * 22.0;
+        let column = egui::Rect::from_min_size(
+            egui::pos2(
+                area.center().x - COLUMN_WIDTH / 2.0,
+                (area.center().y - height / 2.0).max(area.top() + 20.0),
+            ),
+            egui::vec2(COLUMN_WIDTH.min(area.width()), area.height()),
+        );
+        ui.allocate_new_ui(egui::UiBuilder::new().max_rect(column), |ui| {
+            ui.heading("Better Image Viewer");
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.button("Open Image…").on_hover_text("Ctrl+O").clicked() {
+                    action = StartAction::OpenImage;
+                }
+                if ui
+                    .button("Open Folder…")
+                    .on_hover_text("Ctrl+Shift+O")
+                    .clicked()
+                {
+                    action = StartAction::OpenFolder;
+                }
+                if ui
+                    .button("Paste from Clipboard")
+                    .on_hover_text("Ctrl+Shift+V")
+                    .clicked()
+                {
+                    action = StartAction::Paste;
+                }
+            });
+
+            if !recent_files.is_empty() {
+                ui.add_space(16.0);
+                ui.strong("Recent");
+                ui.horizontal_wrapped(|ui| {
+                    for path in recent_files {
+                        if self.tile(ui, path).clicked() {
+                            action = StartAction::Open(path.clone());
+                        }
+                    }
+                });
+
+                ui.add_space(8.0);
+                ui.strong("Recent folders");
+                for folder in recent_folders(recent_files) {
+                    if ui.link(folder.display().to_string()).clicked() {
+                        action = StartAction::Open(folder.to_path_buf());
+                    }
+                }
+            }
+
+            if !albums.is_empty() {
+                ui.add_space(8.0);
+                ui.strong("Albums");
+                for (i, album) in albums.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.link(&album.name).on_hover_text(&album.query).clicked() {
+                            action = StartAction::OpenAlbum(i);
+                        }
+                        if ui
+                            .small_button("Export…")
+                            .on_hover_text("Select every image of the album for batch actions")
+                            .clicked()
+                        {
+                            action = StartAction::ExportAlbum(i);
+                        }
+                        if ui.small_button("✕").on_hover_text("Remove album").clicked() {
+                            action = StartAction::RemoveAlbum(i);
+                        }
+                    });
+                }
+            }
+
+            ui.add_space(16.0);
+            // Highlighted while files are dragged over the window
+            let dragging = ui.input(|i| !i.raw.hovered_files.is_empty());
+            let color = if dragging {
+                egui::Color32::LIGHT_BLUE
+            } else {
+                egui::Color32::GRAY
+            };
+            ui.colored_label(color, "Drop images, folders or links anywhere to open them");
+        });
+        action
+    }
+
+    /// Thumbnail and name of a recent file.
+    fn tile(&self, ui: &mut egui::Ui, path: &Path) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(TILE_SIZE, egui::Sense::click());
+        let visuals = ui.style().interact(&response);
+        if response.hovered() {
+            ui.painter().rect_filled(rect, 6.0, visuals.weak_bg_fill);
+        }
+
+        let image_rect = egui::Rect::from_min_size(
+            rect.min + egui::vec2(4.0, 4.0),
+            egui::Vec2::splat(THUMBNAIL_SIZE as f32),
+        );
+        match self.thumbnails.get(path) {
+            Some(Some(texture)) => {
+                let size = texture.size_vec2();
+                let scale = (image_rect.width() / size.x).min(image_rect.height() / size.y);
+                ui.painter().image(
+                    texture.id(),
+                    egui::Rect::from_center_size(image_rect.center(), size * scale),
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    egui::Color32::WHITE,
+                );
+            }
+            Some(None) => {
+                ui.painter().text(
+                    image_rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "🖼",
+                    egui::FontId::proportional(32.0),
+                    ui.visuals().weak_text_color(),
+                );
+            }
+            None => {}
+        }
+
+        let name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |n| n.to_string_lossy().into_owned(),
+        );
+        let galley = ui.painter().layout(
+            name,
+            egui::FontId::proportional(12.0),
+            visuals.text_color(),
+            rect.width() - 8.0,
+        );
+        let text_pos = egui::pos2(rect.min.x + 4.0, image_rect.max.y + 4.0);
+        ui.painter()
+            .with_clip_rect(rect)
+            .galley(text_pos, galley, visuals.text_color());
+
+        response.on_hover_text(path.display().to_string())
+    }
+
+    fn load_thumbnails(&mut self, ctx: &egui::Context, recent_files: &[PathBuf]) {
+        let loader = self
+            .loader
+            .get_or_insert_with(|| ThumbnailLoader::new(ctx.clone()));
+        for path in recent_files {
+            if self.requested.insert(path.clone()) {
+                let _ = loader
+                    .tx
+                    .send(ThumbnailCommand::Load(path.clone(), THUMBNAIL_SIZE));
+            }
+        }
+        while let Ok(result) = loader.rx.try_recv() {
+            match result {
+                ThumbnailResult::Success(path, image) => {
+                    let name = format!("recent_{}", path.display());
+                    let texture = ctx.load_texture(name, image, egui::TextureOptions::LINEAR);
+                    self.thumbnails.insert(path, Some(texture));
+                }
+                ThumbnailResult::Placeholder(path) | ThumbnailResult::Error(path, _) => {
+                    self.thumbnails.insert(path, None);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Straighten tool: rotates the image by a free angle over a grid for lining up horizons,
+//! and saves it rotated and cropped to the largest rectangle without empty corners.
+//!
+//! The preview is the texture drawn rotated; only saving touches the pixels.
+
+use crate::batch::save_rgba;
+use crate::image_loader::decode_image;
+use crate::osd;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+const MAX_ANGLE: f32 = 45.0;
+// Spacing of the grid lines, in screen points
+const GRID_SPACING: f32 = 40.0;
+
+#[derive(Default)]
+pub struct Straighten {
+    pub enabled: bool,
+    // Clockwise, in degrees
+    angle: f32,
+    rx: Option<Receiver<Result<String, String>>>,
+    status: Option<String>,
+}
+
+/// Share of a `width` x `height` image left by the largest crop of the same aspect ratio
+/// that fits inside it after rotating by `degrees`.
+fn crop_scale(width: f32, height: f32, degrees: f32) -> f32 {
+    let (sin, cos) = degrees.to_radians().abs().sin_cos();
+    (width / (width * cos + height * sin)).min(height / (width * sin + height * cos))
+}
+
+fn sample_bilinear(image: &image::RgbaImage, x: f32, y: f32) -> image::Rgba<u8> {
+    let max_x = image.width() as f32 - 1.0;
+    let max_y = image.height() as f32 - 1.0;
+    let (x, y) = ((x - 0.5).clamp(0.0, max_x), (y - 0.5).clamp(0.0, max_y));
+    let (x0, y0) = (x.floor(), y.floor());
+    let (x1, y1) = ((x0 + 1.0).min(max_x), (y0 + 1.0).min(max_y));
+    let (fx, fy) = (x - x0, y - y0);
+    let pixel = |x: f32, y: f32| image.get_pixel(x as u32, y as u32).0.map(f32::from);
+    let (a, b, c, d) = (pixel(x0, y0), pixel(x1, y0), pixel(x0, y1), pixel(x1, y1));
+    image::Rgba(std::array::from_fn(|i| {
+        let top = a[i] + (b[i] - a[i]) * fx;
+        let bottom = c[i] + (d[i] - c[i]) * fx;
+        (top + (bottom - top) * fy).round() as u8
+    }))
+}
+
+/// `image` rotated clockwise by `degrees` and cropped to the largest rectangle of the
+/// same aspect ratio inside it.
+fn rotate_and_crop(image: &image::RgbaImage, degrees: f32) -> image::RgbaImage {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let scale = crop_scale(width, height, degrees);
+    let out_width = ((width * scale).floor() as u32).max(1);
+    let out_height = ((height * scale).floor() as u32).max(1);
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    image::RgbaImage::from_fn(out_width, out_height, |x, y| {
+        let dx = x as f32 + 0.5 - out_width as f32 / 2.0;
+        let dy = y as f32 + 0.5 - out_height as f32 / 2.0;
+        // Back into the unrotated image
+        let sx = cos * dx + sin * dy + width / 2.0;
+        let sy = -sin * dx + cos * dy + height / 2.0;
+        sample_bilinear(image, sx, sy)
+    })
+}
+
+fn default_file_name(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map_or("png".into(), |e| e.to_string_lossy());
+    format!("{}_straightened.{}", stem, extension)
+}
+
+impl Straighten {
+    /// Back to level, for a newly shown image.
+    pub fn clear(&mut self) {
+        self.angle = 0.0;
+        self.status = None;
+    }
+
+    /// Draws `texture` rotated into `rect` (where it would sit unrotated), dims what the
+    /// crop cuts off and lays the grid over the rest.
+    pub fn paint(&self, ui: &egui::Ui, texture: egui::TextureId, rect: egui::Rect) {
+        egui::Image::from_texture((texture, rect.size()))
+            .rotate(self.angle.to_radians(), egui::Vec2::splat(0.5))
+            .paint_at(ui, rect);
+
+        let crop = egui::Rect::from_center_size(
+            rect.center(),
+            rect.size() * crop_scale(rect.width(), rect.height(), self.angle),
+        );
+        let painter = ui.painter();
+        let clip = ui.clip_rect();
+        let shade = egui::Color32::from_black_alpha(160);
+        for outside in [
+            egui::Rect::from_min_max(clip.min, egui::pos2(clip.max.x, crop.min.y)),
+            egui::Rect::from_min_max(egui::pos2(clip.min.x, crop.max.y), clip.max),
+            egui::Rect::from_min_max(
+                egui::pos2(clip.min.x, crop.min.y),
+                egui::pos2(crop.min.x, crop.max.y),
+            ),
+            egui::Rect::from_min_max(
+                egui::pos2(crop.max.x, crop.min.y),
+                egui::pos2(clip.max.x, crop.max.y),
+            ),
+        ] {
+            painter.rect_filled(outside, 0.0, shade);
+        }
+
+        let visible = crop.intersect(clip);
+        let stroke = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(90));
+        let mut x = crop.center().x - (crop.width() / 2.0 / GRID_SPACING).floor() * GRID_SPACING;
+        while x <= crop.max.x {
+            painter.vline(x, visible.y_range(), stroke);
+            x += GRID_SPACING;
+        }
+        let mut y = crop.center().y - (crop.height() / 2.0 / GRID_SPACING).floor() * GRID_SPACING;
+        while y <= crop.max.y {
+            painter.hline(visible.x_range(), y, stroke);
+            y += GRID_SPACING;
+        }
+        painter.rect_stroke(crop, 0.0, egui::Stroke::new(1.0, egui::Color32::WHITE));
+    }
+
+    /// Angle slider and saving.
+    pub fn toolbar(&mut self, ctx: &egui::Context, current_path: Option<&Path>) {
+        if let Some(rx) = &self.rx
+            && let Ok(result) = rx.try_recv()
+        {
+            self.rx = None;
+            match result {
+                Ok(msg) => osd::toast(ctx, msg),
+                Err(msg) => self.status = Some(msg),
+            }
+        }
+        if !self.enabled {
+            return;
+        }
+
+        egui::Area::new(egui::Id::new("straighten_toolbar"))
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 10.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::Slider::new(&mut self.angle, -MAX_ANGLE..=MAX_ANGLE)
+                                .step_by(0.1)
+                                .suffix("°")
+                                .text("angle"),
+                        );
+                        if ui.button("Reset").clicked() {
+                            self.angle = 0.0;
+                        }
+                        ui.separator();
+                        let can_save = self.angle != 0.0 && self.rx.is_none();
+                        if let Some(path) = current_path
+                            && ui
+                                .add_enabled(can_save, egui::Button::new("Save As…"))
+                                .clicked()
+                            && let Some(target) = rfd::FileDialog::new()
+                                .add_filter("PNG", &["png"])
+                                .add_filter("JPEG", &["jpg", "jpeg"])
+                                .set_file_name(default_file_name(path))
+                                .save_file()
+                        {
+                            self.save(ctx, path.to_path_buf(), target);
+                        }
+                        if self.rx.is_some() {
+                            ui.spinner();
+                        } else if let Some(status) = &self.status {
+                            ui.label(status);
+                        }
+                    });
+                });
+            });
+    }
+
+    fn save(&mut self, ctx: &egui::Context, path: PathBuf, target: PathBuf) {
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.status = None;
+        let angle = self.angle;
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = decode_image(&path)
+                .and_then(|image| save_rgba(rotate_and_crop(&image.to_rgba8(), angle), &target))
+                .map(|_| format!("Saved {}", target.display()))
+                .map_err(|e| format!("Straighten failed: {}", e));
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crop_keeps_everything_when_level() {
+        assert_eq!(crop_scale(400.0, 300.0, 0.0), 1.0);
+        let image = image::RgbaImage::new(40, 30);
+        assert_eq!(rotate_and_crop(&image, 0.0).dimensions(), (40, 30));
+    }
+
+    #[test]
+    fn square_at_45_degrees_shrinks_by_sqrt_2() {
+        let scale = crop_scale(100.0, 100.0, 45.0);
+        assert!((scale - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-5);
+        assert_eq!(crop_scale(100.0, 100.0, -45.0), scale);
+    }
+}
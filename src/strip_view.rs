@@ -0,0 +1,244 @@
+use crate::animation::exp_decay;
+use crate::image_loader::{IMAGE_TEXTURE_OPTIONS, ImageCommand, ImageLoader, ImageResult};
+use eframe::egui;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Continuous vertical reading mode (webtoon/manga style).
+///
+/// Consecutive folder images are stacked top to bottom at window width. Textures are
+/// streamed in around the visible range and dropped again once they scroll far away,
+/// while the decoded sizes are remembered so the layout stays stable.
+pub struct StripView {
+    loader: ImageLoader,
+    textures: HashMap<PathBuf, egui::TextureHandle>,
+    sizes: HashMap<PathBuf, egui::Vec2>,
+    loading: HashSet<PathBuf>,
+    failed: HashSet<PathBuf>,
+
+    scroll: f32,
+    target_scroll: f32,
+    // Image index to jump to on the next frame (layout is only known while drawing)
+    pending_jump: Option<usize>,
+}
+
+pub enum StripAction {
+    None,
+    // The image at the center of the window changed
+    CurrentChanged(usize),
+}
+
+// Aspect ratio (height / width) assumed for images that haven't been decoded yet
+const PLACEHOLDER_ASPECT: f32 = 1.4;
+const GAP: f32 = 4.0;
+
+impl StripView {
+    pub fn new(ctx: &egui::Context) -> Self {
+        Self {
+            loader: ImageLoader::new(ctx.clone()),
+            textures: HashMap::new(),
+            sizes: HashMap::new(),
+            loading: HashSet::new(),
+            failed: HashSet::new(),
+            scroll: 0.0,
+            target_scroll: 0.0,
+            pending_jump: None,
+        }
+    }
+
+    pub fn jump_to(&mut self, index: usize) {
+        self.pending_jump = Some(index);
+    }
+
+    /// Drops all textures and cached layout, e.g. when the folder changes.
+    pub fn clear(&mut self) {
+        self.textures.clear();
+        self.sizes.clear();
+        self.failed.clear();
+        self.scroll = 0.0;
+        self.target_scroll = 0.0;
+    }
+
+    /// Changes how images are rendered (monitor profile, false color) and re-decodes them.
+    pub fn set_render_option(&mut self, command: ImageCommand) {
+        self.loader.send(command);
+        self.textures.clear();
+    }
+
+    fn item_height(&self, path: &Path, width: f32) -> f32 {
+        match self.sizes.get(path) {
+            Some(size) => width * size.y / size.x.max(1.0),
+            None => width * PLACEHOLDER_ASPECT,
+        }
+    }
+
+    fn process_results(&mut self, ctx: &egui::Context, folder_images: &[PathBuf], width: f32) {
+        while let Ok(result) = self.loader.rx.try_recv() {
+            match result {
+                ImageResult::Success(path, image) => {
+                    self.loading.remove(&path);
+                    let new_size = egui::vec2(image.size[0] as f32, image.size[1] as f32);
+
+                    // Keep the content under the viewport still when an image above it
+                    // resolves to a different height than its placeholder
+                    let old_height = self.item_height(&path, width);
+                    self.sizes.insert(path.clone(), new_size);
+                    let new_height = self.item_height(&path, width);
+                    if let Some(idx) = folder_images.iter().position(|p| p == &path) {
+                        let top = self.item_top(folder_images, idx, width);
+                        if top + old_height <= self.scroll {
+                            self.scroll += new_height - old_height;
+                            self.target_scroll += new_height - old_height;
+                        }
+                    }
+
+                    let texture = ctx.load_texture(
+                        format!("strip_{}", path.to_string_lossy()),
+                        image,
+                        IMAGE_TEXTURE_OPTIONS,
+                    );
+                    self.textures.insert(path, texture);
+                }
+                ImageResult::HighBitDepth(_)
+                | ImageResult::Format(..)
+                | ImageResult::Reduced(..)
+                | ImageResult::Slow(_)
+                | ImageResult::Corrupt(_) => {}
+                #[cfg(feature = "pdf")]
+                ImageResult::PdfPage { .. } => {}
+                ImageResult::Missing(path) | ImageResult::Error(path, _) => {
+                    self.loading.remove(&path);
+                    self.failed.insert(path);
+                }
+            }
+        }
+    }
+
+    fn item_top(&self, folder_images: &[PathBuf], index: usize, width: f32) -> f32 {
+        folder_images[..index]
+            .iter()
+            .map(|p| self.item_height(p, width) + GAP)
+            .sum()
+    }
+
+    pub fn display(&mut self, ui: &mut egui::Ui, folder_images: &[PathBuf]) -> StripAction {
+        let ctx = ui.ctx().clone();
+        let viewport = ui.clip_rect();
+        let width = viewport.width();
+
+        self.process_results(&ctx, folder_images, width);
+
+        if let Some(idx) = self.pending_jump.take()
+            && idx < folder_images.len()
+        {
+            self.target_scroll = self.item_top(folder_images, idx, width);
+            self.scroll = self.target_scroll;
+        }
+
+        // 1. Input
+        let wants_pointer = ctx.wants_pointer_input() || ctx.is_pointer_over_area();
+        if !wants_pointer {
+            let (scroll_delta, drag_delta, dragging) = ui.input(|i| {
+                (
+                    i.smooth_scroll_delta.y,
+                    i.pointer.delta().y,
+                    i.pointer.button_down(egui::PointerButton::Primary)
+                        || i.pointer.button_down(egui::PointerButton::Middle),
+                )
+            });
+            self.target_scroll -= scroll_delta;
+            if dragging {
+                self.target_scroll -= drag_delta;
+                self.scroll -= drag_delta;
+            }
+        }
+
+        let page = viewport.height() * 0.9;
+        if ui.input(|i| i.key_pressed(egui::Key::PageDown) || i.key_pressed(egui::Key::Space)) {
+            self.target_scroll += page;
+        } else if ui.input(|i| i.key_pressed(egui::Key::PageUp)) {
+            self.target_scroll -= page;
+        }
+
+        let total_height: f32 = folder_images
+            .iter()
+            .map(|p| self.item_height(p, width) + GAP)
+            .sum();
+        let max_scroll = (total_height - viewport.height()).max(0.0);
+        self.target_scroll = self.target_scroll.clamp(0.0, max_scroll);
+        self.scroll = self.scroll.clamp(0.0, max_scroll);
+
+        let dt = ui.input(|i| i.stable_dt).min(0.1);
+        if exp_decay(&mut self.scroll, self.target_scroll, dt, 15.0) {
+            ctx.request_repaint();
+        }
+
+        // 2. Layout, streaming and drawing
+        // Decode one screen ahead/behind, keep textures within three screens
+        let load_range = (self.scroll - viewport.height())..(self.scroll + viewport.height() * 2.0);
+        let keep_range =
+            (self.scroll - viewport.height() * 3.0)..(self.scroll + viewport.height() * 4.0);
+        let center = self.scroll + viewport.height() / 2.0;
+
+        let mut current = None;
+        let mut keep = HashSet::new();
+        let mut y = 0.0;
+        for (i, path) in folder_images.iter().enumerate() {
+            let height = self.item_height(path, width);
+            let (top, bottom) = (y, y + height);
+            y = bottom + GAP;
+
+            if top <= center && center < bottom + GAP {
+                current = Some(i);
+            }
+            if bottom >= keep_range.start && top <= keep_range.end {
+                keep.insert(path.clone());
+            }
+            if bottom < load_range.start || top > load_range.end {
+                continue;
+            }
+
+            if !self.textures.contains_key(path)
+                && !self.loading.contains(path)
+                && !self.failed.contains(path)
+            {
+                self.loading.insert(path.clone());
+                self.loader.send(ImageCommand::Load(path.clone()));
+            }
+
+            let rect = egui::Rect::from_min_size(
+                egui::pos2(viewport.min.x, viewport.min.y + top - self.scroll),
+                egui::vec2(width, height),
+            );
+            if !ui.is_rect_visible(rect) {
+                continue;
+            }
+
+            if let Some(texture) = self.textures.get(path) {
+                ui.painter().image(
+                    texture.id(),
+                    rect,
+                    egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                    egui::Color32::WHITE,
+                );
+            } else {
+                ui.painter()
+                    .rect_filled(rect, 0.0, egui::Color32::from_gray(30));
+                ui.painter().text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "...",
+                    egui::FontId::proportional(14.0),
+                    egui::Color32::GRAY,
+                );
+            }
+        }
+
+        self.textures.retain(|k, _| keep.contains(k));
+
+        match current {
+            Some(idx) => StripAction::CurrentChanged(idx),
+            None => StripAction::None,
+        }
+    }
+}
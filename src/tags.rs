@@ -0,0 +1,304 @@
+//! Tags: keywords attached to images, kept in a small JSON database in the data folder
+//! so they work for every format (and read-only files), plus the panel for editing them
+//! and browsing everything with a tag.
+
+use crate::config::data_dir;
+use eframe::egui;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const TAGS_FILE: &str = "tags.json";
+// Suggestions shown under the tag input
+const MAX_SUGGESTIONS: usize = 8;
+
+pub type TagMap = BTreeMap<PathBuf, BTreeSet<String>>;
+
+/// Tags by image path.
+#[derive(Default)]
+pub struct TagStore {
+    path: Option<PathBuf>,
+    tags: TagMap,
+    // Bumped on every change, so cached searches know to rerun
+    revision: u64,
+}
+
+/// Tags are compared and stored trimmed and lowercase, so "Beach" and "beach " are one.
+fn normalize(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+impl TagStore {
+    /// Reads the tag database; a missing or unreadable file gives an empty one.
+    pub fn load() -> Self {
+        let Some(dir) = data_dir() else {
+            return Self::default();
+        };
+        let path = dir.join(TAGS_FILE);
+        let tags = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            tags,
+            revision: 0,
+        }
+    }
+
+    fn save(&mut self) {
+        self.revision += 1;
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        match serde_json::to_string_pretty(&self.tags) {
+            Ok(content) => {
+                if let Err(e) = fs::write(path, content) {
+                    eprintln!("Failed to save tags: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Failed to save tags: {}", e),
+        }
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn all(&self) -> &TagMap {
+        &self.tags
+    }
+
+    /// Adds tags from elsewhere (an imported bundle) to the ones already here.
+    pub fn merge(&mut self, tags: TagMap) {
+        for (path, tags) in tags {
+            self.tags.entry(path).or_default().extend(tags);
+        }
+        self.save();
+    }
+
+    pub fn tags_of(&self, path: &Path) -> impl Iterator<Item = &String> {
+        self.tags.get(path).into_iter().flatten()
+    }
+
+    pub fn add(&mut self, path: &Path, tag: &str) {
+        let tag = normalize(tag);
+        if !tag.is_empty() && self.tags.entry(path.to_path_buf()).or_default().insert(tag) {
+            self.save();
+        }
+    }
+
+    pub fn remove(&mut self, path: &Path, tag: &str) {
+        let Some(tags) = self.tags.get_mut(path) else {
+            return;
+        };
+        if tags.remove(tag) {
+            if tags.is_empty() {
+                self.tags.remove(path);
+            }
+            self.save();
+        }
+    }
+
+    /// Every tag in use and how many images have it.
+    pub fn all_tags(&self) -> BTreeMap<&str, usize> {
+        let mut counts = BTreeMap::new();
+        for tag in self.tags.values().flatten() {
+            *counts.entry(tag.as_str()).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Images tagged `tag`, in path order.
+    pub fn images_with(&self, tag: &str) -> Vec<PathBuf> {
+        self.tags
+            .iter()
+            .filter(|(_, tags)| tags.contains(tag))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Moves the tags of renamed files (old, new) to their new paths.
+    pub fn rename(&mut self, renamed: &[(PathBuf, PathBuf)]) {
+        let mut changed = false;
+        for (old, new) in renamed {
+            if let Some(tags) = self.tags.remove(old) {
+                self.tags.insert(new.clone(), tags);
+                changed = true;
+            }
+        }
+        if changed {
+            self.save();
+        }
+    }
+}
+
+/// Existing tags starting with `input` (or containing it, after those), most used first.
+fn suggestions<'a>(all: &BTreeMap<&'a str, usize>, input: &str) -> Vec<&'a str> {
+    let input = normalize(input);
+    if input.is_empty() {
+        return Vec::new();
+    }
+    let mut matches: Vec<(&str, usize)> = all
+        .iter()
+        .filter(|(tag, _)| tag.contains(input.as_str()) && **tag != input)
+        .map(|(tag, count)| (*tag, *count))
+        .collect();
+    matches
+        .sort_by_key(|(tag, count)| (!tag.starts_with(input.as_str()), std::cmp::Reverse(*count)));
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(tag, _)| tag)
+        .collect()
+}
+
+pub enum TagAction {
+    None,
+    // Show the images with this tag as the navigation list
+    Browse(String, Vec<PathBuf>),
+}
+
+#[derive(Default)]
+pub struct TagPanel {
+    pub open: bool,
+    input: String,
+    search: String,
+}
+
+impl TagPanel {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        store: &mut TagStore,
+        current: Option<&Path>,
+    ) -> TagAction {
+        let mut action = TagAction::None;
+        if !self.open {
+            return action;
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Tags")
+            .open(&mut open)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                if let Some(path) = current {
+                    let tags: Vec<String> = store.tags_of(path).cloned().collect();
+                    ui.horizontal_wrapped(|ui| {
+                        if tags.is_empty() {
+                            ui.weak("No tags");
+                        }
+                        for tag in &tags {
+                            if ui
+                                .button(format!("{} ✕", tag))
+                                .on_hover_text("Remove")
+                                .clicked()
+                            {
+                                store.remove(path, tag);
+                            }
+                        }
+                    });
+
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.input)
+                            .hint_text("Add tag, Enter to confirm")
+                            .desired_width(f32::INFINITY),
+                    );
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        store.add(path, &self.input);
+                        self.input.clear();
+                        response.request_focus();
+                    }
+                    let all = store.all_tags();
+                    let picked = ui
+                        .horizontal_wrapped(|ui| {
+                            suggestions(&all, &self.input)
+                                .into_iter()
+                                .find(|tag| ui.small_button(*tag).clicked())
+                                .map(str::to_string)
+                        })
+                        .inner;
+                    if let Some(tag) = picked {
+                        store.add(path, &tag);
+                        self.input.clear();
+                    }
+                } else {
+                    ui.label("No image loaded.");
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Browse");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.search)
+                            .hint_text("Filter tags")
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+                let search = normalize(&self.search);
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        for (tag, count) in store.all_tags() {
+                            if !tag.contains(search.as_str()) {
+                                continue;
+                            }
+                            if ui
+                                .selectable_label(false, format!("{} ({})", tag, count))
+                                .on_hover_text("Show every image with this tag")
+                                .clicked()
+                            {
+                                let images: Vec<PathBuf> = store
+                                    .images_with(tag)
+                                    .into_iter()
+                                    .filter(|p| p.is_file())
+                                    .collect();
+                                action = TagAction::Browse(tag.to_string(), images);
+                            }
+                        }
+                    });
+            });
+        self.open = open;
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_normalized_and_counted() {
+        let mut store = TagStore::default();
+        let (a, b) = (Path::new("/p/a.jpg"), Path::new("/p/b.jpg"));
+        store.add(a, " Beach");
+        store.add(a, "beach");
+        store.add(b, "beach");
+        store.add(b, "sunset");
+        store.add(b, "   ");
+        assert_eq!(
+            store.all_tags(),
+            BTreeMap::from([("beach", 2), ("sunset", 1)])
+        );
+
+        store.rename(&[(a.to_path_buf(), PathBuf::from("/p/c.jpg"))]);
+        assert_eq!(
+            store.images_with("beach"),
+            [PathBuf::from("/p/b.jpg"), PathBuf::from("/p/c.jpg")]
+        );
+        store.remove(b, "sunset");
+        assert!(!store.all_tags().contains_key("sunset"));
+    }
+
+    #[test]
+    fn suggests_prefix_matches_first() {
+        let all = BTreeMap::from([("holiday", 1), ("old town", 5), ("hold", 3), ("dog", 9)]);
+        assert_eq!(suggestions(&all, "Hol"), ["hold", "holiday"]);
+        assert_eq!(suggestions(&all, "old"), ["old town", "hold"]);
+        assert!(suggestions(&all, "").is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+//! Windows taskbar integration: recent files and folders in the jump list, and the
+//! progress of batch jobs on the taskbar button.
+//!
+//! Jump list entries are shortcuts to the viewer with the path as argument, so they work
+//! without the viewer being registered as a handler for the file types.
+
+use crate::start_screen::recent_folders;
+use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+use std::path::{Path, PathBuf};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+use windows::Win32::System::Com::{CLSCTX_INPROC_SERVER, CoCreateInstance};
+use windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+use windows::Win32::UI::Shell::{
+    DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW,
+    ITaskbarList3, ShellLink, TBPF_NOPROGRESS, TBPF_NORMAL, TaskbarList,
+};
+use windows::core::{HSTRING, Interface, PCWSTR, PROPVARIANT, Result};
+
+pub struct Taskbar {
+    hwnd: Option<HWND>,
+    list: Option<ITaskbarList3>,
+    recent: Vec<PathBuf>,
+    // Done and total items last shown on the button
+    progress: Option<(usize, usize)>,
+}
+
+fn wide(text: &HSTRING) -> PCWSTR {
+    PCWSTR(text.as_ptr())
+}
+
+/// A jump list entry opening `path` in the viewer.
+fn shortcut(exe: &HSTRING, path: &Path) -> Result<IShellLinkW> {
+    let title = path
+        .file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned();
+    let arguments = HSTRING::from(format!("\"{}\"", path.display()));
+    let description = HSTRING::from(path);
+    unsafe {
+        let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+        link.SetPath(wide(exe))?;
+        link.SetArguments(wide(&arguments))?;
+        link.SetDescription(wide(&description))?;
+        link.SetIconLocation(wide(exe), 0)?;
+        // Jump lists show the title property rather than the shortcut's name
+        let properties: IPropertyStore = link.cast()?;
+        properties.SetValue(&PKEY_Title, &PROPVARIANT::from(title.as_str()))?;
+        properties.Commit()?;
+        Ok(link)
+    }
+}
+
+fn category(exe: &HSTRING, paths: &[&Path]) -> Result<IObjectArray> {
+    unsafe {
+        let collection: IObjectCollection =
+            CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+        for path in paths {
+            collection.AddObject(&shortcut(exe, path)?)?;
+        }
+        collection.cast()
+    }
+}
+
+fn build_jump_list(exe: &HSTRING, recent: &[PathBuf]) -> Result<()> {
+    unsafe {
+        let list: ICustomDestinationList =
+            CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+        let mut slots = 0u32;
+        let _removed: IObjectArray = list.BeginList(&mut slots)?;
+
+        let slots = slots as usize;
+        let folders: Vec<&Path> = recent_folders(recent).into_iter().take(slots / 3).collect();
+        let files: Vec<&Path> = recent
+            .iter()
+            .map(PathBuf::as_path)
+            .take(slots - folders.len())
+            .collect();
+        // A category holding an entry the user removed from the list is refused; the
+        // other one still goes in
+        for (name, paths) in [("Recent", files), ("Recent folders", folders)] {
+            if !paths.is_empty() {
+                let name = HSTRING::from(name);
+                let _ = list.AppendCategory(wide(&name), &category(exe, &paths)?);
+            }
+        }
+        list.CommitList()
+    }
+}
+
+impl Taskbar {
+    pub fn new(window: &impl HasWindowHandle, recent: &[PathBuf]) -> Self {
+        let hwnd = match window.window_handle().map(|h| h.as_raw()) {
+            Ok(RawWindowHandle::Win32(handle)) => Some(HWND(handle.hwnd.get() as _)),
+            _ => None,
+        };
+        let list = unsafe {
+            CoCreateInstance::<_, ITaskbarList3>(&TaskbarList, None, CLSCTX_INPROC_SERVER)
+                .and_then(|list| list.HrInit().map(|()| list))
+        };
+        let list = list
+            .map_err(|e| eprintln!("Taskbar progress unavailable: {}", e))
+            .ok();
+
+        let mut taskbar = Self {
+            hwnd,
+            list,
+            recent: Vec::new(),
+            progress: None,
+        };
+        taskbar.set_recent(recent);
+        taskbar
+    }
+
+    /// Puts `recent` into the jump list, if it changed.
+    pub fn set_recent(&mut self, recent: &[PathBuf]) {
+        if self.recent == recent {
+            return;
+        }
+        self.recent = recent.to_vec();
+        let Ok(exe) = std::env::current_exe() else {
+            return;
+        };
+        if let Err(e) = build_jump_list(&HSTRING::from(exe.as_path()), &self.recent) {
+            eprintln!("Failed to update the jump list: {}", e);
+        }
+    }
+
+    /// Shows `progress` (done, total) on the taskbar button, or clears it with `None`.
+    pub fn set_progress(&mut self, progress: Option<(usize, usize)>) {
+        if self.progress == progress {
+            return;
+        }
+        self.progress = progress;
+        let (Some(list), Some(hwnd)) = (&self.list, self.hwnd) else {
+            return;
+        };
+        unsafe {
+            let _ = match progress {
+                Some((done, total)) => list
+                    .SetProgressState(hwnd, TBPF_NORMAL)
+                    .and_then(|()| list.SetProgressValue(hwnd, done as u64, total.max(1) as u64)),
+                None => list.SetProgressState(hwnd, TBPF_NOPROGRESS),
+            };
+        }
+    }
+}
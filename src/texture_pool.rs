@@ -0,0 +1,47 @@
+//! Reuse of full-size image textures. Stepping through photos from the same camera evicts
+//! and uploads textures of the same size over and over; a couple of evicted ones are kept
+//! and overwritten in place instead of allocating new GPU memory for every image. Spares
+//! beyond that are freed right away.
+
+use crate::image_loader::IMAGE_TEXTURE_OPTIONS;
+use eframe::egui;
+
+const SPARE_TEXTURES: usize = 2;
+
+#[derive(Default)]
+pub struct TexturePool {
+    // Evicted textures, oldest first
+    spare: Vec<egui::TextureHandle>,
+}
+
+impl TexturePool {
+    /// Keeps an evicted texture to overwrite later, freeing the oldest spare beyond the limit.
+    pub fn recycle(&mut self, texture: egui::TextureHandle) {
+        self.spare.push(texture);
+        if self.spare.len() > SPARE_TEXTURES {
+            drop(self.spare.remove(0));
+        }
+    }
+
+    /// Uploads `image`, overwriting `previous` (the texture it replaces, if any) or a spare
+    /// texture of the same size when there is one.
+    pub fn upload(
+        &mut self,
+        ctx: &egui::Context,
+        name: String,
+        image: egui::ColorImage,
+        previous: Option<egui::TextureHandle>,
+    ) -> egui::TextureHandle {
+        if let Some(previous) = previous {
+            self.recycle(previous);
+        }
+        match self.spare.iter().rposition(|t| t.size() == image.size) {
+            Some(index) => {
+                let mut texture = self.spare.remove(index);
+                texture.set(image, IMAGE_TEXTURE_OPTIONS);
+                texture
+            }
+            None => ctx.load_texture(name, image, IMAGE_TEXTURE_OPTIONS),
+        }
+    }
+}
@@ -1,377 +1,939 @@
-use crate::animation::exp_decay;
-use crate::image_loader::{ThumbnailCommand, ThumbnailLoader, ThumbnailResult};
-use eframe::egui;
-use std::collections::HashMap;
-use std::path::PathBuf;
-
-pub struct ThumbnailList {
-    is_expanded: bool,
-    expand_progress: f32,
-    hover_opacity: f32, // For the bottom arrow
-
-    loader: ThumbnailLoader,
-    thumbnails: HashMap<PathBuf, egui::TextureHandle>,
-    loading_path: Option<PathBuf>,
-}
-
-pub enum ThumbnailAction {
-    None,
-    SelectImage(usize),
-}
-
-impl Default for ThumbnailList {
-    fn default() -> Self {
-        panic!("Cannot use default without context");
-    }
-}
-
-impl ThumbnailList {
-    pub fn new(ctx: &egui::Context) -> Self {
-        Self {
-            is_expanded: false,
-            expand_progress: 0.0,
-            hover_opacity: 0.0,
-            loader: ThumbnailLoader::new(ctx.clone()),
-            thumbnails: HashMap::new(),
-            loading_path: None,
-        }
-    }
-
-    pub fn update_folder(&mut self, folder_images: &[PathBuf], current_index: usize) {
-        if folder_images.is_empty() {
-            return;
-        }
-
-        // Evict if too many
-        if self.thumbnails.len() > 200 {
-            let mut keys: Vec<_> = self.thumbnails.keys().cloned().collect();
-            // Sort by distance from current index. We just do simple string index comparison for now,
-            // but we can find the exact index in folder_images.
-            keys.sort_by(|a, b| {
-                let a_idx = folder_images.iter().position(|p| p == a).unwrap_or(0);
-                let b_idx = folder_images.iter().position(|p| p == b).unwrap_or(0);
-
-                let dist_a = (a_idx as isize - current_index as isize).abs();
-                let dist_b = (b_idx as isize - current_index as isize).abs();
-
-                dist_b.cmp(&dist_a) // furthest first
-            });
-
-            while self.thumbnails.len() > 100 {
-                if let Some(k) = keys.first() {
-                    self.thumbnails.remove(k);
-                }
-                keys.remove(0);
-            }
-        }
-
-        self.try_load_next(folder_images, current_index);
-    }
-
-    fn try_load_next(&mut self, folder_images: &[PathBuf], current_index: usize) {
-        if self.loading_path.is_none() && !folder_images.is_empty() {
-            // Find nearest missing thumbnail
-            let mut best_idx = None;
-            let mut min_dist = usize::MAX;
-
-            for (i, path) in folder_images.iter().enumerate() {
-                if !self.thumbnails.contains_key(path) {
-                    let dist = (i as isize - current_index as isize).abs() as usize;
-                    if dist < min_dist {
-                        min_dist = dist;
-                        best_idx = Some(i);
-                    }
-                }
-            }
-
-            if let Some(idx) = best_idx {
-                let path = folder_images[idx].clone();
-                self.loading_path = Some(path.clone());
-                let _ = self.loader.tx.send(ThumbnailCommand::Load(path, 128));
-            }
-        }
-    }
-
-    pub fn process_results(
-        &mut self,
-        ctx: &egui::Context,
-        folder_images: &[PathBuf],
-        current_index: usize,
-    ) {
-        let mut loaded = false;
-        while let Ok(result) = self.loader.rx.try_recv() {
-            match result {
-                ThumbnailResult::Success(path, color_image) => {
-                    let texture = ctx.load_texture(
-                        format!("thumb_{}", path.to_string_lossy()),
-                        color_image,
-                        egui::TextureOptions::LINEAR,
-                    );
-                    self.thumbnails.insert(path.clone(), texture);
-                }
-                ThumbnailResult::Error(path, _err) => {
-                    // For now, we can just track that we attempted and failed.
-                    // To prevent infinite loops we could insert a dummy invisible texture or
-                    // just let it not find it and not retry if we had a "failed" set.
-                    // For simplicity we create an empty 1x1 image as placeholder.
-                    let dummy = egui::ColorImage::from_rgba_unmultiplied([1, 1], &[0, 0, 0, 0]);
-                    let texture = ctx.load_texture(
-                        format!("thumb_err_{}", path.to_string_lossy()),
-                        dummy,
-                        egui::TextureOptions::LINEAR,
-                    );
-                    self.thumbnails.insert(path.clone(), texture);
-                }
-            }
-            loaded = true;
-        }
-
-        if loaded {
-            self.loading_path = None;
-            self.try_load_next(folder_images, current_index);
-        }
-    }
-
-    pub fn display(
-        &mut self,
-        ui: &mut egui::Ui,
-        folder_images: &[PathBuf],
-        current_index: usize,
-    ) -> ThumbnailAction {
-        let mut action = ThumbnailAction::None;
-
-        let ctx = ui.ctx().clone();
-        let dt = ctx.input(|i| i.stable_dt).min(0.1);
-
-        // 1. Animations
-        let target_expand = if self.is_expanded { 1.0 } else { 0.0 };
-        if exp_decay(&mut self.expand_progress, target_expand, dt, 15.0) {
-            ctx.request_repaint();
-        }
-
-        let screen_rect = ui.clip_rect();
-        let panel_height = 150.0;
-        let arrow_height = 30.0;
-        let arrow_width = 100.0;
-
-        let current_y_offset = screen_rect.max.y
-            - (panel_height * self.expand_progress)
-            - (arrow_height * (1.0 - self.expand_progress));
-
-        // 2. Control logic
-        let arrow_rect = egui::Rect::from_min_size(
-            egui::pos2(screen_rect.center().x - arrow_width / 2.0, current_y_offset),
-            egui::vec2(arrow_width, arrow_height),
-        );
-
-        let panel_rect = egui::Rect::from_min_size(
-            egui::pos2(screen_rect.min.x, current_y_offset + arrow_height),
-            egui::vec2(screen_rect.width(), panel_height),
-        );
-
-        // Arrow hover interaction
-        let interact_rect = if self.is_expanded {
-            panel_rect.union(arrow_rect)
-        } else {
-            arrow_rect
-        };
-        if let Some(pointer) = ctx.input(|i| i.pointer.hover_pos()) {
-            let is_hovered = interact_rect.contains(pointer);
-            let target_hover = if is_hovered || self.is_expanded {
-                1.0
-            } else {
-                0.3
-            };
-            if exp_decay(&mut self.hover_opacity, target_hover, dt, 10.0) {
-                ctx.request_repaint();
-            }
-
-            if arrow_rect.contains(pointer)
-                && ctx.input(|i| i.pointer.button_clicked(egui::PointerButton::Primary))
-            {
-                self.is_expanded = !self.is_expanded;
-            }
-        } else {
-            let target_hover = if self.is_expanded { 1.0 } else { 0.3 };
-            if exp_decay(&mut self.hover_opacity, target_hover, dt, 10.0) {
-                ctx.request_repaint();
-            }
-        }
-
-        // 3. Draw Arrow
-        let arrow_color =
-            egui::Color32::from_rgba_premultiplied(50, 50, 50, (200.0 * self.hover_opacity) as u8);
-        ui.painter().rect(
-            egui::Rect::from_min_size(
-                arrow_rect.min,
-                egui::vec2(
-                    arrow_rect.width(),
-                    arrow_rect.height() + (panel_height * self.expand_progress),
-                ),
-            ),
-            egui::Rounding {
-                nw: 10.0,
-                ne: 10.0,
-                sw: 0.0,
-                se: 0.0,
-            },
-            arrow_color,
-            egui::Stroke::NONE,
-        );
-
-        // Draw icon on arrow manually (vector shape instead of text characters)
-        let center = arrow_rect.center();
-        let arrow_color_fg = egui::Color32::from_white_alpha((255.0 * self.hover_opacity) as u8);
-
-        let path = if self.is_expanded {
-            vec![
-                center + egui::vec2(-6.0, -2.0),
-                center + egui::vec2(6.0, -2.0),
-                center + egui::vec2(0.0, 4.0),
-            ]
-        } else {
-            vec![
-                center + egui::vec2(-6.0, 2.0),
-                center + egui::vec2(6.0, 2.0),
-                center + egui::vec2(0.0, -4.0),
-            ]
-        };
-
-        ui.painter().add(egui::Shape::convex_polygon(
-            path,
-            arrow_color_fg,
-            egui::Stroke::NONE,
-        ));
-
-        // 4. Draw Panel when expanding
-        if self.expand_progress > 0.01 {
-            let panel_bg = egui::Color32::from_rgba_premultiplied(
-                30,
-                30,
-                30,
-                (220.0 * self.expand_progress) as u8,
-            );
-            ui.painter().rect(
-                panel_rect,
-                egui::Rounding::ZERO,
-                panel_bg,
-                egui::Stroke::NONE,
-            );
-
-            let mut child_ui = ui.new_child(
-                egui::UiBuilder::new()
-                    .max_rect(panel_rect.shrink(10.0))
-                    .layout(egui::Layout::left_to_right(egui::Align::Center)),
-            );
-
-            egui::ScrollArea::horizontal()
-                .auto_shrink([false, false])
-                .drag_to_scroll(true)
-                .show(&mut child_ui, |ui| {
-                    if folder_images.is_empty() {
-                        ui.label("No images");
-                        return;
-                    }
-
-                    for (i, path) in folder_images.iter().enumerate() {
-                        let is_current = i == current_index;
-
-                        let thrb_width = 100.0;
-                        let item_size = egui::vec2(thrb_width, 130.0);
-                        let (rect, response) =
-                            ui.allocate_exact_size(item_size, egui::Sense::click());
-
-                        if response.clicked() {
-                            action = ThumbnailAction::SelectImage(i);
-                        }
-
-                        if ui.is_rect_visible(rect) {
-                            // Border & Background
-                            let bg_color = if response.hovered() {
-                                egui::Color32::from_rgba_premultiplied(80, 80, 80, 100)
-                            } else {
-                                egui::Color32::TRANSPARENT
-                            };
-
-                            let stroke = if is_current {
-                                egui::Stroke::new(2.0, egui::Color32::LIGHT_BLUE)
-                            } else {
-                                egui::Stroke::NONE
-                            };
-
-                            ui.painter().rect(rect, 5.0, bg_color, stroke);
-
-                            // Image
-                            let mut thumb_rect = rect;
-                            thumb_rect.min.y += 25.0; // Space for text
-                            thumb_rect = thumb_rect.shrink(5.0);
-
-                            if let Some(texture) = self.thumbnails.get(path) {
-                                // Keep aspect ratio
-                                let aspect = texture.size()[0] as f32 / texture.size()[1] as f32;
-                                let mut draw_size = thumb_rect.size();
-                                if draw_size.x / draw_size.y > aspect {
-                                    draw_size.x = draw_size.y * aspect;
-                                } else {
-                                    draw_size.y = draw_size.x / aspect;
-                                }
-
-                                let center_min = thumb_rect.center() - draw_size / 2.0;
-                                let draw_rect = egui::Rect::from_min_size(center_min, draw_size);
-
-                                ui.painter().image(
-                                    texture.id(),
-                                    draw_rect,
-                                    egui::Rect::from_min_max(
-                                        egui::pos2(0.0, 0.0),
-                                        egui::pos2(1.0, 1.0),
-                                    ),
-                                    egui::Color32::WHITE,
-                                );
-                            } else {
-                                ui.painter().rect(
-                                    thumb_rect,
-                                    2.0,
-                                    egui::Color32::from_gray(50),
-                                    egui::Stroke::NONE,
-                                );
-                                ui.painter().text(
-                                    thumb_rect.center(),
-                                    egui::Align2::CENTER_CENTER,
-                                    "...",
-                                    egui::FontId::proportional(14.0),
-                                    egui::Color32::GRAY,
-                                );
-                            }
-
-                            // File name
-                            let file_name_str =
-                                path.file_name().unwrap_or_default().to_string_lossy();
-                            let mut display_name = file_name_str.to_string();
-                            if display_name.chars().count() > 12 {
-                                display_name = display_name.chars().take(11).collect();
-                                display_name.push('…');
-                            }
-
-                            let text_color = if is_current {
-                                egui::Color32::LIGHT_BLUE
-                            } else {
-                                egui::Color32::WHITE
-                            };
-                            let text_pos = rect.min + egui::vec2(5.0, 5.0);
-
-                            ui.painter().text(
-                                text_pos,
-                                egui::Align2::LEFT_TOP,
-                                display_name,
-                                egui::FontId::proportional(12.0),
-                                text_color,
-                            );
-                        }
-                    }
-                });
-        }
-
-        action
-    }
-}
+//! The filmstrip: a tab at a window edge that expands into a strip of thumbnails,
+//! loaded in the background nearest-first around the current image.
+
+use crate::animation::exp_decay;
+use crate::image_loader::{SharedDecodedCache, ThumbnailCommand, ThumbnailLoader, ThumbnailResult};
+use crate::selection::Selection;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// The seek bar only re-prioritizes thumbnail loading once the pointer rests this long
+const SCRUB_DEBOUNCE: Duration = Duration::from_millis(80);
+// Resting the pointer on a thumbnail this long preloads its full image
+const PREFETCH_DELAY: Duration = Duration::from_millis(300);
+// How often failed thumbnails are checked for changes to their file
+const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Screen edge the filmstrip is docked to; side docks show a vertical list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FilmstripDock {
+    #[default]
+    Bottom,
+    Left,
+    Right,
+}
+
+/// Filmstrip state and its thumbnail textures.
+pub struct ThumbnailList {
+    is_expanded: bool,
+    dock: FilmstripDock,
+    expand_progress: f32,
+    hover_opacity: f32, // For the bottom arrow
+
+    loader: ThumbnailLoader,
+    thumbnails: HashMap<PathBuf, egui::TextureHandle>,
+    // Online-only cloud files, shown with a badge until they've been downloaded
+    placeholders: HashSet<PathBuf>,
+    // Thumbnails that couldn't be made, with the error and the file's modification time
+    // then; they're tried again when clicked or once the file changes
+    failed: HashMap<PathBuf, (String, Option<SystemTime>)>,
+    last_retry_check: Instant,
+    loading_path: Option<PathBuf>,
+
+    // Keyboard cursor while the panel is expanded (arrows/Tab move it, Enter opens)
+    cursor: Option<usize>,
+    scroll_to_cursor: bool,
+
+    // Index under the seek bar while it's dragged, and when the pointer got there
+    scrub: Option<(usize, Instant)>,
+    // Thumbnail under the pointer, since when, and whether its image was asked for
+    hover: Option<(usize, Instant, bool)>,
+}
+
+/// What the user did in the filmstrip this frame.
+pub enum ThumbnailAction {
+    None,
+    SelectImage(usize),
+    // Picked from the arrow tab's context menu
+    SetDock(FilmstripDock),
+    // Open the batch operations window for the selection
+    OpenBatch,
+    // The pointer rests on this thumbnail; preload its image so a click shows it at once
+    Prefetch(usize),
+}
+
+/// Index of the image closest to `center` that `missing` holds for (the earlier one on ties).
+fn nearest_missing(
+    folder_images: &[PathBuf],
+    center: usize,
+    missing: impl Fn(&PathBuf) -> bool,
+) -> Option<usize> {
+    folder_images
+        .iter()
+        .enumerate()
+        .filter(|(_, path)| missing(path))
+        .min_by_key(|(i, _)| i.abs_diff(center))
+        .map(|(i, _)| i)
+}
+
+/// A picture frame with a crack through it, with a warning badge in the corner.
+fn broken_image(painter: &egui::Painter, rect: egui::Rect) {
+    painter.rect(rect, 2.0, egui::Color32::from_gray(50), egui::Stroke::NONE);
+    let frame = egui::Rect::from_center_size(rect.center(), egui::vec2(36.0, 28.0));
+    let stroke = egui::Stroke::new(1.5, egui::Color32::GRAY);
+    painter.rect_stroke(frame, 2.0, stroke);
+    let crack = [
+        egui::pos2(frame.center().x + 2.0, frame.top()),
+        egui::pos2(frame.center().x - 4.0, frame.center().y - 2.0),
+        egui::pos2(frame.center().x + 4.0, frame.center().y + 3.0),
+        egui::pos2(frame.center().x - 2.0, frame.bottom()),
+    ];
+    painter.line_segment([crack[0], crack[1]], stroke);
+    painter.line_segment([crack[1], crack[2]], stroke);
+    painter.line_segment([crack[2], crack[3]], stroke);
+    painter.text(
+        rect.left_bottom() + egui::vec2(3.0, -2.0),
+        egui::Align2::LEFT_BOTTOM,
+        "⚠",
+        egui::FontId::proportional(14.0),
+        egui::Color32::from_rgb(230, 170, 60),
+    );
+}
+
+impl Default for ThumbnailList {
+    fn default() -> Self {
+        panic!("Cannot use default without context");
+    }
+}
+
+impl ThumbnailList {
+    /// Creates the filmstrip and starts its thumbnail worker, which reuses the images in
+    /// `decoded` that were already decoded for viewing.
+    pub fn new(ctx: &egui::Context, decoded: SharedDecodedCache) -> Self {
+        Self {
+            is_expanded: false,
+            dock: FilmstripDock::default(),
+            expand_progress: 0.0,
+            hover_opacity: 0.0,
+            loader: ThumbnailLoader::with_decoded(ctx.clone(), decoded),
+            thumbnails: HashMap::new(),
+            placeholders: HashSet::new(),
+            failed: HashMap::new(),
+            last_retry_check: Instant::now(),
+            loading_path: None,
+            cursor: None,
+            scroll_to_cursor: false,
+            scrub: None,
+            hover: None,
+        }
+    }
+
+    /// Moves the filmstrip to another window edge.
+    pub fn set_dock(&mut self, dock: FilmstripDock) {
+        self.dock = dock;
+    }
+
+    /// Number of thumbnail textures and the GPU memory they take.
+    pub fn texture_stats(&self) -> (usize, usize) {
+        let bytes = self.thumbnails.values().map(|t| t.size()[0] * t.size()[1] * 4).sum();
+        (self.thumbnails.len(), bytes)
+    }
+
+    /// While expanded, the panel takes the arrow keys for its cursor.
+    pub fn is_expanded(&self) -> bool {
+        self.is_expanded
+    }
+
+    /// Moves the keyboard cursor; returns the image to open when Enter is pressed.
+    fn handle_keys(
+        &mut self,
+        ctx: &egui::Context,
+        len: usize,
+        current_index: usize,
+        selection: &mut Selection,
+    ) -> Option<usize> {
+        if ctx.wants_keyboard_input() {
+            return None;
+        }
+        if !selection.is_empty() && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            selection.clear();
+        }
+        let cursor = self.cursor.unwrap_or(current_index).min(len - 1);
+        let (back, forward, home, end, enter) = ctx.input(|i| {
+            let tab = i.key_pressed(egui::Key::Tab);
+            (
+                i.key_pressed(egui::Key::ArrowLeft)
+                    || i.key_pressed(egui::Key::ArrowUp)
+                    || (tab && i.modifiers.shift),
+                i.key_pressed(egui::Key::ArrowRight)
+                    || i.key_pressed(egui::Key::ArrowDown)
+                    || (tab && !i.modifiers.shift),
+                i.key_pressed(egui::Key::Home),
+                i.key_pressed(egui::Key::End),
+                i.key_pressed(egui::Key::Enter),
+            )
+        });
+
+        let moved = if back {
+            cursor.saturating_sub(1)
+        } else if forward {
+            (cursor + 1).min(len - 1)
+        } else if home {
+            0
+        } else if end {
+            len - 1
+        } else {
+            cursor
+        };
+        if Some(moved) != self.cursor {
+            self.cursor = Some(moved);
+            self.scroll_to_cursor = true;
+        }
+        enter.then_some(moved)
+    }
+
+    /// Resets thumbnail loading for a new or reordered image list.
+    pub fn update_folder(&mut self, folder_images: &[PathBuf], current_index: usize) {
+        if folder_images.is_empty() {
+            return;
+        }
+
+        // Evict if too many
+        if self.thumbnails.len() > 200 {
+            let mut keys: Vec<_> = self.thumbnails.keys().cloned().collect();
+            // Sort by distance from current index. We just do simple string index comparison for now,
+            // but we can find the exact index in folder_images.
+            keys.sort_by(|a, b| {
+                let a_idx = folder_images.iter().position(|p| p == a).unwrap_or(0);
+                let b_idx = folder_images.iter().position(|p| p == b).unwrap_or(0);
+
+                let dist_a = (a_idx as isize - current_index as isize).abs();
+                let dist_b = (b_idx as isize - current_index as isize).abs();
+
+                dist_b.cmp(&dist_a) // furthest first
+            });
+
+            while self.thumbnails.len() > 100 {
+                if let Some(k) = keys.first() {
+                    self.thumbnails.remove(k);
+                }
+                keys.remove(0);
+            }
+        }
+
+        self.try_load_next(folder_images, current_index);
+    }
+
+    /// Tries the thumbnail of a cloud placeholder again, once opening it downloaded it.
+    pub fn refresh_placeholder(&mut self, path: &Path) {
+        self.placeholders.remove(path);
+    }
+
+    fn try_load_next(&mut self, folder_images: &[PathBuf], current_index: usize) {
+        // While scrubbing, thumbnails around the seek position come first
+        let current_index = match self.scrub {
+            Some((index, since)) if since.elapsed() >= SCRUB_DEBOUNCE => index,
+            _ => current_index,
+        };
+        if self.loading_path.is_none() {
+            let missing = |path: &PathBuf| {
+                !self.thumbnails.contains_key(path)
+                    && !self.placeholders.contains(path)
+                    && !self.failed.contains_key(path)
+            };
+            if let Some(idx) = nearest_missing(folder_images, current_index, missing) {
+                let path = folder_images[idx].clone();
+                self.loading_path = Some(path.clone());
+                let _ = self.loader.tx.send(ThumbnailCommand::Load(path, 128));
+            }
+        }
+    }
+
+    /// Uploads finished thumbnails and queues the next ones; call once per frame.
+    pub fn process_results(
+        &mut self,
+        ctx: &egui::Context,
+        folder_images: &[PathBuf],
+        current_index: usize,
+    ) {
+        let mut loaded = false;
+        while let Ok(result) = self.loader.rx.try_recv() {
+            match result {
+                ThumbnailResult::Success(path, color_image) => {
+                    let texture = ctx.load_texture(
+                        format!("thumb_{}", path.to_string_lossy()),
+                        color_image,
+                        egui::TextureOptions::LINEAR,
+                    );
+                    self.thumbnails.insert(path.clone(), texture);
+                }
+                ThumbnailResult::Placeholder(path) => {
+                    self.placeholders.insert(path);
+                }
+                ThumbnailResult::Error(path, err) => {
+                    let mtime = modified(&path);
+                    self.failed.insert(path, (err, mtime));
+                }
+            }
+            loaded = true;
+        }
+
+        if loaded {
+            self.loading_path = None;
+            self.try_load_next(folder_images, current_index);
+        }
+
+        // Files rewritten since they failed (say, a download that finished) get another try
+        if !self.failed.is_empty() && self.last_retry_check.elapsed() >= RETRY_INTERVAL {
+            self.last_retry_check = Instant::now();
+            let before = self.failed.len();
+            self.failed.retain(|path, (_, mtime)| modified(path) == *mtime);
+            if self.failed.len() < before {
+                self.try_load_next(folder_images, current_index);
+            }
+        }
+    }
+
+    /// Thin seek bar along the panel's inner edge. Dragging it previews the image under
+    /// the pointer; releasing (or clicking) returns the index to open.
+    fn seek_bar(
+        &mut self,
+        ui: &mut egui::Ui,
+        panel_rect: egui::Rect,
+        folder_images: &[PathBuf],
+        current_index: usize,
+    ) -> Option<usize> {
+        const THICKNESS: f32 = 4.0;
+        let vertical = self.dock != FilmstripDock::Bottom;
+        // Inside the panel's padding, so it never covers thumbnails or the arrow tab
+        let bar = match self.dock {
+            FilmstripDock::Bottom => egui::Rect::from_min_max(
+                egui::pos2(panel_rect.min.x + 10.0, panel_rect.min.y + 3.0),
+                egui::pos2(panel_rect.max.x - 10.0, panel_rect.min.y + 3.0 + THICKNESS),
+            ),
+            FilmstripDock::Left => egui::Rect::from_min_max(
+                egui::pos2(panel_rect.max.x - 3.0 - THICKNESS, panel_rect.min.y + 10.0),
+                egui::pos2(panel_rect.max.x - 3.0, panel_rect.max.y - 10.0),
+            ),
+            FilmstripDock::Right => egui::Rect::from_min_max(
+                egui::pos2(panel_rect.min.x + 3.0, panel_rect.min.y + 10.0),
+                egui::pos2(panel_rect.min.x + 3.0 + THICKNESS, panel_rect.max.y - 10.0),
+            ),
+        };
+        let last = folder_images.len() - 1;
+        let fraction_at = |pos: egui::Pos2| {
+            let t = if vertical {
+                (pos.y - bar.min.y) / bar.height()
+            } else {
+                (pos.x - bar.min.x) / bar.width()
+            };
+            t.clamp(0.0, 1.0)
+        };
+
+        let response = ui.interact(
+            bar.expand(4.0),
+            ui.id().with("seek_bar"),
+            egui::Sense::click_and_drag(),
+        );
+        if let Some(pos) = response.interact_pointer_pos()
+            && (response.is_pointer_button_down_on() || response.clicked())
+        {
+            let index = (fraction_at(pos) * last as f32).round() as usize;
+            if self.scrub.is_none_or(|(i, _)| i != index) {
+                self.scrub = Some((index, Instant::now()));
+                ui.ctx().request_repaint_after(SCRUB_DEBOUNCE);
+            }
+            self.try_load_next(folder_images, current_index);
+        }
+
+        // Track and position
+        let shown = self.scrub.map_or(current_index, |(i, _)| i);
+        let t = shown as f32 / last.max(1) as f32;
+        let alpha = (255.0 * self.expand_progress) as u8;
+        let knob = if vertical {
+            egui::pos2(bar.center().x, bar.min.y + bar.height() * t)
+        } else {
+            egui::pos2(bar.min.x + bar.width() * t, bar.center().y)
+        };
+        let filled = egui::Rect::from_min_max(bar.min, if vertical {
+            egui::pos2(bar.max.x, knob.y)
+        } else {
+            egui::pos2(knob.x, bar.max.y)
+        });
+        let painter = ui.painter();
+        painter.rect_filled(bar, 2.0, egui::Color32::from_white_alpha(alpha / 6));
+        painter.rect_filled(filled, 2.0, egui::Color32::LIGHT_BLUE.gamma_multiply(self.expand_progress));
+        let knob_radius = if response.hovered() || self.scrub.is_some() { 6.0 } else { 4.0 };
+        painter.circle_filled(knob, knob_radius, egui::Color32::from_white_alpha(alpha));
+
+        // Preview of the image under the pointer
+        if let Some((index, _)) = self.scrub
+            && response.is_pointer_button_down_on()
+        {
+            let path = &folder_images[index];
+            let (pivot, offset) = match self.dock {
+                FilmstripDock::Bottom => (egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -16.0)),
+                FilmstripDock::Left => (egui::Align2::LEFT_CENTER, egui::vec2(16.0, 0.0)),
+                FilmstripDock::Right => (egui::Align2::RIGHT_CENTER, egui::vec2(-16.0, 0.0)),
+            };
+            egui::Area::new(ui.id().with("seek_preview"))
+                .order(egui::Order::Tooltip)
+                .fixed_pos(knob + offset)
+                .pivot(pivot)
+                .interactable(false)
+                .show(ui.ctx(), |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        match self.thumbnails.get(path) {
+                            Some(texture) => {
+                                ui.add(egui::Image::new(texture).max_size(egui::vec2(160.0, 120.0)));
+                            }
+                            None => {
+                                ui.allocate_ui(egui::vec2(160.0, 90.0), |ui| {
+                                    ui.centered_and_justified(|ui| ui.spinner());
+                                });
+                            }
+                        }
+                        ui.label(format!(
+                            "{} / {}  {}",
+                            index + 1,
+                            folder_images.len(),
+                            path.file_name().unwrap_or_default().to_string_lossy()
+                        ));
+                    });
+                });
+        }
+
+        if response.drag_stopped() || response.clicked() {
+            return self.scrub.take().map(|(index, _)| index);
+        }
+        None
+    }
+
+    /// Loaded and total thumbnail counts (and failures) next to the arrow tab.
+    fn counts(&self, ui: &egui::Ui, arrow_rect: egui::Rect, folder_images: &[PathBuf]) {
+        let loaded = folder_images
+            .iter()
+            .filter(|p| self.thumbnails.contains_key(*p))
+            .count();
+        let failed = folder_images
+            .iter()
+            .filter(|p| self.failed.contains_key(*p))
+            .count();
+        let mut text = format!("{} / {}", loaded, folder_images.len());
+        if failed > 0 {
+            text += &format!("  ⚠ {}", failed);
+        }
+        let (pos, align) = match self.dock {
+            FilmstripDock::Bottom => (
+                arrow_rect.right_center() + egui::vec2(8.0, 0.0),
+                egui::Align2::LEFT_CENTER,
+            ),
+            FilmstripDock::Left | FilmstripDock::Right => (
+                arrow_rect.center_bottom() + egui::vec2(0.0, 8.0),
+                egui::Align2::CENTER_TOP,
+            ),
+        };
+        let alpha = self.expand_progress;
+        let painter = ui.painter();
+        let galley = painter.layout_no_wrap(
+            text,
+            egui::FontId::proportional(11.0),
+            egui::Color32::LIGHT_GRAY.gamma_multiply(alpha),
+        );
+        let rect = align.anchor_size(pos, galley.size()).expand2(egui::vec2(4.0, 2.0));
+        painter.rect_filled(rect, 3.0, egui::Color32::from_black_alpha((160.0 * alpha) as u8));
+        painter.galley(rect.min + egui::vec2(4.0, 2.0), galley, egui::Color32::LIGHT_GRAY);
+    }
+
+    /// A labelled break between groups of thumbnails, across the strip.
+    fn separator(&self, ui: &mut egui::Ui, label: &str) {
+        let vertical = self.dock != FilmstripDock::Bottom;
+        let size = if vertical {
+            egui::vec2(100.0, 22.0)
+        } else {
+            egui::vec2(44.0, 130.0)
+        };
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        if !ui.is_rect_visible(rect) {
+            return;
+        }
+        let painter = ui.painter();
+        let stroke = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(80));
+        let font = egui::FontId::proportional(10.0);
+        // Date on one line and time on the next when the strip runs horizontally
+        let (date, time) = label.split_once(' ').unwrap_or((label, ""));
+        if vertical {
+            painter.hline(rect.x_range(), rect.bottom() - 2.0, stroke);
+            painter.text(
+                rect.left_center(),
+                egui::Align2::LEFT_CENTER,
+                label,
+                font,
+                egui::Color32::LIGHT_GRAY,
+            );
+        } else {
+            painter.vline(rect.left() + 2.0, rect.y_range(), stroke);
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                format!("{}\n{}", date.get(5..).unwrap_or(date), time),
+                font,
+                egui::Color32::LIGHT_GRAY,
+            );
+        }
+    }
+
+    /// Draws the filmstrip over `ui` and handles its input. `separators` are labelled
+    /// breaks before the given indices (ascending); `badge` gives the text and colour to
+    /// show in the corner of a thumbnail, if any.
+    pub fn display(
+        &mut self,
+        ui: &mut egui::Ui,
+        folder_images: &[PathBuf],
+        current_index: usize,
+        selection: &mut Selection,
+        separators: &[(usize, String)],
+        badge: impl Fn(&Path) -> Option<(String, egui::Color32)>,
+    ) -> ThumbnailAction {
+        let mut action = ThumbnailAction::None;
+
+        let ctx = ui.ctx().clone();
+        let dt = ctx.input(|i| i.stable_dt).min(0.1);
+
+        // 1. Animations
+        let target_expand = if self.is_expanded { 1.0 } else { 0.0 };
+        if exp_decay(&mut self.expand_progress, target_expand, dt, 15.0) {
+            ctx.request_repaint();
+        }
+
+        let screen_rect = ui.clip_rect();
+        let panel_size = 150.0;
+        let arrow_height = 30.0;
+        let arrow_width = 100.0;
+
+        // 2. Control logic
+        // The arrow tab sits on the panel's inner edge and slides in with it
+        let (arrow_rect, panel_rect) = match self.dock {
+            FilmstripDock::Bottom => {
+                let current_y_offset = screen_rect.max.y
+                    - (panel_size * self.expand_progress)
+                    - (arrow_height * (1.0 - self.expand_progress));
+                (
+                    egui::Rect::from_min_size(
+                        egui::pos2(screen_rect.center().x - arrow_width / 2.0, current_y_offset),
+                        egui::vec2(arrow_width, arrow_height),
+                    ),
+                    egui::Rect::from_min_size(
+                        egui::pos2(screen_rect.min.x, current_y_offset + arrow_height),
+                        egui::vec2(screen_rect.width(), panel_size),
+                    ),
+                )
+            }
+            FilmstripDock::Left => {
+                let panel_x = screen_rect.min.x - panel_size * (1.0 - self.expand_progress);
+                (
+                    egui::Rect::from_min_size(
+                        egui::pos2(panel_x + panel_size, screen_rect.center().y - arrow_width / 2.0),
+                        egui::vec2(arrow_height, arrow_width),
+                    ),
+                    egui::Rect::from_min_size(
+                        egui::pos2(panel_x, screen_rect.min.y),
+                        egui::vec2(panel_size, screen_rect.height()),
+                    ),
+                )
+            }
+            FilmstripDock::Right => {
+                let panel_x = screen_rect.max.x - panel_size * self.expand_progress;
+                (
+                    egui::Rect::from_min_size(
+                        egui::pos2(panel_x - arrow_height, screen_rect.center().y - arrow_width / 2.0),
+                        egui::vec2(arrow_height, arrow_width),
+                    ),
+                    egui::Rect::from_min_size(
+                        egui::pos2(panel_x, screen_rect.min.y),
+                        egui::vec2(panel_size, screen_rect.height()),
+                    ),
+                )
+            }
+        };
+
+        // Right-clicking the arrow tab picks the dock edge
+        ui.interact(arrow_rect, ui.id().with("filmstrip_tab"), egui::Sense::click())
+            .context_menu(|ui| {
+                for (dock, label) in [
+                    (FilmstripDock::Bottom, "Dock bottom"),
+                    (FilmstripDock::Left, "Dock left"),
+                    (FilmstripDock::Right, "Dock right"),
+                ] {
+                    if ui.radio(self.dock == dock, label).clicked() {
+                        self.dock = dock;
+                        action = ThumbnailAction::SetDock(dock);
+                        ui.close_menu();
+                    }
+                }
+            });
+
+        // Arrow hover interaction
+        let interact_rect = if self.is_expanded {
+            panel_rect.union(arrow_rect)
+        } else {
+            arrow_rect
+        };
+        if let Some(pointer) = ctx.input(|i| i.pointer.hover_pos()) {
+            let is_hovered = interact_rect.contains(pointer);
+            let target_hover = if is_hovered || self.is_expanded {
+                1.0
+            } else {
+                0.3
+            };
+            if exp_decay(&mut self.hover_opacity, target_hover, dt, 10.0) {
+                ctx.request_repaint();
+            }
+
+            if arrow_rect.contains(pointer)
+                && ctx.input(|i| i.pointer.button_clicked(egui::PointerButton::Primary))
+            {
+                self.is_expanded = !self.is_expanded;
+                self.cursor = None;
+            }
+        } else {
+            let target_hover = if self.is_expanded { 1.0 } else { 0.3 };
+            if exp_decay(&mut self.hover_opacity, target_hover, dt, 10.0) {
+                ctx.request_repaint();
+            }
+        }
+
+        // 3. Draw Arrow
+        let arrow_color =
+            egui::Color32::from_rgba_premultiplied(50, 50, 50, (200.0 * self.hover_opacity) as u8);
+        let extension = panel_size * self.expand_progress;
+        let (tab_rect, rounding) = match self.dock {
+            FilmstripDock::Bottom => (
+                egui::Rect::from_min_size(
+                    arrow_rect.min,
+                    egui::vec2(arrow_rect.width(), arrow_rect.height() + extension),
+                ),
+                egui::Rounding {
+                    nw: 10.0,
+                    ne: 10.0,
+                    sw: 0.0,
+                    se: 0.0,
+                },
+            ),
+            FilmstripDock::Left => (
+                egui::Rect::from_min_max(
+                    arrow_rect.min - egui::vec2(extension, 0.0),
+                    arrow_rect.max,
+                ),
+                egui::Rounding {
+                    nw: 0.0,
+                    ne: 10.0,
+                    sw: 0.0,
+                    se: 10.0,
+                },
+            ),
+            FilmstripDock::Right => (
+                egui::Rect::from_min_max(
+                    arrow_rect.min,
+                    arrow_rect.max + egui::vec2(extension, 0.0),
+                ),
+                egui::Rounding {
+                    nw: 10.0,
+                    ne: 0.0,
+                    sw: 10.0,
+                    se: 0.0,
+                },
+            ),
+        };
+        ui.painter().rect(tab_rect, rounding, arrow_color, egui::Stroke::NONE);
+
+        // Draw icon on arrow manually (vector shape instead of text characters)
+        let center = arrow_rect.center();
+        let arrow_color_fg = egui::Color32::from_white_alpha((255.0 * self.hover_opacity) as u8);
+
+        // Points towards the screen edge when expanded (collapse), away from it otherwise
+        let towards_edge = match self.dock {
+            FilmstripDock::Bottom => egui::vec2(0.0, 1.0),
+            FilmstripDock::Left => egui::vec2(-1.0, 0.0),
+            FilmstripDock::Right => egui::vec2(1.0, 0.0),
+        };
+        let tip = if self.is_expanded {
+            towards_edge
+        } else {
+            -towards_edge
+        };
+        let side = egui::vec2(tip.y, tip.x);
+        let path = vec![
+            center - tip * 2.0 - side * 6.0,
+            center - tip * 2.0 + side * 6.0,
+            center + tip * 4.0,
+        ];
+
+        ui.painter().add(egui::Shape::convex_polygon(
+            path,
+            arrow_color_fg,
+            egui::Stroke::NONE,
+        ));
+
+        if self.is_expanded
+            && !folder_images.is_empty()
+            && let Some(index) = self.handle_keys(&ctx, folder_images.len(), current_index, selection)
+        {
+            action = ThumbnailAction::SelectImage(index);
+        }
+
+        // 4. Draw Panel when expanding
+        if self.expand_progress > 0.01 {
+            // Covers the gaps between thumbnails, so presses there don't reach the image
+            ui.interact(panel_rect, ui.id().with("filmstrip_panel"), egui::Sense::click_and_drag());
+            let panel_bg = egui::Color32::from_rgba_premultiplied(
+                30,
+                30,
+                30,
+                (220.0 * self.expand_progress) as u8,
+            );
+            ui.painter().rect(
+                panel_rect,
+                egui::Rounding::ZERO,
+                panel_bg,
+                egui::Stroke::NONE,
+            );
+
+            if !folder_images.is_empty() {
+                self.counts(ui, arrow_rect, folder_images);
+            }
+
+            if folder_images.len() > 1
+                && let Some(index) = self.seek_bar(ui, panel_rect, folder_images, current_index)
+            {
+                action = ThumbnailAction::SelectImage(index);
+            }
+
+            let (layout, scroll_area) = if self.dock == FilmstripDock::Bottom {
+                (
+                    egui::Layout::left_to_right(egui::Align::Center),
+                    egui::ScrollArea::horizontal(),
+                )
+            } else {
+                (
+                    egui::Layout::top_down(egui::Align::Center),
+                    egui::ScrollArea::vertical(),
+                )
+            };
+            let mut child_ui = ui.new_child(
+                egui::UiBuilder::new()
+                    .max_rect(panel_rect.shrink(10.0))
+                    .layout(layout),
+            );
+
+            scroll_area
+                .auto_shrink([false, false])
+                .drag_to_scroll(true)
+                .show(&mut child_ui, |ui| {
+                    if folder_images.is_empty() {
+                        ui.label("No images");
+                        return;
+                    }
+
+                    let mut separators = separators.iter().peekable();
+                    for (i, path) in folder_images.iter().enumerate() {
+                        let is_current = i == current_index;
+
+                        if let Some((_, label)) = separators.next_if(|(start, _)| *start == i) {
+                            self.separator(ui, label);
+                        }
+
+                        let thrb_width = 100.0;
+                        let item_size = egui::vec2(thrb_width, 130.0);
+                        let (rect, response) =
+                            ui.allocate_exact_size(item_size, egui::Sense::click());
+
+                        if response.clicked() {
+                            self.cursor = Some(i);
+                            if self.failed.remove(path).is_some() {
+                                self.try_load_next(folder_images, current_index);
+                            }
+                            if selection.click(folder_images, i, ui.input(|input| input.modifiers)) {
+                                action = ThumbnailAction::SelectImage(i);
+                            }
+                        }
+
+                        // Right-clicking outside the selection acts on that image alone
+                        if response.secondary_clicked() && !selection.contains(path) {
+                            selection.select_only(folder_images, i);
+                        }
+                        response.context_menu(|ui| {
+                            if ui.button(format!("Batch actions ({} selected)…", selection.len())).clicked() {
+                                action = ThumbnailAction::OpenBatch;
+                                ui.close_menu();
+                            }
+                        });
+
+                        if response.hovered() && i != current_index {
+                            match &mut self.hover {
+                                Some((index, since, requested)) if *index == i => {
+                                    if !*requested
+                                        && since.elapsed() >= PREFETCH_DELAY
+                                        && matches!(action, ThumbnailAction::None)
+                                    {
+                                        *requested = true;
+                                        action = ThumbnailAction::Prefetch(i);
+                                    }
+                                }
+                                _ => {
+                                    self.hover = Some((i, Instant::now(), false));
+                                    ctx.request_repaint_after(PREFETCH_DELAY);
+                                }
+                            }
+                        }
+
+                        let is_cursor = self.is_expanded && self.cursor == Some(i);
+                        if is_cursor && self.scroll_to_cursor {
+                            response.scroll_to_me(Some(egui::Align::Center));
+                            self.scroll_to_cursor = false;
+                        }
+
+                        if ui.is_rect_visible(rect) {
+                            // Border & Background
+                            let bg_color = if selection.contains(path) {
+                                egui::Color32::from_rgba_premultiplied(40, 90, 160, 140)
+                            } else if response.hovered() {
+                                egui::Color32::from_rgba_premultiplied(80, 80, 80, 100)
+                            } else {
+                                egui::Color32::TRANSPARENT
+                            };
+
+                            let stroke = if is_cursor {
+                                egui::Stroke::new(2.0, egui::Color32::WHITE)
+                            } else if is_current {
+                                egui::Stroke::new(2.0, egui::Color32::LIGHT_BLUE)
+                            } else {
+                                egui::Stroke::NONE
+                            };
+
+                            ui.painter().rect(rect, 5.0, bg_color, stroke);
+
+                            // Image
+                            let mut thumb_rect = rect;
+                            thumb_rect.min.y += 25.0; // Space for text
+                            thumb_rect = thumb_rect.shrink(5.0);
+
+                            if let Some(texture) = self.thumbnails.get(path) {
+                                // Keep aspect ratio
+                                let aspect = texture.size()[0] as f32 / texture.size()[1] as f32;
+                                let mut draw_size = thumb_rect.size();
+                                if draw_size.x / draw_size.y > aspect {
+                                    draw_size.x = draw_size.y * aspect;
+                                } else {
+                                    draw_size.y = draw_size.x / aspect;
+                                }
+
+                                let center_min = thumb_rect.center() - draw_size / 2.0;
+                                let draw_rect = egui::Rect::from_min_size(center_min, draw_size);
+
+                                ui.painter().image(
+                                    texture.id(),
+                                    draw_rect,
+                                    egui::Rect::from_min_max(
+                                        egui::pos2(0.0, 0.0),
+                                        egui::pos2(1.0, 1.0),
+                                    ),
+                                    egui::Color32::WHITE,
+                                );
+                            } else if let Some((err, _)) = self.failed.get(path) {
+                                broken_image(ui.painter(), thumb_rect);
+                                response
+                                    .clone()
+                                    .on_hover_text(format!("Couldn't load: {}\nClick to try again", err));
+                            } else {
+                                let is_placeholder = self.placeholders.contains(path);
+                                ui.painter().rect(
+                                    thumb_rect,
+                                    2.0,
+                                    egui::Color32::from_gray(50),
+                                    egui::Stroke::NONE,
+                                );
+                                ui.painter().text(
+                                    thumb_rect.center(),
+                                    egui::Align2::CENTER_CENTER,
+                                    if is_placeholder { "☁" } else { "..." },
+                                    egui::FontId::proportional(if is_placeholder { 24.0 } else { 14.0 }),
+                                    egui::Color32::GRAY,
+                                );
+                            }
+
+                            if let Some((text, color)) = badge(path) {
+                                let galley = ui.painter().layout_no_wrap(
+                                    text,
+                                    egui::FontId::proportional(10.0),
+                                    color,
+                                );
+                                let badge_rect = egui::Rect::from_min_size(
+                                    thumb_rect.right_bottom() - galley.size() - egui::vec2(4.0, 2.0),
+                                    galley.size() + egui::vec2(4.0, 2.0),
+                                );
+                                ui.painter().rect_filled(
+                                    badge_rect,
+                                    3.0,
+                                    egui::Color32::from_black_alpha(180),
+                                );
+                                ui.painter().galley(
+                                    badge_rect.min + egui::vec2(2.0, 1.0),
+                                    galley,
+                                    color,
+                                );
+                            }
+
+                            // File name
+                            let file_name_str =
+                                path.file_name().unwrap_or_default().to_string_lossy();
+                            let mut display_name = file_name_str.to_string();
+                            if display_name.chars().count() > 12 {
+                                display_name = display_name.chars().take(11).collect();
+                                display_name.push('…');
+                            }
+
+                            let text_color = if is_current {
+                                egui::Color32::LIGHT_BLUE
+                            } else {
+                                egui::Color32::WHITE
+                            };
+                            let text_pos = rect.min + egui::vec2(5.0, 5.0);
+
+                            ui.painter().text(
+                                text_pos,
+                                egui::Align2::LEFT_TOP,
+                                display_name,
+                                egui::FontId::proportional(12.0),
+                                text_color,
+                            );
+                        }
+                    }
+                });
+        }
+
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thumbnails_load_nearest_first() {
+        let images: Vec<PathBuf> = (0..6).map(|i| PathBuf::from(format!("{}.jpg", i))).collect();
+        let loaded = |p: &PathBuf| ["2.jpg", "3.jpg", "4.jpg"].iter().any(|n| p.ends_with(n));
+        // 1 and 5 are equally far from 3; the earlier one wins
+        assert_eq!(nearest_missing(&images, 3, |p| !loaded(p)), Some(1));
+        assert_eq!(nearest_missing(&images, 5, |p| !loaded(p)), Some(5));
+        assert_eq!(nearest_missing(&images, 0, |_| false), None);
+    }
+}
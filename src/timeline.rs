@@ -0,0 +1,214 @@
+//! Capture date order: images sorted by EXIF DateTimeOriginal and split into shooting
+//! sessions wherever the gap between two shots is long or the day changes.
+
+use crate::metadata::capture_time;
+use eframe::egui;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+// A pause this long between shots starts a new session
+const SESSION_GAP: i64 = 2 * 60 * 60;
+const DAY: i64 = 24 * 60 * 60;
+
+/// Year, month and day of a count of days since 1970-01-01.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Days since 1970-01-01 of a calendar date.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Seconds since 1970 of a camera time. Cameras record local time without a zone, so
+/// this is only good for ordering and gaps, and formats back to the same wall time.
+pub fn timestamp(time: &exif::DateTime) -> i64 {
+    let days = days_from_civil(
+        i64::from(time.year),
+        u32::from(time.month),
+        u32::from(time.day),
+    );
+    days * DAY + i64::from(time.hour) * 3600 + i64::from(time.minute) * 60 + i64::from(time.second)
+}
+
+/// `2024-05-31 14:25:01`
+pub fn format_time(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(DAY));
+    let seconds = timestamp.rem_euclid(DAY);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Start and end (exclusive) in seconds since 1970 of `2024`, `2024-05` or `2024-05-31`.
+pub fn period(text: &str) -> Option<(i64, i64)> {
+    let mut parts = text.split('-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: Option<u32> = parts.next().map(str::parse).transpose().ok()?;
+    let day: Option<u32> = parts.next().map(str::parse).transpose().ok()?;
+    if parts.next().is_some()
+        || month.is_some_and(|m| !(1..=12).contains(&m))
+        || day.is_some_and(|d| !(1..=31).contains(&d))
+    {
+        return None;
+    }
+    let start = days_from_civil(year, month.unwrap_or(1), day.unwrap_or(1));
+    let end = match (month, day) {
+        (Some(_), Some(_)) => start + 1,
+        (Some(12), None) => days_from_civil(year + 1, 1, 1),
+        (Some(m), None) => days_from_civil(year, m + 1, 1),
+        _ => days_from_civil(year + 1, 1, 1),
+    };
+    Some((start * DAY, end * DAY))
+}
+
+/// `images` oldest shot first; images without a capture date go last, by name.
+fn capture_order(mut images: Vec<PathBuf>, times: &HashMap<PathBuf, i64>) -> Vec<PathBuf> {
+    images.sort_by(|a, b| match (times.get(a), times.get(b)) {
+        (Some(ta), Some(tb)) => ta.cmp(tb).then_with(|| a.cmp(b)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.cmp(b),
+    });
+    images
+}
+
+/// Indices in `images` (in capture order) where a session starts, with its label.
+pub fn sessions(images: &[PathBuf], times: &HashMap<PathBuf, i64>) -> Vec<(usize, String)> {
+    let mut starts = Vec::new();
+    let mut previous: Option<Option<i64>> = None;
+    for (i, path) in images.iter().enumerate() {
+        let time = times.get(path).copied();
+        let new_session = match (previous, time) {
+            (None, _) => true,
+            (Some(Some(last)), Some(time)) => {
+                time - last > SESSION_GAP || last.div_euclid(DAY) != time.div_euclid(DAY)
+            }
+            (Some(last), time) => last.is_some() != time.is_some(),
+        };
+        if new_session {
+            let label = match time {
+                // Down to the minute
+                Some(time) => format_time(time)[..16].to_string(),
+                None => "No date".to_string(),
+            };
+            starts.push((i, label));
+        }
+        previous = Some(time);
+    }
+    starts
+}
+
+/// Reads the capture dates of a folder in the background.
+pub struct CaptureDateSort {
+    rx: Receiver<(PathBuf, Option<i64>)>,
+    images: Vec<PathBuf>,
+    times: HashMap<PathBuf, i64>,
+    done: usize,
+}
+
+impl CaptureDateSort {
+    pub fn start(ctx: &egui::Context, images: &[PathBuf]) -> Self {
+        let (tx, rx) = channel();
+        let paths = images.to_vec();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            for path in paths {
+                let time = capture_time(&path).map(|t| timestamp(&t));
+                if tx.send((path, time)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+        Self {
+            rx,
+            images: images.to_vec(),
+            times: HashMap::new(),
+            done: 0,
+        }
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.done as f32 / self.images.len().max(1) as f32
+    }
+
+    /// Collects dates read so far; returns the order and the dates once all are in.
+    pub fn poll(&mut self) -> Option<(Vec<PathBuf>, HashMap<PathBuf, i64>)> {
+        while let Ok((path, time)) = self.rx.try_recv() {
+            self.done += 1;
+            if let Some(time) = time {
+                self.times.insert(path, time);
+            }
+        }
+        if self.done < self.images.len() {
+            return None;
+        }
+        let times = std::mem::take(&mut self.times);
+        let order = capture_order(std::mem::take(&mut self.images), &times);
+        Some((order, times))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_round_trip() {
+        assert_eq!(civil_from_days(19_874), (2024, 5, 31));
+        assert_eq!(days_from_civil(2024, 5, 31), 19_874);
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        let time = exif::DateTime::from_ascii(b"2024:02:29 23:59:58").unwrap();
+        assert_eq!(format_time(timestamp(&time)), "2024-02-29 23:59:58");
+        let (start, end) = period("2024-02").unwrap();
+        assert_eq!((end - start) / DAY, 29);
+        assert_eq!(format_time(start), "2024-02-01 00:00:00");
+        assert!(period("2024-13").is_none() && period("spring").is_none());
+    }
+
+    #[test]
+    fn splits_sessions_on_long_gaps_and_new_days() {
+        let at = |day: i64, hour: i64, minute: i64| day * DAY + hour * 3600 + minute * 60;
+        let images: Vec<PathBuf> = (0..6)
+            .map(|i| PathBuf::from(format!("{}.jpg", i)))
+            .collect();
+        let times = HashMap::from([
+            (images[0].clone(), at(19_874, 9, 0)),
+            (images[1].clone(), at(19_874, 10, 30)),
+            // Lunch break
+            (images[2].clone(), at(19_874, 14, 0)),
+            (images[3].clone(), at(19_875, 0, 10)),
+            (images[4].clone(), at(19_875, 0, 20)),
+        ]);
+        let starts: Vec<usize> = sessions(&images, &times).iter().map(|(i, _)| *i).collect();
+        assert_eq!(starts, [0, 2, 3, 5]);
+        assert_eq!(sessions(&images, &times)[1].1, "2024-05-31 14:00");
+        assert_eq!(
+            capture_order(images.iter().rev().cloned().collect(), &times),
+            images
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Tray icon: closing the window minimizes the viewer, the tray menu brings it back.
+//!
+//! On Linux the icon lives on its own GTK thread, as libappindicator needs a running GTK
+//! main loop; on Windows it is driven by the window's message loop.
+
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, channel};
+use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tray_icon::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+
+pub enum TrayCommand {
+    Show,
+    Open(PathBuf),
+    PasteClipboard,
+    Quit,
+}
+
+const OPEN_PREFIX: &str = "open:";
+
+pub struct Tray {
+    rx: Receiver<TrayCommand>,
+    #[cfg(not(target_os = "linux"))]
+    icon: TrayIcon,
+    #[cfg(target_os = "linux")]
+    menu_tx: std::sync::mpsc::Sender<Vec<PathBuf>>,
+    recent: Vec<PathBuf>,
+}
+
+fn build_menu(recent: &[PathBuf]) -> Menu {
+    let recent_menu = Submenu::new("Recent files", !recent.is_empty());
+    for path in recent {
+        let label = path.file_name().unwrap_or_default().to_string_lossy();
+        // The path travels in the item id so events don't need a lookup table
+        let id = format!("{}{}", OPEN_PREFIX, path.to_string_lossy());
+        let _ = recent_menu.append(&MenuItem::with_id(id, label, true, None));
+    }
+
+    let menu = Menu::new();
+    let _ = menu.append_items(&[
+        &MenuItem::with_id("show", "Show viewer", true, None),
+        &recent_menu,
+        &MenuItem::with_id("paste", "Paste from clipboard", true, None),
+        &PredefinedMenuItem::separator(),
+        &MenuItem::with_id("quit", "Quit", true, None),
+    ]);
+    menu
+}
+
+fn build_icon(recent: &[PathBuf]) -> Result<TrayIcon, String> {
+    let image = image::load_from_memory(include_bytes!("../icon.ico"))
+        .map_err(|e| e.to_string())?
+        .thumbnail(64, 64)
+        .to_rgba8();
+    let (width, height) = image.dimensions();
+    let icon = tray_icon::Icon::from_rgba(image.into_raw(), width, height)
+        .map_err(|e| e.to_string())?;
+
+    TrayIconBuilder::new()
+        .with_icon(icon)
+        .with_tooltip("Better Image Viewer")
+        .with_menu(Box::new(build_menu(recent)))
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+impl Tray {
+    pub fn new(ctx: &egui::Context, recent: &[PathBuf]) -> Result<Self, String> {
+        let (tx, rx) = channel();
+
+        let menu_ctx = ctx.clone();
+        let menu_tx = tx.clone();
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            let command = match event.id.0.as_str() {
+                "show" => TrayCommand::Show,
+                "paste" => TrayCommand::PasteClipboard,
+                "quit" => TrayCommand::Quit,
+                id => match id.strip_prefix(OPEN_PREFIX) {
+                    Some(path) => TrayCommand::Open(PathBuf::from(path)),
+                    None => return,
+                },
+            };
+            let _ = menu_tx.send(command);
+            menu_ctx.request_repaint();
+        }));
+
+        let icon_ctx = ctx.clone();
+        TrayIconEvent::set_event_handler(Some(move |event: TrayIconEvent| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                let _ = tx.send(TrayCommand::Show);
+                icon_ctx.request_repaint();
+            }
+        }));
+
+        #[cfg(target_os = "linux")]
+        {
+            let (menu_tx, menu_rx) = channel::<Vec<PathBuf>>();
+            let (ready_tx, ready_rx) = channel();
+            let recent = recent.to_vec();
+            std::thread::spawn(move || {
+                if let Err(e) = gtk::init() {
+                    let _ = ready_tx.send(Err(e.to_string()));
+                    return;
+                }
+                let icon = match build_icon(&recent) {
+                    Ok(icon) => icon,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+
+                // Menu updates come from the UI thread
+                gtk::glib::timeout_add_local(std::time::Duration::from_millis(250), move || {
+                    while let Ok(recent) = menu_rx.try_recv() {
+                        icon.set_menu(Some(Box::new(build_menu(&recent))));
+                    }
+                    gtk::glib::ControlFlow::Continue
+                });
+                gtk::main();
+            });
+            ready_rx.recv().map_err(|e| e.to_string())??;
+
+            Ok(Self {
+                rx,
+                menu_tx,
+                recent: recent.to_vec(),
+            })
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        Ok(Self {
+            rx,
+            icon: build_icon(recent)?,
+            recent: recent.to_vec(),
+        })
+    }
+
+    /// Refreshes the "Recent files" submenu when the list changed.
+    pub fn set_recent(&mut self, recent: &[PathBuf]) {
+        if self.recent == recent {
+            return;
+        }
+        self.recent = recent.to_vec();
+        #[cfg(target_os = "linux")]
+        let _ = self.menu_tx.send(self.recent.clone());
+        #[cfg(not(target_os = "linux"))]
+        self.icon.set_menu(Some(Box::new(build_menu(&self.recent))));
+    }
+
+    pub fn poll(&self) -> Option<TrayCommand> {
+        self.rx.try_recv().ok()
+    }
+}
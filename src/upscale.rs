@@ -0,0 +1,172 @@
+//! "Upscale 2x/4x" through an external AI upscaler (Real-ESRGAN and the like).
+//!
+//! The upscaler is a command from the config, run like the external tools; the result is
+//! saved next to the original and compared with it before opening.
+
+use crate::external_tools::expand;
+use crate::image_loader::{decode_image, resize_linear};
+use crate::split_compare;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+// Longest side of the upscaled image shown in the comparison
+const PREVIEW_SIZE: u32 = 1024;
+
+pub enum UpscaleAction {
+    None,
+    Open(PathBuf),
+}
+
+pub struct Upscaler {
+    pub open: bool,
+    scale: u32,
+    rx: Option<Receiver<Result<(PathBuf, egui::ColorImage), String>>>,
+    // Image being upscaled, and the finished result with its preview
+    source: Option<PathBuf>,
+    result: Option<(PathBuf, egui::TextureHandle)>,
+    split: f32,
+    status: Option<String>,
+}
+
+impl Default for Upscaler {
+    fn default() -> Self {
+        Self {
+            open: false,
+            scale: 2,
+            rx: None,
+            source: None,
+            result: None,
+            split: 0.5,
+            status: None,
+        }
+    }
+}
+
+/// Where the upscaled copy of `path` goes: next to it, named after the scale.
+fn output_path(path: &Path, scale: u32) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}_x{}.png", stem, scale))
+}
+
+fn run(command: &str, path: &Path, scale: u32) -> Result<(PathBuf, egui::ColorImage), String> {
+    let output = output_path(path, scale);
+    let args: Vec<String> = expand(command, path)
+        .into_iter()
+        .map(|arg| {
+            arg.replace("{output}", &output.to_string_lossy())
+                .replace("{scale}", &scale.to_string())
+        })
+        .collect();
+    let (program, rest) = args.split_first().ok_or("No upscaler configured")?;
+    let result = Command::new(program)
+        .args(rest)
+        .output()
+        .map_err(|e| format!("Could not run {} ({}); is it installed?", program, e))?;
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let last_line = stderr.lines().last().unwrap_or_default();
+        return Err(format!(
+            "Upscaling failed ({}): {}",
+            result.status, last_line
+        ));
+    }
+
+    let image = decode_image(&output).map_err(|e| format!("Upscaling failed: {}", e))?;
+    let preview = resize_linear(&image, PREVIEW_SIZE, PREVIEW_SIZE);
+    let size = [preview.width() as usize, preview.height() as usize];
+    Ok((
+        output,
+        egui::ColorImage::from_rgba_unmultiplied(size, preview.as_raw()),
+    ))
+}
+
+impl Upscaler {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        current: Option<&Path>,
+        current_texture: Option<&egui::TextureHandle>,
+        command: &str,
+    ) -> UpscaleAction {
+        if let Some(rx) = &self.rx
+            && let Ok(result) = rx.try_recv()
+        {
+            self.rx = None;
+            match result {
+                Ok((path, image)) => {
+                    let texture = ctx.load_texture("upscaled", image, egui::TextureOptions::LINEAR);
+                    self.result = Some((path, texture));
+                }
+                Err(msg) => self.status = Some(msg),
+            }
+        }
+
+        let mut action = UpscaleAction::None;
+        if !self.open {
+            return action;
+        }
+        // A result belongs to the image it was made from
+        if self.source.as_deref() != current {
+            self.result = None;
+            self.status = None;
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Upscale")
+            .open(&mut open)
+            .default_width(540.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.scale, 2, "2x");
+                    ui.selectable_value(&mut self.scale, 4, "4x");
+                    let enabled = current.is_some() && self.rx.is_none();
+                    if ui
+                        .add_enabled(enabled, egui::Button::new("Upscale"))
+                        .on_hover_text(command)
+                        .clicked()
+                        && let Some(path) = current
+                    {
+                        self.start(ctx, command.to_string(), path.to_path_buf());
+                    }
+                    if self.rx.is_some() {
+                        ui.spinner();
+                        ui.label("Upscaling…");
+                    }
+                });
+
+                if let Some((path, after)) = &self.result {
+                    if let Some(before) = current_texture {
+                        let max_size = egui::vec2(ui.available_width(), 400.0);
+                        split_compare::show(ui, before, after, &mut self.split, max_size);
+                    }
+                    ui.horizontal(|ui| {
+                        ui.weak(format!("Saved as {}", path.display()));
+                        if ui.button("Open").clicked() {
+                            action = UpscaleAction::Open(path.clone());
+                        }
+                    });
+                } else if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+            });
+        self.open = open;
+        action
+    }
+
+    fn start(&mut self, ctx: &egui::Context, command: String, path: PathBuf) {
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.source = Some(path.clone());
+        self.result = None;
+        self.status = None;
+        let scale = self.scale;
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(run(&command, &path, scale));
+            ctx.request_repaint();
+        });
+    }
+}
@@ -0,0 +1,34 @@
+//! First-frame previews of video files, so mixed photo/video camera folders browse cleanly.
+//!
+//! Frames are grabbed with the `ffmpeg` command line tool (on `PATH`, or pointed to with
+//! `BIV_FFMPEG`). Playback is left to the system's default player.
+
+use std::path::Path;
+use std::process::Command;
+
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "m4v", "mov", "mkv", "webm", "avi"];
+
+pub fn is_video(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| VIDEO_EXTENSIONS.iter().any(|v| v.eq_ignore_ascii_case(ext)))
+}
+
+/// Decodes the first frame of the video at `path`.
+pub fn first_frame(path: &Path) -> Result<image::DynamicImage, String> {
+    let output = Command::new(std::env::var_os("BIV_FFMPEG").unwrap_or_else(|| "ffmpeg".into()))
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-frames:v", "1", "-f", "image2pipe", "-c:v", "png", "-"])
+        .output()
+        .map_err(|e| format!("Could not run ffmpeg ({}); is it installed?", e))?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(format!(
+            "No video frame: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    image::load_from_memory_with_format(&output.stdout, image::ImageFormat::Png)
+        .map_err(|e| e.to_string())
+}
@@ -1,97 +1,736 @@
-use crate::animation::{exp_decay, exp_decay_vec2};
-use eframe::egui;
-
-pub struct ViewState {
-    pub zoom: f32,
-    pub pan: egui::Vec2,
-    pub target_zoom: f32,
-    pub target_pan: egui::Vec2,
-}
-
-impl Default for ViewState {
-    fn default() -> Self {
-        Self {
-            zoom: 1.0,
-            pan: egui::Vec2::ZERO,
-            target_zoom: 1.0,
-            target_pan: egui::Vec2::ZERO,
-        }
-    }
-}
-
-impl ViewState {
-    pub fn reset(&mut self) {
-        self.zoom = 1.0;
-        self.pan = egui::Vec2::ZERO;
-        self.target_zoom = 1.0;
-        self.target_pan = egui::Vec2::ZERO;
-    }
-
-    pub fn process_input(&mut self, ui: &mut egui::Ui) {
-        let wants_pointer = ui.ctx().wants_pointer_input() || ui.ctx().is_pointer_over_area();
-
-        // 0. Handle Double Click to Reset
-        if !wants_pointer
-            && ui.input(|i| {
-                i.pointer
-                    .button_double_clicked(egui::PointerButton::Primary)
-            })
-        {
-            self.target_zoom = 1.0;
-            self.target_pan = egui::Vec2::ZERO;
-        }
-
-        // 1. Handle Zoom (Scroll)
-        let scroll_delta = if wants_pointer {
-            0.0
-        } else {
-            ui.input(|i| i.smooth_scroll_delta.y)
-        };
-
-        if scroll_delta != 0.0 {
-            // A typical mouse wheel click is 50 points.
-            // We scale the scroll delta to determine how many "steps" to zoom.
-            let zoom_steps = scroll_delta / 50.0;
-            let zoom_multiplier = 1.2_f32.powf(zoom_steps);
-
-            let pointer_pos = ui
-                .input(|i| i.pointer.hover_pos())
-                .unwrap_or(ui.clip_rect().center());
-
-            let old_target_zoom = self.target_zoom;
-            self.target_zoom *= zoom_multiplier;
-            self.target_zoom = self.target_zoom.clamp(0.01, 500.0);
-
-            // Calculate the new target pan so the zoom is centered on the mouse pointer
-            let center_screen = ui.clip_rect().center().to_vec2();
-            let rel_m = pointer_pos.to_vec2() - center_screen;
-
-            self.target_pan =
-                rel_m - (rel_m - self.target_pan) * (self.target_zoom / old_target_zoom);
-        }
-
-        // 2. Handle Pan (Mouse Drag)
-        let is_dragging = !wants_pointer
-            && ui.input(|i| {
-                i.pointer.button_down(egui::PointerButton::Primary)
-                    || i.pointer.button_down(egui::PointerButton::Middle)
-            });
-
-        if is_dragging {
-            let delta = ui.input(|i| i.pointer.delta());
-            self.target_pan += delta;
-            self.pan += delta; // Instant pan for responsiveness
-        }
-
-        // 3. Animate Zoom and Pan
-        let dt = ui.input(|i| i.stable_dt).min(0.1);
-        let speed = 15.0;
-
-        let zoom_animating = exp_decay(&mut self.zoom, self.target_zoom, dt, speed);
-        let pan_animating = exp_decay_vec2(&mut self.pan, self.target_pan, dt, speed);
-
-        if zoom_animating || pan_animating {
-            ui.ctx().request_repaint(); // Keep repainting until animation finishes
-        }
-    }
-}
+//! Zoom and pan of the main view, with the math between screen and image coordinates.
+//!
+//! `zoom` is screen points per image pixel and `pan` the offset of the image center from
+//! the viewport center. Input moves the `target_` values, which `zoom`/`pan` ease towards.
+//! Wheel zooms ease as one transform about the pointer, so the pixel under it stays put
+//! all the way rather than drifting while zoom and pan settle separately.
+//!
+//! What the user sees as the zoom is the "scale", device pixels per image pixel: at 100%
+//! one image pixel covers one monitor pixel whatever the monitor's scale factor.
+
+use crate::animation::{exp_decay, exp_decay_vec2};
+use crate::osd;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+/// How the image is sized to the window.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FitMode {
+    /// Wheel zooms about the cursor, pan is unconstrained.
+    Free,
+    /// Image width fills the window, wheel scrolls vertically (tall scans, webtoons).
+    FitWidth,
+    /// Image height fills the window, wheel scrolls horizontally (panoramas).
+    FitHeight,
+}
+
+/// Which drags pan the image. Middle-drag and Space+drag always do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PanScheme {
+    /// Plain left-drag pans too, unless a tool uses it.
+    #[default]
+    LeftDrag,
+    /// Left-drag is left to the tools (and future selections); pan with Space held.
+    MiddleOrSpace,
+}
+
+/// Zoom limits and wheel steps, from the settings. Limits are scales (1.0 is 100%).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ZoomSettings {
+    pub min: f32,
+    pub max: f32,
+    // Zoom factor per wheel notch, and per notch with Ctrl held
+    pub step: f32,
+    pub fine_step: f32,
+    // Scale a double-click zooms to
+    pub double_click: f32,
+}
+
+impl Default for ZoomSettings {
+    fn default() -> Self {
+        Self {
+            min: 0.01,
+            max: 500.0,
+            step: 1.2,
+            fine_step: 1.05,
+            double_click: 1.0,
+        }
+    }
+}
+
+impl ZoomSettings {
+    /// The settings with nonsensical values (a step below 1, limits the wrong way
+    /// round) replaced by the defaults.
+    fn sanitized(self) -> Self {
+        let default = Self::default();
+        let limits_ok = self.min > 0.0 && self.max > self.min && self.max.is_finite();
+        let step = |s: f32, d: f32| if s > 1.0 && s.is_finite() { s } else { d };
+        Self {
+            min: if limits_ok { self.min } else { default.min },
+            max: if limits_ok { self.max } else { default.max },
+            step: step(self.step, default.step),
+            fine_step: step(self.fine_step, default.fine_step),
+            double_click: if self.double_click > 0.0 && self.double_click.is_finite() {
+                self.double_click
+            } else {
+                default.double_click
+            },
+        }
+    }
+}
+
+/// The current and target zoom/pan of one image.
+pub struct ViewState {
+    pub zoom: f32,
+    pub pan: egui::Vec2,
+    pub target_zoom: f32,
+    pub target_pan: egui::Vec2,
+
+    pub fit_mode: FitMode,
+    // Tools that use plain left-drag (e.g. measuring) turn this off; middle-drag always pans
+    pub left_drag_pans: bool,
+    pub pan_scheme: PanScheme,
+    // Set on reset so the next frame snaps to the start of the image in fit modes
+    fit_pending: bool,
+
+    // Screen-space start of an in-progress rubber band selection (Ctrl + drag)
+    selection_start: Option<egui::Pos2>,
+    // Set while a drag that began on the image itself (not on UI over it) is held
+    grabbed: bool,
+
+    // Settled (zoom, pan) states of the current image, for stepping back and forward
+    history: Vec<(f32, egui::Vec2)>,
+    history_index: usize,
+
+    // Pointer offset from the viewport center and input time of the last wheel zoom,
+    // for springing back from an overshoot about the same point
+    zoom_anchor: egui::Vec2,
+    last_wheel_zoom: f64,
+    // Anchor of the zoom animation in progress and the targets it was set up for; while
+    // the targets are unchanged, pan is derived from zoom to keep the anchor fixed
+    anchored: Option<(egui::Vec2, f32, egui::Vec2)>,
+
+    // Device pixels per point of the monitor the window is on
+    pixels_per_point: f32,
+
+    settings: ZoomSettings,
+}
+
+// Oldest view states are dropped beyond this
+const MAX_HISTORY: usize = 50;
+
+// Wheel scroll of one notch on a typical mouse, in points
+const WHEEL_NOTCH: f32 = 50.0;
+// The wheel can push this far past a zoom limit, against growing resistance, before the
+// view springs back once scrolling pauses
+const OVERSHOOT: f32 = 1.2;
+const OVERSHOOT_RESISTANCE: f32 = 0.35;
+const OVERSHOOT_SETTLE: f64 = 0.15;
+
+impl Default for ViewState {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: egui::Vec2::ZERO,
+            target_zoom: 1.0,
+            target_pan: egui::Vec2::ZERO,
+            fit_mode: FitMode::Free,
+            left_drag_pans: true,
+            pan_scheme: PanScheme::default(),
+            fit_pending: false,
+            selection_start: None,
+            grabbed: false,
+            history: Vec::new(),
+            history_index: 0,
+            zoom_anchor: egui::Vec2::ZERO,
+            last_wheel_zoom: 0.0,
+            anchored: None,
+            pixels_per_point: 1.0,
+            settings: ZoomSettings::default(),
+        }
+    }
+}
+
+impl ViewState {
+    /// Back to 100% and centered, with the view history cleared, for a newly shown image.
+    pub fn reset(&mut self) {
+        self.zoom = self.actual_size();
+        self.pan = egui::Vec2::ZERO;
+        self.target_zoom = self.actual_size();
+        self.target_pan = egui::Vec2::ZERO;
+        self.selection_start = None;
+        self.fit_pending = true;
+        self.history.clear();
+        self.history_index = 0;
+    }
+
+    /// Uses new zoom limits and steps.
+    pub fn set_zoom_settings(&mut self, settings: ZoomSettings) {
+        self.settings = settings.sanitized();
+    }
+
+    /// The zoom limits in points per image pixel.
+    fn zoom_limits(&self) -> (f32, f32) {
+        (
+            self.from_scale(self.settings.min),
+            self.from_scale(self.settings.max),
+        )
+    }
+
+    /// Device pixels per image pixel at `zoom`.
+    pub fn to_scale(&self, zoom: f32) -> f32 {
+        zoom * self.pixels_per_point
+    }
+
+    /// The zoom showing image pixels at `scale` device pixels each.
+    pub fn from_scale(&self, scale: f32) -> f32 {
+        scale / self.pixels_per_point
+    }
+
+    /// The zoom at which one image pixel is one device pixel.
+    fn actual_size(&self) -> f32 {
+        self.from_scale(1.0)
+    }
+
+    /// Follows the window to a monitor with another scale factor, keeping the image the
+    /// same size in device pixels.
+    fn set_pixels_per_point(&mut self, pixels_per_point: f32) {
+        if pixels_per_point == self.pixels_per_point || pixels_per_point <= 0.0 {
+            return;
+        }
+        let factor = self.pixels_per_point / pixels_per_point;
+        self.pixels_per_point = pixels_per_point;
+        self.anchored = None;
+        self.zoom *= factor;
+        self.target_zoom *= factor;
+        self.pan *= factor;
+        self.target_pan *= factor;
+        self.zoom_anchor *= factor;
+        for (zoom, pan) in &mut self.history {
+            *zoom *= factor;
+            *pan *= factor;
+        }
+    }
+
+    /// Records the view once it has come to rest, dropping any states ahead of it.
+    fn record_history(&mut self) {
+        let state = (self.zoom, self.pan);
+        if self.history.get(self.history_index) == Some(&state) {
+            return;
+        }
+        if !self.history.is_empty() {
+            self.history.truncate(self.history_index + 1);
+        }
+        self.history.push(state);
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.history_index = self.history.len() - 1;
+    }
+
+    /// Animates to `target_zoom` about `anchor` (an offset from the viewport center): the
+    /// image point now under it stays under it throughout.
+    fn zoom_about(&mut self, anchor: egui::Vec2, target_zoom: f32) {
+        self.target_pan = anchor - (anchor - self.pan) * (target_zoom / self.zoom);
+        self.target_zoom = target_zoom;
+        self.anchored = Some((anchor, self.target_zoom, self.target_pan));
+    }
+
+    /// Eases zoom and pan towards their targets; returns whether they're still moving.
+    fn animate(&mut self, dt: f32) -> bool {
+        let speed = 15.0;
+        match self.anchored {
+            Some((anchor, zoom, pan)) if zoom == self.target_zoom && pan == self.target_pan => {
+                // Eased in log space, so the last stretch is as smooth at 1% as at 1000%
+                let mut log_zoom = self.zoom.ln();
+                let animating = exp_decay(&mut log_zoom, self.target_zoom.ln(), dt, speed);
+                if animating {
+                    self.zoom = log_zoom.exp();
+                    self.pan = anchor - (anchor - self.target_pan) * (self.zoom / self.target_zoom);
+                } else {
+                    self.zoom = self.target_zoom;
+                    self.pan = self.target_pan;
+                    self.anchored = None;
+                }
+                animating
+            }
+            _ => {
+                self.anchored = None;
+                let zoom_animating = exp_decay(&mut self.zoom, self.target_zoom, dt, speed);
+                let pan_animating = exp_decay_vec2(&mut self.pan, self.target_pan, dt, speed);
+                zoom_animating || pan_animating
+            }
+        }
+    }
+
+    /// Zooms to the double-click scale centered on the image point under `pos`, or back to
+    /// fit if already there. Returns what happened, for a toast.
+    fn double_click(
+        &mut self,
+        viewport: egui::Rect,
+        image_size: egui::Vec2,
+        pos: egui::Pos2,
+    ) -> String {
+        let scale = self.settings.double_click;
+        let zoom = self.from_scale(scale);
+        if self.fit_mode == FitMode::Free && (self.target_zoom / zoom - 1.0).abs() < 0.01 {
+            self.fit_to_window(viewport, image_size);
+            return "Fit to window".to_string();
+        }
+        let point = self.screen_to_image(viewport, image_size, pos);
+        self.fit_mode = FitMode::Free;
+        self.target_zoom = zoom;
+        self.target_pan = (image_size / 2.0 - point.to_vec2()) * zoom;
+        format!("Zoom {:.0}%", scale * 100.0)
+    }
+
+    /// Animates back (`step` < 0) or forward through the recorded view states.
+    pub fn step_history(&mut self, step: isize) {
+        let index = self.history_index as isize + step;
+        if index < 0 || index as usize >= self.history.len() {
+            return;
+        }
+        self.history_index = index as usize;
+        let (zoom, pan) = self.history[self.history_index];
+        self.animate_to(zoom, pan);
+    }
+
+    /// Animates to a stored zoom and pan in free mode.
+    pub fn animate_to(&mut self, zoom: f32, pan: egui::Vec2) {
+        self.fit_mode = FitMode::Free;
+        self.target_zoom = zoom;
+        self.target_pan = pan;
+    }
+
+    /// Keeps the view on screen unchanged after the image was replaced by a rendering
+    /// `factor` times larger (e.g. a PDF page re-rendered for the current zoom).
+    #[cfg(feature = "pdf")]
+    pub fn rescale(&mut self, factor: f32) {
+        self.zoom /= factor;
+        self.target_zoom /= factor;
+        for (zoom, _) in &mut self.history {
+            *zoom /= factor;
+        }
+    }
+
+    /// Jumps straight to a previously remembered view.
+    pub fn restore(&mut self, zoom: f32, pan: egui::Vec2) {
+        self.zoom = zoom;
+        self.pan = pan;
+        self.target_zoom = zoom;
+        self.target_pan = pan;
+        self.fit_pending = false;
+    }
+
+    /// Animates to the largest zoom at which the whole image is visible (never upscaling).
+    pub fn fit_to_window(&mut self, viewport: egui::Rect, image_size: egui::Vec2) {
+        let zoom = (viewport.width() / image_size.x.max(1.0))
+            .min(viewport.height() / image_size.y.max(1.0))
+            .min(self.actual_size());
+        self.fit_mode = FitMode::Free;
+        self.target_zoom = zoom;
+        self.target_pan = egui::Vec2::ZERO;
+    }
+
+    /// Switches fit mode; selecting the active mode again returns to free zoom.
+    pub fn toggle_fit_mode(&mut self, mode: FitMode) {
+        if self.fit_mode == mode {
+            self.fit_mode = FitMode::Free;
+        } else {
+            self.fit_mode = mode;
+            self.fit_pending = true;
+        }
+    }
+
+    fn fit_zoom(&self, viewport: egui::Rect, image_size: egui::Vec2) -> f32 {
+        match self.fit_mode {
+            FitMode::Free => self.target_zoom,
+            FitMode::FitWidth => viewport.width() / image_size.x.max(1.0),
+            FitMode::FitHeight => viewport.height() / image_size.y.max(1.0),
+        }
+    }
+
+    /// Largest pan offset along each axis that still keeps the image edge inside the viewport.
+    pub fn pan_limit(viewport: egui::Rect, image_size: egui::Vec2, zoom: f32) -> egui::Vec2 {
+        ((image_size * zoom - viewport.size()) / 2.0).max(egui::Vec2::ZERO)
+    }
+
+    /// Keeps zoom locked to the fit dimension and the pan inside the scrollable range.
+    fn apply_fit(&mut self, viewport: egui::Rect, image_size: egui::Vec2, scroll_delta: egui::Vec2) {
+        let zoom = self.fit_zoom(viewport, image_size);
+        let limit = Self::pan_limit(viewport, image_size, zoom);
+        self.target_zoom = zoom;
+
+        if self.fit_pending {
+            // Start reading from the top (or left) edge
+            self.fit_pending = false;
+            self.zoom = zoom;
+            self.target_pan = match self.fit_mode {
+                FitMode::FitWidth => egui::vec2(0.0, limit.y),
+                _ => egui::vec2(limit.x, 0.0),
+            };
+            self.pan = self.target_pan;
+        }
+
+        match self.fit_mode {
+            FitMode::FitWidth => {
+                self.target_pan.x = 0.0;
+                self.target_pan.y += scroll_delta.y;
+            }
+            FitMode::FitHeight => {
+                self.target_pan.y = 0.0;
+                // Vertical wheels scroll horizontally; trackpads may already provide x
+                self.target_pan.x += scroll_delta.x + scroll_delta.y;
+            }
+            FitMode::Free => {}
+        }
+
+        self.target_pan = self.target_pan.clamp(-limit, limit);
+        self.pan = self.pan.clamp(-limit, limit);
+    }
+
+    /// Returns the screen rect the image currently occupies inside `viewport`.
+    pub fn image_rect(&self, viewport: egui::Rect, image_size: egui::Vec2) -> egui::Rect {
+        egui::Rect::from_center_size(viewport.center() + self.pan, image_size * self.zoom)
+    }
+
+    /// Converts a screen position into image pixel coordinates (may lie outside the image).
+    pub fn screen_to_image(
+        &self,
+        viewport: egui::Rect,
+        image_size: egui::Vec2,
+        screen_pos: egui::Pos2,
+    ) -> egui::Pos2 {
+        let rel = screen_pos - viewport.center() - self.pan;
+        (rel / self.zoom + image_size / 2.0).to_pos2()
+    }
+
+    /// Converts image pixel coordinates into a screen position.
+    pub fn image_to_screen(
+        &self,
+        viewport: egui::Rect,
+        image_size: egui::Vec2,
+        image_pos: egui::Pos2,
+    ) -> egui::Pos2 {
+        viewport.center() + self.pan + (image_pos.to_vec2() - image_size / 2.0) * self.zoom
+    }
+
+    /// Animates the view so that `region` (in image pixels) fills the viewport.
+    pub fn zoom_to_region(&mut self, viewport: egui::Rect, image_size: egui::Vec2, region: egui::Rect) {
+        if region.width() < 1.0 || region.height() < 1.0 {
+            return;
+        }
+
+        let (min_zoom, max_zoom) = self.zoom_limits();
+        let zoom = (viewport.width() / region.width())
+            .min(viewport.height() / region.height())
+            .clamp(min_zoom, max_zoom);
+
+        self.target_zoom = zoom;
+        self.target_pan = (image_size / 2.0 - region.center().to_vec2()) * zoom;
+    }
+
+    /// The current rubber band rectangle in screen space, if the user is selecting.
+    pub fn selection_rect(&self, ui: &egui::Ui) -> Option<egui::Rect> {
+        let start = self.selection_start?;
+        let current = ui.input(|i| i.pointer.hover_pos())?;
+        Some(egui::Rect::from_two_pos(start, current))
+    }
+
+    /// Draws the rubber band selection, if one is in progress.
+    pub fn paint_selection(&self, ui: &egui::Ui) {
+        if let Some(rect) = self.selection_rect(ui) {
+            ui.painter().rect(
+                rect,
+                0.0,
+                egui::Color32::from_rgba_unmultiplied(100, 160, 255, 40),
+                egui::Stroke::new(1.0, egui::Color32::LIGHT_BLUE),
+            );
+        }
+    }
+
+    /// Whether Space is held to pan; tools should ignore the pointer meanwhile.
+    pub fn space_panning(ui: &egui::Ui) -> bool {
+        ui.input(|i| i.key_down(egui::Key::Space)) && !ui.ctx().wants_keyboard_input()
+    }
+
+    /// Applies this frame's wheel, click and drag input, then advances the animation.
+    ///
+    /// Input only counts where the image area itself is hovered: egui's hit-testing leaves
+    /// it unhovered under windows, the filmstrip and other widgets drawn after the image,
+    /// and while another widget is being dragged.
+    pub fn process_input(&mut self, ui: &mut egui::Ui, image_size: egui::Vec2) {
+        let viewport = ui.clip_rect();
+        let over_image = ui
+            .interact(viewport, ui.id().with("image_view"), egui::Sense::hover())
+            .hovered();
+        self.set_pixels_per_point(ui.ctx().pixels_per_point());
+
+        // 0. Double-click zooms in on the clicked point, and again back out to fit
+        if over_image
+            && let Some(pos) = ui.input(|i| {
+                i.pointer
+                    .button_double_clicked(egui::PointerButton::Primary)
+                    .then(|| i.pointer.interact_pos())
+                    .flatten()
+            })
+        {
+            let message = self.double_click(viewport, image_size, pos);
+            osd::toast(ui.ctx(), message);
+        }
+
+        // 1. Handle Zoom (Scroll)
+        let (scroll_delta, ctrl_zoom) = if over_image {
+            ui.input(|i| (i.smooth_scroll_delta, i.zoom_delta()))
+        } else {
+            (egui::Vec2::ZERO, 1.0)
+        };
+        // egui turns Ctrl+wheel into a zoom factor of e^(points / 200); back to notches
+        let fine_steps = ctrl_zoom.ln() * 200.0 / WHEEL_NOTCH;
+
+        if self.fit_mode != FitMode::Free {
+            // In fit modes the wheel scrolls through the image instead of zooming
+            self.apply_fit(viewport, image_size, scroll_delta);
+        } else if scroll_delta.y != 0.0 || fine_steps != 0.0 {
+            // Notches of the wheel, each zooming by one step (a fine one with Ctrl)
+            let (mut zoom_steps, step) = if scroll_delta.y != 0.0 {
+                (scroll_delta.y / WHEEL_NOTCH, self.settings.step)
+            } else {
+                (fine_steps, self.settings.fine_step)
+            };
+            let (min_zoom, max_zoom) = self.zoom_limits();
+            if (self.target_zoom >= max_zoom && zoom_steps > 0.0)
+                || (self.target_zoom <= min_zoom && zoom_steps < 0.0)
+            {
+                zoom_steps *= OVERSHOOT_RESISTANCE;
+            }
+            let zoom_multiplier = step.powf(zoom_steps);
+
+            let pointer_pos = ui
+                .input(|i| i.pointer.hover_pos())
+                .unwrap_or(viewport.center());
+            let rel_m = pointer_pos - viewport.center();
+
+            let target_zoom = (self.target_zoom * zoom_multiplier)
+                .clamp(min_zoom / OVERSHOOT, max_zoom * OVERSHOOT);
+            self.zoom_about(rel_m, target_zoom);
+            self.zoom_anchor = rel_m;
+            self.last_wheel_zoom = ui.input(|i| i.time);
+        } else if !(self.settings.min..=self.settings.max)
+            .contains(&self.to_scale(self.target_zoom))
+        {
+            if ui.input(|i| i.time) - self.last_wheel_zoom > OVERSHOOT_SETTLE {
+                // Spring back to the limit, about the point that was zoomed on
+                let (min_zoom, max_zoom) = self.zoom_limits();
+                let clamped = self.target_zoom.clamp(min_zoom, max_zoom);
+                self.zoom_about(self.zoom_anchor, clamped);
+            } else {
+                ui.ctx().request_repaint();
+            }
+        }
+
+        // 2. Handle Rubber Band Selection (Ctrl + Drag)
+        let (primary_pressed, primary_down, ctrl) = ui.input(|i| {
+            (
+                i.pointer.button_pressed(egui::PointerButton::Primary),
+                i.pointer.button_down(egui::PointerButton::Primary),
+                i.modifiers.command,
+            )
+        });
+
+        if primary_pressed && ctrl && over_image {
+            self.selection_start = ui.input(|i| i.pointer.press_origin());
+        }
+
+        if let Some(start) = self.selection_start {
+            if !primary_down {
+                self.selection_start = None;
+                if let Some(end) = ui.input(|i| i.pointer.latest_pos()) {
+                    let region = egui::Rect::from_two_pos(
+                        self.screen_to_image(viewport, image_size, start),
+                        self.screen_to_image(viewport, image_size, end),
+                    );
+                    self.fit_mode = FitMode::Free;
+                    self.zoom_to_region(viewport, image_size, region);
+                }
+            }
+            ui.ctx().request_repaint();
+        }
+
+        // 3. Handle Pan (Mouse Drag)
+        let space = Self::space_panning(ui);
+        let left_drag_pans = space
+            || (self.left_drag_pans && self.pan_scheme == PanScheme::LeftDrag);
+        let (pressed, down) = ui.input(|i| {
+            let pointer = &i.pointer;
+            (
+                (left_drag_pans && pointer.button_pressed(egui::PointerButton::Primary))
+                    || pointer.button_pressed(egui::PointerButton::Middle),
+                (left_drag_pans && pointer.button_down(egui::PointerButton::Primary))
+                    || pointer.button_down(egui::PointerButton::Middle),
+            )
+        });
+        // A drag belongs to where it started, even once the UI there has moved away
+        if pressed {
+            self.grabbed = over_image;
+        } else if !down {
+            self.grabbed = false;
+        }
+        let is_dragging = self.grabbed && down && self.selection_start.is_none();
+        if space && over_image {
+            ui.ctx().set_cursor_icon(if is_dragging {
+                egui::CursorIcon::Grabbing
+            } else {
+                egui::CursorIcon::Grab
+            });
+        }
+
+        if is_dragging {
+            let delta = ui.input(|i| i.pointer.delta());
+            self.target_pan += delta;
+            self.pan += delta; // Instant pan for responsiveness
+            // A zoom still settling carries on about the dragged point
+            if let Some((anchor, _, pan)) = &mut self.anchored {
+                *anchor += delta;
+                *pan += delta;
+            }
+
+            if self.fit_mode != FitMode::Free {
+                self.apply_fit(viewport, image_size, egui::Vec2::ZERO);
+            }
+        }
+
+        // 4. Animate Zoom and Pan
+        let dt = ui.input(|i| i.stable_dt).min(0.1);
+        if self.animate(dt) {
+            ui.ctx().request_repaint(); // Keep repainting until animation finishes
+        } else if self.fit_mode == FitMode::Free && !is_dragging && self.selection_start.is_none() {
+            self.record_history();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport() -> egui::Rect {
+        egui::Rect::from_min_size(egui::pos2(0.0, 0.0), egui::vec2(800.0, 600.0))
+    }
+
+    #[test]
+    fn scale_survives_a_monitor_change() {
+        let mut view = ViewState::default();
+        view.restore(2.0, egui::vec2(30.0, -10.0));
+        view.set_pixels_per_point(2.0);
+        assert_eq!(view.zoom, 1.0);
+        assert_eq!(view.target_pan, egui::vec2(15.0, -5.0));
+        assert_eq!(view.to_scale(view.target_zoom), 2.0);
+        view.reset();
+        assert_eq!(view.to_scale(view.zoom), 1.0);
+    }
+
+    #[test]
+    fn unusable_zoom_settings_fall_back_to_defaults() {
+        let settings = ZoomSettings {
+            min: 10.0,
+            max: 2.0,
+            step: 0.8,
+            fine_step: 1.1,
+            double_click: 2.0,
+        };
+        let default = ZoomSettings::default();
+        assert_eq!(
+            settings.sanitized(),
+            ZoomSettings {
+                fine_step: 1.1,
+                double_click: 2.0,
+                ..default
+            }
+        );
+    }
+
+    #[test]
+    fn animated_zoom_keeps_the_anchor_point_still() {
+        let mut view = ViewState::default();
+        view.restore(1.0, egui::vec2(20.0, -10.0));
+        let anchor = egui::vec2(150.0, 80.0);
+        let under_anchor = |view: &ViewState| (anchor - view.pan) / view.zoom;
+        let start = under_anchor(&view);
+
+        view.zoom_about(anchor, 4.0);
+        let mut frames = 0;
+        while view.animate(1.0 / 60.0) {
+            assert!((under_anchor(&view) - start).length() < 1e-3);
+            frames += 1;
+        }
+        assert!(frames > 1);
+        assert_eq!(view.zoom, 4.0);
+        assert!((under_anchor(&view) - start).length() < 1e-3);
+    }
+
+    #[test]
+    fn double_click_zooms_in_on_the_point_then_fits() {
+        let mut view = ViewState::default();
+        let image_size = egui::vec2(1600.0, 1200.0);
+        view.restore(0.5, egui::Vec2::ZERO);
+        // Image pixel (600, 450) sits at screen (300, 225)
+        view.double_click(viewport(), image_size, egui::pos2(300.0, 225.0));
+        assert_eq!(view.target_zoom, 1.0);
+        view.restore(view.target_zoom, view.target_pan);
+        let center = view.screen_to_image(viewport(), image_size, viewport().center());
+        assert!((center - egui::pos2(600.0, 450.0)).length() < 1e-3);
+
+        view.double_click(viewport(), image_size, egui::pos2(10.0, 10.0));
+        assert_eq!(view.target_zoom, 0.5);
+        assert_eq!(view.target_pan, egui::Vec2::ZERO);
+    }
+
+    #[test]
+    fn image_is_centered_at_full_size() {
+        let view = ViewState::default();
+        let rect = view.image_rect(viewport(), egui::vec2(400.0, 300.0));
+        let expected = egui::Rect::from_min_max(egui::pos2(200.0, 150.0), egui::pos2(600.0, 450.0));
+        assert_eq!(rect, expected);
+    }
+
+    #[test]
+    fn screen_and_image_coordinates_round_trip() {
+        let mut view = ViewState::default();
+        view.restore(2.5, egui::vec2(-40.0, 15.0));
+        let image_size = egui::vec2(1000.0, 700.0);
+        let image_pos = egui::pos2(123.0, 456.0);
+        let screen = view.image_to_screen(viewport(), image_size, image_pos);
+        let back = view.screen_to_image(viewport(), image_size, screen);
+        assert!((back - image_pos).length() < 1e-3);
+    }
+
+    #[test]
+    fn fit_to_window_shrinks_but_never_enlarges() {
+        let mut view = ViewState::default();
+        view.fit_to_window(viewport(), egui::vec2(1600.0, 600.0));
+        assert_eq!(view.target_zoom, 0.5);
+        view.fit_to_window(viewport(), egui::vec2(100.0, 100.0));
+        assert_eq!(view.target_zoom, 1.0);
+        assert_eq!(view.target_pan, egui::Vec2::ZERO);
+    }
+
+    #[test]
+    fn pan_limit_is_zero_along_axes_that_fit() {
+        let limit = ViewState::pan_limit(viewport(), egui::vec2(1000.0, 400.0), 1.0);
+        assert_eq!(limit, egui::vec2(100.0, 0.0));
+    }
+
+    #[test]
+    fn zoom_to_region_centers_the_region() {
+        let mut view = ViewState::default();
+        let image_size = egui::vec2(1000.0, 1000.0);
+        let region = egui::Rect::from_min_size(egui::pos2(100.0, 100.0), egui::vec2(200.0, 150.0));
+        view.zoom_to_region(viewport(), image_size, region);
+        assert_eq!(view.target_zoom, 4.0);
+        view.restore(view.target_zoom, view.target_pan);
+        let center = view.image_to_screen(viewport(), image_size, region.center());
+        assert!((center - viewport().center()).length() < 1e-3);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::annotations::ExportTarget;
+use crate::clipboard;
+use crate::osd;
+use eframe::egui;
+use std::sync::mpsc::{Receiver, channel};
+use std::thread;
+
+enum CaptureState {
+    Idle,
+    // The next frame is drawn (optionally without overlays) and a screenshot is requested
+    Requested {
+        target: ExportTarget,
+        include_overlays: bool,
+    },
+    // Waiting for the screenshot event of the frame that was drawn for capture
+    Waiting { target: ExportTarget },
+}
+
+/// Saves exactly what is visible in the window (current zoom, crop and pan) as an image.
+pub struct ViewportExport {
+    state: CaptureState,
+    // Region of the window to keep, in points
+    crop: Option<egui::Rect>,
+    rx: Option<Receiver<Result<String, String>>>,
+    status: Option<String>,
+}
+
+impl Default for ViewportExport {
+    fn default() -> Self {
+        Self {
+            state: CaptureState::Idle,
+            crop: None,
+            rx: None,
+            status: None,
+        }
+    }
+}
+
+impl ViewportExport {
+    pub fn request(&mut self, target: ExportTarget, include_overlays: bool) {
+        if matches!(self.state, CaptureState::Idle) {
+            self.state = CaptureState::Requested {
+                target,
+                include_overlays,
+            };
+            self.status = None;
+        }
+    }
+
+    /// Whether UI overlays (filmstrip, HUD, toolbars) should be drawn this frame.
+    pub fn show_overlays(&self) -> bool {
+        !matches!(
+            self.state,
+            CaptureState::Requested {
+                include_overlays: false,
+                ..
+            }
+        )
+    }
+
+    /// Sets the visible image area, which is what ends up in the export.
+    pub fn set_crop(&mut self, crop: egui::Rect) {
+        self.crop = Some(crop);
+    }
+
+    /// Drives the capture; call once per frame after everything has been drawn.
+    pub fn update(&mut self, ctx: &egui::Context) {
+        match std::mem::replace(&mut self.state, CaptureState::Idle) {
+            CaptureState::Idle => {}
+            CaptureState::Requested { target, .. } => {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot);
+                self.state = CaptureState::Waiting { target };
+                ctx.request_repaint();
+            }
+            CaptureState::Waiting { target } => {
+                let screenshot = ctx.input(|i| {
+                    i.raw.events.iter().find_map(|e| match e {
+                        egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                        _ => None,
+                    })
+                });
+                match screenshot {
+                    Some(image) => self.export(ctx, &image, target),
+                    None => {
+                        self.state = CaptureState::Waiting { target };
+                        ctx.request_repaint();
+                    }
+                }
+            }
+        }
+
+        if let Some(rx) = &self.rx
+            && let Ok(result) = rx.try_recv()
+        {
+            match result {
+                Ok(msg) => osd::toast(ctx, msg),
+                Err(msg) => self.status = Some(msg),
+            }
+            self.rx = None;
+        }
+
+        if let Some(status) = &self.status {
+            let mut dismiss = false;
+            egui::Area::new(egui::Id::new("viewport_export_status"))
+                .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(10.0, -80.0))
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        dismiss = ui.label(status).on_hover_text("Click to dismiss").clicked();
+                    });
+                });
+            if dismiss {
+                self.status = None;
+            }
+        }
+    }
+
+    fn export(&mut self, ctx: &egui::Context, image: &egui::ColorImage, target: ExportTarget) {
+        let ppp = ctx.pixels_per_point();
+        let [w, h] = image.size;
+        let full = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(w as f32, h as f32));
+        let crop = self
+            .crop
+            .map(|r| egui::Rect::from_min_max((r.min.to_vec2() * ppp).to_pos2(), (r.max.to_vec2() * ppp).to_pos2()))
+            .unwrap_or(full)
+            .intersect(full);
+        let crop = egui::Rect::from_min_max(crop.min.round(), crop.max.round());
+        if !crop.is_positive() {
+            self.status = Some("Nothing visible to export".to_string());
+            return;
+        }
+
+        let mut rgba = image::RgbaImage::new(w as u32, h as u32);
+        for (dst, src) in rgba.pixels_mut().zip(&image.pixels) {
+            *dst = image::Rgba(src.to_srgba_unmultiplied());
+        }
+        let cropped = image::imageops::crop_imm(
+            &rgba,
+            crop.min.x as u32,
+            crop.min.y as u32,
+            crop.width() as u32,
+            crop.height() as u32,
+        )
+        .to_image();
+
+        let ctx = ctx.clone();
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        thread::spawn(move || {
+            let result = match target {
+                ExportTarget::File(path) => cropped
+                    .save(&path)
+                    .map(|_| format!("Saved view to {}", path.display()))
+                    .map_err(|e| format!("Export failed: {}", e)),
+                ExportTarget::Clipboard => {
+                    clipboard::copy_image(&cropped).map(|_| "Copied view to clipboard".to_string())
+                }
+            };
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+}
@@ -0,0 +1,127 @@
+//! Zoom feedback: a chip by the cursor that briefly shows the new zoom level, and a
+//! clickable zoom readout with preset levels.
+
+use crate::animation::exp_decay;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// How long the chip stays up after the zoom last changed
+const CHIP_DURATION: Duration = Duration::from_secs(1);
+const PRESETS: [f32; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+
+pub enum ZoomPreset {
+    Fit,
+    Scale(f32),
+    // Resize the window to the image at 100%
+    SizeWindow,
+    // Toggle fitting new images to the monitor's work area
+    FitWorkArea,
+}
+
+#[derive(Default)]
+pub struct ZoomIndicator {
+    // Zoom of the image last frame; switching images isn't a zoom change
+    last: Option<(PathBuf, f32)>,
+    changed_at: Option<Instant>,
+    opacity: f32,
+}
+
+fn percent(zoom: f32) -> String {
+    if zoom < 0.1 {
+        format!("{:.1}%", zoom * 100.0)
+    } else {
+        format!("{:.0}%", zoom * 100.0)
+    }
+}
+
+impl ZoomIndicator {
+    /// Tracks the zoom of `path` and draws the chip while it's fresh. Changes made while
+    /// `quiet` (e.g. slideshow motion) don't show it.
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        path: Option<&Path>,
+        zoom: f32,
+        quiet: bool,
+        opacity: f32,
+    ) {
+        let Some(path) = path else {
+            self.last = None;
+            return;
+        };
+        if let Some((last_path, last_zoom)) = &self.last
+            && last_path == path
+            && *last_zoom != zoom
+            && !quiet
+        {
+            self.changed_at = Some(Instant::now());
+        }
+        self.last = Some((path.to_path_buf(), zoom));
+
+        let Some(changed_at) = self.changed_at else {
+            return;
+        };
+        let target = if changed_at.elapsed() < CHIP_DURATION {
+            1.0
+        } else {
+            0.0
+        };
+        let dt = ctx.input(|i| i.stable_dt).min(0.1);
+        if exp_decay(&mut self.opacity, target, dt, 10.0) {
+            ctx.request_repaint();
+        } else if target == 0.0 {
+            self.changed_at = None;
+            return;
+        } else {
+            ctx.request_repaint_after(CHIP_DURATION.saturating_sub(changed_at.elapsed()));
+        }
+
+        // Next to the cursor while it's over the window, otherwise in the corner
+        let area = egui::Area::new(egui::Id::new("zoom_chip"))
+            .order(egui::Order::Tooltip)
+            .interactable(false);
+        let area = match ctx.pointer_hover_pos() {
+            Some(pos) => area.fixed_pos(pos + egui::vec2(18.0, 18.0)),
+            None => area.anchor(egui::Align2::LEFT_TOP, egui::vec2(10.0, 10.0)),
+        };
+        area.show(ctx, |ui| {
+            ui.multiply_opacity(self.opacity * opacity);
+            egui::Frame::popup(ui.style())
+                .rounding(10.0)
+                .show(ui, |ui| ui.strong(percent(zoom)));
+        });
+    }
+}
+
+/// Zoom level as a button opening the preset levels. Returns the preset picked.
+pub fn readout(ui: &mut egui::Ui, zoom: f32, fit_work_area: bool) -> Option<ZoomPreset> {
+    let mut picked = None;
+    ui.menu_button(format!("Zoom {}", percent(zoom)), |ui| {
+        if ui.button("Fit to window").clicked() {
+            picked = Some(ZoomPreset::Fit);
+        }
+        if ui.button("Size window to image (0)").clicked() {
+            picked = Some(ZoomPreset::SizeWindow);
+        }
+        if ui
+            .selectable_label(fit_work_area, "Fit new images to the monitor (Alt+0)")
+            .clicked()
+        {
+            picked = Some(ZoomPreset::FitWorkArea);
+        }
+        ui.separator();
+        for preset in PRESETS {
+            if ui
+                .selectable_label((zoom - preset).abs() < 0.001, percent(preset))
+                .clicked()
+            {
+                picked = Some(ZoomPreset::Scale(preset));
+            }
+        }
+        if picked.is_some() {
+            ui.close_menu();
+        }
+    });
+    picked
+}